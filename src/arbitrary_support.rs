@@ -0,0 +1,42 @@
+// `Arbitrary` support, behind the `arbitrary` feature, so fuzz targets can
+// take whole maps as input. A map is generated from a run of inserts and
+// removes rather than from a final set of entries, which lets the fuzzer
+// reach the shapes left behind by merging and rebalancing as well as by
+// splitting.
+
+use std::fmt::Debug;
+use std::ops::ControlFlow;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::bplus_tree_map::BPlusTreeMap;
+
+impl<'a, K, V> Arbitrary<'a> for BPlusTreeMap<K, V>
+where
+    K: Arbitrary<'a> + Ord + Clone + Debug,
+    V: Arbitrary<'a> + Clone + Debug,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let branching_factor = u.int_in_range(2..=16)?;
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        u.arbitrary_loop(None, None, |u| {
+            // Exhausted input would otherwise keep inserting default keys
+            if u.is_empty() {
+                return Ok(ControlFlow::Break(()));
+            }
+            // Removes pick a key already in the map; a random key would
+            // almost never be present
+            if !map.is_empty() && u.ratio(1, 4)? {
+                let index = u.choose_index(map.len())?;
+                let key = map.keys().nth(index).cloned();
+                if let Some(key) = key {
+                    map.remove(&key);
+                }
+            } else {
+                map.insert(K::arbitrary(u)?, V::arbitrary(u)?);
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(map)
+    }
+}
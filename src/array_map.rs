@@ -0,0 +1,582 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::iter::{FusedIterator, Zip};
+use std::mem;
+use std::slice;
+
+use crate::array_vec::ArrayVec;
+use crate::node_operations::{
+    ArrayNodeMerger, ArrayNodeSplitter, MergeResult, NodeMerger, NodeSplitter, SplitResult,
+};
+
+/// A leaf of a `BPlusTreeArrayMap`, holding up to `B` entries inline
+#[derive(Clone)]
+pub(crate) struct ArrayLeaf<K, V, const B: usize> {
+    pub(crate) keys: ArrayVec<K, B>,
+    pub(crate) values: ArrayVec<V, B>,
+}
+
+/// A branch of a `BPlusTreeArrayMap`, holding up to `B` children inline
+/// and one separator key fewer. The last key slot is never used.
+#[derive(Clone)]
+pub(crate) struct ArrayBranch<K, V, const B: usize> {
+    pub(crate) keys: ArrayVec<K, B>,
+    pub(crate) children: ArrayVec<ArrayNode<K, V, B>, B>,
+}
+
+/// A node of a `BPlusTreeArrayMap`. Each node is a single allocation: its
+/// keys, values and children live in the box with it.
+#[derive(Clone)]
+pub(crate) enum ArrayNode<K, V, const B: usize> {
+    Leaf(Box<ArrayLeaf<K, V, B>>),
+    Branch(Box<ArrayBranch<K, V, B>>),
+}
+
+// Written out rather than derived, which would need `K` and `V` to
+// implement `Default` too
+impl<K, V, const B: usize> Default for ArrayLeaf<K, V, B> {
+    fn default() -> Self {
+        Self {
+            keys: ArrayVec::new(),
+            values: ArrayVec::new(),
+        }
+    }
+}
+
+impl<K, V, const B: usize> Default for ArrayBranch<K, V, B> {
+    fn default() -> Self {
+        Self {
+            keys: ArrayVec::new(),
+            children: ArrayVec::new(),
+        }
+    }
+}
+
+impl<K, V, const B: usize> ArrayNode<K, V, B> {
+    /// Returns true if the node has no room for another entry or child
+    fn is_full(&self) -> bool {
+        match self {
+            ArrayNode::Leaf(leaf) => leaf.keys.is_full(),
+            ArrayNode::Branch(branch) => branch.children.is_full(),
+        }
+    }
+}
+
+/// A B+ tree map whose nodes store their entries in fixed-size arrays
+/// rather than in `Vec`s, for small branching factors known at compile
+/// time. `B` is the most entries a leaf holds and the most children a
+/// branch holds; it must be at least 4.
+///
+/// A `BPlusTreeMap` node is a node plus two `Vec` buffers, so reaching a
+/// key takes two pointer hops per level and every node costs three
+/// allocations. Here each node is one allocation and its keys sit next to
+/// its header. The price is that every node takes room for `B` entries
+/// however few it holds.
+///
+/// Full nodes are split on the way down an insert, before the insert
+/// reaches them, so a node never needs room for one entry more than `B`.
+pub struct BPlusTreeArrayMap<K, V, const B: usize> {
+    root: Option<ArrayNode<K, V, B>>,
+    len: usize,
+}
+
+impl<K, V, const B: usize> BPlusTreeArrayMap<K, V, B> {
+    /// Creates an empty map
+    pub const fn new() -> Self {
+        const {
+            assert!(
+                B >= 4,
+                "BPlusTreeArrayMap needs a branching factor of at least 4"
+            );
+        }
+        Self { root: None, len: 0 }
+    }
+
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every entry from the map
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the entries of the map, in key order
+    pub fn iter(&self) -> Iter<'_, K, V, B> {
+        Iter {
+            stack: vec![self.root.as_slice().iter()],
+            leaf: [].iter().zip([].iter()),
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over the values of the map, in key order
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> BPlusTreeArrayMap<K, V, B> {
+    /// Returns a reference to the value for the key
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_ref()?;
+        loop {
+            match node {
+                ArrayNode::Leaf(leaf) => {
+                    let idx = leaf.keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+                    return Some(&leaf.values[idx]);
+                }
+                ArrayNode::Branch(branch) => node = &branch.children[child_index(branch, key)],
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value for the key
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_mut()?;
+        loop {
+            match node {
+                ArrayNode::Leaf(leaf) => {
+                    let idx = leaf.keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+                    return Some(&mut leaf.values[idx]);
+                }
+                ArrayNode::Branch(branch) => {
+                    let idx = child_index(branch, key);
+                    node = &mut branch.children[idx];
+                }
+            }
+        }
+    }
+
+    /// Returns true if the map holds the key
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts an entry, returning the value it replaced, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let root = self
+            .root
+            .get_or_insert_with(|| ArrayNode::Leaf(Box::default()));
+        if root.is_full() {
+            let (separator, right) = split(root);
+            let left = mem::replace(root, ArrayNode::Branch(Box::default()));
+            let ArrayNode::Branch(branch) = root else {
+                unreachable!()
+            };
+            branch.keys.push(separator);
+            branch.children.push(left);
+            branch.children.push(right);
+        }
+
+        // The node descended into always has room, so a full child can be
+        // split into it
+        let mut node = root;
+        loop {
+            match node {
+                ArrayNode::Leaf(leaf) => {
+                    return match leaf.keys.binary_search(&key) {
+                        Ok(idx) => Some(mem::replace(&mut leaf.values[idx], value)),
+                        Err(idx) => {
+                            leaf.keys.insert(idx, key);
+                            leaf.values.insert(idx, value);
+                            self.len += 1;
+                            None
+                        }
+                    };
+                }
+                ArrayNode::Branch(branch) => {
+                    let mut idx = child_index(branch, &key);
+                    if branch.children[idx].is_full() {
+                        let (separator, right) = split(&mut branch.children[idx]);
+                        let goes_right = key >= separator;
+                        branch.keys.insert(idx, separator);
+                        branch.children.insert(idx + 1, right);
+                        idx += usize::from(goes_right);
+                    }
+                    node = &mut branch.children[idx];
+                }
+            }
+        }
+    }
+
+    /// Removes the key, returning its value if the map held it
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (_, value) = remove_from(self.root.as_mut()?, key)?;
+        self.len -= 1;
+
+        // The root may have lost its last entry, or all but one child
+        let new_root = match &mut self.root {
+            Some(ArrayNode::Leaf(leaf)) if leaf.keys.is_empty() => Some(None),
+            Some(ArrayNode::Branch(branch)) if branch.children.len() == 1 => {
+                Some(branch.children.pop())
+            }
+            _ => None,
+        };
+        if let Some(root) = new_root {
+            self.root = root;
+        }
+        Some(value)
+    }
+}
+
+/// Returns the index of the child of `branch` whose subtree holds `key`.
+/// As in `BPlusTreeMap`, a key equal to a separator belongs to the child
+/// on its right.
+fn child_index<K, V, Q, const B: usize>(branch: &ArrayBranch<K, V, B>, key: &Q) -> usize
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    branch.keys.partition_point(|k| k.borrow() <= key)
+}
+
+/// Splits a full node in two, keeping the left half in place, and returns
+/// the separator and the right half
+fn split<K: Ord + Clone, V, const B: usize>(
+    node: &mut ArrayNode<K, V, B>,
+) -> (K, ArrayNode<K, V, B>) {
+    match node {
+        ArrayNode::Leaf(leaf) => match ArrayNodeSplitter.split(mem::take(&mut **leaf)) {
+            SplitResult::Split {
+                left,
+                right,
+                separator,
+            } => {
+                **leaf = left;
+                (separator, ArrayNode::Leaf(Box::new(right)))
+            }
+            SplitResult::NoSplit(_) => unreachable!("only full nodes are split"),
+        },
+        ArrayNode::Branch(branch) => match ArrayNodeSplitter.split(mem::take(&mut **branch)) {
+            SplitResult::Split {
+                left,
+                right,
+                separator,
+            } => {
+                **branch = left;
+                (separator, ArrayNode::Branch(Box::new(right)))
+            }
+            SplitResult::NoSplit(_) => unreachable!("only full nodes are split"),
+        },
+    }
+}
+
+/// Removes the key from the subtree under `node`. A child left with too
+/// few entries is merged with or refilled from a sibling on the way back
+/// up; `node` itself is left for its parent to fix.
+fn remove_from<K, V, Q, const B: usize>(node: &mut ArrayNode<K, V, B>, key: &Q) -> Option<(K, V)>
+where
+    K: Ord + Clone + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match node {
+        ArrayNode::Leaf(leaf) => {
+            let idx = leaf.keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+            Some((leaf.keys.remove(idx), leaf.values.remove(idx)))
+        }
+        ArrayNode::Branch(branch) => {
+            let idx = child_index(branch, key);
+            let removed = remove_from(&mut branch.children[idx], key)?;
+            if is_underfull(&branch.children[idx]) {
+                // Every branch has at least two children; the leftmost
+                // child is balanced against its right sibling
+                balance_children(branch, idx.max(1) - 1);
+            }
+            Some(removed)
+        }
+    }
+}
+
+/// Returns true if a node other than the root holds too few entries
+fn is_underfull<K, V, const B: usize>(node: &ArrayNode<K, V, B>) -> bool {
+    match node {
+        ArrayNode::Leaf(leaf) => leaf.keys.len() < B / 2,
+        ArrayNode::Branch(branch) => branch.children.len() < B / 2,
+    }
+}
+
+/// Merges the children of `branch` at `idx` and `idx + 1`, or moves
+/// entries between them, so that neither is underfull
+fn balance_children<K: Ord + Clone, V, const B: usize>(
+    branch: &mut ArrayBranch<K, V, B>,
+    idx: usize,
+) {
+    let separator = branch.keys.remove(idx);
+    let right = branch.children.remove(idx + 1);
+    let left = &mut branch.children[idx];
+    let balanced = match (left, right) {
+        (ArrayNode::Leaf(left), ArrayNode::Leaf(mut right)) => {
+            match ArrayNodeMerger.merge(mem::take(&mut **left), mem::take(&mut *right), separator) {
+                MergeResult::Merged(merged) => {
+                    **left = merged;
+                    None
+                }
+                MergeResult::Rebalanced {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                }
+                | MergeResult::NoMerge {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                } => {
+                    **left = new_left;
+                    *right = new_right;
+                    Some((separator, ArrayNode::Leaf(right)))
+                }
+            }
+        }
+        (ArrayNode::Branch(left), ArrayNode::Branch(mut right)) => {
+            match ArrayNodeMerger.merge(mem::take(&mut **left), mem::take(&mut *right), separator) {
+                MergeResult::Merged(merged) => {
+                    **left = merged;
+                    None
+                }
+                MergeResult::Rebalanced {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                }
+                | MergeResult::NoMerge {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                } => {
+                    **left = new_left;
+                    *right = new_right;
+                    Some((separator, ArrayNode::Branch(right)))
+                }
+            }
+        }
+        _ => unreachable!("siblings are at the same depth"),
+    };
+    if let Some((separator, right)) = balanced {
+        branch.keys.insert(idx, separator);
+        branch.children.insert(idx + 1, right);
+    }
+}
+
+impl<K, V, const B: usize> Default for BPlusTreeArrayMap<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone, V: Clone, const B: usize> Clone for BPlusTreeArrayMap<K, V, B> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Debug, V: Debug, const B: usize> Debug for BPlusTreeArrayMap<K, V, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> FromIterator<(K, V)> for BPlusTreeArrayMap<K, V, B> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> Extend<(K, V)> for BPlusTreeArrayMap<K, V, B> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K, V, const B: usize> IntoIterator for &'a BPlusTreeArrayMap<K, V, B> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `BPlusTreeArrayMap`, in key order
+pub struct Iter<'a, K, V, const B: usize> {
+    /// The nodes still to visit at each level above the current leaf
+    stack: Vec<slice::Iter<'a, ArrayNode<K, V, B>>>,
+    /// The entries of the current leaf still to visit
+    leaf: Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V, const B: usize> Iterator for Iter<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.leaf.next() {
+                self.remaining -= 1;
+                return Some(entry);
+            }
+            let siblings = self.stack.last_mut()?;
+            let Some(mut node) = siblings.next() else {
+                self.stack.pop();
+                continue;
+            };
+            // Descend to the leftmost leaf under the next node
+            while let ArrayNode::Branch(branch) = node {
+                let mut children = branch.children.iter();
+                node = children.next()?;
+                self.stack.push(children);
+            }
+            if let ArrayNode::Leaf(leaf) = node {
+                self.leaf = leaf.keys.iter().zip(leaf.values.iter());
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, const B: usize> ExactSizeIterator for Iter<'_, K, V, B> {}
+
+impl<K, V, const B: usize> FusedIterator for Iter<'_, K, V, B> {}
+
+#[cfg(test)]
+impl<K: Ord + Debug, V, const B: usize> BPlusTreeArrayMap<K, V, B> {
+    /// Checks that keys are in order and within the bounds of their
+    /// separators, that every leaf is at the same depth, that every node
+    /// but the root is at least half full and that the length is right
+    pub(crate) fn check_invariants(&self) -> Result<(), String> {
+        let Some(root) = &self.root else {
+            return if self.len == 0 {
+                Ok(())
+            } else {
+                Err(format!("empty tree with length {}", self.len))
+            };
+        };
+        let mut leaf_depth = None;
+        let mut count = 0;
+        check_node(root, None, None, 0, true, &mut leaf_depth, &mut count)?;
+        if count != self.len {
+            return Err(format!("{} entries but length {}", count, self.len));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl<K, V, const B: usize> BPlusTreeArrayMap<K, V, B> {
+    /// Counts the nodes of the tree
+    pub(crate) fn node_count(&self) -> usize {
+        fn count<K, V, const B: usize>(node: &ArrayNode<K, V, B>) -> usize {
+            match node {
+                ArrayNode::Leaf(_) => 1,
+                ArrayNode::Branch(branch) => 1 + branch.children.iter().map(count).sum::<usize>(),
+            }
+        }
+        self.root.as_ref().map_or(0, count)
+    }
+}
+
+#[cfg(test)]
+fn check_node<K: Ord + Debug, V, const B: usize>(
+    node: &ArrayNode<K, V, B>,
+    lower: Option<&K>,
+    upper: Option<&K>,
+    depth: usize,
+    is_root: bool,
+    leaf_depth: &mut Option<usize>,
+    count: &mut usize,
+) -> Result<(), String> {
+    let keys: &[K] = match node {
+        ArrayNode::Leaf(leaf) => &leaf.keys,
+        ArrayNode::Branch(branch) => &branch.keys,
+    };
+    if !keys.is_sorted_by(|a, b| a < b) {
+        return Err(format!("keys out of order: {:?}", keys));
+    }
+    if let (Some(lower), Some(first)) = (lower, keys.first())
+        && first < lower
+    {
+        return Err(format!(
+            "key {:?} is below its separator {:?}",
+            first, lower
+        ));
+    }
+    if let (Some(upper), Some(last)) = (upper, keys.last())
+        && last >= upper
+    {
+        return Err(format!(
+            "key {:?} is not below its separator {:?}",
+            last, upper
+        ));
+    }
+    if !is_root && is_underfull(node) {
+        return Err(format!("underfull node with keys {:?}", keys));
+    }
+    match node {
+        ArrayNode::Leaf(leaf) => {
+            if leaf.keys.len() != leaf.values.len() {
+                return Err(format!(
+                    "leaf with keys {:?} has {} values",
+                    keys,
+                    leaf.values.len()
+                ));
+            }
+            if *leaf_depth.get_or_insert(depth) != depth {
+                return Err(format!("leaf with keys {:?} is at depth {}", keys, depth));
+            }
+            *count += leaf.keys.len();
+        }
+        ArrayNode::Branch(branch) => {
+            if branch.children.len() != branch.keys.len() + 1 || branch.children.len() < 2 {
+                return Err(format!(
+                    "branch with keys {:?} has {} children",
+                    keys,
+                    branch.children.len()
+                ));
+            }
+            for (idx, child) in branch.children.iter().enumerate() {
+                let lower = idx.checked_sub(1).map(|i| &branch.keys[i]).or(lower);
+                let upper = branch.keys.get(idx).or(upper);
+                check_node(child, lower, upper, depth + 1, false, leaf_depth, count)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::fmt::{self, Debug};
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+
+/// A vector of at most `N` items stored inline, for the nodes of
+/// `BPlusTreeArrayMap`. Only the first `len` slots are initialized.
+///
+/// This is the one place the array-backed nodes use unsafe code: every
+/// other operation on them goes through the slices and the moves below.
+/// No user code (no `Clone`, `Ord` or `Drop` of an item) runs while the
+/// slots are out of step with `len`, so a panic can't expose an
+/// uninitialized slot or drop an item twice.
+///
+/// The tests of this type and of the array-backed map are sized to run
+/// under Miri as well: `cargo +nightly miri test array`.
+pub(crate) struct ArrayVec<T, const N: usize> {
+    len: usize,
+    items: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    /// Creates an empty vector
+    pub(crate) const fn new() -> Self {
+        Self {
+            len: 0,
+            items: [const { MaybeUninit::uninit() }; N],
+        }
+    }
+
+    /// Returns true if every slot is taken
+    pub(crate) fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn as_ptr(&self) -> *const T {
+        self.items.as_ptr().cast()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.items.as_mut_ptr().cast()
+    }
+
+    /// Appends an item. Panics if the vector is full.
+    pub(crate) fn push(&mut self, item: T) {
+        self.insert(self.len, item);
+    }
+
+    /// Removes the last item
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the slot at the old last index was initialized, and is
+        // now outside `len`, so it is read exactly once
+        Some(unsafe { self.as_ptr().add(self.len).read() })
+    }
+
+    /// Inserts an item at `idx`, shifting the items after it to the right.
+    /// Panics if `idx` is past the end or the vector is full.
+    pub(crate) fn insert(&mut self, idx: usize, item: T) {
+        assert!(idx <= self.len, "insertion index {} is past the end", idx);
+        assert!(self.len < N, "ArrayVec of {} items is full", N);
+        // SAFETY: idx <= len < N, so the shifted items and the written slot
+        // are all inside the array
+        unsafe {
+            let slot = self.as_mut_ptr().add(idx);
+            ptr::copy(slot, slot.add(1), self.len - idx);
+            slot.write(item);
+        }
+        self.len += 1;
+    }
+
+    /// Removes and returns the item at `idx`, shifting the items after it
+    /// to the left. Panics if `idx` is out of bounds.
+    pub(crate) fn remove(&mut self, idx: usize) -> T {
+        assert!(idx < self.len, "removal index {} is out of bounds", idx);
+        // SAFETY: idx < len, so the slot is initialized; it is read once and
+        // then overwritten by the items after it
+        unsafe {
+            let slot = self.as_mut_ptr().add(idx);
+            let item = slot.read();
+            ptr::copy(slot.add(1), slot, self.len - idx - 1);
+            self.len -= 1;
+            item
+        }
+    }
+
+    /// Moves the items from `at` on into a new vector
+    pub(crate) fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "split index {} is past the end", at);
+        let mut tail = Self::new();
+        let count = self.len - at;
+        // SAFETY: the moved slots are initialized, and are outside `len` of
+        // this vector once it is shortened, so each item has one owner
+        unsafe {
+            ptr::copy_nonoverlapping(self.as_ptr().add(at), tail.as_mut_ptr(), count);
+        }
+        self.len = at;
+        tail.len = count;
+        tail
+    }
+
+    /// Moves every item of `other` onto the end of this vector, leaving
+    /// `other` empty. Panics if they don't fit.
+    pub(crate) fn append(&mut self, other: &mut Self) {
+        assert!(
+            self.len + other.len <= N,
+            "{} and {} items don't fit in an ArrayVec of {}",
+            self.len,
+            other.len,
+            N
+        );
+        // SAFETY: the moved items fit after the initialized ones, and
+        // `other` gives them up by being emptied
+        unsafe {
+            ptr::copy_nonoverlapping(other.as_ptr(), self.as_mut_ptr().add(self.len), other.len);
+        }
+        self.len += other.len;
+        other.len = 0;
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: the first `len` slots are initialized
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: the first `len` slots are initialized
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: the first `len` slots are initialized and dropped once
+        unsafe { ptr::drop_in_place(&mut **self) }
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        // Items are pushed one at a time, so if a clone panics the copy
+        // drops just the items cloned so far
+        let mut copy = Self::new();
+        for item in self.iter() {
+            copy.push(item.clone());
+        }
+        copy
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
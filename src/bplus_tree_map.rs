@@ -1,35 +1,331 @@
 use std::borrow::Borrow;
+use std::cell::Cell;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt::{self, Debug};
-use std::iter::FromIterator;
-use std::ops::Index;
+#[cfg(feature = "bloom")]
+use std::hash::Hash;
+use std::iter;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::ops::{Bound, ControlFlow, Index, IndexMut, RangeBounds};
+use std::ptr::{self, NonNull};
+use std::slice;
 use std::vec;
 
 use std::rc::Rc;
 
-use crate::node_balancer::{BalanceResult, InsertionBalancer, NodeBalancer, RemovalBalancer};
 use crate::config::BPlusTreeConfig;
+use crate::key_prefix::KeyPrefix;
+use crate::key_search::{SearchableKey, search_keys_by, search_keys_in};
+use crate::leaf_filter::FilterKey;
+#[cfg(feature = "bloom")]
+use crate::leaf_filter::{LeafFilter, filter_hash};
+use crate::node_balancer::{BalanceResult, InsertionBalancer, NodeBalancer, RemovalBalancer};
+pub use crate::node_box::NodeBox;
+use crate::node_box::{LeafLink, LeafLinks, link_leaves, linked_leaf};
+use crate::node_pool::NodePool;
+use crate::node_vec::{NodeVec, NodeVecExt, NodeVecIntoIter};
+use crate::separator::{SeparatorKey, SeparatorPolicy};
 
 // Node types for the B+ tree
-#[derive(Clone)]
 pub struct LeafNode<K, V> {
-    pub keys: Vec<K>,
-    pub values: Vec<V>,
+    pub keys: NodeVec<K>,
+    pub values: NodeVec<V>,
+    /// Rejects most keys the leaf doesn't hold, so lookups can skip
+    /// searching it. Rebuilt by `refresh_filter` whenever the keys change.
+    #[cfg(feature = "bloom")]
+    pub filter: LeafFilter<K>,
+    /// The leaves before and after this one, kept by the map that holds it
+    pub(crate) links: LeafLinks<K, V>,
 }
 
-#[derive(Clone)]
 pub struct BranchNode<K, V> {
-    pub keys: Vec<K>,
-    pub children: Vec<Node<K, V>>,
+    pub keys: NodeVec<SeparatorKey<K>>,
+    pub children: Vec<NodeBox<K, V>>,
+    /// Copies of the smallest and largest keys in the branch's subtree, so
+    /// a key outside them is known to be missing without descending. They
+    /// are kept up to date by `refresh_fences` whenever the children
+    /// change, and are only `None` while the branch has no children.
+    pub min_key: Option<K>,
+    pub max_key: Option<K>,
 }
 
 // Enum to represent different node types
-#[derive(Clone)]
 pub enum Node<K, V> {
     Leaf(LeafNode<K, V>),
     Branch(BranchNode<K, V>),
 }
 
+// The node types implement `clone_from` so that copying a tree over one of
+// the same shape reuses its Vecs: `Vec::clone_from` clones into the
+// elements it already has, which for children means node by node. A
+// cloned leaf isn't linked to anything until the map holding it links it.
+impl<K: Clone, V: Clone> Clone for LeafNode<K, V> {
+    fn clone(&self) -> Self {
+        LeafNode {
+            keys: self.keys.clone(),
+            values: self.values.clone(),
+            #[cfg(feature = "bloom")]
+            filter: self.filter.clone(),
+            links: LeafLinks::default(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.keys.clone_from(&source.keys);
+        self.values.clone_from(&source.values);
+        #[cfg(feature = "bloom")]
+        self.filter.clone_from(&source.filter);
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for BranchNode<K, V> {
+    fn clone(&self) -> Self {
+        BranchNode {
+            keys: self.keys.clone(),
+            children: self.children.clone(),
+            min_key: self.min_key.clone(),
+            max_key: self.max_key.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.keys.clone_from(&source.keys);
+        self.children.clone_from(&source.children);
+        self.min_key.clone_from(&source.min_key);
+        self.max_key.clone_from(&source.max_key);
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for Node<K, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Node::Leaf(leaf) => Node::Leaf(leaf.clone()),
+            Node::Branch(branch) => Node::Branch(branch.clone()),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        match (self, source) {
+            (Node::Leaf(leaf), Node::Leaf(source)) => leaf.clone_from(source),
+            (Node::Branch(branch), Node::Branch(source)) => branch.clone_from(source),
+            // A node of the other kind has nothing worth keeping
+            (node, source) => *node = source.clone(),
+        }
+    }
+}
+
+impl<K, V> LeafNode<K, V> {
+    /// Creates a leaf holding `keys` and their `values`, without a filter
+    pub fn new(keys: impl Into<NodeVec<K>>, values: impl Into<NodeVec<V>>) -> Self {
+        LeafNode {
+            keys: keys.into(),
+            values: values.into(),
+            #[cfg(feature = "bloom")]
+            filter: LeafFilter::new(),
+            links: LeafLinks::default(),
+        }
+    }
+
+    /// Rebuilds the leaf's filter over its keys, after they have changed.
+    /// Without the `bloom` feature leaves have no filters and this does
+    /// nothing.
+    pub fn refresh_filter(&mut self) {
+        #[cfg(feature = "bloom")]
+        self.filter.rebuild(&self.keys);
+    }
+
+    /// Whether the leaf's filter shows that `key` is not in the leaf
+    #[cfg(feature = "bloom")]
+    fn filter_rejects<Q: FilterKey + ?Sized>(&self, key: &Q) -> bool {
+        !self.filter.may_contain(filter_hash(key))
+    }
+
+    /// Whether the leaf's filter shows that `key` is not in the leaf,
+    /// which without filters it never does
+    #[cfg(not(feature = "bloom"))]
+    fn filter_rejects<Q: FilterKey + ?Sized>(&self, _key: &Q) -> bool {
+        false
+    }
+}
+
+impl<K, V> BranchNode<K, V> {
+    /// Returns the index of the child whose subtree holds `key`, or would
+    /// hold it. A key equal to a separator belongs to the child on the
+    /// separator's right; every lookup, insert and removal descends through
+    /// here so they all agree on that.
+    pub fn child_index_for<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.child_index_with(key, 0)
+    }
+
+    /// Returns the index of the child for `key` like `child_index_for`,
+    /// scanning the separators from the front if there are fewer than
+    /// `linear_search_threshold` of them
+    pub fn child_index_with<Q>(&self, key: &Q, linear_search_threshold: usize) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Self::child_index_among(&self.keys, key, linear_search_threshold, false)
+    }
+
+    /// Returns the index of the child for `key` like `child_index_for`,
+    /// searching the separators the way `config` says
+    pub(crate) fn child_index_in<Q>(&self, key: &Q, config: &BPlusTreeConfig) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Self::child_index_among(
+            &self.keys,
+            key,
+            config.linear_search_threshold,
+            config.branchless_search,
+        )
+    }
+
+    /// Returns the index of the child for `key` like `child_index_with`,
+    /// given only the branch's separators, so that its children can be
+    /// borrowed at the same time
+    fn child_index_among<Q>(
+        separators: &[SeparatorKey<K>],
+        key: &Q,
+        linear_search_threshold: usize,
+        branchless: bool,
+    ) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match search_keys_by(
+            separators,
+            key,
+            linear_search_threshold,
+            branchless,
+            |separator| (**separator).borrow(),
+        ) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        }
+    }
+
+    /// Whether `key` lies between the fences, so that it could be stored
+    /// in the branch's subtree
+    pub fn fences_cover<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.min_key.as_ref().is_some_and(|min| min.borrow() <= key)
+            && self.max_key.as_ref().is_some_and(|max| key <= max.borrow())
+    }
+
+    /// Takes the child at `idx` out of its box so it can be passed down by
+    /// value, as `Node::take` does
+    fn take_child(&mut self, idx: usize) -> Node<K, V> {
+        self.children[idx].take()
+    }
+
+    /// The links from the children in `range` out to the leaves either
+    /// side of them, if the children are leaves, to be restored by
+    /// `relink_children` once the children have changed
+    fn outer_links(&self, range: std::ops::Range<usize>) -> Option<LeafLinks<K, V>> {
+        let prev = self.children[range.start].leaf_links()?.prev;
+        let next = self.children[range.end - 1].leaf_links()?.next;
+        Some(LeafLinks { prev, next })
+    }
+
+    /// Links the leaves among the children in `range` to each other, and
+    /// the first and last of them to the leaves `outer` leads to, which
+    /// must be the neighbours the children in `range` had before they
+    /// changed
+    fn relink_children(&self, range: std::ops::Range<usize>, outer: LeafLinks<K, V>) {
+        // SAFETY: `outer` was read from leaves of the branch's tree, and the
+        // leaves it leads to are outside `range`, so they are still alive.
+        // The tree is being changed through an exclusive borrow of the map,
+        // so nothing else borrows its leaves.
+        unsafe { link_leaves(outer.prev, &self.children[range], outer.next) }
+    }
+}
+
+impl<K: Clone + PartialEq, V> BranchNode<K, V> {
+    /// Creates a branch over `children`, boxing any that aren't boxed yet,
+    /// separated by `keys`, with fences taken from the children
+    pub fn new(
+        keys: impl Into<NodeVec<SeparatorKey<K>>>,
+        children: impl IntoIterator<Item = impl Into<NodeBox<K, V>>>,
+    ) -> Self {
+        let mut branch = BranchNode {
+            keys: keys.into(),
+            children: children.into_iter().map(Into::into).collect(),
+            min_key: None,
+            max_key: None,
+        };
+        branch.refresh_fences();
+        branch
+    }
+
+    /// Brings the fences up to date with the first and last children,
+    /// whose own fences must already be up to date. A fence is only cloned
+    /// when it has changed, so refreshing a branch whose extremes are where
+    /// they were costs two equality checks.
+    pub fn refresh_fences(&mut self) {
+        fn refresh<K: Clone + PartialEq>(fence: &mut Option<K>, key: Option<&K>) {
+            if fence.as_ref() != key {
+                *fence = key.cloned();
+            }
+        }
+        let min_key = self.children.first().and_then(|child| child.min_key());
+        refresh(&mut self.min_key, min_key);
+        let max_key = self.children.last().and_then(|child| child.max_key());
+        refresh(&mut self.max_key, max_key);
+    }
+}
+
+impl<K, V> Node<K, V> {
+    /// The smallest key in the subtree, read from a leaf's keys or a
+    /// branch's fence
+    pub fn min_key(&self) -> Option<&K> {
+        match self {
+            Node::Leaf(leaf) => leaf.keys.first(),
+            Node::Branch(branch) => branch.min_key.as_ref(),
+        }
+    }
+
+    /// The largest key in the subtree, read from a leaf's keys or a
+    /// branch's fence
+    pub fn max_key(&self) -> Option<&K> {
+        match self {
+            Node::Leaf(leaf) => leaf.keys.last(),
+            Node::Branch(branch) => branch.max_key.as_ref(),
+        }
+    }
+
+    /// Takes the node out so it can be passed down by value, leaving an
+    /// empty leaf in its place until the caller puts a node back. The box
+    /// the node was in is kept and the empty leaf's Vecs have no capacity,
+    /// so this never allocates.
+    fn take(&mut self) -> Node<K, V> {
+        std::mem::replace(
+            self,
+            Node::Leaf(LeafNode::new(NodeVec::new(), NodeVec::new())),
+        )
+    }
+
+    /// The links of a leaf to its neighbours, or `None` for a branch
+    fn leaf_links(&self) -> Option<LeafLinks<K, V>> {
+        match self {
+            Node::Leaf(leaf) => Some(leaf.links),
+            Node::Branch(_) => None,
+        }
+    }
+}
+
 /// The type of node stored at the root of the tree. This is useful in tests
 /// and for debugging the tree structure.
 #[derive(Debug, PartialEq, Eq)]
@@ -42,13 +338,65 @@ pub enum RootKind {
     Branch,
 }
 
+/// Selects the entry that a removal descends to
+enum RemovalTarget<'a, Q: ?Sized> {
+    /// The entry with the given key
+    Key(&'a Q),
+    /// The entry with the smallest key
+    First,
+    /// The entry with the largest key
+    Last,
+}
+
+/// The outcome of removing from a subtree: what is left of the subtree,
+/// if anything, and the removed entry, if one was found
+type RemovalResult<K, V> = (Option<Node<K, V>>, Option<(K, V)>);
+
+/// The two parts of a subtree cut at a key: the part holding the keys
+/// below it and the part holding the rest, either of which may be empty
+type SplitHalves<K, V> = (Option<Node<K, V>>, Option<Node<K, V>>);
+
+/// A node updated by grafting a tree beneath it, along with the separator
+/// and right half if the node overflowed and had to be split
+type GraftResult<K, V> = (Node<K, V>, Option<(SeparatorKey<K>, Node<K, V>)>);
+
+/// The branching factor of maps created without one being given
+pub(crate) const DEFAULT_BRANCHING_FACTOR: usize = 4;
+
+/// Drops all but the last pair of each run of pairs with the same key in
+/// `entries`, which are sorted by key. The last pair is moved into the
+/// place of the first, so the pairs kept stay in order.
+pub(crate) fn dedup_sorted<K: PartialEq, V>(entries: &mut Vec<(K, V)>) {
+    entries.dedup_by(|later, kept| {
+        let same = later.0 == kept.0;
+        if same {
+            std::mem::swap(later, kept);
+        }
+        same
+    });
+}
+
+/// Splits `total` items into `parts` runs whose lengths differ by at most
+/// one, the longer runs first
+pub(crate) fn even_runs(total: usize, parts: usize) -> impl Iterator<Item = usize> {
+    (0..parts).map(move |i| total / parts + usize::from(i < total % parts))
+}
+
 // Main B+ tree map structure
 pub struct BPlusTreeMap<K, V> {
-    root: Option<Node<K, V>>,
+    root: Option<NodeBox<K, V>>,
     config: Rc<BPlusTreeConfig>,
     size: usize,
-    insertion_balancer: InsertionBalancer,
+    insertion_balancer: InsertionBalancer<K>,
     removal_balancer: RemovalBalancer,
+    /// Source of new nodes: emptied nodes kept by `clear` for later inserts
+    /// to reuse, and the capacity set by `reserve` for nodes created afresh
+    pool: NodePool<K, V>,
+    /// The child indices leading to the leaf the last lookup ended in, so
+    /// lookups of nearby keys can skip the descent. It is updated through
+    /// `&self`; the map shares its config through an `Rc` and so is not
+    /// `Sync` anyway.
+    last_leaf: Cell<Vec<usize>>,
 }
 
 impl<K, V> BPlusTreeMap<K, V>
@@ -58,7 +406,7 @@ where
 {
     /// Creates a new empty BPlusTreeMap with default branching factor of 4
     pub fn new() -> Self {
-        Self::with_branching_factor(4)
+        Self::with_branching_factor(DEFAULT_BRANCHING_FACTOR)
     }
 
     /// Creates a new empty BPlusTreeMap with the specified branching factor
@@ -66,14 +414,137 @@ where
         if branching_factor < 2 {
             panic!("Branching factor must be at least 2");
         }
-        let config = Rc::new(BPlusTreeConfig { branching_factor });
+        let config = Rc::new(BPlusTreeConfig::new(branching_factor));
         BPlusTreeMap {
             root: None,
             config: config.clone(),
             size: 0,
             insertion_balancer: InsertionBalancer::new(config.clone()),
             removal_balancer: RemovalBalancer::new(config.clone()),
+            pool: NodePool::new(),
+            last_leaf: Cell::new(Vec::new()),
+        }
+    }
+
+    /// Creates a new empty BPlusTreeMap with the specified branching factor,
+    /// whose branches are given separators picked by `P` when leaves split,
+    /// such as `ShortestSeparators` for string keys. Trees built bottom-up,
+    /// as by `from_sorted_iter`, are still given copies of keys.
+    pub fn with_separator_policy<P: SeparatorPolicy<K>>(branching_factor: usize) -> Self {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.insertion_balancer = InsertionBalancer::with_separator_policy::<P>(map.config.clone());
+        map
+    }
+
+    /// Creates a new empty BPlusTreeMap with the specified branching factor,
+    /// whose leaves each carry a Bloom filter of `bits_per_key` bits per
+    /// key. `get` and `contains_key` skip searching a leaf whose filter
+    /// rejects the key, so most misses cost no comparisons within the
+    /// leaf, at the price of rebuilding a leaf's filter whenever its keys
+    /// change. About 10 bits per key lets through 1% of misses.
+    #[cfg(feature = "bloom")]
+    pub fn with_leaf_filters(branching_factor: usize, bits_per_key: usize) -> Self
+    where
+        K: Hash,
+    {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.config = Rc::new(BPlusTreeConfig {
+            bloom_bits_per_key: bits_per_key,
+            ..BPlusTreeConfig::new(branching_factor)
+        });
+        map.insertion_balancer = InsertionBalancer::with_leaf_filters(map.config.clone());
+        map.removal_balancer = RemovalBalancer::new(map.config.clone());
+        map
+    }
+
+    /// Creates a new empty BPlusTreeMap with the specified branching factor,
+    /// whose nodes holding fewer than `threshold` keys are searched by
+    /// comparing the key with each of theirs in turn. For small nodes of
+    /// cheaply compared keys a scan can beat binary search, whose branches
+    /// are hard to predict. A threshold of 0, as in maps created any other
+    /// way, binary searches every node.
+    pub fn with_linear_search_threshold(branching_factor: usize, threshold: usize) -> Self {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.config = Rc::new(BPlusTreeConfig {
+            linear_search_threshold: threshold,
+            ..BPlusTreeConfig::new(branching_factor)
+        });
+        map.insertion_balancer = InsertionBalancer::new(map.config.clone());
+        map.removal_balancer = RemovalBalancer::new(map.config.clone());
+        map
+    }
+
+    /// Creates a new empty BPlusTreeMap with the specified branching factor,
+    /// whose nodes of 8 keys or more are searched by halving them with
+    /// conditional moves rather than branches, so the processor has nothing
+    /// to mispredict. With the `simd` feature the last run of up to 32 keys
+    /// is counted through in chunks, which compiles to vector comparisons
+    /// where the target has them. Either way a search finds exactly what
+    /// binary search would, but takes more comparisons, which only pays off
+    /// for keys as cheap to compare as the primitive integers.
+    pub fn with_branchless_search(branching_factor: usize) -> Self
+    where
+        K: SearchableKey,
+    {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.config = Rc::new(BPlusTreeConfig {
+            branchless_search: true,
+            ..BPlusTreeConfig::new(branching_factor)
+        });
+        map.insertion_balancer = InsertionBalancer::new(map.config.clone());
+        map.removal_balancer = RemovalBalancer::new(map.config.clone());
+        map
+    }
+
+    /// Creates a new empty BPlusTreeMap with the specified branching factor,
+    /// sized for about `capacity` entries to be inserted
+    pub fn with_capacity(branching_factor: usize, capacity: usize) -> Self {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.reserve(capacity);
+        map
+    }
+
+    /// Creates a map from key-value pairs in any order, with the default
+    /// branching factor. Where a key is repeated, the last value for it
+    /// wins, as with `from_iter`. Rather than inserting the pairs one by
+    /// one, each descending from the root and splitting nodes as they
+    /// fill, the pairs are sorted and the tree is built bottom-up.
+    pub fn from_unsorted<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut entries: Vec<(K, V)> = iter.into_iter().collect();
+        // The sort is stable, so the last pair of a run with the same key
+        // is the last one given. Sorting input that is already sorted takes
+        // a single pass.
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        dedup_sorted(&mut entries);
+        Self::from_sorted_entries(DEFAULT_BRANCHING_FACTOR, entries)
+    }
+
+    /// Creates a map from pairs already sorted by key, with no key
+    /// repeated, such as those read back from a sorted file. The leaves are
+    /// packed as full as the branching factor allows and the branch levels
+    /// are built bottom-up over them, so no key descends from the root.
+    /// Fails on the first key that does not come after the one before it.
+    pub fn from_sorted_iter<I>(
+        iter: I,
+        branching_factor: usize,
+    ) -> Result<Self, NotAscendingError<K>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let iter = iter.into_iter();
+        let mut entries: Vec<(K, V)> = Vec::with_capacity(iter.size_hint().0);
+        for (key, value) in iter {
+            if let Some((previous, _)) = entries.last()
+                && *previous >= key
+            {
+                return Err(NotAscendingError {
+                    previous: previous.clone(),
+                    key,
+                });
+            }
+            entries.push((key, value));
         }
+        Ok(Self::from_sorted_entries(branching_factor, entries))
     }
 
     /// Creates a BPlusTreeMap with a branch node as root
@@ -86,7 +557,7 @@ where
         if branching_factor < 2 {
             panic!("Branching factor must be at least 2");
         }
-        let config = Rc::new(BPlusTreeConfig { branching_factor });
+        let config = Rc::new(BPlusTreeConfig::new(branching_factor));
 
         // Calculate the size
         let size = left_leaf.keys.len() + right_leaf.keys.len();
@@ -98,18 +569,79 @@ where
         };
 
         // Create the branch node
-        let branch = BranchNode {
-            keys: vec![separator],
-            children: vec![Node::Leaf(left_leaf), Node::Leaf(right_leaf)],
-        };
+        let branch = BranchNode::new(
+            NodeVec::from_iter([SeparatorKey::new(separator)]),
+            vec![Node::Leaf(left_leaf), Node::Leaf(right_leaf)],
+        );
 
         // Create the tree map
-        BPlusTreeMap {
-            root: Some(Node::Branch(branch)),
+        let mut map = BPlusTreeMap {
+            root: Some(NodeBox::new(Node::Branch(branch))),
             config: config.clone(),
             size,
             insertion_balancer: InsertionBalancer::new(config.clone()),
             removal_balancer: RemovalBalancer::new(config.clone()),
+            pool: NodePool::new(),
+            last_leaf: Cell::new(Vec::new()),
+        };
+        map.link_all_leaves();
+        map
+    }
+
+    /// Prepares the map for at least `additional` more entries. The empty
+    /// nodes that many entries need at the least, with every node full,
+    /// are allocated up front and kept for the inserts to use. From then
+    /// on every node the map creates has room for a full node too, so
+    /// leaves and branches are not reallocated as they fill towards the
+    /// branching factor.
+    pub fn reserve(&mut self, additional: usize) {
+        if additional == 0 {
+            return;
+        }
+        // A node briefly holds one key more than the branching factor
+        // before it is split
+        let branching_factor = self.config.branching_factor;
+        self.pool.set_node_capacity(branching_factor + 1);
+
+        // Nodes split into halves might need up to twice as many, which
+        // are created as they are needed
+        let leaves = additional.div_ceil(branching_factor);
+        let mut branches = 0;
+        let mut level = leaves;
+        while level > 1 {
+            level = level.div_ceil(branching_factor + 1);
+            branches += level;
+        }
+        self.pool.reserve(leaves, branches);
+    }
+
+    /// Gives back the spare capacity of every node, and drops the emptied
+    /// nodes `clear` kept for reuse. Nodes keep the room they grew to as
+    /// entries come and go, so a map that has seen many inserts and
+    /// removals can hold a lot of it. Only the capacities change; the
+    /// entries and the shape of the tree stay as they are.
+    pub fn shrink_to_fit(&mut self) {
+        if let Some(root) = self.root.as_deref_mut() {
+            Self::shrink_node(root);
+        }
+        self.pool.shrink_to_fit();
+        self.last_leaf.get_mut().shrink_to_fit();
+    }
+
+    /// Shrinks the Vecs of every node under `node` to fit their contents
+    fn shrink_node(node: &mut Node<K, V>) {
+        match node {
+            Node::Leaf(leaf) => {
+                leaf.keys.shrink_to_fit();
+                leaf.values.shrink_to_fit();
+            }
+            Node::Branch(branch) => {
+                branch.keys.shrink_to_fit();
+                branch.children.shrink_to_fit();
+                for child in &mut branch.children {
+                    Self::shrink_node(child);
+                }
+            }
         }
     }
 
@@ -123,10 +655,21 @@ where
         self.size == 0
     }
 
+    /// Removes all entries from the map. The emptied nodes are kept, up to
+    /// one tree's worth, so that refilling the map can reuse their
+    /// allocations instead of making new ones.
+    pub fn clear(&mut self) {
+        if let Some(root) = self.root.take() {
+            self.pool.recycle_tree(root.into_inner());
+        }
+        self.size = 0;
+        self.last_leaf.get_mut().clear();
+    }
+
     /// Returns the type of node stored at the root of the tree. This is mainly
     /// for testing and debugging purposes.
     pub fn root_kind(&self) -> RootKind {
-        match &self.root {
+        match self.root.as_deref() {
             None => RootKind::Empty,
             Some(Node::Leaf(_)) => RootKind::Leaf,
             Some(Node::Branch(_)) => RootKind::Branch,
@@ -136,22 +679,71 @@ where
     /// Inserts a key-value pair into the map
     /// Returns the old value if the key already existed
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        match self.root.take() {
+        self.insert_entry(key, value, true)
+    }
+
+    /// Tries to insert a key-value pair into the map, and returns a mutable
+    /// reference to the value in the map. If the map already had this key
+    /// present, nothing is updated, and an error containing the occupied
+    /// entry and the value is returned.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, OccupiedError<'_, K, V>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
+        }
+    }
+
+    /// Inserts a key-value pair in a single pass from the root. If the key
+    /// already exists, its value is replaced when `overwrite` is set and left
+    /// alone otherwise. Returns whichever value did not end up in the map.
+    fn insert_entry(&mut self, key: K, value: V, overwrite: bool) -> Option<V> {
+        // A key past the end of the map, as when keys arrive in ascending
+        // order, goes straight onto the rightmost leaf. The last key is
+        // read from the tree each time, so removals can't leave it stale.
+        if let Some((last, _)) = self.last_key_value()
+            && *last < key
+        {
+            self.push_last(key, value);
+            return None;
+        }
+
+        match self.root.as_deref_mut() {
             None => {
                 // Create a new leaf node for the first insertion
-                let leaf = LeafNode {
-                    keys: vec![key],
-                    values: vec![value],
-                };
-                self.root = Some(Node::Leaf(leaf));
+                let mut leaf = self.pool.take_leaf();
+                leaf.keys.push(key);
+                leaf.values.push(value);
+                self.insertion_balancer.filter_leaf(&mut leaf);
+                self.root = Some(NodeBox::new(Node::Leaf(leaf)));
                 self.size = 1;
                 None
             }
             Some(root) => {
                 // Handle insertion into an existing tree
-                let (new_root, old_value) =
-                    Self::insert_recursive(root, key, value, &self.insertion_balancer);
-                self.root = Some(new_root);
+                let (result, old_value) = Self::insert_recursive(
+                    root.take(),
+                    key,
+                    value,
+                    overwrite,
+                    &self.insertion_balancer,
+                    &mut self.pool,
+                );
+                let new_root = match result {
+                    // The root split, so the tree grows a level
+                    BalanceResult::Split {
+                        left,
+                        right,
+                        separator,
+                    } => self.insertion_balancer.join_split_pooled(
+                        left,
+                        right,
+                        separator,
+                        &mut self.pool,
+                    ),
+                    BalanceResult::NoChange(node) => node,
+                    _ => panic!("Unexpected balance result for insertion"),
+                };
+                *root = new_root;
 
                 // Update size if this is a new key
                 if old_value.is_none() {
@@ -163,110 +755,365 @@ where
         }
     }
 
-    /// Recursive helper for insertion
+    /// Appends an entry whose key is greater than every key in the map,
+    /// which must have a leaf at the end of its right spine. The entry
+    /// goes down the right spine without comparing keys, and nodes that
+    /// overfill on the way back up are split by the balancer as usual.
+    fn push_last(&mut self, key: K, value: V) {
+        let root = self
+            .root
+            .as_deref_mut()
+            .expect("push_last needs a non-empty tree");
+        *root = match Self::push_last_recursive(
+            root.take(),
+            key,
+            value,
+            &self.insertion_balancer,
+            &mut self.pool,
+        ) {
+            BalanceResult::Split {
+                left,
+                right,
+                separator,
+            } => self
+                .insertion_balancer
+                .join_split_pooled(left, right, separator, &mut self.pool),
+            BalanceResult::NoChange(node) => node,
+            _ => panic!("Unexpected balance result for insertion"),
+        };
+        self.size += 1;
+    }
+
+    /// Recursive helper for `push_last`. A split is handed back to the
+    /// caller, whose node takes both halves in place of the one it passed.
+    fn push_last_recursive(
+        node: Node<K, V>,
+        key: K,
+        value: V,
+        balancer: &InsertionBalancer<K>,
+        pool: &mut NodePool<K, V>,
+    ) -> BalanceResult<K, V> {
+        match node {
+            Node::Leaf(mut leaf) => {
+                leaf.keys.push(key);
+                leaf.values.push(value);
+                leaf.refresh_filter();
+                balancer.balance_node_pooled(Node::Leaf(leaf), pool)
+            }
+            Node::Branch(mut branch) => {
+                let idx = branch.children.len() - 1;
+                let last = branch.take_child(idx);
+                let result = Self::push_last_recursive(last, key, value, balancer, pool);
+                Self::reattach_child(&mut branch, idx, result);
+                balancer.balance_node_pooled(Node::Branch(branch), pool)
+            }
+        }
+    }
+
+    /// Puts a child that was taken out of `branch` at `idx` back in its
+    /// box, or both of its halves if it was split
+    fn reattach_child(branch: &mut BranchNode<K, V>, idx: usize, result: BalanceResult<K, V>) {
+        match result {
+            BalanceResult::Split {
+                left,
+                right,
+                separator,
+            } => {
+                // The halves of a split leaf take its place between its
+                // neighbours
+                let links = left.leaf_links();
+                branch.keys.insert(idx, separator);
+                *branch.children[idx] = left;
+                branch.children.insert(idx + 1, NodeBox::new(right));
+                if let Some(links) = links {
+                    branch.relink_children(idx..idx + 2, links);
+                }
+            }
+            BalanceResult::NoChange(node) => *branch.children[idx] = node,
+            _ => panic!("Unexpected balance result for insertion"),
+        }
+        branch.refresh_fences();
+    }
+
+    /// Inserts a key-value pair starting from the leaf `hint` points to,
+    /// like an insert placed just after the previous one. Where the key
+    /// does not belong in that leaf, because the hint is wrong or the tree
+    /// has changed since, it is inserted from the root as usual; a hint
+    /// never makes the insert go wrong, only slower. Afterwards `hint`
+    /// points to the leaf now holding the key, ready for the next insert.
+    /// Returns the old value if the key already existed.
+    pub fn insert_hint(&mut self, hint: &mut InsertHint, key: K, value: V) -> Option<V> {
+        // Keys arriving in order soon pass the end of the hinted leaf, so
+        // the leaf after it is tried before searching from the root
+        let fits = self.hint_fits(&hint.path, &key)
+            || (self.step_to_next_leaf(&mut hint.path) && self.hint_fits(&hint.path, &key));
+        if !fits && !self.find_path(&key, &mut hint.path) {
+            // The tree is not in a shape a path can lead through
+            hint.path.clear();
+            return self.insert(key, value);
+        }
+
+        self.insert_at(&mut hint.path, key, value).0
+    }
+
+    /// Inserts a key-value pair into the leaf `path` leads to, which must
+    /// be the leaf a search for `key` reaches. Afterwards `path` leads to
+    /// the leaf holding the key, wherever splits have moved it. Returns the
+    /// old value if the key already existed, and the key's position in its
+    /// leaf.
+    fn insert_at(&mut self, path: &mut Vec<usize>, key: K, value: V) -> (Option<V>, usize) {
+        let Some(root) = self.root.as_deref_mut() else {
+            let mut leaf = self.pool.take_leaf();
+            leaf.keys.push(key);
+            leaf.values.push(value);
+            self.insertion_balancer.filter_leaf(&mut leaf);
+            self.root = Some(NodeBox::new(Node::Leaf(leaf)));
+            self.size = 1;
+            path.clear();
+            return (None, 0);
+        };
+
+        let (result, old_value, in_right, slot) = Self::insert_on_path(
+            root.take(),
+            path,
+            0,
+            key,
+            value,
+            &self.insertion_balancer,
+            &mut self.pool,
+        );
+        *root = match result {
+            BalanceResult::Split {
+                left,
+                right,
+                separator,
+            } => {
+                path.insert(0, usize::from(in_right));
+                self.insertion_balancer
+                    .join_split_pooled(left, right, separator, &mut self.pool)
+            }
+            BalanceResult::NoChange(node) => node,
+            _ => panic!("Unexpected balance result for insertion"),
+        };
+        if old_value.is_none() {
+            self.size += 1;
+        }
+        (old_value, slot)
+    }
+
+    /// Whether `path` leads from the root to a leaf whose separators bound
+    /// `key`, so that an insert of `key` belongs in that leaf
+    fn hint_fits(&self, path: &[usize], key: &K) -> bool {
+        let Some(mut node) = self.root.as_deref() else {
+            return path.is_empty();
+        };
+        let mut lower = None;
+        let mut upper = None;
+        for &idx in path {
+            let Node::Branch(branch) = node else {
+                return false;
+            };
+            let Some(child) = branch.children.get(idx) else {
+                return false;
+            };
+            // The separators nearest the leaf bound it most tightly
+            if idx > 0 {
+                lower = branch.keys.get(idx - 1);
+            }
+            if idx < branch.keys.len() {
+                upper = branch.keys.get(idx);
+            }
+            node = child;
+        }
+        matches!(node, Node::Leaf(_))
+            && lower.is_none_or(|lower| **lower <= *key)
+            && upper.is_none_or(|upper| *key < **upper)
+    }
+
+    /// Moves `path` on to the leaf after the one it leads to. Returns false,
+    /// leaving `path` in an unknown state, if there is no such leaf or
+    /// `path` does not lead to a leaf.
+    fn step_to_next_leaf(&self, path: &mut Vec<usize>) -> bool {
+        // Find the deepest branch on the path with a child after the one
+        // the path takes, then the first leaf under that child
+        let Some(mut node) = self.root.as_deref() else {
+            return false;
+        };
+        let mut turn = None;
+        for (depth, &idx) in path.iter().enumerate() {
+            let Node::Branch(branch) = node else {
+                return false;
+            };
+            let Some(child) = branch.children.get(idx) else {
+                return false;
+            };
+            if idx + 1 < branch.children.len() {
+                turn = Some((depth, &*branch.children[idx + 1]));
+            }
+            node = child;
+        }
+        let Some((depth, mut node)) = turn else {
+            return false;
+        };
+        path[depth] += 1;
+        path.truncate(depth + 1);
+        while let Node::Branch(branch) = node {
+            let Some(child) = branch.children.first() else {
+                return false;
+            };
+            path.push(0);
+            node = child;
+        }
+        true
+    }
+
+    /// Fills `path` with the child indices a search for `key` follows from
+    /// the root, and returns whether they lead to a leaf
+    fn find_path(&self, key: &K, path: &mut Vec<usize>) -> bool {
+        path.clear();
+        let Some(mut node) = self.root.as_deref() else {
+            return true;
+        };
+        while let Node::Branch(branch) = node {
+            let idx = branch.child_index_in(key, &self.config);
+            let Some(child) = branch.children.get(idx) else {
+                return false;
+            };
+            path.push(idx);
+            node = child;
+        }
+        true
+    }
+
+    /// Recursive helper for `insert_at`: inserts into the leaf that `path`
+    /// leads to from `node`, which is `depth` levels down. On the way back
+    /// up, `path` is updated to lead to the key wherever splits have moved
+    /// it. Also returns the old value, if any, whether the key ended up in
+    /// the right half of `node` when it was split, and the key's position
+    /// in its leaf.
+    fn insert_on_path(
+        node: Node<K, V>,
+        path: &mut [usize],
+        depth: usize,
+        key: K,
+        value: V,
+        balancer: &InsertionBalancer<K>,
+        pool: &mut NodePool<K, V>,
+    ) -> (BalanceResult<K, V>, Option<V>, bool, usize) {
+        match node {
+            Node::Leaf(mut leaf) => match search_keys_in(&leaf.keys, &key, balancer.config()) {
+                Ok(idx) => {
+                    let old_value = std::mem::replace(&mut leaf.values[idx], value);
+                    (
+                        BalanceResult::NoChange(Node::Leaf(leaf)),
+                        Some(old_value),
+                        false,
+                        idx,
+                    )
+                }
+                Err(idx) => {
+                    leaf.keys.insert(idx, key);
+                    leaf.values.insert(idx, value);
+                    leaf.refresh_filter();
+                    let result = balancer.balance_node_pooled(Node::Leaf(leaf), pool);
+                    match &result {
+                        BalanceResult::Split {
+                            left: Node::Leaf(left),
+                            ..
+                        } if idx >= left.keys.len() => {
+                            let slot = idx - left.keys.len();
+                            (result, None, true, slot)
+                        }
+                        _ => (result, None, false, idx),
+                    }
+                }
+            },
+            Node::Branch(mut branch) => {
+                let idx = path[depth];
+                let child = branch.take_child(idx);
+                let (result, old_value, child_in_right, slot) =
+                    Self::insert_on_path(child, path, depth + 1, key, value, balancer, pool);
+                Self::reattach_child(&mut branch, idx, result);
+                let idx = idx + usize::from(child_in_right);
+
+                let result = balancer.balance_node_pooled(Node::Branch(branch), pool);
+                let in_right = match &result {
+                    BalanceResult::Split {
+                        left: Node::Branch(left),
+                        ..
+                    } if idx >= left.children.len() => {
+                        path[depth] = idx - left.children.len();
+                        true
+                    }
+                    _ => {
+                        path[depth] = idx;
+                        false
+                    }
+                };
+                (result, old_value, in_right, slot)
+            }
+        }
+    }
+
+    /// Recursive helper for insertion. A split is handed back to the
+    /// caller, whose node takes both halves in place of the one it passed,
+    /// so nothing below the split is copied.
     fn insert_recursive(
         node: Node<K, V>,
         key: K,
         value: V,
-        balancer: &InsertionBalancer,
-    ) -> (Node<K, V>, Option<V>) {
+        overwrite: bool,
+        balancer: &InsertionBalancer<K>,
+        pool: &mut NodePool<K, V>,
+    ) -> (BalanceResult<K, V>, Option<V>) {
         match node {
             Node::Leaf(mut leaf) => {
                 // Find the position to insert the key
-                match leaf.keys.binary_search(&key) {
+                match search_keys_in(&leaf.keys, &key, balancer.config()) {
+                    Ok(_) if !overwrite => {
+                        // Key already exists and must be kept, hand the value back
+                        (BalanceResult::NoChange(Node::Leaf(leaf)), Some(value))
+                    }
                     Ok(idx) => {
                         // Key already exists, replace the value
                         let old_value = std::mem::replace(&mut leaf.values[idx], value);
-                        (Node::Leaf(leaf), Some(old_value))
+                        (BalanceResult::NoChange(Node::Leaf(leaf)), Some(old_value))
                     }
                     Err(idx) => {
                         // Key doesn't exist, insert it
                         leaf.keys.insert(idx, key);
                         leaf.values.insert(idx, value);
+                        leaf.refresh_filter();
 
                         // Use the balancer to check if the node needs to be split
-                        match balancer.balance_node(Node::Leaf(leaf)) {
-                            BalanceResult::Split {
-                                left,
-                                right,
-                                separator,
-                            } => {
-                                // Create a branch node with the separator key and the two nodes
-                                let branch = BranchNode {
-                                    keys: vec![separator],
-                                    children: vec![left, right],
-                                };
-
-                                (Node::Branch(branch), None)
-                            }
-                            BalanceResult::NoChange(node) => (node, None),
-                            _ => panic!("Unexpected balance result for insertion"),
-                        }
+                        (balancer.balance_node_pooled(Node::Leaf(leaf), pool), None)
                     }
                 }
             }
             Node::Branch(mut branch) => {
                 // Find the child node to insert into
-                let idx = match branch.keys.binary_search(&key) {
-                    Ok(idx) => idx + 1, // If key exists, go to the right child
-                    Err(idx) => idx,    // Otherwise, go to the appropriate child
-                };
-
-                // Check if the index is valid
-                if idx >= branch.children.len() {
-                    // This can happen if we're trying to insert a key that's greater than all existing keys
-                    // In this case, we need to add a new child node
-                    branch.children.push(Node::Leaf(Self::create_empty_leaf()));
-                }
-
-                // Take the child node out
-                let child = std::mem::replace(
-                    &mut branch.children[idx],
-                    Node::Leaf(Self::create_empty_leaf()),
+                let idx = branch.child_index_in(&key, balancer.config());
+
+                // There is a child on either side of every separator, so a
+                // key past the last one still has a child to go to
+                debug_assert_eq!(
+                    branch.children.len(),
+                    branch.keys.len() + 1,
+                    "branch has a child for each side of its separators"
                 );
 
-                // Recursively insert into the child node
-                let (new_child, old_value) = Self::insert_recursive(child, key, value, balancer);
-
-                // Put the child back
-                branch.children[idx] = new_child;
-
-                // Check if the child was split and we need to update the branch
-                if let Node::Branch(new_branch) = &branch.children[idx] {
-                    // If the child is now a branch node, it means it was split
-                    // We need to extract the middle key and add the new child
-                    if new_branch.keys.len() == 1 && new_branch.children.len() == 2 {
-                        // Extract the middle key and the right child
-                        let middle_key = new_branch.keys[0].clone();
-                        let right_child = new_branch.children[1].clone();
-
-                        // Replace the child with its left child
-                        branch.children[idx] = new_branch.children[0].clone();
-
-                        // Insert the middle key and the right child into the branch
-                        branch.keys.insert(idx, middle_key);
-                        branch.children.insert(idx + 1, right_child);
-                    }
-                }
+                // Recursively insert into the child node, then put it back,
+                // or both of its halves if it was split
+                let child = branch.take_child(idx);
+                let (result, old_value) =
+                    Self::insert_recursive(child, key, value, overwrite, balancer, pool);
+                Self::reattach_child(&mut branch, idx, result);
 
                 // Use the balancer to check if the branch node needs to be split
-                match balancer.balance_node(Node::Branch(branch)) {
-                    BalanceResult::Split {
-                        left,
-                        right,
-                        separator,
-                    } => {
-                        // Create a new branch node with the separator key and the two branch nodes
-                        let new_branch = BranchNode {
-                            keys: vec![separator],
-                            children: vec![left, right],
-                        };
-
-                        (Node::Branch(new_branch), old_value)
-                    }
-                    BalanceResult::NoChange(node) => (node, old_value),
-                    _ => panic!("Unexpected balance result for insertion"),
-                }
+                (
+                    balancer.balance_node_pooled(Node::Branch(branch), pool),
+                    old_value,
+                )
             }
         }
     }
@@ -275,26 +1122,170 @@ where
     pub fn get<Q>(&self, key: &Q) -> Option<&V>
     where
         K: Borrow<Q>,
-        Q: Ord + ?Sized,
+        Q: Ord + FilterKey + ?Sized,
     {
         // Use the find_leaf_for_key helper to locate the leaf node that might contain the key
-        if let Some((leaf, _)) = self.find_leaf_for_key(key) {
-            // Search for the key in the leaf node
-            for (i, k) in leaf.keys.iter().enumerate() {
-                if k.borrow() == key {
-                    return Some(&leaf.values[i]);
-                }
+        let (leaf, _) = self.find_leaf_for_key(key)?;
+        // A leaf with a filter needn't be searched for most keys it lacks
+        if leaf.filter_rejects(key) {
+            return None;
+        }
+        // Leaf keys are sorted, so the key is found by searching them
+        let idx = search_keys_in(&leaf.keys, key, &self.config).ok()?;
+        Some(&leaf.values[idx])
+    }
+
+    /// Gets a reference to the key stored in the map that is equal to the
+    /// given key. The reference points at the key held in the leaf, not at
+    /// a copy of it.
+    pub fn get_key<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (leaf, _) = self.find_leaf_for_key(key)?;
+        let idx = search_keys_in(&leaf.keys, key, &self.config).ok()?;
+        Some(&leaf.keys[idx])
+    }
+
+    /// Replaces the stored key that is equal to the given key, leaving its
+    /// value in place, and returns the old key. Returns None without
+    /// inserting anything if no equal key is stored.
+    ///
+    /// Branch separators may still hold copies of the old key. They are only
+    /// compared against when routing a lookup, and the new key compares
+    /// equal to the old one, so they route every key exactly as before.
+    pub fn replace_key(&mut self, key: K) -> Option<K> {
+        let config = Rc::clone(&self.config);
+        let leaf = self.find_leaf_for_key_mut(&key)?;
+        let idx = search_keys_in(&leaf.keys, &key, &config).ok()?;
+        Some(std::mem::replace(&mut leaf.keys[idx], key))
+    }
+
+    /// Gets a mutable reference to the value associated with the key
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_value_mut(key)
+    }
+
+    /// Applies `f` to the value associated with the key, if there is one.
+    /// Returns true if the key was found. The leaf is reached with a single
+    /// descent, and `f` is not called when the key is absent.
+    pub fn update<Q, F>(&mut self, key: &Q, f: F) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        F: FnOnce(&mut V),
+    {
+        match self.get_mut(key) {
+            Some(value) => {
+                f(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gets mutable references to the values of several keys at once.
+    /// Returns None if any of the keys is missing. The keys are sorted and
+    /// found together in one descent from the root, which hands each
+    /// subtree the keys that fall in it.
+    ///
+    /// Panics if the same key is requested more than once.
+    pub fn get_many_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> Option<[&mut V; N]>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        for (i, key) in keys.iter().enumerate() {
+            if keys[..i].contains(key) {
+                panic!("duplicate keys found");
             }
         }
 
-        None
+        let root = self.root.as_deref_mut()?;
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        order.sort_unstable_by(|&a, &b| keys[a].cmp(keys[b]));
+        let mut values = [const { None }; N];
+        Self::collect_values_mut(root, &keys, &order, &mut values, &self.config)?;
+        Some(values.map(|value| value.expect("every key was found")))
+    }
+
+    /// Finds the values of the keys picked by `order`, which are in
+    /// ascending order and all fall in the subtree at `node`, and stores
+    /// each in `values` at the key's position. Returns None if a key is
+    /// missing. Each subtree is borrowed once, so the values handed out
+    /// never alias.
+    fn collect_values_mut<'a, Q>(
+        node: &'a mut Node<K, V>,
+        keys: &[&Q],
+        order: &[usize],
+        values: &mut [Option<&'a mut V>],
+        config: &BPlusTreeConfig,
+    ) -> Option<()>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match node {
+            Node::Leaf(leaf) => {
+                // The keys are in order, so their values come in order too
+                let LeafNode {
+                    keys: leaf_keys,
+                    values: leaf_values,
+                    ..
+                } = leaf;
+                let mut rest = &mut leaf_values[..];
+                let mut offset = 0;
+                for &i in order {
+                    let idx = search_keys_in(leaf_keys, keys[i], config).ok()?;
+                    let (value, tail) =
+                        std::mem::take(&mut rest)[idx - offset..].split_first_mut()?;
+                    values[i] = Some(value);
+                    rest = tail;
+                    offset = idx + 1;
+                }
+                Some(())
+            }
+            Node::Branch(branch) => {
+                let BranchNode {
+                    keys: separators,
+                    children,
+                    ..
+                } = branch;
+                let child_index = |i: usize| {
+                    BranchNode::<K, V>::child_index_among(
+                        separators,
+                        keys[i],
+                        config.linear_search_threshold,
+                        config.branchless_search,
+                    )
+                };
+                let mut rest = order;
+                let mut remaining = children.iter_mut();
+                let mut next = 0;
+                while let Some(&first) = rest.first() {
+                    // The keys for one child are next to each other
+                    let idx = child_index(first);
+                    let run = rest.iter().take_while(|&&i| child_index(i) == idx).count();
+                    let child = remaining.nth(idx - next)?;
+                    next = idx + 1;
+                    Self::collect_values_mut(child, keys, &rest[..run], values, config)?;
+                    rest = &rest[run..];
+                }
+                Some(())
+            }
+        }
     }
 
     /// Checks if a key exists in the map
     pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
         K: Borrow<Q>,
-        Q: Ord + ?Sized,
+        Q: Ord + FilterKey + ?Sized,
     {
         self.get(key).is_some()
     }
@@ -306,19 +1297,60 @@ where
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        match self.root.take() {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key-value pair from the map
+    /// Returns the stored key and its value if the key was present in the map
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_target(RemovalTarget::Key(key))
+    }
+
+    /// Removes and returns the entry with the smallest key, if any
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.remove_target(RemovalTarget::<K>::First)
+    }
+
+    /// Removes and returns the entry with the largest key, if any
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.remove_target(RemovalTarget::<K>::Last)
+    }
+
+    /// Removes the entry selected by `target` in a single pass from the root
+    fn remove_target<Q>(&mut self, target: RemovalTarget<'_, Q>) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.root.as_deref_mut() {
             None => None,
             Some(root) => {
-                let (new_root, removed_value) =
-                    Self::remove_recursive(root, key, &self.removal_balancer);
-                self.root = new_root;
+                let (new_root, removed) =
+                    Self::remove_recursive(root.take(), &target, &self.removal_balancer);
+                match new_root {
+                    // A branch root that lost its last child holds nothing,
+                    // however many levels emptied out beneath it
+                    Some(Node::Branch(branch)) if branch.children.is_empty() => self.root = None,
+                    // A root left with a single child is replaced by it, so
+                    // the tree gets shorter as it empties
+                    Some(new_root) => *root = Self::collapse_root(new_root),
+                    None => self.root = None,
+                }
+                debug_assert!(
+                    !matches!(self.root.as_deref(), Some(Node::Branch(root)) if root.children.is_empty()),
+                    "a branch root always has children"
+                );
 
-                // Update size if a key was removed
-                if removed_value.is_some() {
+                // Update size if an entry was removed
+                if removed.is_some() {
                     self.size -= 1;
                 }
 
-                removed_value
+                removed
             }
         }
     }
@@ -326,36 +1358,37 @@ where
     /// Recursive helper for remove
     fn remove_recursive<Q>(
         node: Node<K, V>,
-        key: &Q,
+        target: &RemovalTarget<'_, Q>,
         balancer: &RemovalBalancer,
-    ) -> (Option<Node<K, V>>, Option<V>)
+    ) -> RemovalResult<K, V>
     where
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
         match node {
             Node::Leaf(mut leaf) => {
-                // Find the position of the key
-                let mut found_idx = None;
-                for (i, k) in leaf.keys.iter().enumerate() {
-                    if k.borrow() == key {
-                        found_idx = Some(i);
-                        break;
+                // Find the position of the entry
+                let found_idx = match target {
+                    RemovalTarget::Key(key) => {
+                        search_keys_in(&leaf.keys, *key, balancer.config()).ok()
                     }
-                }
+                    RemovalTarget::First => (!leaf.keys.is_empty()).then_some(0),
+                    RemovalTarget::Last => leaf.keys.len().checked_sub(1),
+                };
 
-                // If the key is found, remove it
+                // If the entry is found, remove it
                 if let Some(idx) = found_idx {
-                    let _removed_key = leaf.keys.remove(idx);
+                    let removed_key = leaf.keys.remove(idx);
                     let removed_value = leaf.values.remove(idx);
+                    leaf.refresh_filter();
 
                     // If the leaf is now empty, return None for the node
                     if leaf.keys.is_empty() {
-                        return (None, Some(removed_value));
+                        return (None, Some((removed_key, removed_value)));
                     }
 
                     // Otherwise, return the updated leaf
-                    return (Some(Node::Leaf(leaf)), Some(removed_value));
+                    return (Some(Node::Leaf(leaf)), Some((removed_key, removed_value)));
                 }
 
                 // Key not found
@@ -363,84 +1396,74 @@ where
             }
             Node::Branch(mut branch) => {
                 // Find the child node to remove from
-                let mut idx = 0;
-                for (i, k) in branch.keys.iter().enumerate() {
-                    if key.cmp(k.borrow()) == Ordering::Less {
-                        break;
-                    }
-                    idx = i + 1;
-                }
+                let idx = match target {
+                    RemovalTarget::Key(key) => branch.child_index_in(key, balancer.config()),
+                    RemovalTarget::First => 0,
+                    RemovalTarget::Last => branch.children.len().saturating_sub(1),
+                };
 
                 // Check if the index is valid
                 if idx < branch.children.len() {
-                    // Take the child node out
-                    let child = std::mem::replace(
-                        &mut branch.children[idx],
-                        Node::Leaf(Self::create_empty_leaf()),
-                    );
+                    // Take the child node out, noting the neighbours of a
+                    // leaf in case it is emptied
+                    let links = branch.children[idx].leaf_links();
+                    let child = branch.take_child(idx);
 
                     // Recursively remove from the child node
-                    let (new_child, removed_value) = Self::remove_recursive(child, key, balancer);
+                    let (new_child, removed) = Self::remove_recursive(child, target, balancer);
+
+                    // A branch child that lost its last child is as empty as a leaf
+                    // that lost its last key, and is removed the same way
+                    let new_child = match new_child {
+                        Some(Node::Branch(child)) if child.children.is_empty() => None,
+                        new_child => new_child,
+                    };
 
                     // Update the branch node
                     if let Some(child) = new_child {
-                        branch.children[idx] = child;
+                        *branch.children[idx] = child;
+                        // A separator equal to the removed key was the first
+                        // key of this child, and is replaced by its new first
+                        // key before any balancing moves it around
+                        if idx > 0
+                            && let Some((key, _)) = &removed
+                            && *branch.keys[idx - 1] == *key
+                            && let Some(first) = branch.children[idx].min_key()
+                        {
+                            branch.keys[idx - 1] = SeparatorKey::new(first.clone());
+                        }
                     } else {
-                        // Child node is now empty, remove it
+                        // Child node is now empty, remove it along with the
+                        // separator on its left, or on its right if it was
+                        // the first child. A lone child has neither.
                         branch.children.remove(idx);
-                        if idx > 0 {
-                            branch.keys.remove(idx - 1);
-                        } else if !branch.keys.is_empty() {
-                            branch.keys.remove(0);
+                        if let Some(links) = links {
+                            branch.relink_children(idx..idx, links);
+                        }
+                        if !branch.keys.is_empty() {
+                            branch.keys.remove(idx.saturating_sub(1));
                         }
+                        debug_assert!(
+                            branch.children.is_empty()
+                                || branch.keys.len() + 1 == branch.children.len(),
+                            "branch with {} keys left with {} children",
+                            branch.keys.len(),
+                            branch.children.len()
+                        );
                     }
 
                     // Check if we need to balance adjacent nodes
                     if idx > 0 && idx < branch.children.len() {
-                        let left_child = std::mem::replace(
-                            &mut branch.children[idx - 1],
-                            Node::Leaf(Self::create_empty_leaf()),
-                        );
-                        let right_child = std::mem::replace(
-                            &mut branch.children[idx],
-                            Node::Leaf(Self::create_empty_leaf()),
-                        );
-                        let separator = branch.keys[idx - 1].clone();
-
-                        // Clone the right child for potential use later
-                        let right_child_clone = right_child.clone();
-
-                        // Balance the nodes
-                        match balancer.balance_nodes(left_child, right_child, separator) {
-                            BalanceResult::Merged(merged_node) => {
-                                // Replace the left child with the merged node
-                                branch.children[idx - 1] = merged_node;
-                                // Remove the right child and the separator
-                                branch.children.remove(idx);
-                                branch.keys.remove(idx - 1);
-                            }
-                            BalanceResult::Rebalanced {
-                                left,
-                                right,
-                                separator,
-                            } => {
-                                // Update the children and separator
-                                branch.children[idx - 1] = left;
-                                branch.children[idx] = right;
-                                branch.keys[idx - 1] = separator;
-                            }
-                            BalanceResult::NoChange(node) => {
-                                // Put the left child back
-                                branch.children[idx - 1] = node;
-                                // We need to put the right child back too
-                                branch.children[idx] = right_child_clone;
-                            }
-                            _ => panic!("Unexpected balance result for removal"),
-                        }
+                        Self::balance_children(&mut branch, idx, balancer, true);
+                    } else if idx == 0 {
+                        // The leftmost child has no left sibling, so it is
+                        // balanced against its right one instead
+                        Self::balance_first_child(&mut branch, balancer, true);
                     }
+                    branch.refresh_fences();
 
-                    // Return the updated branch and removed value
-                    return (Some(Node::Branch(branch)), removed_value);
+                    // Return the updated branch and removed entry
+                    return (Some(Node::Branch(branch)), removed);
                 }
 
                 // Key not found
@@ -448,537 +1471,3878 @@ where
             }
         }
     }
-}
 
-impl<K, V> FromIterator<(K, V)> for BPlusTreeMap<K, V>
-where
-    K: Ord + Clone + Debug,
-    V: Clone + Debug,
-{
-    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
-        let mut map = BPlusTreeMap::new();
-        for (k, v) in iter {
-            map.insert(k, v);
+    /// Balances the child at `idx` against its left sibling, merging the two
+    /// or moving keys between them as the balancer decides.
+    /// Returns true if the two children were merged into one.
+    ///
+    /// `linked` says whether the leaves under `branch` are linked to their
+    /// neighbours, as they are outside the bulk operations that relink the
+    /// tree once they are done. If they are, leaves that are merged stay
+    /// linked.
+    fn balance_children(
+        branch: &mut BranchNode<K, V>,
+        idx: usize,
+        balancer: &RemovalBalancer,
+        linked: bool,
+    ) -> bool {
+        let links = if linked {
+            branch.outer_links(idx - 1..idx + 1)
+        } else {
+            None
+        };
+        let left_child = branch.take_child(idx - 1);
+        let right_child = branch.take_child(idx);
+        let separator = branch.keys[idx - 1].clone();
+
+        // Balance the nodes. Both are handed back whatever happens, so
+        // neither needs to be kept aside in case they aren't.
+        let merged = match balancer.balance_nodes(left_child, right_child, separator) {
+            BalanceResult::Merged(merged_node) => {
+                // Replace the left child with the merged node
+                *branch.children[idx - 1] = merged_node;
+                // Remove the right child and the separator
+                branch.children.remove(idx);
+                branch.keys.remove(idx - 1);
+                true
+            }
+            BalanceResult::Rebalanced {
+                left,
+                right,
+                separator,
+            } => {
+                // Update the children and separator
+                *branch.children[idx - 1] = left;
+                *branch.children[idx] = right;
+                branch.keys[idx - 1] = separator;
+                false
+            }
+            BalanceResult::NoChangeToPair { left, right } => {
+                // Put the children back where they were
+                *branch.children[idx - 1] = left;
+                *branch.children[idx] = right;
+                false
+            }
+            _ => panic!("Unexpected balance result for removal"),
+        };
+        if let Some(links) = links {
+            let end = if merged { idx } else { idx + 1 };
+            branch.relink_children(idx - 1..end, links);
         }
-        map
+        merged
     }
-}
 
-impl<K, V> Extend<(K, V)> for BPlusTreeMap<K, V>
-where
-    K: Ord + Clone + Debug,
-    V: Clone + Debug,
-{
-    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
-        for (k, v) in iter {
-            self.insert(k, v);
+    /// Retains only the entries for which the predicate returns true.
+    /// The predicate sees every entry in ascending key order and may modify
+    /// the values it is given. Underfull nodes left behind are merged or
+    /// rebalanced with their siblings afterwards.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        if let Some(root) = self.root.take() {
+            let (new_root, removed) = Self::retain_recursive(
+                root.into_inner(),
+                &mut f,
+                &self.insertion_balancer,
+                &self.removal_balancer,
+            );
+            self.set_root(new_root.map(Self::collapse_root));
+            self.size -= removed;
         }
     }
-}
 
-/// A common base iterator for all BPlusTreeMap iterators.
-/// This provides a unified way to iterate over the tree's entries.
-pub struct TreeIterator<T> {
-    /// The entries to iterate over
-    entries: Vec<T>,
-    /// The current position in the entries
-    position: usize,
-}
+    /// Recursive helper for retain. Returns what is left of the subtree,
+    /// if anything, and the number of entries removed from it.
+    fn retain_recursive<F>(
+        node: Node<K, V>,
+        f: &mut F,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> (Option<Node<K, V>>, usize)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        match node {
+            Node::Leaf(mut leaf) => {
+                // Compact the kept entries towards the front, preserving their order
+                let mut kept = 0;
+                for i in 0..leaf.keys.len() {
+                    if f(&leaf.keys[i], &mut leaf.values[i]) {
+                        leaf.keys.swap(kept, i);
+                        leaf.values.swap(kept, i);
+                        kept += 1;
+                    }
+                }
+                let removed = leaf.keys.len() - kept;
+                leaf.keys.truncate(kept);
+                leaf.values.truncate(kept);
+                leaf.refresh_filter();
 
-impl<T> TreeIterator<T> {
-    /// Creates a new TreeIterator with the given entries
-    pub fn new(entries: Vec<T>) -> Self {
-        Self {
-            entries,
-            position: 0,
+                if leaf.keys.is_empty() {
+                    (None, removed)
+                } else {
+                    (Some(Node::Leaf(leaf)), removed)
+                }
+            }
+            Node::Branch(branch) => {
+                let mut removed = 0;
+                let mut separators = branch.keys.into_iter();
+                let children = branch.children.into_iter().enumerate().map(|(i, child)| {
+                    let separator = if i > 0 { separators.next() } else { None };
+                    let (child, child_removed) = Self::retain_recursive(
+                        child.into_inner(),
+                        f,
+                        insertion_balancer,
+                        removal_balancer,
+                    );
+                    removed += child_removed;
+                    (separator, child.map(NodeBox::new))
+                });
+
+                let node = Self::rejoin_children(children, insertion_balancer, removal_balancer);
+                (node, removed)
+            }
         }
     }
-}
 
-impl<T> Iterator for TreeIterator<T>
-where
-    T: Clone,
-{
-    type Item = T;
+    /// Removes the entries for which the predicate returns true and yields
+    /// them in ascending key order. Extraction is lazy: each call to `next`
+    /// moves a cursor on from the last entry visited and takes the next
+    /// match out of its leaf in place, so dropping the iterator early
+    /// leaves the remaining entries untouched. Leaves are rebalanced as the
+    /// cursor leaves them. Entries for which the predicate returns false
+    /// stay in the map, keeping any changes the predicate made to their
+    /// values.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf::new(self, pred)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.position < self.entries.len() {
-            let item = self.entries[self.position].clone();
-            self.position += 1;
-            Some(item)
-        } else {
-            None
+    /// Settles the child at `idx` of `branch` once an `ExtractIf` cursor
+    /// has left it, and returns the index of the child after it. A child
+    /// left empty is dropped along with a separator. Any other child is
+    /// merged or rebalanced with the child before it, except the first,
+    /// which is balanced when the child after it is settled.
+    fn settle_child(
+        branch: &mut BranchNode<K, V>,
+        idx: usize,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> usize {
+        let empty = match &*branch.children[idx] {
+            Node::Leaf(leaf) => leaf.keys.is_empty(),
+            Node::Branch(child) => child.children.is_empty(),
+        };
+        if empty {
+            let links = branch.outer_links(idx..idx + 1);
+            branch.children.remove(idx);
+            if let Some(links) = links {
+                branch.relink_children(idx..idx, links);
+            }
+            if !branch.keys.is_empty() {
+                branch.keys.remove(idx.saturating_sub(1));
+            }
+            return idx;
+        }
+        if idx == 0 {
+            return 1;
         }
-    }
-}
 
-/// An owning iterator over the entries of a `BPlusTreeMap`.
-pub struct IntoIter<K, V> {
-    inner: TreeIterator<(K, V)>,
-}
+        if insertion_balancer.promotes_first_keys() {
+            Self::refresh_separator(branch, idx);
+        }
 
-impl<K, V> Iterator for IntoIter<K, V>
-where
-    K: Clone,
-    V: Clone,
-{
-    type Item = (K, V);
+        // A merge that leaves the merged child with a single child of its
+        // own passes it on to the child before, which the cursor has left;
+        // the first child can wait for the child after it
+        let mut next = idx + 1;
+        let mut idx = idx;
+        while Self::balance_pair(branch, idx, removal_balancer) {
+            next -= 1;
+            if idx == 1 || !Self::has_only_child(&branch.children[idx - 1]) {
+                break;
+            }
+            idx -= 1;
+        }
+        next
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    /// Whether `node` is a branch left with a single child
+    fn has_only_child(node: &Node<K, V>) -> bool {
+        matches!(node, Node::Branch(branch) if branch.keys.is_empty())
     }
-}
 
-/// A reference iterator over the entries of a `BPlusTreeMap`.
-pub struct Iter<'a, K, V> {
-    inner: TreeIterator<(&'a K, &'a V)>,
-}
+    /// Balances the child at `idx` of `branch` against its left sibling as
+    /// `balance_children` does, where either may be a branch left with a
+    /// single child by an `ExtractIf` cursor. That child gains a sibling
+    /// in the process and is balanced against it in turn. Should the two
+    /// children still be apart with either left with a single child, or
+    /// with one short of the keys it gave up to a merge below, they are
+    /// balanced again. Returns true if they were merged.
+    fn balance_pair(branch: &mut BranchNode<K, V>, idx: usize, balancer: &RemovalBalancer) -> bool {
+        loop {
+            let left_only_child = Self::has_only_child(&branch.children[idx - 1]);
+            let right_only_child = Self::has_only_child(&branch.children[idx]);
+            let merged = Self::balance_children(branch, idx, balancer, true);
+            // A child that had an only child took its siblings' spare keys;
+            // settling its own children may hand one back, so the pair is
+            // balanced again afterwards
+            let mut settled = false;
+            if right_only_child
+                && let Node::Branch(node) = &mut *branch.children[idx - usize::from(merged)]
+                && node.children.len() > 1
+            {
+                settled |= Self::balance_pair(node, node.children.len() - 1, balancer);
+            }
+            if left_only_child
+                && let Node::Branch(node) = &mut *branch.children[idx - 1]
+                && node.children.len() > 1
+            {
+                settled |= Self::balance_pair(node, 1, balancer);
+            }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V>
-where
-    K: 'a,
-    V: 'a,
-{
-    type Item = (&'a K, &'a V);
+            if merged
+                || !(settled
+                    || Self::has_only_child(&branch.children[idx - 1])
+                    || Self::has_only_child(&branch.children[idx]))
+            {
+                return merged;
+            }
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    /// Drops a root that lost everything it held, or replaces one left
+    /// with a single child by that child, once an `ExtractIf` cursor has
+    /// left it
+    fn settle_root(&mut self) {
+        let Some(root) = self.root.as_deref_mut() else {
+            return;
+        };
+        let empty = match root {
+            Node::Leaf(leaf) => leaf.keys.is_empty(),
+            Node::Branch(branch) => branch.children.is_empty(),
+        };
+        if empty {
+            self.root = None;
+        } else {
+            *root = Self::collapse_root(root.take());
+        }
     }
-}
 
-/// A mutable iterator over the entries of a `BPlusTreeMap`.
-pub struct IterMut<'a, K, V> {
-    // Store key-value pairs as (K, &'a mut V) to avoid lifetime issues
-    entries: Vec<(K, &'a mut V)>,
-    position: usize,
-}
+    /// Removes every entry whose key falls within `range` and returns the
+    /// number of entries removed. Subtrees lying entirely inside the range
+    /// are dropped whole; only the leaves at the two ends of the range are
+    /// trimmed key by key, and each branch along those two paths is
+    /// rebalanced once afterwards.
+    ///
+    /// Panics if the start of the range is greater than its end, or if both
+    /// ends are equal and excluded.
+    pub fn remove_range<R: RangeBounds<K>>(&mut self, range: R) -> usize {
+        check_range_bounds(&range);
+
+        let removed = match self.root.take() {
+            Some(root) => {
+                let (new_root, removed) = Self::remove_range_recursive(
+                    root.into_inner(),
+                    &range,
+                    &self.insertion_balancer,
+                    &self.removal_balancer,
+                );
+                self.root = new_root.map(|root| NodeBox::new(Self::collapse_root(root)));
+                // Leaves were only trimmed, merged and dropped under the two
+                // branches above the ends of the range, now either side of
+                // where the range was
+                let reach = self.config.branching_factor + 2;
+                self.relink_around(reach, |branch| {
+                    child_index_for_bound(branch, range.start_bound())
+                });
+                removed
+            }
+            None => 0,
+        };
 
-impl<'a, K, V> Iterator for IterMut<'a, K, V>
-where
-    K: Ord + Clone + Debug + 'a,
-{
-    type Item = (&'a K, &'a mut V);
+        self.size -= removed;
+        removed
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.position < self.entries.len() {
-            let position = self.position;
-            self.position += 1;
+    /// Recursive helper for remove_range. Returns what is left of the
+    /// subtree, if anything, and the number of entries removed from it.
+    fn remove_range_recursive<R: RangeBounds<K>>(
+        node: Node<K, V>,
+        range: &R,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> (Option<Node<K, V>>, usize) {
+        let end_bound = end_as_start_bound(range.end_bound());
+        match node {
+            Node::Leaf(mut leaf) => {
+                let start = leaf_index_for_bound(&leaf.keys, range.start_bound());
+                let end = match end_bound {
+                    Some(bound) => leaf_index_for_bound(&leaf.keys, bound),
+                    None => leaf.keys.len(),
+                }
+                .max(start);
 
-            // Get a reference to the key and a mutable reference to the value
-            let entry = &mut self.entries[position];
+                leaf.keys.drain(start..end);
+                leaf.values.drain(start..end);
+                leaf.refresh_filter();
 
-            // This is safe because we're returning each entry exactly once
-            // and we know the indices are valid
-            unsafe {
-                let key_ptr = &entry.0 as *const K;
-                let value_ptr = &mut *(entry.1 as *mut V);
-                Some((&*key_ptr, value_ptr))
+                if leaf.keys.is_empty() {
+                    (None, end - start)
+                } else {
+                    (Some(Node::Leaf(leaf)), end - start)
+                }
+            }
+            Node::Branch(branch) => {
+                // The children holding the two ends of the range; everything
+                // strictly between them lies entirely inside the range
+                let last_child = branch.children.len().saturating_sub(1);
+                let first = child_index_for_bound(&branch, range.start_bound()).min(last_child);
+                let last = match end_bound {
+                    Some(bound) => child_index_for_bound(&branch, bound).min(last_child),
+                    None => last_child,
+                }
+                .max(first);
+
+                let mut removed = 0;
+                let mut separators = branch.keys.into_iter();
+                let children = branch.children.into_iter().enumerate().map(|(i, child)| {
+                    let separator = if i > 0 { separators.next() } else { None };
+                    if i == first || i == last {
+                        let (child, child_removed) = Self::remove_range_recursive(
+                            child.into_inner(),
+                            range,
+                            insertion_balancer,
+                            removal_balancer,
+                        );
+                        removed += child_removed;
+                        (separator, child.map(NodeBox::new))
+                    } else if i > first && i < last {
+                        removed += Self::count_entries(&child);
+                        (separator, None)
+                    } else {
+                        (separator, Some(child))
+                    }
+                });
+
+                let node = Self::rejoin_children(children, insertion_balancer, removal_balancer);
+                (node, removed)
             }
-        } else {
-            None
         }
     }
-}
-
-/// An iterator over the keys of a `BPlusTreeMap`.
-pub struct Keys<'a, K> {
-    inner: TreeIterator<&'a K>,
-}
 
-impl<'a, K> Iterator for Keys<'a, K>
-where
-    K: 'a + Clone,
-{
-    type Item = &'a K;
+    /// Splits the map in two at `key`. Entries whose keys are greater than
+    /// or equal to `key` are moved into the returned map, which keeps this
+    /// map's branching factor; the rest stay behind. The tree is cut along
+    /// the path to `key` instead of being rebuilt, and only the nodes on
+    /// either side of the cut are rebalanced.
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut other = Self::with_branching_factor(self.config.branching_factor);
+        other.config = self.config.clone();
+        other.insertion_balancer = self.insertion_balancer.with_config(other.config.clone());
+        other.removal_balancer = RemovalBalancer::new(other.config.clone());
+
+        if let Some(root) = self.root.take() {
+            let height =
+                Self::spine_height(&root, |branch| branch.children.first().map(NodeBox::as_ref));
+            let (left, right) =
+                Self::split_off_recursive(root.into_inner(), key, &self.removal_balancer);
+            self.root = left.map(|left| NodeBox::new(Self::collapse_root(left)));
+            other.root = right.map(|right| NodeBox::new(Self::collapse_root(right)));
+            // The leaves that changed are the two halves of the cut leaf and
+            // those merged into them, at most one more on each level
+            self.relink_around(height + 2, |branch| branch.children.len() - 1);
+            other.relink_around(height + 2, |_| 0);
+
+            other.size = other.root.as_deref().map_or(0, Self::count_entries);
+            self.size -= other.size;
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        other
     }
-}
 
-/// An iterator over the values of a `BPlusTreeMap`.
-pub struct Values<'a, V> {
-    inner: TreeIterator<&'a V>,
-}
-
-impl<'a, V> Iterator for Values<'a, V>
-where
-    V: 'a + Clone,
-{
-    type Item = &'a V;
+    /// Recursive helper for split_off. Returns the parts of the subtree
+    /// holding the keys below `key` and the keys from `key` onwards.
+    fn split_off_recursive<Q>(
+        node: Node<K, V>,
+        key: &Q,
+        balancer: &RemovalBalancer,
+    ) -> SplitHalves<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match node {
+            Node::Leaf(mut leaf) => {
+                let idx = leaf.keys.partition_point(|k| k.borrow() < key);
+                let mut right = LeafNode::new(leaf.keys.split_off(idx), leaf.values.split_off(idx));
+                #[cfg(feature = "bloom")]
+                right.filter.set_spec(leaf.filter.spec());
+                leaf.refresh_filter();
+                right.refresh_filter();
+
+                let non_empty =
+                    |leaf: LeafNode<K, V>| (!leaf.keys.is_empty()).then_some(Node::Leaf(leaf));
+                (non_empty(leaf), non_empty(right))
+            }
+            Node::Branch(branch) => {
+                let idx = branch.child_index_for(key);
+
+                let mut left_keys = branch.keys;
+                let mut left_children = branch.children;
+                let right_keys = left_keys.split_off(idx);
+                let mut right_children = left_children.split_off(idx);
+                let child = right_children.remove(0);
+
+                let (child_left, child_right) =
+                    Self::split_off_recursive(child.into_inner(), key, balancer);
+                let (child_left, child_right) =
+                    (child_left.map(NodeBox::new), child_right.map(NodeBox::new));
+
+                // Each side keeps the separators of the children it keeps; the
+                // separator between the two halves of the cut child stays with
+                // the left half, which it still bounds from below
+                let left = Self::collect_children(
+                    iter::once(None).chain(left_keys.into_iter().map(Some)).zip(
+                        left_children
+                            .into_iter()
+                            .map(Some)
+                            .chain(iter::once(child_left)),
+                    ),
+                );
+                let right = Self::collect_children(
+                    iter::once(None)
+                        .chain(right_keys.into_iter().map(Some))
+                        .zip(iter::once(child_right).chain(right_children.into_iter().map(Some))),
+                );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+                let left = left.map(|mut branch| {
+                    Self::balance_last_child(&mut branch, balancer, false);
+                    Node::Branch(branch)
+                });
+                let right = right.map(|mut branch| {
+                    Self::balance_first_child(&mut branch, balancer, false);
+                    Node::Branch(branch)
+                });
+                (left, right)
+            }
+        }
     }
-}
 
-/// A mutable iterator over the values of a `BPlusTreeMap`.
-pub struct ValuesMut<'a, V> {
-    // We can't use TreeIterator for mutable references because they don't implement Clone
-    entries: Vec<&'a mut V>,
-    position: usize,
-}
-
-impl<'a, V> ValuesMut<'a, V> {
-    /// Creates a new ValuesMut with the given entries
-    pub fn new(entries: Vec<&'a mut V>) -> Self {
-        Self {
-            entries,
-            position: 0,
+    /// Merges or rebalances the last child of `branch` with its left sibling.
+    /// If that child was a branch left with a single child of its own, the
+    /// grandchild gains a new sibling in the process and is balanced in turn;
+    /// should that merge leave the child with a single child again, it is
+    /// balanced once more. `linked` is as for `balance_children`.
+    fn balance_last_child(branch: &mut BranchNode<K, V>, balancer: &RemovalBalancer, linked: bool) {
+        if branch.children.len() < 2 {
+            return;
+        }
+        let idx = branch.children.len() - 1;
+        let only_child =
+            matches!(&*branch.children[idx], Node::Branch(child) if child.keys.is_empty());
+
+        Self::balance_children(branch, idx, balancer, linked);
+
+        if only_child
+            && let Some(Node::Branch(child)) = branch.children.last_mut().map(NodeBox::as_mut)
+        {
+            Self::balance_last_child(child, balancer, linked);
+            if child.keys.is_empty() {
+                Self::balance_last_child(branch, balancer, linked);
+            }
         }
     }
-}
 
-impl<'a, V> Iterator for ValuesMut<'a, V>
-where
-    V: 'a,
+    /// Merges or rebalances the first child of `branch` with its right
+    /// sibling, descending the same way as `balance_last_child`.
+    fn balance_first_child(
+        branch: &mut BranchNode<K, V>,
+        balancer: &RemovalBalancer,
+        linked: bool,
+    ) {
+        if branch.children.len() < 2 {
+            return;
+        }
+        let only_child =
+            matches!(&*branch.children[0], Node::Branch(child) if child.keys.is_empty());
+
+        Self::balance_children(branch, 1, balancer, linked);
+
+        if only_child
+            && let Some(Node::Branch(child)) = branch.children.first_mut().map(NodeBox::as_mut)
+        {
+            Self::balance_first_child(child, balancer, linked);
+            if child.keys.is_empty() {
+                Self::balance_first_child(branch, balancer, linked);
+            }
+        }
+    }
+
+    /// Removes branch levels holding a single child from the top of a tree
+    fn collapse_root(mut root: Node<K, V>) -> Node<K, V> {
+        while let Node::Branch(branch) = &mut root {
+            if branch.children.len() != 1 {
+                break;
+            }
+            root = branch.children.pop().unwrap().into_inner();
+        }
+        root
+    }
+
+    /// Moves all entries from `other` into this map, leaving `other` empty.
+    /// When every key in `other` is greater than every key in this map (or
+    /// the other way round) and both maps share a branching factor, the
+    /// shorter tree is grafted onto the edge of the taller one in a single
+    /// pass. Otherwise the entries are moved over one by one, and values
+    /// from `other` replace those already stored under the same key.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+        if self.config.branching_factor != other.config.branching_factor {
+            let other = std::mem::replace(
+                other,
+                Self::with_branching_factor(other.config.branching_factor),
+            );
+            self.extend(other);
+            return;
+        }
+
+        let other_root = other.root.take().unwrap();
+        let other_size = std::mem::take(&mut other.size);
+        let root = match self.root.take() {
+            None => {
+                self.root = Some(other_root);
+                self.size = other_size;
+                return;
+            }
+            Some(root) => root,
+        };
+
+        let (left, right) = if Self::last_key(&root) < Self::first_key(&other_root) {
+            (root, other_root)
+        } else if Self::last_key(&other_root) < Self::first_key(&root) {
+            (other_root, root)
+        } else {
+            // The key ranges overlap, so the trees can't be joined as they are
+            self.root = Some(root);
+            let mut other_map = Self::with_branching_factor(other.config.branching_factor);
+            other_map.root = Some(other_root);
+            other_map.size = other_size;
+            self.extend(other_map);
+            return;
+        };
+
+        let junction = Self::first_key(&right).clone();
+        self.root = Some(NodeBox::new(Self::graft(
+            left.into_inner(),
+            right.into_inner(),
+            &self.insertion_balancer,
+            &self.removal_balancer,
+        )));
+        // Only the leaves either side of the junction were merged or
+        // rebalanced, or moved out of a root's box
+        self.relink_around(1, |branch| branch.child_index_for(&junction));
+        self.size += other_size;
+    }
+
+    /// Joins two trees, where every key in `left` is less than every key in
+    /// `right`. The shorter tree becomes a child of the node on the facing
+    /// spine of the taller one, separated from its new sibling by the
+    /// smallest key of `right`. Nodes along that spine that overflow are
+    /// split on the way back up.
+    fn graft(
+        left: Node<K, V>,
+        right: Node<K, V>,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> Node<K, V> {
+        let separator = SeparatorKey::new(Self::first_key(&right).clone());
+        let left_height =
+            Self::spine_height(&left, |branch| branch.children.last().map(NodeBox::as_ref));
+        let right_height = Self::spine_height(&right, |branch| {
+            branch.children.first().map(NodeBox::as_ref)
+        });
+
+        if left_height == right_height {
+            // Neither tree fits inside the other, so they become siblings
+            // under a new root, and are merged or rebalanced like any others
+            let mut branch = BranchNode::new(NodeVec::from_iter([separator]), vec![left, right]);
+            Self::balance_children(&mut branch, 1, removal_balancer, false);
+            return Self::collapse_root(Node::Branch(branch));
+        }
+
+        let (node, split) = if left_height > right_height {
+            Self::graft_last(
+                left,
+                left_height,
+                separator,
+                right,
+                right_height,
+                insertion_balancer,
+                removal_balancer,
+            )
+        } else {
+            Self::graft_first(
+                right,
+                right_height,
+                separator,
+                left,
+                left_height,
+                insertion_balancer,
+                removal_balancer,
+            )
+        };
+
+        match split {
+            Some((separator, right)) => Node::Branch(BranchNode::new(
+                NodeVec::from_iter([separator]),
+                vec![node, right],
+            )),
+            None => node,
+        }
+    }
+
+    /// Attaches `tree` after the last child of the node at height
+    /// `tree_height + 1` on the right spine of `node`
+    fn graft_last(
+        node: Node<K, V>,
+        height: usize,
+        separator: SeparatorKey<K>,
+        tree: Node<K, V>,
+        tree_height: usize,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> GraftResult<K, V> {
+        let mut branch = match node {
+            Node::Branch(branch) => branch,
+            Node::Leaf(_) => unreachable!("only branches sit above the grafted tree"),
+        };
+
+        if height == tree_height + 1 {
+            branch.keys.push(separator);
+            branch.children.push(NodeBox::new(tree));
+            // The grafted root may hold fewer keys than a node below the root should
+            let idx = branch.children.len() - 1;
+            Self::balance_children(&mut branch, idx, removal_balancer, false);
+        } else {
+            let idx = branch.children.len() - 1;
+            let child = branch.take_child(idx);
+            let (child, split) = Self::graft_last(
+                child,
+                height - 1,
+                separator,
+                tree,
+                tree_height,
+                insertion_balancer,
+                removal_balancer,
+            );
+            *branch.children[idx] = child;
+            if let Some((separator, right)) = split {
+                branch.keys.push(separator);
+                branch.children.push(NodeBox::new(right));
+            }
+        }
+
+        Self::split_if_overfull(branch, insertion_balancer)
+    }
+
+    /// Attaches `tree` before the first child of the node at height
+    /// `tree_height + 1` on the left spine of `node`
+    fn graft_first(
+        node: Node<K, V>,
+        height: usize,
+        separator: SeparatorKey<K>,
+        tree: Node<K, V>,
+        tree_height: usize,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> GraftResult<K, V> {
+        let mut branch = match node {
+            Node::Branch(branch) => branch,
+            Node::Leaf(_) => unreachable!("only branches sit above the grafted tree"),
+        };
+
+        if height == tree_height + 1 {
+            branch.keys.insert(0, separator);
+            branch.children.insert(0, NodeBox::new(tree));
+            // The grafted root may hold fewer keys than a node below the root should
+            Self::balance_children(&mut branch, 1, removal_balancer, false);
+        } else {
+            let child = branch.take_child(0);
+            let (child, split) = Self::graft_first(
+                child,
+                height - 1,
+                separator,
+                tree,
+                tree_height,
+                insertion_balancer,
+                removal_balancer,
+            );
+            *branch.children[0] = child;
+            if let Some((separator, right)) = split {
+                branch.keys.insert(0, separator);
+                branch.children.insert(1, NodeBox::new(right));
+            }
+        }
+
+        Self::split_if_overfull(branch, insertion_balancer)
+    }
+
+    /// Splits `branch` in two if it holds more keys than the branching factor allows
+    fn split_if_overfull(
+        mut branch: BranchNode<K, V>,
+        balancer: &InsertionBalancer<K>,
+    ) -> GraftResult<K, V> {
+        branch.refresh_fences();
+        match balancer.balance_node(Node::Branch(branch)) {
+            BalanceResult::Split {
+                left,
+                right,
+                separator,
+            } => (left, Some((separator, right))),
+            BalanceResult::NoChange(node) => (node, None),
+            _ => panic!("Unexpected balance result for insertion"),
+        }
+    }
+
+    /// Returns the number of branch levels above the leaf reached by
+    /// repeatedly following `next` down from `node`
+    fn spine_height<'a>(
+        mut node: &'a Node<K, V>,
+        next: impl Fn(&'a BranchNode<K, V>) -> Option<&'a Node<K, V>>,
+    ) -> usize {
+        let mut height = 0;
+        while let Node::Branch(branch) = node {
+            node = next(branch).expect("branch has no children");
+            height += 1;
+        }
+        height
+    }
+
+    /// Returns the smallest key in the non-empty subtree rooted at `node`
+    fn first_key(mut node: &Node<K, V>) -> &K {
+        while let Node::Branch(branch) = node {
+            node = &branch.children[0];
+        }
+        match node {
+            Node::Leaf(leaf) => &leaf.keys[0],
+            Node::Branch(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the largest key in the non-empty subtree rooted at `node`
+    fn last_key(mut node: &Node<K, V>) -> &K {
+        while let Node::Branch(branch) = node {
+            node = branch.children.last().expect("branch has no children");
+        }
+        match node {
+            Node::Leaf(leaf) => leaf.keys.last().expect("leaf is empty"),
+            Node::Branch(_) => unreachable!(),
+        }
+    }
+
+    /// Moves all entries from `other` into this map. Where both maps hold
+    /// the same key, `resolve` is called with the key, the value already in
+    /// this map, and the value from `other`, and the value it returns is
+    /// kept. The two maps are merged in a single ordered pass over their
+    /// entries, after which the tree is rebuilt from the merged entries.
+    pub fn merge_from<F>(&mut self, other: BPlusTreeMap<K, V>, mut resolve: F)
+    where
+        F: FnMut(&K, V, V) -> V,
+    {
+        if other.is_empty() {
+            return;
+        }
+
+        let mut existing = Vec::with_capacity(self.size);
+        if let Some(root) = self.root.take() {
+            Self::move_entries(root.into_inner(), &mut existing);
+        }
+        let mut incoming = Vec::with_capacity(other.size);
+        if let Some(root) = other.root {
+            Self::move_entries(root.into_inner(), &mut incoming);
+        }
+
+        let mut merged = Vec::with_capacity(existing.len() + incoming.len());
+        let mut existing = existing.into_iter().peekable();
+        let mut incoming = incoming.into_iter().peekable();
+        loop {
+            let ordering = match (existing.peek(), incoming.peek()) {
+                (Some((a, _)), Some((b, _))) => a.cmp(b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => merged.push(existing.next().unwrap()),
+                Ordering::Greater => merged.push(incoming.next().unwrap()),
+                Ordering::Equal => {
+                    let (key, value) = existing.next().unwrap();
+                    let (_, other_value) = incoming.next().unwrap();
+                    let value = resolve(&key, value, other_value);
+                    merged.push((key, value));
+                }
+            }
+        }
+
+        self.size = merged.len();
+        let root = self.build_from_sorted(merged);
+        self.set_root(root);
+    }
+
+    /// Rebuilds the tree with its leaves and branches packed full, as
+    /// `from_sorted_iter` would build it. After many removals the tree can
+    /// be left with leaves that are nearly empty and levels it no longer
+    /// needs; compacting it gives it the fewest leaves and the least height
+    /// that hold its entries. The entries are moved, not cloned.
+    pub fn compact(&mut self) {
+        self.compact_with_fill_factor(1.0);
+    }
+
+    /// Rebuilds the tree like `compact`, filling each node to about
+    /// `fill_factor` of the branching factor. Leaving room in the nodes
+    /// lets later inserts go in without splitting them straight away.
+    ///
+    /// Panics unless the fill factor is between 0.5 and 1.
+    pub fn compact_with_fill_factor(&mut self, fill_factor: f64) {
+        if !(0.5..=1.0).contains(&fill_factor) {
+            panic!("Fill factor must be between 0.5 and 1");
+        }
+        let Some(root) = self.root.take() else {
+            return;
+        };
+        let mut entries = Vec::with_capacity(self.size);
+        Self::move_entries(root.into_inner(), &mut entries);
+
+        let branching_factor = self.config.branching_factor;
+        let keys_per_leaf = ((branching_factor as f64 * fill_factor).round() as usize).max(1);
+        let children_per_branch =
+            (((branching_factor + 1) as f64 * fill_factor).round() as usize).max(2);
+        let root = self.build_filled(entries, keys_per_leaf, children_per_branch);
+        self.set_root(root);
+        self.last_leaf.get_mut().clear();
+    }
+
+    /// Rebuilds the map with nodes of `branching_factor` keys, keeping its
+    /// entries, their order and its other settings. The entries are moved
+    /// out of the old nodes and the new tree is built bottom-up over them,
+    /// packed as `from_sorted_iter` packs it; nothing is cloned but the
+    /// separators. Inserts and removals from then on use the new factor.
+    ///
+    /// Panics if the branching factor is less than 2.
+    pub fn rebuild_with_branching_factor(&mut self, branching_factor: usize) {
+        if branching_factor < 2 {
+            panic!("Branching factor must be at least 2");
+        }
+        self.config = Rc::new(BPlusTreeConfig {
+            branching_factor,
+            ..BPlusTreeConfig::clone(&self.config)
+        });
+        self.insertion_balancer = self.insertion_balancer.with_config(self.config.clone());
+        self.removal_balancer = RemovalBalancer::new(self.config.clone());
+        self.pool.resize_nodes(branching_factor + 1);
+
+        if let Some(root) = self.root.take() {
+            let mut entries = Vec::with_capacity(self.size);
+            Self::move_entries(root.into_inner(), &mut entries);
+            let root = self.build_from_sorted(entries);
+            self.set_root(root);
+        }
+        self.last_leaf.get_mut().clear();
+    }
+
+    /// Creates a map holding `entries`, which must be sorted by key
+    /// without duplicates, built bottom-up rather than by insertion
+    pub(crate) fn from_sorted_entries(branching_factor: usize, entries: Vec<(K, V)>) -> Self {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.size = entries.len();
+        let root = map.build_from_sorted(entries);
+        map.set_root(root);
+        map
+    }
+
+    /// Moves the entries of the subtree rooted at `node` onto the end of
+    /// `entries`, in ascending key order
+    fn move_entries(node: Node<K, V>, entries: &mut Vec<(K, V)>) {
+        match node {
+            Node::Leaf(leaf) => entries.extend(leaf.keys.into_iter().zip(leaf.values)),
+            Node::Branch(branch) => {
+                for child in branch.children {
+                    Self::move_entries(child.into_inner(), entries);
+                }
+            }
+        }
+    }
+
+    /// Builds a tree bottom-up from entries sorted by key without
+    /// duplicates. Each level uses as few nodes as can hold the level below
+    /// and spreads the entries or children evenly between them, so every
+    /// node ends up at least half full. The leaves are given filters if
+    /// the map's leaves have them.
+    fn build_from_sorted(&self, entries: Vec<(K, V)>) -> Option<Node<K, V>> {
+        let branching_factor = self.config.branching_factor;
+        self.build_filled(entries, branching_factor, branching_factor + 1)
+    }
+
+    /// Builds a tree bottom-up like `build_from_sorted`, with at most
+    /// `keys_per_leaf` entries in each leaf and `children_per_branch`
+    /// children in each branch
+    fn build_filled(
+        &self,
+        entries: Vec<(K, V)>,
+        keys_per_leaf: usize,
+        children_per_branch: usize,
+    ) -> Option<Node<K, V>> {
+        // Each node is paired with the smallest key beneath it, which
+        // becomes its separator in the level above
+        let leaf_count = entries.len().div_ceil(keys_per_leaf);
+        let total = entries.len();
+        let mut entries = entries.into_iter();
+        let level = even_runs(total, leaf_count)
+            .map(|len| {
+                let (keys, values): (NodeVec<K>, NodeVec<V>) = entries.by_ref().take(len).unzip();
+                let mut leaf = LeafNode::new(keys, values);
+                self.insertion_balancer.filter_leaf(&mut leaf);
+                (SeparatorKey::new(leaf.keys[0].clone()), Node::Leaf(leaf))
+            })
+            .collect();
+        Self::build_branches(level, children_per_branch)
+    }
+
+    /// Builds the branch levels of a tree bottom-up over `level`, its nodes
+    /// in key order, each paired with the smallest key beneath it. Each
+    /// branch has at most `children_per_branch` children, and the nodes of
+    /// a level are spread evenly between the branches above them.
+    pub(crate) fn build_branches(
+        mut level: Vec<(SeparatorKey<K>, Node<K, V>)>,
+        children_per_branch: usize,
+    ) -> Option<Node<K, V>> {
+        while level.len() > 1 {
+            let branch_count = level.len().div_ceil(children_per_branch);
+            let total = level.len();
+            let mut nodes = level.into_iter();
+            level = even_runs(total, branch_count)
+                .map(|len| {
+                    let (first_key, first_child) = nodes.next().unwrap();
+                    let mut keys = NodeVec::with_capacity(len - 1);
+                    let mut children = Vec::with_capacity(len);
+                    children.push(first_child);
+                    for (key, child) in nodes.by_ref().take(len - 1) {
+                        keys.push(key);
+                        children.push(child);
+                    }
+                    (first_key, Node::Branch(BranchNode::new(keys, children)))
+                })
+                .collect();
+        }
+
+        level.pop().map(|(_, node)| node)
+    }
+
+    /// Creates a map of `size` entries whose nodes are built already, with
+    /// `root` at the top of them
+    #[cfg(feature = "rayon")]
+    pub(crate) fn from_built_root(
+        branching_factor: usize,
+        root: Option<Node<K, V>>,
+        size: usize,
+    ) -> Self {
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.set_root(root);
+        map.size = size;
+        map
+    }
+
+    /// Counts the entries stored in the subtree rooted at `node`
+    fn count_entries(node: &Node<K, V>) -> usize {
+        match node {
+            Node::Leaf(leaf) => leaf.keys.len(),
+            Node::Branch(branch) => branch
+                .children
+                .iter()
+                .map(|child| Self::count_entries(child))
+                .sum(),
+        }
+    }
+
+    /// Builds a branch from its children in order, each paired with the
+    /// separator that preceded it in the original branch. Children that
+    /// were emptied are dropped along with their separators, and a child
+    /// that ends up leftmost drops its separator, since it needs none.
+    /// Returns None if no children survive.
+    fn collect_children<I>(children: I) -> Option<BranchNode<K, V>>
+    where
+        I: Iterator<Item = (Option<SeparatorKey<K>>, Option<NodeBox<K, V>>)>,
+    {
+        let mut branch = BranchNode::new(NodeVec::new(), Vec::<NodeBox<K, V>>::new());
+
+        for (separator, child) in children {
+            match child {
+                None => {}
+                Some(child) if matches!(&*child, Node::Branch(child) if child.children.is_empty()) =>
+                    {}
+                Some(child) => {
+                    if let Some(separator) = separator.filter(|_| !branch.children.is_empty()) {
+                        branch.keys.push(separator);
+                    }
+                    branch.children.push(child);
+                }
+            }
+        }
+
+        branch.refresh_fences();
+        (!branch.children.is_empty()).then_some(branch)
+    }
+
+    /// Rebuilds a branch from what a bulk removal left of its children, as
+    /// `collect_children` does. Children that lost levels on the way, and
+    /// came back shorter than their siblings, are grafted onto their
+    /// neighbours. Otherwise the separators are brought up to date and
+    /// underfull children are merged or rebalanced, and a branch left with
+    /// a single child is replaced by it. What is returned is a tree in
+    /// which only the root may be underfull, and which may be shorter than
+    /// the branch was. Returns None if no children survive.
+    fn rejoin_children<I>(
+        children: I,
+        insertion_balancer: &InsertionBalancer<K>,
+        removal_balancer: &RemovalBalancer,
+    ) -> Option<Node<K, V>>
+    where
+        I: Iterator<Item = (Option<SeparatorKey<K>>, Option<NodeBox<K, V>>)>,
+    {
+        let mut branch = Self::collect_children(children)?;
+        let height = |child: &NodeBox<K, V>| {
+            Self::spine_height(child, |branch| branch.children.first().map(NodeBox::as_ref))
+        };
+        let first_height = height(&branch.children[0]);
+        if branch
+            .children
+            .iter()
+            .any(|child| height(child) != first_height)
+        {
+            return branch
+                .children
+                .into_iter()
+                .map(NodeBox::into_inner)
+                .reduce(|left, right| {
+                    Self::graft(left, right, insertion_balancer, removal_balancer)
+                });
+        }
+
+        if insertion_balancer.promotes_first_keys() {
+            Self::refresh_separators(&mut branch);
+        }
+        Self::balance_all_children(&mut branch, removal_balancer);
+        Some(Self::collapse_root(Node::Branch(branch)))
+    }
+
+    /// Sets each separator of `branch` that removals have left below the
+    /// first key of the child on its right to that key
+    fn refresh_separators(branch: &mut BranchNode<K, V>) {
+        for idx in 1..branch.children.len() {
+            Self::refresh_separator(branch, idx);
+        }
+    }
+
+    /// Sets the separator before the child at `idx` of `branch` to the
+    /// child's first key, if removals have left it below that key
+    fn refresh_separator(branch: &mut BranchNode<K, V>, idx: usize) {
+        let first = branch.children[idx]
+            .min_key()
+            .expect("children are not empty");
+        if *branch.keys[idx - 1] != *first {
+            branch.keys[idx - 1] = SeparatorKey::new(first.clone());
+        }
+    }
+
+    /// Merges or rebalances each pair of adjacent children of `branch`
+    fn balance_all_children(branch: &mut BranchNode<K, V>, balancer: &RemovalBalancer) {
+        let mut idx = 1;
+        while idx < branch.children.len() {
+            if !Self::balance_children(branch, idx, balancer, false) {
+                idx += 1;
+            }
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = BPlusTreeMap::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<'a, K, V> FromIterator<(&'a K, &'a V)> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn from_iter<I: IntoIterator<Item = (&'a K, &'a V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+}
+
+impl<K, V> Extend<(K, V)> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Runs of ascending keys appended past the end of the map take the
+    /// same fast path as single inserts of such keys.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V> Extend<(&'a K, &'a V)> for BPlusTreeMap<K, V>
+where
+    K: Ord + Copy + Debug,
+    V: Copy + Debug,
+{
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+impl<K, V> From<Vec<(K, V)>> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Creates a map from key-value pairs, like `from_unsorted`. Pairs
+    /// that are already sorted by key take a single pass to sort.
+    fn from(entries: Vec<(K, V)>) -> Self {
+        BPlusTreeMap::from_unsorted(entries)
+    }
+}
+
+impl<K, V, S> From<HashMap<K, V, S>> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Creates a map holding the entries of a `HashMap`, sorted and built
+    /// bottom-up like `from_unsorted`
+    fn from(entries: HashMap<K, V, S>) -> Self {
+        BPlusTreeMap::from_unsorted(entries)
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Creates a map from an array of key-value pairs, like `From<Vec>`
+    fn from(entries: [(K, V); N]) -> Self {
+        BPlusTreeMap::from(Vec::from(entries))
+    }
+}
+
+/// A boxed node that a `LeafWalk` can descend through: a shared or
+/// mutable reference to one, or one owned by the walk
+trait WalkNode: Sized {
+    /// What the walk hands out for each leaf
+    type LeafHandle;
+    /// The children of a branch in key order
+    type Children: DoubleEndedIterator<Item = Self>;
+
+    /// The level above the root, holding the root if there is one
+    fn root_level(root: Option<Self>) -> Self::Children;
+
+    /// Returns the leaf this node is, or the children of the branch it is
+    fn descend(self) -> Result<Self::LeafHandle, Self::Children>;
+}
+
+impl<'a, K, V> WalkNode for &'a NodeBox<K, V> {
+    type LeafHandle = &'a LeafNode<K, V>;
+    type Children = slice::Iter<'a, NodeBox<K, V>>;
+
+    fn root_level(root: Option<Self>) -> Self::Children {
+        root.map_or(&[][..], slice::from_ref).iter()
+    }
+
+    fn descend(self) -> Result<Self::LeafHandle, Self::Children> {
+        match &**self {
+            Node::Leaf(leaf) => Ok(leaf),
+            Node::Branch(branch) => Err(branch.children.iter()),
+        }
+    }
+}
+
+impl<'a, K, V> WalkNode for &'a mut NodeBox<K, V> {
+    type LeafHandle = &'a mut LeafNode<K, V>;
+    type Children = slice::IterMut<'a, NodeBox<K, V>>;
+
+    fn root_level(root: Option<Self>) -> Self::Children {
+        root.map_or(&mut [][..], slice::from_mut).iter_mut()
+    }
+
+    fn descend(self) -> Result<Self::LeafHandle, Self::Children> {
+        match &mut **self {
+            Node::Leaf(leaf) => Ok(leaf),
+            Node::Branch(branch) => Err(branch.children.iter_mut()),
+        }
+    }
+}
+
+impl<K, V> WalkNode for NodeBox<K, V> {
+    type LeafHandle = LeafNode<K, V>;
+    type Children = vec::IntoIter<NodeBox<K, V>>;
+
+    fn root_level(root: Option<Self>) -> Self::Children {
+        Vec::from_iter(root).into_iter()
+    }
+
+    fn descend(self) -> Result<Self::LeafHandle, Self::Children> {
+        match self.into_inner() {
+            Node::Leaf(leaf) => Ok(leaf),
+            Node::Branch(branch) => Err(branch.children.into_iter()),
+        }
+    }
+}
+
+/// Walks the leaves of a tree in key order from both ends, holding only
+/// the paths to the leaves each end has reached.
+///
+/// The two ends share the deepest level they both still have nodes under.
+/// Below it, each end keeps the levels of its own path. Once an end runs
+/// out of both its own levels and the shared one, everything left lies on
+/// the other end's path, so the top of that path becomes the shared level.
+/// Each node is only ever taken out of one level, so the ends never hand
+/// out the same leaf.
+///
+/// The iterators that borrow the tree mutably, or take it apart, walk it
+/// this way, so that each leaf is reached through the branch that owns it.
+/// The shared iterators follow the links between leaves with a `LeafChain`
+/// instead.
+struct LeafWalk<N: WalkNode> {
+    /// The deepest level both ends still have nodes under
+    shared: N::Children,
+    /// The levels below the shared one on the path to the front leaf
+    front: Vec<N::Children>,
+    /// The levels below the shared one on the path to the back leaf
+    back: Vec<N::Children>,
+    /// The number of leaves handed out so far
+    #[cfg(test)]
+    visited: usize,
+}
+
+impl<N: WalkNode> Clone for LeafWalk<N>
+where
+    N::Children: Clone,
+{
+    fn clone(&self) -> Self {
+        LeafWalk {
+            shared: self.shared.clone(),
+            front: self.front.clone(),
+            back: self.back.clone(),
+            #[cfg(test)]
+            visited: self.visited,
+        }
+    }
+}
+
+impl<N: WalkNode> LeafWalk<N> {
+    fn new(root: Option<N>) -> Self {
+        LeafWalk {
+            shared: N::root_level(root),
+            front: Vec::new(),
+            back: Vec::new(),
+            #[cfg(test)]
+            visited: 0,
+        }
+    }
+
+    /// Hands out `node` if it is a leaf, or otherwise adds its children to
+    /// the path of one end
+    fn enter(&mut self, node: N, from_back: bool) -> Option<N::LeafHandle> {
+        match node.descend() {
+            Ok(leaf) => {
+                #[cfg(test)]
+                {
+                    self.visited += 1;
+                }
+                Some(leaf)
+            }
+            Err(children) if from_back => {
+                self.back.push(children);
+                None
+            }
+            Err(children) => {
+                self.front.push(children);
+                None
+            }
+        }
+    }
+}
+
+impl<N: WalkNode> Iterator for LeafWalk<N> {
+    type Item = N::LeafHandle;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = if let Some(level) = self.front.last_mut() {
+                match level.next() {
+                    Some(node) => node,
+                    None => {
+                        self.front.pop();
+                        continue;
+                    }
+                }
+            } else if let Some(node) = self.shared.next() {
+                node
+            } else if !self.back.is_empty() {
+                self.shared = self.back.remove(0);
+                continue;
+            } else {
+                return None;
+            };
+
+            if let Some(leaf) = self.enter(node, false) {
+                return Some(leaf);
+            }
+        }
+    }
+}
+
+impl<N: WalkNode> DoubleEndedIterator for LeafWalk<N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = if let Some(level) = self.back.last_mut() {
+                match level.next_back() {
+                    Some(node) => node,
+                    None => {
+                        self.back.pop();
+                        continue;
+                    }
+                }
+            } else if let Some(node) = self.shared.next_back() {
+                node
+            } else if !self.front.is_empty() {
+                self.shared = self.front.remove(0);
+                continue;
+            } else {
+                return None;
+            };
+
+            if let Some(leaf) = self.enter(node, true) {
+                return Some(leaf);
+            }
+        }
+    }
+}
+
+impl<K, V> LeafWalk<&mut NodeBox<K, V>> {
+    /// The subtrees the walk has yet to hand out leaves from, in key order
+    fn remaining(&self) -> impl Iterator<Item = &NodeBox<K, V>> {
+        let front = self.front.iter().rev().flat_map(|level| level.as_slice());
+        let back = self.back.iter().flat_map(|level| level.as_slice());
+        front.chain(self.shared.as_slice()).chain(back)
+    }
+}
+
+/// The boxes of the first and last leaves of a run, if it has any
+type LeafEnds<K, V> = Option<(NonNull<Node<K, V>>, NonNull<Node<K, V>>)>;
+
+/// Follows the links between the leaves of a tree from both ends of a run
+/// of them, handing out each leaf of the run once. Stepping to the next
+/// leaf is a single pointer read, with no path to keep.
+struct LeafChain<'a, K, V> {
+    /// The first and last leaves of the run not yet handed out, until the
+    /// ends have met
+    ends: LeafEnds<K, V>,
+    /// The chain borrows the tree its leaves are in
+    marker: PhantomData<&'a NodeBox<K, V>>,
+    /// The number of leaves handed out so far
+    #[cfg(test)]
+    visited: usize,
+}
+
+// SAFETY: the chain only reads the leaves of a tree it borrows, as a
+// shared reference to the tree would
+unsafe impl<K: Sync, V: Sync> Send for LeafChain<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LeafChain<'_, K, V> {}
+
+impl<K, V> Clone for LeafChain<'_, K, V> {
+    fn clone(&self) -> Self {
+        LeafChain {
+            ends: self.ends,
+            marker: PhantomData,
+            #[cfg(test)]
+            visited: self.visited,
+        }
+    }
+}
+
+impl<'a, K, V> LeafChain<'a, K, V> {
+    /// The run between the leaves `ends` leads to, in a tree borrowed
+    /// for `'a`
+    fn new(ends: LeafEnds<K, V>) -> Self {
+        LeafChain {
+            ends,
+            marker: PhantomData,
+            #[cfg(test)]
+            visited: 0,
+        }
+    }
+
+    /// Every leaf of the tree under `root`
+    fn whole(root: Option<&'a NodeBox<K, V>>) -> Self {
+        /// The first or last leaf under `node`
+        fn edge<K, V>(mut node: &NodeBox<K, V>, last: bool) -> &NodeBox<K, V> {
+            while let Node::Branch(branch) = &**node {
+                let edge = if last {
+                    branch.children.last()
+                } else {
+                    branch.children.first()
+                };
+                node = edge.expect("branches have children");
+            }
+            node
+        }
+        Self::new(root.map(|root| (edge(root, false).link(), edge(root, true).link())))
+    }
+
+    /// The leaves strictly between `first` and `last`, two leaves of the
+    /// same tree with `first` not after `last`
+    fn inside(first: &'a LeafNode<K, V>, last: &'a LeafNode<K, V>) -> Self {
+        let ends = match (first.links.next, last.links.prev) {
+            // SAFETY: the links of a leaf of a borrowed tree lead to leaves
+            // of that tree
+            (Some(next), Some(prev))
+                if !ptr::eq(first, last) && !ptr::eq(unsafe { linked_leaf(prev) }, first) =>
+            {
+                Some((next, prev))
+            }
+            _ => None,
+        };
+        Self::new(ends)
+    }
+}
+
+impl<'a, K, V> Iterator for LeafChain<'a, K, V> {
+    type Item = &'a LeafNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, last) = self.ends?;
+        // SAFETY: the ends lead to leaves of the tree the chain borrows, and
+        // the map keeps the links between its leaves correct
+        let leaf = unsafe { linked_leaf(first) };
+        self.ends = match leaf.links.next {
+            Some(next) if first != last => Some((next, last)),
+            _ => None,
+        };
+        #[cfg(test)]
+        {
+            self.visited += 1;
+        }
+        Some(leaf)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for LeafChain<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (first, last) = self.ends?;
+        // SAFETY: as in `next`
+        let leaf = unsafe { linked_leaf(last) };
+        self.ends = match leaf.links.prev {
+            Some(prev) if first != last => Some((first, prev)),
+            _ => None,
+        };
+        #[cfg(test)]
+        {
+            self.visited += 1;
+        }
+        Some(leaf)
+    }
+}
+
+/// Yields the items of each leaf of a walk or chain in turn, from either
+/// end. When the ends meet inside a leaf, each takes the rest of the
+/// leaf's items from the other's side.
+struct LeafItems<L: Iterator, I> {
+    leaves: L,
+    /// The number of items not yet yielded from either end, when known
+    len: Option<usize>,
+    /// The remaining items of the leaf the front has reached
+    front: Option<I>,
+    /// The remaining items of the leaf the back has reached
+    back: Option<I>,
+    /// Turns a leaf into an iterator over the items wanted from it
+    open: fn(L::Item) -> I,
+}
+
+impl<L: Iterator + Clone, I: Clone> Clone for LeafItems<L, I> {
+    fn clone(&self) -> Self {
+        LeafItems {
+            leaves: self.leaves.clone(),
+            len: self.len,
+            front: self.front.clone(),
+            back: self.back.clone(),
+            open: self.open,
+        }
+    }
+}
+
+impl<L: Iterator, I> LeafItems<L, I> {
+    /// Yields the items of `leaves`, which hold `len` entries
+    fn new(leaves: L, len: usize, open: fn(L::Item) -> I) -> Self {
+        LeafItems {
+            leaves,
+            len: Some(len),
+            front: None,
+            back: None,
+            open,
+        }
+    }
+
+    fn count_yielded(&mut self) {
+        if let Some(len) = &mut self.len {
+            *len -= 1;
+        }
+    }
+}
+
+impl<L: DoubleEndedIterator, I: DoubleEndedIterator> Iterator for LeafItems<L, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = loop {
+            if let Some(item) = self.front.as_mut().and_then(|items| items.next()) {
+                break item;
+            }
+            match self.leaves.next() {
+                Some(leaf) => self.front = Some((self.open)(leaf)),
+                None => break self.back.as_mut()?.next()?,
+            }
+        };
+        self.count_yielded();
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.len {
+            Some(len) => (len, Some(len)),
+            None => (0, None),
+        }
+    }
+}
+
+impl<L: DoubleEndedIterator, I: DoubleEndedIterator> DoubleEndedIterator for LeafItems<L, I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = loop {
+            if let Some(item) = self.back.as_mut().and_then(|items| items.next_back()) {
+                break item;
+            }
+            match self.leaves.next_back() {
+                Some(leaf) => self.back = Some((self.open)(leaf)),
+                None => break self.front.as_mut()?.next_back()?,
+            }
+        };
+        self.count_yielded();
+        Some(item)
+    }
+}
+
+/// The entries of a leaf by reference
+type LeafEntries<'a, K, V> = iter::Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>;
+
+/// The entries of a leaf with mutable access to the values. Unlike a zip
+/// of the two slice iterators, the entries left can still be looked at.
+struct LeafEntriesMut<'a, K, V> {
+    keys: slice::Iter<'a, K>,
+    values: slice::IterMut<'a, V>,
+}
+
+impl<'a, K, V> LeafEntriesMut<'a, K, V> {
+    fn new(keys: &'a [K], values: &'a mut [V]) -> Self {
+        LeafEntriesMut {
+            keys: keys.iter(),
+            values: values.iter_mut(),
+        }
+    }
+
+    /// The entries not yet yielded
+    fn remaining(&self) -> iter::Zip<slice::Iter<'_, K>, slice::Iter<'_, V>> {
+        self.keys.as_slice().iter().zip(self.values.as_slice())
+    }
+}
+
+impl<'a, K, V> Iterator for LeafEntriesMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((self.keys.next()?, self.values.next()?))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for LeafEntriesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some((self.keys.next_back()?, self.values.next_back()?))
+    }
+}
+
+/// The entries of a leaf moved out of it
+type LeafEntriesOwned<K, V> = iter::Zip<NodeVecIntoIter<K>, NodeVecIntoIter<V>>;
+
+/// A reference iterator over the entries of a `BPlusTreeMap`.
+/// It walks the leaves lazily, one at a time, from either end.
+pub struct Iter<'a, K, V> {
+    inner: LeafItems<LeafChain<'a, K, V>, LeafEntries<'a, K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn new(root: Option<&'a NodeBox<K, V>>, len: usize) -> Self {
+        Iter {
+            inner: LeafItems::new(LeafChain::whole(root), len, |leaf| {
+                leaf.keys.iter().zip(leaf.values.iter())
+            }),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over the entries of a `BPlusTreeMap`.
+/// It walks the leaves lazily, one at a time, from either end.
+pub struct IterMut<'a, K, V> {
+    inner: LeafItems<LeafWalk<&'a mut NodeBox<K, V>>, LeafEntriesMut<'a, K, V>>,
+}
+
+impl<'a, K, V> IterMut<'a, K, V> {
+    fn new(root: Option<&'a mut NodeBox<K, V>>, len: usize) -> Self {
+        IterMut {
+            inner: LeafItems::new(LeafWalk::new(root), len, |leaf| {
+                LeafEntriesMut::new(&leaf.keys, &mut leaf.values)
+            }),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+impl<K: Debug, V: Debug> Debug for IterMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        if let Some(front) = &self.inner.front {
+            list.entries(front.remaining());
+        }
+        for leaf in self
+            .inner
+            .leaves
+            .remaining()
+            .flat_map(|node| LeafWalk::new(Some(node)))
+        {
+            list.entries(leaf.keys.iter().zip(&leaf.values));
+        }
+        if let Some(back) = &self.inner.back {
+            list.entries(back.remaining());
+        }
+        list.finish()
+    }
+}
+
+/// An iterator over the keys of a `BPlusTreeMap`.
+/// It walks the leaves lazily and never touches the values.
+pub struct Keys<'a, K, V> {
+    inner: LeafItems<LeafChain<'a, K, V>, slice::Iter<'a, K>>,
+}
+
+impl<'a, K, V> Keys<'a, K, V> {
+    fn new(root: Option<&'a NodeBox<K, V>>, len: usize) -> Self {
+        Keys {
+            inner: LeafItems::new(LeafChain::whole(root), len, |leaf| leaf.keys.iter()),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for Keys<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the values of a `BPlusTreeMap`.
+/// It walks the leaves lazily and never touches the keys.
+pub struct Values<'a, K, V> {
+    inner: LeafItems<LeafChain<'a, K, V>, slice::Iter<'a, V>>,
+}
+
+impl<'a, K, V> Values<'a, K, V> {
+    fn new(root: Option<&'a NodeBox<K, V>>, len: usize) -> Self {
+        Values {
+            inner: LeafItems::new(LeafChain::whole(root), len, |leaf| leaf.values.iter()),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for Values<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[cfg(test)]
+impl<K, V> Keys<'_, K, V> {
+    /// Number of leaves the iterator has reached so far
+    pub(crate) fn leaves_visited(&self) -> usize {
+        self.inner.leaves.visited
+    }
+}
+
+#[cfg(test)]
+impl<K, V> Values<'_, K, V> {
+    /// Number of leaves the iterator has reached so far
+    pub(crate) fn leaves_visited(&self) -> usize {
+        self.inner.leaves.visited
+    }
+}
+
+/// An owning iterator over the keys of a `BPlusTreeMap`.
+/// The keys are moved out of the leaves rather than cloned.
+pub struct IntoKeys<K, V> {
+    inner: LeafItems<LeafWalk<NodeBox<K, V>>, NodeVecIntoIter<K>>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+/// An owning iterator over the values of a `BPlusTreeMap`.
+/// The values are moved out of the leaves rather than cloned.
+pub struct IntoValues<K, V> {
+    inner: LeafItems<LeafWalk<NodeBox<K, V>>, NodeVecIntoIter<V>>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+/// An owning iterator over the entries of a `BPlusTreeMap`.
+/// The entries are moved out of the leaves rather than cloned.
+pub struct IntoIter<K, V> {
+    inner: LeafItems<LeafWalk<NodeBox<K, V>>, LeafEntriesOwned<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+/// A mutable iterator over the values of a `BPlusTreeMap`.
+/// It walks the leaves lazily and never touches the keys.
+pub struct ValuesMut<'a, K, V> {
+    inner: LeafItems<LeafWalk<&'a mut NodeBox<K, V>>, slice::IterMut<'a, V>>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V: Debug> Debug for ValuesMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        if let Some(front) = &self.inner.front {
+            list.entries(front.as_slice());
+        }
+        for leaf in self
+            .inner
+            .leaves
+            .remaining()
+            .flat_map(|node| LeafWalk::new(Some(node)))
+        {
+            list.entries(&leaf.values);
+        }
+        if let Some(back) = &self.inner.back {
+            list.entries(back.as_slice());
+        }
+        list.finish()
+    }
+}
+
+/// An iterator over a sub-range of the entries of a `BPlusTreeMap`.
+/// It descends once to each end of the range and follows the links between
+/// the leaves in between lazily, from either end.
+pub struct Range<'a, K, V> {
+    inner: LeafItems<LeafChain<'a, K, V>, LeafEntries<'a, K, V>>,
+}
+
+/// Panics if `range` is one that `BTreeMap::range` would also reject
+pub(crate) fn check_range_bounds<Q, R>(range: &R)
+where
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+{
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+            panic!("range start and end are equal and excluded in BPlusTreeMap")
+        }
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) if start > end => panic!("range start is greater than range end in BPlusTreeMap"),
+        _ => {}
+    }
+}
+
+/// Converts the end bound of a range into the start bound that lands on the
+/// first key past the range, or None if the range runs to the end of the map
+fn end_as_start_bound<Q: ?Sized>(bound: Bound<&Q>) -> Option<Bound<&Q>> {
+    match bound {
+        Bound::Included(key) => Some(Bound::Excluded(key)),
+        Bound::Excluded(key) => Some(Bound::Included(key)),
+        Bound::Unbounded => None,
+    }
+}
+
+/// Returns the index of the child of `branch` where keys satisfying the
+/// start `bound` begin
+fn child_index_for_bound<K, V, Q>(branch: &BranchNode<K, V>, bound: Bound<&Q>) -> usize
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match bound {
+        Bound::Included(key) | Bound::Excluded(key) => branch.child_index_for(key),
+        Bound::Unbounded => 0,
+    }
+}
+
+/// Returns the index of the first key in a leaf that satisfies the start `bound`
+fn leaf_index_for_bound<K, Q>(keys: &[K], bound: Bound<&Q>) -> usize
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match bound {
+        Bound::Included(key) => keys.partition_point(|k| k.borrow() < key),
+        Bound::Excluded(key) => keys.partition_point(|k| k.borrow() <= key),
+        Bound::Unbounded => 0,
+    }
+}
+
+impl<'a, K, V> Range<'a, K, V> {
+    /// Creates a range iterator over the tree rooted at `root`
+    fn new<Q, R>(root: Option<&'a Node<K, V>>, range: R) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        check_range_bounds(&range);
+        Self::between(root, range.start_bound(), range.end_bound())
+    }
+
+    /// Creates an iterator over the entries of the tree rooted at `root`
+    /// from the `start` bound to the `end` bound, which must be in order
+    fn between<Q>(root: Option<&'a Node<K, V>>, start: Bound<&Q>, end: Bound<&Q>) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Self::between_by(
+            root,
+            |key| match start {
+                Bound::Included(start) => key.borrow() < start,
+                Bound::Excluded(start) => key.borrow() <= start,
+                Bound::Unbounded => false,
+            },
+            |key| match end {
+                Bound::Included(end) => key.borrow() <= end,
+                Bound::Excluded(end) => key.borrow() < end,
+                Bound::Unbounded => true,
+            },
+        )
+    }
+
+    /// Creates an iterator over the entries of the tree rooted at `root`
+    /// from the first key for which `before_start` is false up to the first
+    /// key for which `before_end` is false. Each must hold for a run of keys
+    /// at the start of the map, the run of `before_end` being the longer.
+    fn between_by(
+        root: Option<&'a Node<K, V>>,
+        before_start: impl Fn(&K) -> bool,
+        before_end: impl Fn(&K) -> bool,
+    ) -> Self {
+        let mut leaves = LeafChain::new(None);
+        let mut front = None;
+        let mut back = None;
+
+        // A range ending before the tree's smallest key, or starting after
+        // its largest, holds nothing from it
+        let outside = |root: &Node<K, V>| match root {
+            Node::Branch(branch) => {
+                branch.min_key.as_ref().is_some_and(|min| !before_end(min))
+                    || branch.max_key.as_ref().is_some_and(&before_start)
+            }
+            Node::Leaf(_) => false,
+        };
+        if let Some(root) = root
+            && !outside(root)
+        {
+            // Each end descends to its own leaf, and the leaves between
+            // them are reached along the links. An end before the start
+            // is taken to be the start, leaving the range empty.
+            let (first, start) = Self::descend(root, &before_start);
+            let (last, end) = Self::descend(root, &|key| before_start(key) || before_end(key));
+            if ptr::eq(first, last) {
+                let end = end.max(start);
+                front = Some(first.keys[start..end].iter().zip(&first.values[start..end]));
+            } else {
+                front = Some(first.keys[start..].iter().zip(&first.values[start..]));
+                back = Some(last.keys[..end].iter().zip(&last.values[..end]));
+                leaves = LeafChain::inside(first, last);
+            }
+        }
+
+        Range {
+            inner: LeafItems {
+                leaves,
+                len: None,
+                front,
+                back,
+                open: |leaf| leaf.keys.iter().zip(leaf.values.iter()),
+            },
+        }
+    }
+
+    /// Descends from `node` to the leaf holding the first key for which
+    /// `before` is false, and returns it with the index of that key
+    fn descend(
+        mut node: &'a Node<K, V>,
+        before: &impl Fn(&K) -> bool,
+    ) -> (&'a LeafNode<K, V>, usize) {
+        loop {
+            match node {
+                Node::Leaf(leaf) => return (leaf, leaf.keys.partition_point(before)),
+                Node::Branch(branch) => {
+                    let idx = branch.keys.partition_point(|key| before(key));
+                    node = &branch.children[idx];
+                }
+            }
+        }
+    }
+
+    /// Descends from `node` to the leaf where the start `bound` falls and
+    /// returns it with the index of the first key inside the bound
+    fn seek<Q>(mut node: &'a Node<K, V>, bound: Bound<&Q>) -> Option<(&'a LeafNode<K, V>, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        loop {
+            match node {
+                Node::Leaf(leaf) => return Some((leaf, leaf_index_for_bound(&leaf.keys, bound))),
+                Node::Branch(branch) => {
+                    let idx = child_index_for_bound(branch, bound);
+                    node = branch.children.get(idx)?;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+
+impl<K, V> Clone for Range<'_, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for Range<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A mutable iterator over a sub-range of the entries of a `BPlusTreeMap`.
+/// Like `Range`, it descends once to the start of the range and walks
+/// forward leaf by leaf, handing out mutable references to the values.
+pub struct RangeMut<'a, K, V> {
+    /// The unvisited siblings of each node on the path to the current leaf
+    stack: Vec<slice::IterMut<'a, NodeBox<K, V>>>,
+    /// The remaining in-range entries of the current leaf
+    entries: Option<LeafEntriesMut<'a, K, V>>,
+    /// The leaf and position just past the last entry in the range, or None
+    /// if the range runs to the end of the map. The leaf is only ever
+    /// compared by address, never dereferenced.
+    end: Option<(*const LeafNode<K, V>, usize)>,
+    /// Whether the current leaf holds the end of the range
+    at_end: bool,
+}
+
+impl<'a, K, V> RangeMut<'a, K, V> {
+    /// Creates a mutable range iterator over the tree rooted at `root`
+    fn new<Q, R>(root: Option<&'a mut Node<K, V>>, range: R) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        check_range_bounds(&range);
+
+        let end = end_as_start_bound(range.end_bound()).and_then(|bound| {
+            let root = root.as_deref()?;
+            let (leaf, position) = Range::seek(root, bound)?;
+            Some((leaf as *const LeafNode<K, V>, position))
+        });
+
+        let mut iter = RangeMut {
+            stack: Vec::new(),
+            entries: None,
+            end,
+            at_end: false,
+        };
+
+        let mut node = match root {
+            Some(root) => root,
+            None => return iter,
+        };
+        let bound = range.start_bound();
+        loop {
+            match node {
+                Node::Leaf(leaf) => {
+                    let position = leaf_index_for_bound(&leaf.keys, bound);
+                    iter.enter_leaf(leaf, position);
+                    return iter;
+                }
+                Node::Branch(branch) => {
+                    let idx = child_index_for_bound(branch, bound);
+                    let mut siblings = match branch.children.get_mut(idx..) {
+                        Some(children) => children.iter_mut(),
+                        None => return iter,
+                    };
+                    node = match siblings.next() {
+                        Some(child) => child,
+                        None => return iter,
+                    };
+                    iter.stack.push(siblings);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K, V> RangeMut<'a, K, V> {
+    /// Starts iterating over `leaf` from `position`, stopping early if the
+    /// range ends inside it
+    fn enter_leaf(&mut self, leaf: &'a mut LeafNode<K, V>, position: usize) {
+        self.at_end = false;
+        let mut limit = leaf.keys.len();
+        if let Some((end_leaf, end_position)) = self.end
+            && std::ptr::eq(&*leaf, end_leaf)
+        {
+            self.at_end = true;
+            limit = end_position.max(position);
+        }
+
+        self.entries = Some(LeafEntriesMut::new(
+            &leaf.keys[position..limit],
+            &mut leaf.values[position..limit],
+        ));
+    }
+
+    /// Moves on to the next leaf in key order
+    fn next_leaf(&mut self) -> Option<&'a mut LeafNode<K, V>> {
+        loop {
+            let siblings = self.stack.last_mut()?;
+            match siblings.next().map(NodeBox::as_mut) {
+                Some(Node::Leaf(leaf)) => return Some(leaf),
+                Some(Node::Branch(branch)) => self.stack.push(branch.children.iter_mut()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.as_mut().and_then(|entries| entries.next()) {
+                return Some(entry);
+            }
+            if self.at_end {
+                self.entries = None;
+                return None;
+            }
+            let leaf = self.next_leaf()?;
+            self.enter_leaf(leaf, 0);
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for RangeMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        if let Some(entries) = &self.entries {
+            list.entries(entries.remaining());
+        }
+        if self.at_end {
+            return list.finish();
+        }
+
+        let nodes = self
+            .stack
+            .iter()
+            .rev()
+            .flat_map(|siblings| siblings.as_slice());
+        for leaf in nodes.flat_map(|node| LeafWalk::new(Some(node))) {
+            match self.end {
+                Some((end_leaf, end_position)) if std::ptr::eq(leaf, end_leaf) => {
+                    let entries = leaf.keys[..end_position].iter().zip(&leaf.values);
+                    list.entries(entries);
+                    break;
+                }
+                _ => {
+                    list.entries(leaf.keys.iter().zip(&leaf.values));
+                }
+            }
+        }
+        list.finish()
+    }
+}
+
+/// An iterator that removes and yields the entries of a `BPlusTreeMap`
+/// matching a predicate. Created by `BPlusTreeMap::extract_if`.
+///
+/// A cursor walks the leaves in key order and takes matching entries out
+/// of the leaf it is in. Each node the cursor leaves is merged or
+/// rebalanced with the sibling before it, which the cursor has already
+/// left; a first child waits for the sibling after it instead. Dropping
+/// the iterator settles the nodes the cursor is still in.
+pub struct ExtractIf<'a, K, V, F>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    map: &'a mut BPlusTreeMap<K, V>,
+    pred: F,
+    /// The boxes of the branches above the cursor's leaf, from the root
+    /// down, each with the index of the child the cursor is under
+    path: Vec<(NonNull<Node<K, V>>, usize)>,
+    /// The box of the leaf the cursor is in, until it has left the last one
+    leaf: Option<NonNull<Node<K, V>>>,
+    /// The index in the leaf of the next entry to hand to the predicate
+    idx: usize,
+    /// Whether any entries have been taken out of the leaf
+    trimmed: bool,
+}
+
+// SAFETY: the pointers lead into the tree of the map the iterator borrows
+// exclusively, and are only followed through a borrow of the iterator
+unsafe impl<K, V, F> Send for ExtractIf<'_, K, V, F>
+where
+    K: Ord + Clone + Debug + Send,
+    V: Clone + Debug + Send,
+    F: Send,
+{
+}
+unsafe impl<K, V, F> Sync for ExtractIf<'_, K, V, F>
+where
+    K: Ord + Clone + Debug + Sync,
+    V: Clone + Debug + Sync,
+    F: Sync,
+{
+}
+
+impl<'a, K, V, F> ExtractIf<'a, K, V, F>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Creates an iterator with its cursor on the first entry of `map`
+    fn new(map: &'a mut BPlusTreeMap<K, V>, pred: F) -> Self {
+        let root = map.root.as_ref().map(NodeBox::link);
+        let mut iter = ExtractIf {
+            map,
+            pred,
+            path: Vec::new(),
+            leaf: None,
+            idx: 0,
+            trimmed: false,
+        };
+        if let Some(root) = root {
+            iter.descend(root);
+        }
+        iter
+    }
+
+    /// The branch in the box `node` leads to.
+    ///
+    /// # Safety
+    ///
+    /// `node` must lead to the live box of a branch of the map's tree that
+    /// nothing else borrows for `'b`.
+    unsafe fn branch<'b>(node: NonNull<Node<K, V>>) -> &'b mut BranchNode<K, V> {
+        // SAFETY: the caller vouches for the box and its branch
+        match unsafe { &mut *node.as_ptr() } {
+            Node::Branch(branch) => branch,
+            Node::Leaf(_) => unreachable!("the path holds only branches"),
+        }
+    }
+
+    /// Moves the cursor to the first entry under the node in the box `node`
+    fn descend(&mut self, mut node: NonNull<Node<K, V>>) {
+        // SAFETY: `node` leads to a box of the map's tree, which the
+        // iterator borrows exclusively
+        while let Node::Branch(branch) = unsafe { &*node.as_ptr() } {
+            self.path.push((node, 0));
+            node = branch.children[0].link();
+        }
+        self.leaf = Some(node);
+        self.idx = 0;
+        self.trimmed = false;
+    }
+
+    /// Takes the cursor out of its leaf, bringing the leaf's filter up to
+    /// date if entries were taken out of it
+    fn leave_leaf(&mut self) {
+        if let Some(leaf) = self.leaf.take()
+            && self.trimmed
+            // SAFETY: the cursor's leaf is a live leaf of the map's tree
+            && let Node::Leaf(leaf) = unsafe { &mut *leaf.as_ptr() }
+        {
+            leaf.refresh_filter();
+        }
+    }
+
+    /// Moves the cursor on to the next leaf, settling each node it leaves
+    /// on the way, or out of the tree after the last leaf
+    fn next_leaf(&mut self) {
+        self.leave_leaf();
+        while let Some((node, idx)) = self.path.pop() {
+            // SAFETY: the path leads through live branches of the map's
+            // tree, which only the iterator changes while it borrows the map
+            let branch = unsafe { Self::branch(node) };
+            let next = BPlusTreeMap::settle_child(
+                branch,
+                idx,
+                &self.map.insertion_balancer,
+                &self.map.removal_balancer,
+            );
+            if next < branch.children.len() {
+                self.path.push((node, next));
+                self.descend(branch.children[next].link());
+                return;
+            }
+            branch.refresh_fences();
+        }
+        self.map.settle_root();
+    }
+}
+
+impl<K, V, F> Iterator for ExtractIf<'_, K, V, F>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // SAFETY: the cursor's leaf is a live leaf of the map's tree,
+            // and nothing else borrows it while the iterator does
+            let Node::Leaf(leaf) = (unsafe { &mut *self.leaf?.as_ptr() }) else {
+                unreachable!("the cursor is in a leaf");
+            };
+            if self.idx == leaf.keys.len() {
+                self.next_leaf();
+            } else if (self.pred)(&leaf.keys[self.idx], &mut leaf.values[self.idx]) {
+                self.trimmed = true;
+                self.map.size -= 1;
+                return Some((leaf.keys.remove(self.idx), leaf.values.remove(self.idx)));
+            } else {
+                self.idx += 1;
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.len()))
+    }
+}
+
+impl<K, V, F> Drop for ExtractIf<'_, K, V, F>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn drop(&mut self) {
+        if self.leaf.is_none() {
+            return;
+        }
+        // Settle the nodes the cursor is in from its leaf up, as if it had
+        // left them, and the first child of each, which may be waiting for
+        // a sibling the cursor never reached
+        self.leave_leaf();
+        while let Some((node, idx)) = self.path.pop() {
+            // SAFETY: as in `next_leaf`
+            let branch = unsafe { Self::branch(node) };
+            BPlusTreeMap::settle_child(
+                branch,
+                idx,
+                &self.map.insertion_balancer,
+                &self.map.removal_balancer,
+            );
+            if branch.children.len() > 1 {
+                BPlusTreeMap::balance_pair(branch, 1, &self.map.removal_balancer);
+            }
+            branch.refresh_fences();
+        }
+        self.map.settle_root();
+    }
+}
+
+impl<K, V> IntoIterator for BPlusTreeMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: LeafItems::new(LeafWalk::new(self.root), self.size, |leaf| {
+                leaf.keys.into_iter().zip(leaf.values)
+            }),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for BPlusTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Format the entries where they are, without copying the map
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Implement Clone for BPlusTreeMap
+impl<K: Clone, V: Clone> Clone for BPlusTreeMap<K, V> {
+    fn clone(&self) -> Self {
+        // Copy the nodes as they are, so the clone has the same shape as
+        // this map and no entry is compared or cloned more than once
+        let mut map = BPlusTreeMap {
+            root: self.root.clone(),
+            config: self.config.clone(),
+            size: self.size,
+            insertion_balancer: self.insertion_balancer.with_config(self.config.clone()),
+            removal_balancer: RemovalBalancer::new(self.config.clone()),
+            pool: NodePool::new(),
+            last_leaf: Cell::new(Vec::new()),
+        };
+        map.link_all_leaves();
+        map
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        // Clone into the nodes this map already has where the two trees
+        // line up, keeping their allocations; the rest are copied afresh
+        self.root.clone_from(&source.root);
+        self.size = source.size;
+        if !Rc::ptr_eq(&self.config, &source.config) {
+            self.config = source.config.clone();
+            self.removal_balancer = RemovalBalancer::new(self.config.clone());
+        }
+        self.insertion_balancer = source.insertion_balancer.with_config(self.config.clone());
+        self.last_leaf.get_mut().clear();
+        self.link_all_leaves();
+    }
+}
+
+// Implement Default for BPlusTreeMap
+impl<K, V> Default for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implement Index for BPlusTreeMap
+impl<K, V, Q> Index<&Q> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug + Borrow<Q>,
+    V: Clone + Debug,
+    Q: Ord + FilterKey + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &Self::Output {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+// Implement IndexMut for BPlusTreeMap
+impl<K, V, Q> IndexMut<&Q> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug + Borrow<Q>,
+    V: Clone + Debug,
+    Q: Ord + FilterKey + ?Sized,
+{
+    fn index_mut(&mut self, key: &Q) -> &mut Self::Output {
+        self.get_mut(key).expect("no entry found for key")
+    }
+}
+
+impl<K, V> BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    /// This method provides a more efficient way to manipulate entries in the map
+    /// without having to do multiple lookups: the entry is found with a single
+    /// descent, and the path to its leaf is kept for the entry's methods to
+    /// follow back without comparing keys again.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match self.find_slot(&key) {
+            Ok(idx) => Entry::Occupied(OccupiedEntry {
+                map: self,
+                key,
+                idx,
+            }),
+            Err(idx) => Entry::Vacant(VacantEntry {
+                map: self,
+                key,
+                idx,
+            }),
+        }
+    }
+
+    /// Gets the entry for a borrowed key in the map for in-place manipulation.
+    /// Unlike `entry`, no owned key is needed up front: it is built from the
+    /// borrowed key only when a value is inserted into a vacant entry.
+    pub fn entry_ref<'b, Q>(&mut self, key: &'b Q) -> EntryRef<'_, 'b, K, V, Q>
+    where
+        K: Borrow<Q> + From<&'b Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.find_slot(key) {
+            Ok(idx) => EntryRef::Occupied(OccupiedEntryRef {
+                map: self,
+                key,
+                idx,
+            }),
+            Err(idx) => EntryRef::Vacant(VacantEntryRef {
+                map: self,
+                key,
+                idx,
+            }),
+        }
+    }
+
+    /// Returns the entry with the smallest key for in-place manipulation,
+    /// or None if the map is empty
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let path = self.last_leaf.get_mut();
+        path.clear();
+        let mut node = self.root.as_deref()?;
+        while let Node::Branch(branch) = node {
+            path.push(0);
+            node = branch.children.first()?;
+        }
+        let key = match node {
+            Node::Leaf(leaf) => leaf.keys.first()?.clone(),
+            Node::Branch(_) => unreachable!(),
+        };
+        Some(OccupiedEntry {
+            map: self,
+            key,
+            idx: 0,
+        })
+    }
+
+    /// Returns the entry with the largest key for in-place manipulation,
+    /// or None if the map is empty
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let path = self.last_leaf.get_mut();
+        path.clear();
+        let mut node = self.root.as_deref()?;
+        while let Node::Branch(branch) = node {
+            path.push(branch.children.len().checked_sub(1)?);
+            node = branch.children.last()?;
+        }
+        let (key, idx) = match node {
+            Node::Leaf(leaf) => (leaf.keys.last()?.clone(), leaf.keys.len() - 1),
+            Node::Branch(_) => unreachable!(),
+        };
+        Some(OccupiedEntry {
+            map: self,
+            key,
+            idx,
+        })
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys fall within `range`.
+    /// The iterator yields the pairs in ascending order by key. Like `get`,
+    /// it takes bounds of any type the keys borrow as, so a map with `String`
+    /// keys can be queried with `&str` bounds without building `String`s:
+    /// `map.range::<str, _>((Bound::Included("a"), Bound::Excluded("m")))`.
+    ///
+    /// Panics if the start of the range is greater than its end, or if both
+    /// ends are equal and excluded.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range::new(self.root.as_deref(), range)
+    }
+
+    /// Returns a view of the entries whose keys fall within `range`. The
+    /// view borrows the map and answers lookups as if only those entries
+    /// existed; keys outside the range are never found.
+    ///
+    /// Panics in the same cases as `range`.
+    pub fn sub_map<R>(&self, range: R) -> SubMap<'_, K, V>
+    where
+        R: RangeBounds<K>,
+    {
+        check_range_bounds(&range);
+        SubMap {
+            map: self,
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs with keys greater than
+    /// or equal to `key`, in ascending order by key. It descends straight
+    /// to the leaf where `key` falls, so resuming a walk through the map
+    /// costs no more than a lookup.
+    pub fn iter_from<Q>(&self, key: &Q) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Range::between(self.root.as_deref(), Bound::Included(key), Bound::Unbounded)
+    }
+
+    /// Returns an iterator over the key-value pairs with keys strictly
+    /// greater than `key`, in ascending order by key. Like `iter_from`, it
+    /// descends straight to the leaf where `key` falls.
+    pub fn iter_from_exclusive<Q>(&self, key: &Q) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Range::between(self.root.as_deref(), Bound::Excluded(key), Bound::Unbounded)
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys start with
+    /// `prefix`, in ascending order by key. With `(user, timestamp)` keys,
+    /// `range_prefix(&user)` yields every entry of one user without making
+    /// up the smallest and largest timestamps to bound a `range` with.
+    pub fn range_prefix<P>(&self, prefix: &P) -> Range<'_, K, V>
+    where
+        K: KeyPrefix<P>,
+        P: ?Sized,
+    {
+        Range::between_by(
+            self.root.as_deref(),
+            |key| key.cmp_prefix(prefix) == Ordering::Less,
+            |key| key.cmp_prefix(prefix) != Ordering::Greater,
+        )
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys start with
+    /// the string `prefix`, in ascending order by key. It descends to the
+    /// first such key and stops at the last one, without looking at the
+    /// rest of the map. An empty prefix yields the whole map.
+    pub fn iter_prefix(&self, prefix: &str) -> Range<'_, K, V>
+    where
+        K: Borrow<str>,
+    {
+        // Keys starting with the prefix sort right after the prefix itself
+        Range::between_by(
+            self.root.as_deref(),
+            |key| key.borrow() < prefix,
+            |key| key.borrow() < prefix || key.borrow().starts_with(prefix),
+        )
+    }
+
+    /// Returns a mutable iterator over the key-value pairs whose keys fall
+    /// within `range`. The iterator yields the pairs in ascending order by key.
+    ///
+    /// Panics if the start of the range is greater than its end, or if both
+    /// ends are equal and excluded.
+    pub fn range_mut<Q, R>(&mut self, range: R) -> RangeMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        RangeMut::new(self.root.as_deref_mut(), range)
+    }
+}
+
+/// A leaf of a tree found by descending from the root: the children of
+/// each branch on the way down, and which of them the way takes
+struct LeafPosition<'a, K, V> {
+    path: Vec<(&'a [NodeBox<K, V>], usize)>,
+}
+
+impl<'a, K, V> LeafPosition<'a, K, V> {
+    /// The leaf reached from `root` by taking the child `descend` picks at
+    /// each branch
+    fn new(root: &'a NodeBox<K, V>, descend: impl Fn(&BranchNode<K, V>) -> usize) -> Self {
+        let mut path = vec![(slice::from_ref(root), 0)];
+        while let Node::Branch(branch) = &**node_at(&path) {
+            let idx = descend(branch).min(branch.children.len() - 1);
+            path.push((&branch.children[..], idx));
+        }
+        LeafPosition { path }
+    }
+
+    /// The box of the leaf
+    fn leaf(&self) -> &'a NodeBox<K, V> {
+        node_at(&self.path)
+    }
+
+    /// Moves to the leaf after this one, or before it if not `forward`.
+    /// Returns false, staying put, if there is no such leaf.
+    fn step(&mut self, forward: bool) -> bool {
+        let Some(depth) = self.path.iter().rposition(|&(siblings, idx)| {
+            if forward {
+                idx + 1 < siblings.len()
+            } else {
+                idx > 0
+            }
+        }) else {
+            return false;
+        };
+        self.path.truncate(depth + 1);
+        let (_, idx) = &mut self.path[depth];
+        if forward {
+            *idx += 1;
+        } else {
+            *idx -= 1;
+        }
+        while let Node::Branch(branch) = &**self.leaf() {
+            let idx = if forward {
+                0
+            } else {
+                branch.children.len() - 1
+            };
+            self.path.push((&branch.children[..], idx));
+        }
+        true
+    }
+}
+
+/// The node the last step of `path` leads to
+fn node_at<'a, K, V>(path: &[(&'a [NodeBox<K, V>], usize)]) -> &'a NodeBox<K, V> {
+    let &(siblings, idx) = path.last().expect("a path starts at the root");
+    &siblings[idx]
+}
+
+// Linking the leaves only looks at the shape of the tree
+impl<K, V> BPlusTreeMap<K, V> {
+    /// Puts `root` at the top of the map's tree, after an operation that
+    /// built the tree or took it apart, and links its leaves
+    fn set_root(&mut self, root: Option<Node<K, V>>) {
+        self.root = root.map(NodeBox::new);
+        self.link_all_leaves();
+    }
+
+    /// Links every leaf to the leaves either side of it, after an
+    /// operation that put the tree together without keeping them linked
+    fn link_all_leaves(&mut self) {
+        fn link<K, V>(node: &NodeBox<K, V>, prev: &mut LeafLink<K, V>) {
+            match &**node {
+                Node::Leaf(_) => {
+                    // SAFETY: `prev` is the leaf linked just before this one,
+                    // in the tree the map holds exclusively
+                    unsafe { link_leaves(*prev, [node], None) };
+                    *prev = Some(node.link());
+                }
+                Node::Branch(branch) => {
+                    for child in &branch.children {
+                        link(child, prev);
+                    }
+                }
+            }
+        }
+        if let Some(root) = &self.root {
+            link(root, &mut None);
+        }
+    }
+
+    /// Links the leaves within `reach` of the one `descend` picks a way
+    /// down to, after an operation that changed leaves only there, and the
+    /// facing links of the leaves just beyond them. The leaves are found
+    /// through the branches, so links left leading to freed boxes are
+    /// overwritten without being followed.
+    fn relink_around(&mut self, reach: usize, descend: impl Fn(&BranchNode<K, V>) -> usize) {
+        let Some(root) = &self.root else {
+            return;
+        };
+        let mut position = LeafPosition::new(root, descend);
+        let mut before = 0;
+        while before <= reach && position.step(false) {
+            before += 1;
+        }
+        let mut leaves = vec![position.leaf()];
+        while leaves.len() < before + reach + 2 && position.step(true) {
+            leaves.push(position.leaf());
+        }
+        // The leaves one past the reach keep their far links
+        let prev = if before > reach {
+            Some(leaves.remove(0).link())
+        } else {
+            None
+        };
+        let next = if leaves.len() > before.min(reach) + reach + 1 {
+            leaves.pop().map(NodeBox::link)
+        } else {
+            None
+        };
+        // SAFETY: `prev` and `next` lead to leaves of the tree found through
+        // its branches, which the map holds exclusively
+        unsafe { link_leaves(prev, leaves, next) };
+    }
+}
+
+// Iterating hands out references into the leaves or moves their contents
+// out, so it needs nothing from the keys and values themselves
+impl<K, V> BPlusTreeMap<K, V> {
+    /// Returns an iterator over the key-value pairs of the map.
+    /// The iterator yields all key-value pairs in ascending order by key.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self.root.as_ref(), self.size)
+    }
+
+    /// Returns an iterator over the keys of the map.
+    /// The iterator yields all keys in ascending order.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys::new(self.root.as_ref(), self.size)
+    }
+
+    /// Returns an iterator over the values of the map.
+    /// The iterator yields all values in ascending order by key.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values::new(self.root.as_ref(), self.size)
+    }
+
+    /// Returns the key-value pair with the smallest key, or None if the
+    /// map is empty
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node {
+                Node::Leaf(leaf) => return Some((leaf.keys.first()?, leaf.values.first()?)),
+                Node::Branch(branch) => node = branch.children.first()?,
+            }
+        }
+    }
+
+    /// Returns the key-value pair with the largest key, or None if the
+    /// map is empty
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node {
+                Node::Leaf(leaf) => return Some((leaf.keys.last()?, leaf.values.last()?)),
+                Node::Branch(branch) => node = branch.children.last()?,
+            }
+        }
+    }
+
+    /// Returns the branching factor the map was created with
+    pub fn branching_factor(&self) -> usize {
+        self.config.branching_factor
+    }
+
+    /// The root node of the tree, or None if the map is empty
+    #[cfg(any(test, feature = "serde", feature = "rayon", feature = "deepsize"))]
+    pub(crate) fn root_node(&self) -> Option<&NodeBox<K, V>> {
+        self.root.as_ref()
+    }
+
+    /// The configuration shared by the map and its balancers
+    #[cfg(feature = "deepsize")]
+    pub(crate) fn config(&self) -> &Rc<BPlusTreeConfig> {
+        &self.config
+    }
+
+    /// The pool of emptied nodes kept for reuse
+    #[cfg(feature = "deepsize")]
+    pub(crate) fn node_pool(&self) -> &NodePool<K, V> {
+        &self.pool
+    }
+
+    /// Returns a view of the map that iterates in descending key order.
+    /// Nothing is copied: the view borrows the map and walks its leaves
+    /// from the back.
+    pub fn reversed(&self) -> Reversed<'_, K, V> {
+        Reversed { map: self }
+    }
+
+    /// Creates a consuming iterator visiting all the keys in ascending order.
+    /// The map cannot be used after calling this.
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            inner: LeafItems::new(LeafWalk::new(self.root), self.size, |leaf| {
+                leaf.keys.into_iter()
+            }),
+        }
+    }
+
+    /// Creates a consuming iterator visiting all the values in ascending
+    /// order by key. The map cannot be used after calling this.
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues {
+            inner: LeafItems::new(LeafWalk::new(self.root), self.size, |leaf| {
+                leaf.values.into_iter()
+            }),
+        }
+    }
+
+    /// Calls `f` with the keys and values of each leaf in turn, in
+    /// ascending key order. The slices are borrowed straight from the
+    /// leaves, so nothing is allocated or cloned.
+    pub fn for_each_leaf<F>(&self, mut f: F)
+    where
+        F: FnMut(&[K], &[V]),
+    {
+        let _ = self.try_for_each_leaf(|keys, values| {
+            f(keys, values);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+
+    /// Like `for_each_leaf`, but stops at the first leaf for which `f`
+    /// returns `ControlFlow::Break`, and returns that break
+    pub fn try_for_each_leaf<B, F>(&self, mut f: F) -> ControlFlow<B>
+    where
+        F: FnMut(&[K], &[V]) -> ControlFlow<B>,
+    {
+        fn visit<K, V, B>(
+            node: &Node<K, V>,
+            f: &mut impl FnMut(&[K], &[V]) -> ControlFlow<B>,
+        ) -> ControlFlow<B> {
+            match node {
+                Node::Leaf(leaf) => f(&leaf.keys, &leaf.values),
+                Node::Branch(branch) => {
+                    branch.children.iter().try_for_each(|child| visit(child, f))
+                }
+            }
+        }
+
+        match &self.root {
+            Some(root) => visit(root, &mut f),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    /// Returns a mutable iterator over the values of the map.
+    /// The iterator yields all values in ascending order by key, the same
+    /// order `keys` yields the keys in, whatever the shape of the tree.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: LeafItems::new(LeafWalk::new(self.root.as_mut()), self.size, |leaf| {
+                leaf.values.iter_mut()
+            }),
+        }
+    }
+
+    /// Returns a mutable iterator over the key-value pairs of the map.
+    /// The iterator yields all key-value pairs in ascending order by key,
+    /// whatever the shape of the tree.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut::new(self.root.as_mut(), self.size)
+    }
+}
+
+/// A view of a `BPlusTreeMap` in descending key order, created by
+/// `BPlusTreeMap::reversed`. Its iterators walk the map from the largest
+/// key down, and its first entry is the map's last.
+pub struct Reversed<'a, K, V> {
+    map: &'a BPlusTreeMap<K, V>,
+}
+
+impl<'a, K, V> Reversed<'a, K, V> {
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.map.size
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.map.size == 0
+    }
+
+    /// Returns an iterator over the key-value pairs in descending order by key
+    pub fn iter(&self) -> iter::Rev<Iter<'a, K, V>> {
+        self.map.iter().rev()
+    }
+
+    /// Returns an iterator over the keys in descending order
+    pub fn keys(&self) -> iter::Rev<Keys<'a, K, V>> {
+        self.map.keys().rev()
+    }
+
+    /// Returns an iterator over the values in descending order by key
+    pub fn values(&self) -> iter::Rev<Values<'a, K, V>> {
+        self.map.values().rev()
+    }
+
+    /// Returns the key-value pair with the largest key, which comes first
+    /// in descending order, or None if the map is empty
+    pub fn first_key_value(&self) -> Option<(&'a K, &'a V)> {
+        self.map.last_key_value()
+    }
+
+    /// Returns the key-value pair with the smallest key, which comes last
+    /// in descending order, or None if the map is empty
+    pub fn last_key_value(&self) -> Option<(&'a K, &'a V)> {
+        self.map.first_key_value()
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys fall within
+    /// `range`, in descending order by key. The range is given low end
+    /// first, as for `BPlusTreeMap::range`, and panics in the same cases.
+    pub fn range<Q, R>(&self, range: R) -> iter::Rev<Range<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range::new(self.map.root.as_deref(), range).rev()
+    }
+}
+
+impl<K, V> Clone for Reversed<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Reversed<'_, K, V> {}
+
+impl<K: Debug, V: Debug> Debug for Reversed<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A view of the entries of a `BPlusTreeMap` whose keys fall within a
+/// range, created by `BPlusTreeMap::sub_map`. Lookups are clamped to the
+/// range and iteration covers only the entries inside it.
+pub struct SubMap<'a, K, V> {
+    map: &'a BPlusTreeMap<K, V>,
+    start: Bound<K>,
+    end: Bound<K>,
+}
+
+impl<'a, K, V> SubMap<'a, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Returns true if `key` falls within the bounds of the view
+    fn in_bounds<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let after_start = match &self.start {
+            Bound::Included(start) => key >= start.borrow(),
+            Bound::Excluded(start) => key > start.borrow(),
+            Bound::Unbounded => true,
+        };
+        let before_end = match &self.end {
+            Bound::Included(end) => key <= end.borrow(),
+            Bound::Excluded(end) => key < end.borrow(),
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+
+    /// Returns a reference to the value for `key`, or None if the key is
+    /// outside the view or not in the map
+    pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + FilterKey + ?Sized,
+    {
+        if self.in_bounds(key) {
+            self.map.get(key)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if `key` is inside the view and in the map
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + FilterKey + ?Sized,
+    {
+        self.in_bounds(key) && self.map.contains_key(key)
+    }
+
+    /// Returns the number of entries inside the view. The entries are
+    /// counted one by one, so this takes time linear in the answer.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns true if no entries fall inside the view
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns an iterator over the entries inside the view, in ascending
+    /// order by key
+    pub fn iter(&self) -> Range<'a, K, V> {
+        // Unlike `range`, nothing is rejected here: nested views whose
+        // bounds don't overlap are simply empty
+        Range::between(
+            self.map.root.as_deref(),
+            self.start.as_ref(),
+            self.end.as_ref(),
+        )
+    }
+
+    /// Returns the entry with the smallest key inside the view, or None if
+    /// the view is empty
+    pub fn first_key_value(&self) -> Option<(&'a K, &'a V)> {
+        self.iter().next()
+    }
+
+    /// Returns the entry with the largest key inside the view, or None if
+    /// the view is empty
+    pub fn last_key_value(&self) -> Option<(&'a K, &'a V)> {
+        self.iter().next_back()
+    }
+
+    /// Returns a view of the entries that fall both inside this view and
+    /// within `range`
+    ///
+    /// Panics in the same cases as `BPlusTreeMap::range`.
+    pub fn sub_map<R>(&self, range: R) -> SubMap<'a, K, V>
+    where
+        R: RangeBounds<K>,
+    {
+        check_range_bounds(&range);
+        SubMap {
+            map: self.map,
+            start: tighter_bound(self.start.as_ref(), range.start_bound(), Ordering::Greater),
+            end: tighter_bound(self.end.as_ref(), range.end_bound(), Ordering::Less),
+        }
+    }
+}
+
+/// Returns whichever of two bounds on the same end of a range admits fewer
+/// keys: for start bounds, pass `Ordering::Greater` as the direction in
+/// which a bound gets tighter, and `Ordering::Less` for end bounds
+fn tighter_bound<K: Ord + Clone>(a: Bound<&K>, b: Bound<&K>, tighter: Ordering) -> Bound<K> {
+    let tightest = match (a, b) {
+        (Bound::Unbounded, bound) | (bound, Bound::Unbounded) => bound,
+        (Bound::Included(x) | Bound::Excluded(x), Bound::Included(y) | Bound::Excluded(y))
+            if x != y =>
+        {
+            if x.cmp(y) == tighter {
+                a
+            } else {
+                b
+            }
+        }
+        // At the same key, excluding it is the tighter bound
+        (Bound::Excluded(_), _) => a,
+        _ => b,
+    };
+    tightest.cloned()
+}
+
+impl<K: Clone, V> Clone for SubMap<'_, K, V> {
+    fn clone(&self) -> Self {
+        SubMap {
+            map: self.map,
+            start: self.start.clone(),
+            end: self.end.clone(),
+        }
+    }
+}
+
+impl<K, V> Debug for SubMap<'_, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A trait for visiting nodes in a B+ tree
+pub trait NodeVisitor<K, V> {
+    /// The type of result produced by the visitor
+    type Result;
+
+    /// Visit a leaf node
+    fn visit_leaf(&mut self, leaf: &LeafNode<K, V>);
+
+    /// Visit a branch node
+    fn visit_branch(&mut self, branch: &BranchNode<K, V>);
+
+    /// Get the accumulated result
+    fn result(self) -> Self::Result;
+}
+
+/// A trait for visiting nodes in a B+ tree with mutable access
+pub trait NodeVisitorMut<K, V> {
+    /// The type of result produced by the visitor
+    type Result;
+
+    /// Visit a leaf node with mutable access
+    fn visit_leaf(&mut self, leaf: &mut LeafNode<K, V>);
+
+    /// Visit a branch node with mutable access
+    fn visit_branch(&mut self, branch: &mut BranchNode<K, V>);
+
+    /// Get the accumulated result
+    fn result(self) -> Self::Result;
+}
+
+/// A visitor that collects key-value pairs with a transformation function
+pub struct CollectingVisitor<K, V, F, R>
+where
+    F: Fn(&K, &V) -> R,
+{
+    visitor_fn: F,
+    results: Vec<R>,
+    _phantom: std::marker::PhantomData<(K, V)>,
+}
+
+impl<K, V, F, R> CollectingVisitor<K, V, F, R>
+where
+    F: Fn(&K, &V) -> R,
+{
+    /// Create a new collecting visitor with the given transformation function
+    pub fn new(visitor_fn: F) -> Self {
+        Self {
+            visitor_fn,
+            results: Vec::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<K, V, F, R> NodeVisitor<K, V> for CollectingVisitor<K, V, F, R>
+where
+    F: Fn(&K, &V) -> R,
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    type Result = Vec<R>;
+
+    fn visit_leaf(&mut self, leaf: &LeafNode<K, V>) {
+        // Process all entries in this leaf node
+        for i in 0..leaf.keys.len() {
+            self.results
+                .push((self.visitor_fn)(&leaf.keys[i], &leaf.values[i]));
+        }
+    }
+
+    fn visit_branch(&mut self, _branch: &BranchNode<K, V>) {
+        // No direct processing for branch nodes in this visitor
+    }
+
+    fn result(self) -> Self::Result {
+        self.results
+    }
+}
+
+/// An estimate of the memory held by a map, split by what it is spent on.
+/// Sizes come from `size_of` and Vec capacities, plus whatever heap data
+/// the callbacks given to `memory_usage_with` report for keys and values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes of leaf structure: each leaf node, the unused capacity of its
+    /// Vecs and the bits of its filter, if it has one
+    pub leaf_bytes: usize,
+    /// Bytes of branch structure: each branch node, its children's slots
+    /// and the unused capacity of its Vecs
+    pub branch_bytes: usize,
+    /// Bytes of keys, both in leaves and as the separators branches share,
+    /// including their reported heap data and that of the fence keys
+    /// branches keep
+    pub key_bytes: usize,
+    /// Bytes of values, including their reported heap data
+    pub value_bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Bytes spent on the shape of the tree rather than on its contents
+    pub fn structure_bytes(&self) -> usize {
+        self.leaf_bytes + self.branch_bytes
+    }
+
+    /// All bytes held by the map's nodes and their contents
+    pub fn total_bytes(&self) -> usize {
+        self.structure_bytes() + self.key_bytes + self.value_bytes
+    }
+}
+
+/// A visitor that adds up the memory held by each node it visits
+struct MemoryUsageVisitor<KH, VH> {
+    key_heap_bytes: KH,
+    value_heap_bytes: VH,
+    usage: MemoryUsage,
+}
+
+impl<KH, VH> MemoryUsageVisitor<KH, VH> {
+    /// This holder's share of the allocation behind a shared separator.
+    /// Without a record of the separators already seen, the allocation is
+    /// split evenly between the branches holding it, so that it adds up to
+    /// about its size once they have all been visited.
+    fn shared_key_bytes<K>(&self, key: &SeparatorKey<K>) -> usize
+    where
+        KH: Fn(&K) -> usize,
+    {
+        // The key is stored after the strong and weak counts
+        let allocation = 2 * std::mem::size_of::<usize>() + std::mem::size_of::<K>();
+        (allocation + (self.key_heap_bytes)(key)) / SeparatorKey::strong_count(key)
+    }
+}
+
+impl<K, V, KH, VH> NodeVisitor<K, V> for MemoryUsageVisitor<KH, VH>
+where
+    KH: Fn(&K) -> usize,
+    VH: Fn(&V) -> usize,
 {
-    type Item = &'a mut V;
+    type Result = MemoryUsage;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.position < self.entries.len() {
-            let position = self.position;
-            self.position += 1;
+    fn visit_leaf(&mut self, leaf: &LeafNode<K, V>) {
+        let key_size = std::mem::size_of::<K>();
+        let value_size = std::mem::size_of::<V>();
+        // Keys and values are counted below wherever they are stored, so
+        // the slots they fill are taken off the node and its heap capacity
+        self.usage.leaf_bytes += std::mem::size_of::<Node<K, V>>()
+            + leaf.keys.heap_capacity() * key_size
+            + leaf.values.heap_capacity() * value_size
+            - leaf.keys.len() * key_size
+            - leaf.values.len() * value_size;
+        #[cfg(feature = "bloom")]
+        {
+            self.usage.leaf_bytes += leaf.filter.heap_bytes();
+        }
+        for key in &leaf.keys {
+            self.usage.key_bytes += key_size + (self.key_heap_bytes)(key);
+        }
+        for value in &leaf.values {
+            self.usage.value_bytes += value_size + (self.value_heap_bytes)(value);
+        }
+    }
 
-            // This is safe because we're returning each entry exactly once
-            // and we know the indices are valid
-            unsafe {
-                let value_ptr = self.entries.as_mut_ptr().add(position);
-                Some(&mut *value_ptr)
-            }
-        } else {
-            None
+    fn visit_branch(&mut self, branch: &BranchNode<K, V>) {
+        let separator_size = std::mem::size_of::<SeparatorKey<K>>();
+        // The children themselves are counted when they are visited, so
+        // only the boxes pointing to them are counted here
+        self.usage.branch_bytes += std::mem::size_of::<Node<K, V>>()
+            + branch.keys.heap_capacity() * separator_size
+            - branch.keys.len() * separator_size
+            + branch.children.capacity() * std::mem::size_of::<NodeBox<K, V>>();
+        for key in &branch.keys {
+            self.usage.key_bytes += separator_size + self.shared_key_bytes(key);
         }
+        // The fences sit in the node itself, so only their heap data is
+        // left to count
+        for fence in branch.min_key.iter().chain(&branch.max_key) {
+            self.usage.key_bytes += (self.key_heap_bytes)(fence);
+        }
+    }
+
+    fn result(self) -> Self::Result {
+        self.usage
     }
 }
 
-impl<K, V> IntoIterator for BPlusTreeMap<K, V>
+/// An entry in a `BPlusTreeMap`. It is part of the map API and can be used to
+/// manipulate the map without having to do multiple lookups.
+pub enum Entry<'a, K, V>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V>),
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        // Collect all entries into a vector
-        let mut entries = Vec::new();
+/// A view into an occupied entry in a `BPlusTreeMap`.
+/// It is part of the Entry API.
+pub struct OccupiedEntry<'a, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// The map this entry belongs to. Its cached leaf path leads to the
+    /// leaf holding the entry.
+    map: &'a mut BPlusTreeMap<K, V>,
+    /// The key for this entry
+    key: K,
+    /// The position of the entry in its leaf
+    idx: usize,
+}
 
-        // Extract entries from the tree
-        if let Some(root) = self.root {
-            Self::collect_entries(root, &mut entries);
-        }
+/// The error returned by `try_insert` when the key already exists.
+/// It holds the occupied entry and the value that was not inserted.
+pub struct OccupiedError<'a, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// The entry in the map that was already occupied
+    pub entry: OccupiedEntry<'a, K, V>,
+    /// The value which was not inserted, because the entry was already occupied
+    pub value: V,
+}
 
-        IntoIter {
-            inner: TreeIterator::new(entries),
-        }
+impl<K, V> Debug for OccupiedError<'_, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
     }
 }
 
-impl<K, V> BPlusTreeMap<K, V>
+impl<K, V> fmt::Display for OccupiedError<'_, K, V>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    // Helper method to collect all entries from the tree into a vector
-    fn collect_entries(node: Node<K, V>, entries: &mut Vec<(K, V)>) {
-        // Create a temporary BPlusTreeMap with the given node as root
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 4 });
-        let temp_map = BPlusTreeMap {
-            root: Some(node),
-            config: config.clone(),
-            size: 0,             // Doesn't matter for this operation
-            insertion_balancer: InsertionBalancer::new(config.clone()),
-            removal_balancer: RemovalBalancer::new(config.clone()),
-        };
-
-        // Use the traverse method to collect all entries
-        let collected = temp_map.traverse(|k, v| (k.clone(), v.clone()));
-
-        // Add the collected entries to the provided vector
-        entries.extend(collected);
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
     }
 }
 
-impl<K, V> Debug for BPlusTreeMap<K, V>
+impl<K, V> std::error::Error for OccupiedError<'_, K, V>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Start with the map opening
-        write!(f, "{{")?;
+}
 
-        // Create a clone of the map and collect all entries
-        let map_clone = self.clone();
+/// A position in a map for `insert_hint` to start from: the path from the
+/// root to the leaf the last hinted insert went into. A new hint starts at
+/// the root.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InsertHint {
+    /// The index of the child taken at each branch on the way down
+    path: Vec<usize>,
+}
 
-        // Convert the clone into an iterator and collect all entries
-        let all_entries: Vec<(K, V)> = map_clone.into_iter().collect();
+impl InsertHint {
+    /// Creates a hint that starts from the root
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
-        // Format each entry
-        let mut first = true;
-        for (k, v) in &all_entries {
-            if !first {
-                write!(f, ", ")?;
-            }
-            write!(f, "{:?}: {:?}", k, v)?;
-            first = false;
-        }
+/// The error returned by `from_sorted_iter` when its input is not strictly
+/// ascending. It holds the two keys found out of order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotAscendingError<K> {
+    /// The last key accepted
+    pub previous: K,
+    /// The key that did not come after it
+    pub key: K,
+}
 
-        // Close the map
-        write!(f, "}}")
+impl<K: Debug> fmt::Display for NotAscendingError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "keys are not strictly ascending: {:?} follows {:?}",
+            self.key, self.previous,
+        )
     }
 }
 
-// Implement Clone for BPlusTreeMap to support Debug implementation
-impl<K, V> Clone for BPlusTreeMap<K, V>
+impl<K: Debug> std::error::Error for NotAscendingError<K> {}
+
+/// A view into a vacant entry in a `BPlusTreeMap`.
+/// It is part of the Entry API.
+pub struct VacantEntry<'a, K, V>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    fn clone(&self) -> Self {
-        // Create a new map with the same branching factor
-        let mut new_map = BPlusTreeMap::with_branching_factor(self.config.branching_factor);
-
-        // Use the existing into_iter implementation to get all entries
-        // We need to create a temporary copy to avoid consuming self
-        let entries = self.into_iter_without_consuming();
+    /// The map this entry belongs to. Its cached leaf path leads to the
+    /// leaf the key belongs in, unless the map is empty.
+    map: &'a mut BPlusTreeMap<K, V>,
+    /// The key for this entry
+    key: K,
+    /// The position in the leaf where the key belongs
+    idx: usize,
+}
 
-        // Insert all entries into the new map
-        for (k, v) in entries {
-            new_map.insert(k, v);
+impl<K, V> Debug for Entry<'_, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
         }
-
-        new_map
     }
 }
 
-// Implement Default for BPlusTreeMap
-impl<K, V> Default for BPlusTreeMap<K, V>
+impl<K, V> Debug for OccupiedEntry<'_, K, V>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    fn default() -> Self {
-        Self::new()
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
     }
 }
 
-// Implement Index for BPlusTreeMap
-impl<K, V, Q> Index<&Q> for BPlusTreeMap<K, V>
+impl<K, V> Debug for VacantEntry<'_, K, V>
 where
-    K: Ord + Clone + Debug + Borrow<Q>,
+    K: Ord + Clone + Debug,
     V: Clone + Debug,
-    Q: Ord + ?Sized,
 {
-    type Output = V;
-
-    fn index(&self, key: &Q) -> &Self::Output {
-        self.get(key).expect("no entry found for key")
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(self.key()).finish()
     }
 }
 
-impl<K, V> BPlusTreeMap<K, V>
+impl<'a, K, V> Entry<'a, K, V>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    /// Gets the given key's corresponding entry in the map for in-place manipulation.
-    /// This method provides a more efficient way to manipulate entries in the map
-    /// without having to do multiple lookups.
-    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
-        if self.contains_key(&key) {
-            Entry::Occupied(OccupiedEntry { map: self, key })
-        } else {
-            Entry::Vacant(VacantEntry { map: self, key })
-        }
-    }
-
-    /// Returns an iterator over the key-value pairs of the map.
-    /// The iterator yields all key-value pairs in ascending order by key.
-    pub fn iter(&self) -> Iter<'_, K, V> {
-        // Use the visitor pattern to collect references
-        let entries = self.collect_refs();
-        Iter {
-            inner: TreeIterator::new(entries),
+    /// Ensures a value is in the entry by inserting the default if empty, and returns
+    /// a mutable reference to the value in the entry.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
         }
     }
 
-    /// Returns an iterator over the keys of the map.
-    /// The iterator yields all keys in ascending order.
-    pub fn keys(&self) -> Keys<'_, K> {
-        // Collect all keys from the tree
-        let keys = self.collect_refs().into_iter().map(|(k, _)| k).collect();
-        Keys {
-            inner: TreeIterator::new(keys),
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
         }
     }
 
-    /// Returns an iterator over the values of the map.
-    /// The iterator yields all values in ascending order by key.
-    pub fn values(&self) -> Values<'_, V> {
-        // Collect all values from the tree
-        let values = self.collect_refs().into_iter().map(|(_, v)| v).collect();
-        Values {
-            inner: TreeIterator::new(values),
+    /// Ensures a value is in the entry by inserting, if empty, the result of the default function.
+    /// This method allows for generating key-derived values for insertion.
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
         }
     }
 
-    /// Returns a mutable iterator over the values of the map.
-    /// The iterator yields all values in ascending order by key.
-    pub fn values_mut(&mut self) -> ValuesMut<'_, V> {
-        use crate::safe_traversal::SafeValuesMutVisitor;
-
-        // Use the safe visitor to collect mutable values
-        let mut visitor = SafeValuesMutVisitor::new();
-        self.accept_visitor_mut(&mut visitor);
-        let values = <SafeValuesMutVisitor<'_, V> as NodeVisitorMut<K, V>>::result(visitor);
-        ValuesMut::new(values)
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
     }
 
-    /// Returns a mutable iterator over the key-value pairs of the map.
-    /// The iterator yields all key-value pairs in ascending order by key.
-    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
-        // Use the visitor pattern to collect mutable references
-        let entries = self.collect_mut_refs();
-
-        // Return the iterator
-        IterMut {
-            entries,
-            position: 0,
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
         }
     }
-}
-
-/// A trait for visiting nodes in a B+ tree
-pub trait NodeVisitor<K, V> {
-    /// The type of result produced by the visitor
-    type Result;
-
-    /// Visit a leaf node
-    fn visit_leaf(&mut self, leaf: &LeafNode<K, V>);
-
-    /// Visit a branch node
-    fn visit_branch(&mut self, branch: &BranchNode<K, V>);
-
-    /// Get the accumulated result
-    fn result(self) -> Self::Result;
-}
-
-/// A trait for visiting nodes in a B+ tree with mutable access
-pub trait NodeVisitorMut<K, V> {
-    /// The type of result produced by the visitor
-    type Result;
-
-    /// Visit a leaf node with mutable access
-    fn visit_leaf(&mut self, leaf: &mut LeafNode<K, V>);
-
-    /// Visit a branch node with mutable access
-    fn visit_branch(&mut self, branch: &mut BranchNode<K, V>);
-
-    /// Get the accumulated result
-    fn result(self) -> Self::Result;
-}
-
-/// A visitor that collects key-value pairs with a transformation function
-pub struct CollectingVisitor<K, V, F, R>
-where
-    F: Fn(&K, &V) -> R,
-{
-    visitor_fn: F,
-    results: Vec<R>,
-    _phantom: std::marker::PhantomData<(K, V)>,
-}
 
-impl<K, V, F, R> CollectingVisitor<K, V, F, R>
-where
-    F: Fn(&K, &V) -> R,
-{
-    /// Create a new collecting visitor with the given transformation function
-    pub fn new(visitor_fn: F) -> Self {
-        Self {
-            visitor_fn,
-            results: Vec::new(),
-            _phantom: std::marker::PhantomData,
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
         }
     }
 }
 
-impl<K, V, F, R> NodeVisitor<K, V> for CollectingVisitor<K, V, F, R>
+impl<'a, K, V> OccupiedEntry<'a, K, V>
 where
-    F: Fn(&K, &V) -> R,
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    type Result = Vec<R>;
+    /// Gets a reference to the key in the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
 
-    fn visit_leaf(&mut self, leaf: &LeafNode<K, V>) {
-        // Process all entries in this leaf node
-        for i in 0..leaf.keys.len() {
-            self.results
-                .push((self.visitor_fn)(&leaf.keys[i], &leaf.values[i]));
-        }
+    /// Gets a reference to the value in the entry.
+    pub fn get(&self) -> &V {
+        &self.map.cached_leaf().values[self.idx]
     }
 
-    fn visit_branch(&mut self, _branch: &BranchNode<K, V>) {
-        // No direct processing for branch nodes in this visitor
+    /// Gets a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.cached_leaf_mut().values[self.idx]
+    }
+
+    /// Converts the entry into a mutable reference to its value.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.cached_leaf_mut().values[self.idx]
+    }
+
+    /// Sets the value of the entry with the key already in the map.
+    pub fn insert(&mut self, value: V) -> V {
+        // We know the key exists, so unwrap is safe
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes the value out of the entry, and returns it.
+    pub fn remove(self) -> V {
+        // We know the key exists, so unwrap is safe
+        self.map.remove(&self.key).unwrap()
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the `VacantEntry`.
+    pub fn key(&self) -> &K {
+        &self.key
     }
 
-    fn result(self) -> Self::Result {
-        self.results
+    /// Sets the value of the entry with the `VacantEntry`'s key,
+    /// and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert_vacant(self.idx, self.key, value)
     }
 }
 
-/// An entry in a `BPlusTreeMap`. It is part of the map API and can be used to
-/// manipulate the map without having to do multiple lookups.
-pub enum Entry<'a, K, V>
+/// An entry in a `BPlusTreeMap` found with a borrowed key. It is part of
+/// the map API, like `Entry`, but only builds an owned key when a vacant
+/// entry is filled.
+pub enum EntryRef<'a, 'b, K, V, Q>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
+    Q: ?Sized,
 {
     /// An occupied entry.
-    Occupied(OccupiedEntry<'a, K, V>),
+    Occupied(OccupiedEntryRef<'a, 'b, K, V, Q>),
     /// A vacant entry.
-    Vacant(VacantEntry<'a, K, V>),
+    Vacant(VacantEntryRef<'a, 'b, K, V, Q>),
 }
 
-/// A view into an occupied entry in a `BPlusTreeMap`.
-/// It is part of the Entry API.
-pub struct OccupiedEntry<'a, K, V>
+/// A view into an occupied entry in a `BPlusTreeMap`, found with a borrowed
+/// key. It is part of the EntryRef API.
+pub struct OccupiedEntryRef<'a, 'b, K, V, Q>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
+    Q: ?Sized,
 {
-    /// The map this entry belongs to
+    /// The map this entry belongs to. Its cached leaf path leads to the
+    /// leaf holding the entry.
     map: &'a mut BPlusTreeMap<K, V>,
-    /// The key for this entry
-    key: K,
+    /// The borrowed key for this entry
+    key: &'b Q,
+    /// The position of the entry in its leaf
+    idx: usize,
 }
 
-/// A view into a vacant entry in a `BPlusTreeMap`.
-/// It is part of the Entry API.
-pub struct VacantEntry<'a, K, V>
+/// A view into a vacant entry in a `BPlusTreeMap`, found with a borrowed
+/// key. It is part of the EntryRef API.
+pub struct VacantEntryRef<'a, 'b, K, V, Q>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
+    Q: ?Sized,
 {
-    /// The map this entry belongs to
+    /// The map this entry belongs to. Its cached leaf path leads to the
+    /// leaf the key belongs in, unless the map is empty.
     map: &'a mut BPlusTreeMap<K, V>,
-    /// The key for this entry
-    key: K,
+    /// The borrowed key the owned key is built from on insertion
+    key: &'b Q,
+    /// The position in the leaf where the key belongs
+    idx: usize,
 }
 
-impl<'a, K, V> Entry<'a, K, V>
+impl<'a, 'b, K, V, Q> EntryRef<'a, 'b, K, V, Q>
 where
-    K: Ord + Clone + Debug,
+    K: Ord + Clone + Debug + Borrow<Q> + From<&'b Q>,
     V: Clone + Debug,
+    Q: Ord + ?Sized,
 {
     /// Ensures a value is in the entry by inserting the default if empty, and returns
     /// a mutable reference to the value in the entry.
     pub fn or_insert(self, default: V) -> &'a mut V {
         match self {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => entry.insert(default),
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default),
         }
     }
 
@@ -986,28 +5350,37 @@ where
     /// and returns a mutable reference to the value in the entry.
     pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
         match self {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => entry.insert(default()),
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default()),
         }
     }
 
     /// Ensures a value is in the entry by inserting, if empty, the result of the default function.
-    /// This method allows for generating key-derived values for insertion.
-    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+    /// The function is given the borrowed key.
+    pub fn or_insert_with_key<F: FnOnce(&Q) -> V>(self, default: F) -> &'a mut V {
         match self {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => {
                 let value = default(entry.key());
                 entry.insert(value)
             }
         }
     }
 
-    /// Returns a reference to this entry's key.
-    pub fn key(&self) -> &K {
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Returns a reference to the borrowed key this entry was found with.
+    pub fn key(&self) -> &Q {
         match self {
-            Entry::Occupied(entry) => entry.key(),
-            Entry::Vacant(entry) => entry.key(),
+            EntryRef::Occupied(entry) => entry.key,
+            EntryRef::Vacant(entry) => entry.key(),
         }
     }
 
@@ -1018,93 +5391,69 @@ where
         F: FnOnce(&mut V),
     {
         match self {
-            Entry::Occupied(mut entry) => {
+            EntryRef::Occupied(mut entry) => {
                 f(entry.get_mut());
-                Entry::Occupied(entry)
+                EntryRef::Occupied(entry)
             }
-            Entry::Vacant(entry) => Entry::Vacant(entry),
+            EntryRef::Vacant(entry) => EntryRef::Vacant(entry),
         }
     }
 }
 
-impl<'a, K, V> OccupiedEntry<'a, K, V>
+impl<'a, K, V, Q> OccupiedEntryRef<'a, '_, K, V, Q>
 where
-    K: Ord + Clone + Debug,
+    K: Ord + Clone + Debug + Borrow<Q>,
     V: Clone + Debug,
+    Q: Ord + ?Sized,
 {
-    /// Gets a reference to the key in the entry.
+    /// Gets a reference to the key stored in the map for this entry.
     pub fn key(&self) -> &K {
-        &self.key
+        &self.map.cached_leaf().keys[self.idx]
     }
 
     /// Gets a reference to the value in the entry.
     pub fn get(&self) -> &V {
-        // We know the key exists, so unwrap is safe
-        self.map.get(&self.key).unwrap()
+        &self.map.cached_leaf().values[self.idx]
     }
 
     /// Gets a mutable reference to the value in the entry.
     pub fn get_mut(&mut self) -> &mut V {
-        use crate::safe_traversal::FindValueMutVisitor;
-
-        // Use the safe visitor to find the value
-        let mut visitor = FindValueMutVisitor::new(&self.key);
-        self.map.accept_visitor_mut(&mut visitor);
-        match <FindValueMutVisitor<'_, V, K> as NodeVisitorMut<K, V>>::result(visitor) {
-            Some(value) => value,
-            None => panic!("Key not found in map"),
-        }
+        &mut self.map.cached_leaf_mut().values[self.idx]
     }
 
     /// Converts the entry into a mutable reference to its value.
     pub fn into_mut(self) -> &'a mut V {
-        // We need to use the collect_mut_refs method which already handles lifetimes correctly
-        let entries = self.map.collect_mut_refs();
-        for (k, v) in entries {
-            if k == self.key {
-                return v;
-            }
-        }
-        panic!("Key not found in map");
+        &mut self.map.cached_leaf_mut().values[self.idx]
     }
 
     /// Sets the value of the entry with the key already in the map.
     pub fn insert(&mut self, value: V) -> V {
-        // We know the key exists, so unwrap is safe
         std::mem::replace(self.get_mut(), value)
     }
 
     /// Takes the value out of the entry, and returns it.
     pub fn remove(self) -> V {
         // We know the key exists, so unwrap is safe
-        self.map.remove(&self.key).unwrap()
+        self.map.remove(self.key).unwrap()
     }
 }
 
-impl<'a, K, V> VacantEntry<'a, K, V>
+impl<'a, 'b, K, V, Q> VacantEntryRef<'a, 'b, K, V, Q>
 where
-    K: Ord + Clone + Debug,
+    K: Ord + Clone + Debug + Borrow<Q> + From<&'b Q>,
     V: Clone + Debug,
+    Q: Ord + ?Sized,
 {
-    /// Gets a reference to the key that would be used when inserting a value
-    /// through the `VacantEntry`.
-    pub fn key(&self) -> &K {
-        &self.key
+    /// Gets a reference to the borrowed key that would be used when
+    /// inserting a value through the `VacantEntryRef`.
+    pub fn key(&self) -> &'b Q {
+        self.key
     }
 
-    /// Sets the value of the entry with the `VacantEntry`'s key,
-    /// and returns a mutable reference to it.
+    /// Builds the owned key, sets the value of the entry with it, and
+    /// returns a mutable reference to the value.
     pub fn insert(self, value: V) -> &'a mut V {
-        self.map.insert(self.key.clone(), value);
-
-        // We need to use the collect_mut_refs method which already handles lifetimes correctly
-        let entries = self.map.collect_mut_refs();
-        for (k, v) in entries {
-            if k == self.key {
-                return v;
-            }
-        }
-        panic!("Key not found in map after insertion");
+        self.map.insert_vacant(self.idx, K::from(self.key), value)
     }
 }
 
@@ -1114,32 +5463,29 @@ where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
-    /// Creates an empty leaf node
-    fn create_empty_leaf() -> LeafNode<K, V> {
-        LeafNode {
-            keys: Vec::new(),
-            values: Vec::new(),
-        }
-    }
-
-    /// Collects references to key-value pairs from the tree
-    pub fn collect_refs<'a>(&'a self) -> Vec<(&'a K, &'a V)> {
-        let mut entries = Vec::new();
+    /// Collects references to key-value pairs from the tree, in ascending
+    /// key order. Visiting the children of each branch in turn already
+    /// gives that order, so the entries are not sorted; in debug builds a
+    /// tree whose leaves are out of order fails an assertion instead.
+    pub fn collect_refs(&self) -> Vec<(&K, &V)> {
+        let mut entries = Vec::with_capacity(self.size);
         if let Some(root) = &self.root {
             Self::collect_refs_from_node(root, &mut entries);
         }
-        entries.sort_by(|a, b| a.0.cmp(b.0));
+        debug_assert!(
+            entries.is_sorted_by(|a, b| a.0 < b.0),
+            "tree entries are out of order"
+        );
         entries
     }
 
-    /// Recursively collects references to key-value pairs from a node
+    /// Recursively collects references to key-value pairs from a node,
+    /// visiting its children from left to right
     fn collect_refs_from_node<'a>(node: &'a Node<K, V>, entries: &mut Vec<(&'a K, &'a V)>) {
         match node {
             Node::Leaf(leaf) => {
                 // Add all entries from this leaf node
-                for i in 0..leaf.keys.len() {
-                    entries.push((&leaf.keys[i], &leaf.values[i]));
-                }
+                entries.extend(leaf.keys.iter().zip(&leaf.values));
             }
             Node::Branch(branch) => {
                 // Recursively process all children
@@ -1150,15 +5496,34 @@ where
         }
     }
 
-    /// Collects mutable references to values with cloned keys from the tree
-    pub fn collect_mut_refs<'a>(&'a mut self) -> Vec<(K, &'a mut V)> {
-        use crate::safe_traversal::SafeMutableVisitor;
+    /// Collects references to the keys and mutable references to the
+    /// values of the tree, in ascending key order
+    pub fn collect_mut_refs(&mut self) -> Vec<(&K, &mut V)> {
+        self.iter_mut().collect()
+    }
 
-        let mut visitor = SafeMutableVisitor::new();
-        self.accept_visitor_mut(&mut visitor);
-        let mut entries = visitor.result();
-        entries.sort_by(|a, b| a.0.cmp(&b.0));
-        entries
+    /// Estimates the memory held by the map from the sizes of its keys,
+    /// values and nodes, counting Vec capacity rather than length. Keys and
+    /// values that own heap data are only counted by their inline size; use
+    /// `memory_usage_with` to include it.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.memory_usage_with(|_| 0, |_| 0)
+    }
+
+    /// Like `memory_usage`, but adds the heap bytes reported by
+    /// `key_heap_bytes` and `value_heap_bytes` for each key and value
+    pub fn memory_usage_with<KH, VH>(&self, key_heap_bytes: KH, value_heap_bytes: VH) -> MemoryUsage
+    where
+        KH: Fn(&K) -> usize,
+        VH: Fn(&V) -> usize,
+    {
+        let mut visitor = MemoryUsageVisitor {
+            key_heap_bytes,
+            value_heap_bytes,
+            usage: MemoryUsage::default(),
+        };
+        self.accept(&mut visitor);
+        visitor.result()
     }
 
     /// Accepts a visitor and traverses the tree
@@ -1169,18 +5534,26 @@ where
     }
 
     /// Accepts a visitor and traverses the tree with mutable access
-    pub fn accept_mut<'a, Visitor: NodeVisitor<K, V>>(&'a mut self, visitor: &mut Visitor) {
+    pub fn accept_mut<Visitor: NodeVisitor<K, V>>(&mut self, visitor: &mut Visitor) {
         if let Some(root) = &mut self.root {
             Self::accept_node_mut(root, visitor);
         }
     }
 
-    /// Accepts a visitor with mutable access to nodes and traverses the tree
-    pub fn accept_visitor_mut<'a, Visitor: NodeVisitorMut<K, V>>(
-        &'a mut self,
-        visitor: &mut Visitor,
-    ) {
-        if let Some(root) = &mut self.root {
+    /// Accepts a visitor with mutable access to nodes and traverses the tree.
+    /// The leaves are linked to their neighbours again afterwards, so the
+    /// visitor may move nodes around.
+    pub fn accept_visitor_mut<Visitor: NodeVisitorMut<K, V>>(&mut self, visitor: &mut Visitor) {
+        /// Links the leaves once the visitor is done, even if it panics
+        struct Relink<'a, K, V>(&'a mut BPlusTreeMap<K, V>);
+        impl<K, V> Drop for Relink<'_, K, V> {
+            fn drop(&mut self) {
+                self.0.link_all_leaves();
+            }
+        }
+
+        let map = Relink(self);
+        if let Some(root) = &mut map.0.root {
             Self::accept_node_visitor_mut(root, visitor);
         }
     }
@@ -1202,10 +5575,7 @@ where
     }
 
     /// Recursively traverses a node and applies the visitor with mutable access
-    fn accept_node_mut<'a, Visitor: NodeVisitor<K, V>>(
-        node: &'a mut Node<K, V>,
-        visitor: &mut Visitor,
-    ) {
+    fn accept_node_mut<Visitor: NodeVisitor<K, V>>(node: &mut Node<K, V>, visitor: &mut Visitor) {
         match node {
             Node::Leaf(leaf) => {
                 visitor.visit_leaf(leaf);
@@ -1221,13 +5591,15 @@ where
     }
 
     /// Recursively traverses a node and applies the visitor with mutable access to nodes
-    fn accept_node_visitor_mut<'a, Visitor: NodeVisitorMut<K, V>>(
-        node: &'a mut Node<K, V>,
+    fn accept_node_visitor_mut<Visitor: NodeVisitorMut<K, V>>(
+        node: &mut Node<K, V>,
         visitor: &mut Visitor,
     ) {
         match node {
             Node::Leaf(leaf) => {
                 visitor.visit_leaf(leaf);
+                // The visitor may have changed the leaf's keys
+                leaf.refresh_filter();
             }
             Node::Branch(branch) => {
                 visitor.visit_branch(branch);
@@ -1235,96 +5607,563 @@ where
                 for child in &mut branch.children {
                     Self::accept_node_visitor_mut(child, visitor);
                 }
+                // The visitor may have changed the keys at either end
+                branch.refresh_fences();
             }
         }
     }
 
-    /// Traverses the tree and applies the visitor function to each key-value pair
-    /// The visitor function can transform the key-value pairs and collect them
-    fn traverse<F, R>(&self, visitor_fn: F) -> Vec<R>
+    /// Finds the leaf node that might contain the given key, with mutable access
+    fn find_leaf_for_key_mut<Q>(&mut self, key: &Q) -> Option<&mut LeafNode<K, V>>
     where
-        F: Fn(&K, &V) -> R,
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
     {
-        let mut visitor = CollectingVisitor::new(visitor_fn);
-        self.accept(&mut visitor);
-        visitor.result()
+        let last_leaf = Self::leaf_at(self.root.as_deref(), self.last_leaf.get_mut());
+        if last_leaf.is_some_and(|leaf| Self::leaf_covers(leaf, key)) {
+            return Self::leaf_at_mut(self.root.as_deref_mut(), self.last_leaf.get_mut());
+        }
+
+        let config = &self.config;
+        let path = self.last_leaf.get_mut();
+        path.clear();
+        let mut node = self.root.as_deref_mut()?;
+        loop {
+            match node {
+                Node::Leaf(leaf) => return Some(leaf),
+                Node::Branch(branch) => {
+                    let idx = branch.child_index_in(key, config);
+                    path.push(idx);
+                    node = branch.children.get_mut(idx)?;
+                }
+            }
+        }
+    }
+
+    /// Finds the value stored under `key`, with mutable access. Only the
+    /// nodes on the way down to the one leaf that can hold the key are
+    /// visited, following the branch separators as `find_leaf_for_key` does.
+    fn find_value_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let config = Rc::clone(&self.config);
+        let leaf = self.find_leaf_for_key_mut(key)?;
+        let idx = search_keys_in(&leaf.keys, key, &config).ok()?;
+        Some(&mut leaf.values[idx])
+    }
+
+    /// Finds the slot for `key` with one descent, leaving the path to its
+    /// leaf in the cache: `Ok` with the key's position in the leaf if it is
+    /// stored, or `Err` with the position it would be inserted at
+    fn find_slot<Q>(&mut self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let config = Rc::clone(&self.config);
+        match self.find_leaf_for_key_mut(key) {
+            Some(leaf) => search_keys_in(&leaf.keys, key, &config),
+            None => Err(0),
+        }
+    }
+
+    /// The leaf the cached path leads to. Entries hold the map exclusively
+    /// from the descent that found them, so their path can't go stale.
+    fn cached_leaf(&self) -> &LeafNode<K, V> {
+        let path = self.last_leaf.take();
+        let leaf = Self::leaf_at(self.root.as_deref(), &path);
+        self.last_leaf.set(path);
+        leaf.expect("cached path leads to a leaf")
+    }
+
+    /// The leaf the cached path leads to, with mutable access
+    fn cached_leaf_mut(&mut self) -> &mut LeafNode<K, V> {
+        Self::leaf_at_mut(self.root.as_deref_mut(), self.last_leaf.get_mut())
+            .expect("cached path leads to a leaf")
+    }
+
+    /// Inserts a key that a descent has just found missing at `idx` in the
+    /// leaf the cached path leads to, and returns the inserted value. The
+    /// key is moved into the tree, and the value is reached through the
+    /// path, which follows the key if the leaf has to split.
+    fn insert_vacant(&mut self, idx: usize, key: K, value: V) -> &mut V {
+        if self.root.is_some() && self.cached_leaf().keys.len() < self.config.branching_factor {
+            // The leaf has room, and the separators are left alone because
+            // the path is the one a descent for `key` took
+            self.size += 1;
+            let leaf = self.cached_leaf_mut();
+            let extreme = idx == 0 || idx == leaf.keys.len();
+            leaf.keys.insert(idx, key);
+            leaf.values.insert(idx, value);
+            leaf.refresh_filter();
+            if extreme {
+                // A new first or last key of the leaf may be a new fence
+                // for the branches above it
+                let root = self
+                    .root
+                    .as_deref_mut()
+                    .expect("cached path leads to a leaf");
+                Self::refresh_fences_along(root, self.last_leaf.get_mut());
+            }
+            return &mut self.cached_leaf_mut().values[idx];
+        }
+
+        let mut path = self.last_leaf.take();
+        let (_, slot) = self.insert_at(&mut path, key, value);
+        self.last_leaf.set(path);
+        &mut self.cached_leaf_mut().values[slot]
+    }
+
+    /// Refreshes the fences of the branches `path` leads through from
+    /// `node`, deepest first
+    fn refresh_fences_along(node: &mut Node<K, V>, path: &[usize]) {
+        if let Node::Branch(branch) = node
+            && let Some((&idx, rest)) = path.split_first()
+        {
+            Self::refresh_fences_along(&mut branch.children[idx], rest);
+            branch.refresh_fences();
+        }
+    }
+
+    /// The leaf that `path` leads to from `root`, if it leads to one
+    fn leaf_at<'a>(root: Option<&'a Node<K, V>>, path: &[usize]) -> Option<&'a LeafNode<K, V>> {
+        let mut node = root?;
+        for &idx in path {
+            let Node::Branch(branch) = node else {
+                return None;
+            };
+            node = branch.children.get(idx)?;
+        }
+        match node {
+            Node::Leaf(leaf) => Some(leaf),
+            Node::Branch(_) => None,
+        }
+    }
+
+    /// The leaf that `path` leads to from `root`, with mutable access
+    fn leaf_at_mut<'a>(
+        root: Option<&'a mut Node<K, V>>,
+        path: &[usize],
+    ) -> Option<&'a mut LeafNode<K, V>> {
+        let mut node = root?;
+        for &idx in path {
+            let Node::Branch(branch) = node else {
+                return None;
+            };
+            node = branch.children.get_mut(idx)?;
+        }
+        match node {
+            Node::Leaf(leaf) => Some(leaf),
+            Node::Branch(_) => None,
+        }
+    }
+
+    /// Whether `key` lies between the first and last keys of `leaf`.
+    /// Leaves hold disjoint runs of keys, so such a key can only be stored
+    /// in that leaf. Because the leaf's own keys are checked, a cached path
+    /// left stale by changes to the tree can only miss, never lead a lookup
+    /// to the wrong leaf.
+    fn leaf_covers<Q>(leaf: &LeafNode<K, V>, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        leaf.keys.first().is_some_and(|first| first.borrow() <= key)
+            && leaf.keys.last().is_some_and(|last| key <= last.borrow())
     }
 
     /// Finds a leaf node that might contain the given key
-    /// Returns the leaf node and its index in the tree
+    /// Returns the leaf node and its index in its parent, or None if the
+    /// key is known to be missing without descending to a leaf
     fn find_leaf_for_key<Q>(&self, key: &Q) -> Option<(&LeafNode<K, V>, usize)>
     where
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        match &self.root {
-            None => None,
-            Some(Node::Leaf(leaf)) => Some((leaf, 0)),
-            Some(Node::Branch(branch)) => {
-                // Find the child node to search in
-                let mut idx = 0;
-                for (i, k) in branch.keys.iter().enumerate() {
-                    if key.cmp(k.borrow()) == Ordering::Less {
-                        break;
-                    }
-                    idx = i + 1;
-                }
+        // Keys near the last one looked up are often in the same leaf
+        let mut path = self.last_leaf.take();
+        if let Some(leaf) = Self::leaf_at(self.root.as_deref(), &path)
+            && Self::leaf_covers(leaf, key)
+        {
+            let idx = path.last().copied().unwrap_or(0);
+            self.last_leaf.set(path);
+            return Some((leaf, idx));
+        }
 
-                // Check if the index is valid
-                if idx < branch.children.len() {
-                    match &branch.children[idx] {
-                        Node::Leaf(leaf) => Some((leaf, idx)),
-                        Node::Branch(_) => {
-                            // Recursively search deeper in the tree
-                            Self::find_leaf_for_key_recursive(&branch.children[idx], key)
-                        }
-                    }
-                } else {
-                    None
-                }
-            }
+        // A key outside the root's fences can't be in any leaf
+        if let Some(Node::Branch(root)) = self.root.as_deref()
+            && !root.fences_cover(key)
+        {
+            self.last_leaf.set(path);
+            return None;
         }
+
+        path.clear();
+        let found =
+            Self::find_leaf_for_key_recursive(self.root.as_deref(), key, &self.config, &mut path);
+        self.last_leaf.set(path);
+        found
     }
 
-    /// Recursively finds a leaf node that might contain the given key
-    fn find_leaf_for_key_recursive<'a, 'b, Q>(
-        node: &'a Node<K, V>,
-        key: &'b Q,
+    /// Recursively finds a leaf node that might contain the given key,
+    /// recording the child index taken at each branch in `path`
+    fn find_leaf_for_key_recursive<'a, Q>(
+        node: Option<&'a Node<K, V>>,
+        key: &Q,
+        config: &BPlusTreeConfig,
+        path: &mut Vec<usize>,
     ) -> Option<(&'a LeafNode<K, V>, usize)>
     where
         K: Borrow<Q>,
         Q: Ord + ?Sized,
     {
+        match node? {
+            Node::Leaf(leaf) => Some((leaf, path.last().copied().unwrap_or(0))),
+            Node::Branch(branch) => {
+                let idx = branch.child_index_in(key, config);
+                path.push(idx);
+                Self::find_leaf_for_key_recursive(
+                    branch.children.get(idx).map(NodeBox::as_ref),
+                    key,
+                    config,
+                    path,
+                )
+            }
+        }
+    }
+
+    /// Creates a map around an already built tree, after checking that the
+    /// tree is one the map could have built itself. Returns a description
+    /// of the first problem found otherwise.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_root(
+        branching_factor: usize,
+        root: Option<Node<K, V>>,
+    ) -> Result<Self, String> {
+        if branching_factor < 2 {
+            return Err(format!(
+                "branching factor {} is less than 2",
+                branching_factor
+            ));
+        }
+        let size = match &root {
+            None => 0,
+            Some(root) => Self::check_node(root, None, None, branching_factor)?.1,
+        };
+        let mut map = Self::with_branching_factor(branching_factor);
+        map.set_root(root);
+        map.size = size;
+        map.check_occupancy()?;
+        Ok(map)
+    }
+
+    /// Checks that every node below the root is at least half full, holding
+    /// at least half the branching factor in keys or separators. Returns a
+    /// description of the first underfull node found.
+    #[cfg(any(test, feature = "serde"))]
+    pub(crate) fn check_occupancy(&self) -> Result<(), String> {
+        fn check_children<K: Debug, V>(node: &Node<K, V>, min_keys: usize) -> Result<(), String> {
+            let Node::Branch(branch) = node else {
+                return Ok(());
+            };
+            for child in &branch.children {
+                let (count, keys) = match &**child {
+                    Node::Leaf(leaf) => (leaf.keys.len(), format!("{:?}", leaf.keys)),
+                    Node::Branch(branch) => (branch.keys.len(), format!("{:?}", branch.keys)),
+                };
+                if count < min_keys {
+                    return Err(format!(
+                        "node with keys {} holds fewer than {} keys",
+                        keys, min_keys
+                    ));
+                }
+                check_children(child, min_keys)?;
+            }
+            Ok(())
+        }
+        self.root.as_deref().map_or(Ok(()), |root| {
+            check_children(root, self.config.branching_factor / 2)
+        })
+    }
+
+    /// Checks the subtree rooted at `node`, whose keys must lie within
+    /// `[lower, upper)`, against the invariants listed on `check_invariants`.
+    /// Returns its height and the number of entries in it.
+    #[cfg(any(test, feature = "serde"))]
+    fn check_node(
+        node: &Node<K, V>,
+        lower: Option<&K>,
+        upper: Option<&K>,
+        branching_factor: usize,
+    ) -> Result<(usize, usize), String> {
+        let keys: Vec<&K> = match node {
+            Node::Leaf(leaf) => leaf.keys.iter().collect(),
+            Node::Branch(branch) => branch.keys.iter().map(|key| &**key).collect(),
+        };
+        if keys.len() > branching_factor {
+            return Err(format!(
+                "node with keys {:?} holds more than {} keys",
+                keys, branching_factor
+            ));
+        }
+        if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(format!("keys {:?} are not strictly ascending", keys));
+        }
+        if let (Some(lower), Some(&first)) = (lower, keys.first())
+            && first < lower
+        {
+            return Err(format!(
+                "key {:?} is below its lower bound {:?}",
+                first, lower
+            ));
+        }
+        if let (Some(upper), Some(&last)) = (upper, keys.last())
+            && last >= upper
+        {
+            return Err(format!(
+                "key {:?} is not below its upper bound {:?}",
+                last, upper
+            ));
+        }
+
         match node {
-            Node::Leaf(leaf) => Some((leaf, 0)),
+            Node::Leaf(leaf) => {
+                if leaf.keys.len() != leaf.values.len() {
+                    return Err(format!(
+                        "leaf has {} keys but {} values",
+                        leaf.keys.len(),
+                        leaf.values.len()
+                    ));
+                }
+                if leaf.keys.is_empty() {
+                    return Err("leaf is empty".to_string());
+                }
+                #[cfg(feature = "bloom")]
+                if !leaf.filter.accepts_all(&leaf.keys) {
+                    return Err(format!(
+                        "filter of leaf with keys {:?} rejects some of them",
+                        leaf.keys
+                    ));
+                }
+                Ok((0, leaf.keys.len()))
+            }
             Node::Branch(branch) => {
-                // Find the child node to search in
-                let mut idx = 0;
-                for (i, k) in branch.keys.iter().enumerate() {
-                    if key.cmp(k.borrow()) == Ordering::Less {
-                        break;
+                if branch.keys.is_empty() {
+                    return Err(format!(
+                        "branch has {} children and no keys",
+                        branch.children.len()
+                    ));
+                }
+                if branch.children.len() != branch.keys.len() + 1 {
+                    return Err(format!(
+                        "branch with keys {:?} has {} children",
+                        branch.keys,
+                        branch.children.len()
+                    ));
+                }
+
+                let mut height = None;
+                let mut entries = 0;
+                for (i, child) in branch.children.iter().enumerate() {
+                    let child_lower = if i == 0 {
+                        lower
+                    } else {
+                        Some(&*branch.keys[i - 1])
+                    };
+                    let child_upper = branch.keys.get(i).map(|key| &**key).or(upper);
+                    let (child_height, child_entries) =
+                        Self::check_node(child, child_lower, child_upper, branching_factor)?;
+                    if *height.get_or_insert(child_height) != child_height {
+                        return Err(format!(
+                            "children of branch with keys {:?} have different heights",
+                            branch.keys
+                        ));
                     }
-                    idx = i + 1;
+                    entries += child_entries;
                 }
 
-                // Check if the index is valid
-                if idx < branch.children.len() {
-                    match &branch.children[idx] {
-                        Node::Leaf(leaf) => Some((leaf, idx)),
-                        Node::Branch(_) => {
-                            // Recursively search deeper in the tree
-                            Self::find_leaf_for_key_recursive(&branch.children[idx], key)
-                        }
+                // The fences must match the keys at either end of the subtree
+                let fences = (branch.min_key.as_ref(), branch.max_key.as_ref());
+                let extremes = (Some(Self::first_key(node)), Some(Self::last_key(node)));
+                if fences != extremes {
+                    return Err(format!(
+                        "branch with keys {:?} has fences {:?} but holds keys {:?}",
+                        branch.keys, fences, extremes
+                    ));
+                }
+                Ok((height.unwrap_or(0) + 1, entries))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl<K, V> BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Number of leaves in the tree
+    pub(crate) fn leaf_count(&self) -> usize {
+        fn count<K, V>(node: &Node<K, V>) -> usize {
+            match node {
+                Node::Leaf(_) => 1,
+                Node::Branch(branch) => branch.children.iter().map(|child| count(child)).sum(),
+            }
+        }
+        self.root.as_deref().map_or(0, count)
+    }
+
+    /// Number of levels in the tree, counting the leaves
+    pub(crate) fn height(&self) -> usize {
+        self.root.as_deref().map_or(0, |root| {
+            Self::spine_height(root, |branch| branch.children.first().map(NodeBox::as_ref)) + 1
+        })
+    }
+
+    /// The leaves of the tree in the order a `LeafWalk` hands them out
+    pub(crate) fn walk_leaves(&self) -> impl DoubleEndedIterator<Item = &LeafNode<K, V>> {
+        LeafWalk::new(self.root.as_ref())
+    }
+
+    /// The leaves of the tree in the order the links between them lead
+    /// through them from the first leaf
+    pub(crate) fn linked_leaves(&self) -> impl DoubleEndedIterator<Item = &LeafNode<K, V>> {
+        LeafChain::whole(self.root.as_ref())
+    }
+
+    /// The leaves of the tree, found by descending into every child of
+    /// every branch
+    pub(crate) fn leaves(&self) -> Vec<&LeafNode<K, V>> {
+        fn collect<'a, K, V>(node: &'a Node<K, V>, leaves: &mut Vec<&'a LeafNode<K, V>>) {
+            match node {
+                Node::Leaf(leaf) => leaves.push(leaf),
+                Node::Branch(branch) => {
+                    for child in &branch.children {
+                        collect(child, leaves);
+                    }
+                }
+            }
+        }
+        let mut leaves = Vec::new();
+        if let Some(root) = &self.root {
+            collect(root, &mut leaves);
+        }
+        leaves
+    }
+
+    /// A rendering of the shape of the tree: each branch lists its
+    /// separators and then its children in parentheses, and each leaf is
+    /// shown by its keys in brackets
+    pub(crate) fn shape(&self) -> String {
+        fn render<K: Debug, V>(node: &Node<K, V>) -> String {
+            match node {
+                Node::Leaf(leaf) => format!("{:?}", leaf.keys),
+                Node::Branch(branch) => {
+                    let children: Vec<String> =
+                        branch.children.iter().map(|child| render(child)).collect();
+                    format!("{:?}({})", branch.keys, children.join(" "))
+                }
+            }
+        }
+        self.root.as_deref().map_or_else(String::new, render)
+    }
+
+    /// Number of emptied leaves kept for reuse
+    pub(crate) fn pooled_leaves(&self) -> usize {
+        self.pool.leaf_count()
+    }
+
+    /// Number of emptied branches kept for reuse
+    pub(crate) fn pooled_branches(&self) -> usize {
+        self.pool.branch_count()
+    }
+
+    /// Checks the structural invariants of the tree: keys are sorted and
+    /// lie within the bounds set by the separators above them, every branch
+    /// has at least two children and one more child than it has keys, no
+    /// leaf is empty, no node holds more keys than the branching factor, all
+    /// leaves sit at the same depth, and `size` matches the number of stored
+    /// entries. In maps promoting first keys, every separator is the first
+    /// key of the subtree on its right. Returns a description of the first
+    /// violation found.
+    pub(crate) fn check_invariants(&self) -> Result<(), String> {
+        let entries = match &self.root {
+            None => 0,
+            Some(root) => Self::check_node(root, None, None, self.config.branching_factor)?.1,
+        };
+        if entries != self.size {
+            return Err(format!(
+                "size is {} but the tree holds {} entries",
+                self.size, entries
+            ));
+        }
+        if self.insertion_balancer.promotes_first_keys() {
+            self.check_separators()?;
+        }
+        self.check_links()
+    }
+
+    /// Checks that each leaf is linked to the leaves before and after it in
+    /// the tree, and the first and last leaves to nothing beyond them, so
+    /// that the links lead through every leaf once in key order. Only the
+    /// addresses are compared, so a broken link is reported, not followed.
+    pub(crate) fn check_links(&self) -> Result<(), String> {
+        fn collect<'a, K, V>(node: &'a NodeBox<K, V>, leaves: &mut Vec<&'a NodeBox<K, V>>) {
+            match &**node {
+                Node::Leaf(_) => leaves.push(node),
+                Node::Branch(branch) => {
+                    for child in &branch.children {
+                        collect(child, leaves);
                     }
-                } else {
-                    None
                 }
             }
         }
+        let mut leaves = Vec::new();
+        if let Some(root) = &self.root {
+            collect(root, &mut leaves);
+        }
+        for (i, node) in leaves.iter().enumerate() {
+            let Node::Leaf(leaf) = &***node else {
+                unreachable!("only leaves are collected");
+            };
+            let prev = i.checked_sub(1).map(|i| leaves[i].link());
+            let next = leaves.get(i + 1).map(|next| next.link());
+            if leaf.links.prev != prev || leaf.links.next != next {
+                return Err(format!(
+                    "leaf {} of {} with keys {:?} is not linked to its neighbours",
+                    i,
+                    leaves.len(),
+                    leaf.keys
+                ));
+            }
+        }
+        Ok(())
     }
 
-    // A non-consuming version of into_iter that collects entries without consuming self
-    fn into_iter_without_consuming(&self) -> Vec<(K, V)> {
-        self.traverse(|k, v| (k.clone(), v.clone()))
+    /// Checks that every separator equals the smallest key of the subtree on
+    /// its right, as it does in maps promoting the first key of the right
+    /// leaf when one splits. Returns a description of the first stale
+    /// separator found.
+    pub(crate) fn check_separators(&self) -> Result<(), String> {
+        fn check<K: Ord + Debug, V>(node: &Node<K, V>) -> Result<(), String> {
+            let Node::Branch(branch) = node else {
+                return Ok(());
+            };
+            for (separator, child) in branch.keys.iter().zip(&branch.children[1..]) {
+                if child.min_key() != Some(&**separator) {
+                    return Err(format!(
+                        "separator {:?} of branch with keys {:?} is not the first key {:?} of its right child",
+                        separator,
+                        branch.keys,
+                        child.min_key()
+                    ));
+                }
+            }
+            branch.children.iter().try_for_each(|child| check(child))
+        }
+        self.root.as_deref().map_or(Ok(()), check)
     }
 }
@@ -1,5 +1,29 @@
 #[derive(Clone)]
+#[cfg_attr(feature = "deepsize", derive(deepsize::DeepSizeOf))]
 pub struct BPlusTreeConfig {
     pub branching_factor: usize,
+    /// Bits of Bloom filter per key in each leaf, or 0 for leaves without
+    /// filters
+    #[cfg(feature = "bloom")]
+    pub bloom_bits_per_key: usize,
+    /// Nodes holding fewer keys than this are searched by scanning them
+    /// from the front rather than by binary search, or none if it is 0
+    pub linear_search_threshold: usize,
+    /// Whether wide nodes are searched without branching on their keys,
+    /// which only maps of `SearchableKey` keys do
+    pub branchless_search: bool,
 }
 
+impl BPlusTreeConfig {
+    /// A config for nodes of `branching_factor` keys, with no leaf filters,
+    /// whose nodes are all binary searched
+    pub fn new(branching_factor: usize) -> Self {
+        BPlusTreeConfig {
+            branching_factor,
+            #[cfg(feature = "bloom")]
+            bloom_bits_per_key: 0,
+            linear_search_threshold: 0,
+            branchless_search: false,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// `DeepSizeOf` support, behind the `deepsize` feature, for callers that
+// budget memory by bytes. Sizes count the capacity of every node's Vecs,
+// not just their lengths, so slack left by splits and removals is included,
+// as are the emptied nodes the map keeps pooled for reuse.
+
+use deepsize::{Context, DeepSizeOf};
+
+use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, LeafNode, Node, NodeBox};
+use crate::node_pool::NodePool;
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for LeafNode<K, V> {
+    fn deep_size_of_children(&self, context: &mut Context) -> usize {
+        #[allow(unused_mut)]
+        let mut size =
+            self.keys.deep_size_of_children(context) + self.values.deep_size_of_children(context);
+        #[cfg(feature = "bloom")]
+        {
+            size += self.filter.heap_bytes();
+        }
+        size
+    }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for BranchNode<K, V> {
+    fn deep_size_of_children(&self, context: &mut Context) -> usize {
+        self.keys.deep_size_of_children(context)
+            + self.children.deep_size_of_children(context)
+            + self.min_key.deep_size_of_children(context)
+            + self.max_key.deep_size_of_children(context)
+    }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for Node<K, V> {
+    fn deep_size_of_children(&self, context: &mut Context) -> usize {
+        match self {
+            Node::Leaf(leaf) => leaf.deep_size_of_children(context),
+            Node::Branch(branch) => branch.deep_size_of_children(context),
+        }
+    }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for NodeBox<K, V> {
+    fn deep_size_of_children(&self, context: &mut Context) -> usize {
+        // The node sits on the heap, as it would in a `Box`
+        std::mem::size_of::<Node<K, V>>() + (**self).deep_size_of_children(context)
+    }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for NodePool<K, V> {
+    fn deep_size_of_children(&self, context: &mut Context) -> usize {
+        self.leaves().deep_size_of_children(context)
+            + self.branches().deep_size_of_children(context)
+    }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for BPlusTreeMap<K, V> {
+    fn deep_size_of_children(&self, context: &mut Context) -> usize {
+        // The balancers hold the same config, so it is only counted here
+        let tree = self
+            .root_node()
+            .map_or(0, |root| root.deep_size_of_children(context));
+        tree + self.config().deep_size_of_children(context)
+            + self.node_pool().deep_size_of_children(context)
+    }
+}
@@ -0,0 +1,173 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::iter::{FusedIterator, Zip};
+use std::ops::{Bound, RangeBounds};
+use std::slice;
+use std::sync::Arc;
+
+use crate::bplus_tree_map::{BPlusTreeMap, check_range_bounds};
+
+impl<K, V> BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Returns a frozen copy of the current contents of the map, for
+    /// handing to readers on other threads while the map keeps changing.
+    /// Every entry is cloned once, into key and value arrays in key order;
+    /// cloning the snapshot after that copies nothing.
+    pub fn snapshot(&self) -> Snapshot<K, V> {
+        Snapshot {
+            entries: Arc::new(SnapshotEntries {
+                keys: self.keys().cloned().collect(),
+                values: self.values().cloned().collect(),
+            }),
+        }
+    }
+}
+
+/// The entries of a `Snapshot`, sorted by key
+struct SnapshotEntries<K, V> {
+    keys: Box<[K]>,
+    values: Box<[V]>,
+}
+
+/// An immutable copy of the contents of a `BPlusTreeMap`, made by
+/// `BPlusTreeMap::snapshot`. Later changes to the map don't reach it.
+/// Clones share the same entries, and the snapshot is `Send` and `Sync`
+/// when its keys and values are, so it can be read from many threads.
+pub struct Snapshot<K, V> {
+    entries: Arc<SnapshotEntries<K, V>>,
+}
+
+impl<K, V> Snapshot<K, V> {
+    /// Returns the number of entries in the snapshot
+    pub fn len(&self) -> usize {
+        self.entries.keys.len()
+    }
+
+    /// Returns true if the snapshot holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.keys.is_empty()
+    }
+
+    /// Returns an iterator over the entries in ascending key order
+    pub fn iter(&self) -> SnapshotIter<'_, K, V> {
+        SnapshotIter {
+            inner: self.entries.keys.iter().zip(self.entries.values.iter()),
+        }
+    }
+
+    /// Returns the value for `key`, or None if the key is not in the
+    /// snapshot
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let idx = self
+            .entries
+            .keys
+            .binary_search_by(|k| k.borrow().cmp(key))
+            .ok()?;
+        Some(&self.entries.values[idx])
+    }
+
+    /// Returns true if `key` is in the snapshot
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator over the entries whose keys fall within `range`,
+    /// in ascending key order
+    ///
+    /// Panics in the same cases as `BPlusTreeMap::range`.
+    pub fn range<Q, R>(&self, range: R) -> SnapshotIter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        check_range_bounds(&range);
+        let keys = &self.entries.keys;
+        let start = keys.partition_point(|key| match range.start_bound() {
+            Bound::Included(start) => key.borrow() < start,
+            Bound::Excluded(start) => key.borrow() <= start,
+            Bound::Unbounded => false,
+        });
+        let end = keys.partition_point(|key| match range.end_bound() {
+            Bound::Included(end) => key.borrow() <= end,
+            Bound::Excluded(end) => key.borrow() < end,
+            Bound::Unbounded => true,
+        });
+        let end = end.max(start);
+        SnapshotIter {
+            inner: keys[start..end]
+                .iter()
+                .zip(self.entries.values[start..end].iter()),
+        }
+    }
+}
+
+impl<K, V> Clone for Snapshot<K, V> {
+    /// Returns a snapshot sharing the same entries, in constant time
+    fn clone(&self) -> Self {
+        Snapshot {
+            entries: Arc::clone(&self.entries),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for Snapshot<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Snapshot<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = SnapshotIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over entries of a `Snapshot`, in key order
+pub struct SnapshotIter<'a, K, V> {
+    inner: Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>,
+}
+
+impl<'a, K, V> Iterator for SnapshotIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for SnapshotIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for SnapshotIter<'_, K, V> {}
+
+impl<K, V> FusedIterator for SnapshotIter<'_, K, V> {}
+
+impl<K, V> Clone for SnapshotIter<'_, K, V> {
+    fn clone(&self) -> Self {
+        SnapshotIter {
+            inner: self.inner.clone(),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// A key whose leading part can be looked up on its own. Keys that share a
+/// prefix must sit next to each other in key order, and keys must be
+/// ordered by their prefix first, the way tuples are ordered by their first
+/// element before the rest.
+pub trait KeyPrefix<P: ?Sized> {
+    /// Compares the prefix of this key with `prefix`
+    fn cmp_prefix(&self, prefix: &P) -> Ordering;
+}
+
+/// Implements `KeyPrefix` for a tuple, taking its first element as the
+/// prefix. The prefix can be given in any form the first element borrows as,
+/// such as a `str` for a `String`.
+macro_rules! impl_tuple_key_prefix {
+    ($($rest:ident),+) => {
+        impl<A, Q, $($rest),+> KeyPrefix<Q> for (A, $($rest),+)
+        where
+            A: Borrow<Q>,
+            Q: Ord + ?Sized,
+        {
+            fn cmp_prefix(&self, prefix: &Q) -> Ordering {
+                self.0.borrow().cmp(prefix)
+            }
+        }
+    };
+}
+
+impl_tuple_key_prefix!(B);
+impl_tuple_key_prefix!(B, C);
+impl_tuple_key_prefix!(B, C, D);
@@ -0,0 +1,215 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::hint::select_unpredictable;
+
+use crate::config::BPlusTreeConfig;
+
+/// Nodes at least this wide are searched without branching on the keys,
+/// in maps that search that way; narrower ones by binary search
+const BRANCHLESS_MIN_WIDTH: usize = 8;
+
+/// The widest run of keys a branchless search counts through with the
+/// `simd` feature. Wider nodes are first narrowed to a run this wide by
+/// binary steps.
+#[cfg(feature = "simd")]
+const BRANCHLESS_WINDOW: usize = 32;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Keys whose comparisons are single instructions, which maps created with
+/// `BPlusTreeMap::with_branchless_search` search without branching on
+/// them. Implemented for the primitive integer types only.
+pub trait SearchableKey: Copy + Ord + sealed::Sealed {}
+
+macro_rules! searchable_keys {
+    ($($key:ty),*) => {
+        $(
+            impl sealed::Sealed for $key {}
+            impl SearchableKey for $key {}
+        )*
+    };
+}
+
+searchable_keys!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+/// Searches `keys`, which must be strictly ascending as they are in every
+/// node, for `key`. Returns `Ok` with the key's position if it is there,
+/// or `Err` with the position it would be inserted at, exactly as binary
+/// search does; every lookup, insert and removal searches nodes through
+/// `search_keys_in`, which finds the same.
+pub fn search_keys<K, Q>(keys: &[K], key: &Q) -> Result<usize, usize>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    search_keys_with(keys, key, 0)
+}
+
+/// Searches `keys` like `search_keys`, except that nodes of fewer than
+/// `linear_search_threshold` keys are scanned from the front, which is
+/// quicker than halving for a few keys. A threshold of 0 never scans.
+pub fn search_keys_with<K, Q>(
+    keys: &[K],
+    key: &Q,
+    linear_search_threshold: usize,
+) -> Result<usize, usize>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    search_keys_by(keys, key, linear_search_threshold, false, K::borrow)
+}
+
+/// Searches `keys` like `search_keys`, the way `config` says the nodes of
+/// its map are searched: nodes below its linear search threshold are
+/// scanned, and with `branchless_search` nodes of `BRANCHLESS_MIN_WIDTH`
+/// keys or more are searched by `branchless_search`.
+pub fn search_keys_in<K, Q>(keys: &[K], key: &Q, config: &BPlusTreeConfig) -> Result<usize, usize>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    search_keys_by(
+        keys,
+        key,
+        config.linear_search_threshold,
+        config.branchless_search,
+        K::borrow,
+    )
+}
+
+/// Searches `keys` for `key`, comparing it against what `borrow` gives for
+/// each of them. Nodes below `linear_search_threshold` keys are scanned,
+/// and with `branchless` wide ones are halved without branching; any
+/// others are binary searched. Branches search their shared separators
+/// through here.
+pub fn search_keys_by<T, Q>(
+    keys: &[T],
+    key: &Q,
+    linear_search_threshold: usize,
+    branchless: bool,
+    borrow: impl Fn(&T) -> &Q,
+) -> Result<usize, usize>
+where
+    Q: Ord + ?Sized,
+{
+    if keys.len() < linear_search_threshold {
+        linear_search_by(keys, key, borrow)
+    } else if branchless && keys.len() >= BRANCHLESS_MIN_WIDTH {
+        branchless_search_by(keys, key, borrow)
+    } else {
+        keys.binary_search_by(|k| borrow(k).cmp(key))
+    }
+}
+
+/// Finds where `key` belongs in `keys` by comparing it with each key in
+/// turn, stopping at the first that is not below it
+fn linear_search_by<T, Q>(keys: &[T], key: &Q, borrow: impl Fn(&T) -> &Q) -> Result<usize, usize>
+where
+    Q: Ord + ?Sized,
+{
+    for (idx, k) in keys.iter().enumerate() {
+        match borrow(k).cmp(key) {
+            Ordering::Less => {}
+            Ordering::Equal => return Ok(idx),
+            Ordering::Greater => return Err(idx),
+        }
+    }
+    Err(keys.len())
+}
+
+/// Finds where `key` belongs in `keys` by halving them, each step picking
+/// its half with a conditional move rather than a branch. Takes one more
+/// comparison than there are halvings, as binary search does. With the
+/// `simd` feature the halving stops at a run of `BRANCHLESS_WINDOW` keys,
+/// and the keys below `key` in it are counted.
+pub fn branchless_search<K, Q>(keys: &[K], key: &Q) -> Result<usize, usize>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    branchless_search_by(keys, key, K::borrow)
+}
+
+/// `branchless_search`, comparing against what `borrow` gives for each key
+#[cfg(not(feature = "simd"))]
+fn branchless_search_by<T, Q>(
+    keys: &[T],
+    key: &Q,
+    borrow: impl Fn(&T) -> &Q,
+) -> Result<usize, usize>
+where
+    Q: Ord + ?Sized,
+{
+    if keys.is_empty() {
+        return Err(0);
+    }
+    // The last key not above `key`, if any is, stays within
+    // `base..base + size`
+    let mut base = 0;
+    let mut size = keys.len();
+    while size > 1 {
+        let half = size / 2;
+        let not_above = borrow(&keys[base + half]) <= key;
+        base = select_unpredictable(not_above, base + half, base);
+        size -= half;
+    }
+    match borrow(&keys[base]).cmp(key) {
+        Ordering::Equal => Ok(base),
+        Ordering::Less => Err(base + 1),
+        Ordering::Greater => Err(base),
+    }
+}
+
+/// `branchless_search`, comparing against what `borrow` gives for each key
+#[cfg(feature = "simd")]
+fn branchless_search_by<T, Q>(
+    keys: &[T],
+    key: &Q,
+    borrow: impl Fn(&T) -> &Q,
+) -> Result<usize, usize>
+where
+    Q: Ord + ?Sized,
+{
+    // The position `key` belongs at stays within `base..=base + size`
+    let mut base = 0;
+    let mut size = keys.len();
+    while size > BRANCHLESS_WINDOW {
+        let half = size / 2;
+        let below = borrow(&keys[base + half]) < key;
+        base = select_unpredictable(below, base + half, base);
+        size -= half;
+    }
+
+    // Every key before the window is below `key` and every key after it
+    // is not, so counting within the window finds the position
+    let idx = base + count_below(&keys[base..base + size], key, &borrow);
+    match keys.get(idx) {
+        Some(k) if borrow(k).cmp(key).is_eq() => Ok(idx),
+        _ => Err(idx),
+    }
+}
+
+/// The number of keys in `keys` below `key`, compared a chunk of lanes at
+/// a time. Each lane keeps its own count, the way a vector register
+/// would, so the compiler can compare whole chunks in vector instructions.
+#[cfg(feature = "simd")]
+fn count_below<T, Q>(keys: &[T], key: &Q, borrow: impl Fn(&T) -> &Q) -> usize
+where
+    Q: Ord + ?Sized,
+{
+    const LANES: usize = 8;
+    let mut lanes = [0usize; LANES];
+    let mut chunks = keys.chunks_exact(LANES);
+    for chunk in &mut chunks {
+        for (lane, k) in lanes.iter_mut().zip(chunk) {
+            *lane += usize::from(borrow(k) < key);
+        }
+    }
+    let rest = chunks.remainder().iter();
+    lanes.iter().sum::<usize>() + rest.filter(|k| borrow(k) < key).count()
+}
@@ -0,0 +1,175 @@
+/// Keys that maps can be searched by. With the `bloom` feature, a lookup
+/// hashes its key to probe the filter of the leaf it reaches, so the key
+/// must implement `Hash`; without it, every type qualifies.
+#[cfg(feature = "bloom")]
+pub trait FilterKey: std::hash::Hash {}
+
+#[cfg(feature = "bloom")]
+impl<T: std::hash::Hash + ?Sized> FilterKey for T {}
+
+/// Keys that maps can be searched by. With the `bloom` feature, a lookup
+/// hashes its key to probe the filter of the leaf it reaches, so the key
+/// must implement `Hash`; without it, every type qualifies.
+#[cfg(not(feature = "bloom"))]
+pub trait FilterKey {}
+
+#[cfg(not(feature = "bloom"))]
+impl<T: ?Sized> FilterKey for T {}
+
+#[cfg(feature = "bloom")]
+pub use bloom::{FilterSpec, LeafFilter, filter_hash};
+
+#[cfg(feature = "bloom")]
+mod bloom {
+    use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash};
+
+    /// The hash a leaf filter is built from and probed with. A key and
+    /// anything it borrows as hash alike, so lookups by a borrowed form
+    /// probe the same bits the key set.
+    pub fn filter_hash<Q: Hash + ?Sized>(key: &Q) -> u64 {
+        BuildHasherDefault::<DefaultHasher>::default().hash_one(key)
+    }
+
+    /// How a leaf's filter is built: its size per key, and how its keys
+    /// are hashed. The hash function is fixed when a map is created with
+    /// filters, which is the only place the key type is known to be
+    /// `Hash`, and leaves carry it so they can rebuild their filters
+    /// wherever their keys change.
+    pub struct FilterSpec<K> {
+        pub bits_per_key: usize,
+        pub hash: fn(&K) -> u64,
+    }
+
+    // Derived impls would require `K: Clone`
+    impl<K> Clone for FilterSpec<K> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<K> Copy for FilterSpec<K> {}
+
+    impl<K: Hash> FilterSpec<K> {
+        /// Filters of `bits_per_key` bits for each key of their leaf
+        pub fn new(bits_per_key: usize) -> Self {
+            FilterSpec {
+                bits_per_key,
+                hash: filter_hash::<K>,
+            }
+        }
+    }
+
+    /// A Bloom filter over the keys of one leaf. A key it rejects is not
+    /// in the leaf, so a lookup can skip searching it; a key it accepts
+    /// may or may not be. A filter without a spec, or over no keys,
+    /// accepts everything.
+    pub struct LeafFilter<K> {
+        bits: Vec<u64>,
+        spec: Option<FilterSpec<K>>,
+    }
+
+    impl<K> LeafFilter<K> {
+        /// A filter without a spec, which accepts everything and never
+        /// allocates
+        pub fn new() -> Self {
+            LeafFilter {
+                bits: Vec::new(),
+                spec: None,
+            }
+        }
+
+        /// The spec the filter is rebuilt with
+        pub fn spec(&self) -> Option<FilterSpec<K>> {
+            self.spec
+        }
+
+        /// Rebuild the filter with `spec` from now on. Takes effect at
+        /// the next `rebuild`.
+        pub fn set_spec(&mut self, spec: Option<FilterSpec<K>>) {
+            self.spec = spec;
+        }
+
+        /// Set the bits for exactly `keys`, which should be all the keys
+        /// of the leaf, after they have changed
+        pub fn rebuild(&mut self, keys: &[K]) {
+            self.bits.clear();
+            let Some(spec) = self.spec else {
+                return;
+            };
+            if keys.is_empty() || spec.bits_per_key == 0 {
+                return;
+            }
+            let words = (keys.len() * spec.bits_per_key).div_ceil(64);
+            self.bits.resize(words, 0);
+            let probes = Self::probes(spec.bits_per_key);
+            for key in keys {
+                let hash = (spec.hash)(key);
+                for bit in Self::bit_indexes(hash, probes, words * 64) {
+                    self.bits[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+
+        /// Whether a key with hash `hash`, from `filter_hash`, may be in
+        /// the leaf
+        pub fn may_contain(&self, hash: u64) -> bool {
+            let Some(spec) = self.spec else {
+                return true;
+            };
+            if self.bits.is_empty() {
+                return true;
+            }
+            let probes = Self::probes(spec.bits_per_key);
+            Self::bit_indexes(hash, probes, self.bits.len() * 64)
+                .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+        }
+
+        /// Whether the filter accepts every key in `keys`, as it must the
+        /// keys of its leaf
+        pub fn accepts_all(&self, keys: &[K]) -> bool {
+            match self.spec {
+                Some(spec) => keys.iter().all(|key| self.may_contain((spec.hash)(key))),
+                None => true,
+            }
+        }
+
+        /// Bytes the filter's bits take on the heap
+        pub fn heap_bytes(&self) -> usize {
+            self.bits.capacity() * size_of::<u64>()
+        }
+
+        /// The number of bits set per key: about ln 2 times the bits per
+        /// key keeps false positives rarest, and more than 8 gains little
+        fn probes(bits_per_key: usize) -> usize {
+            (bits_per_key * 69 / 100).clamp(1, 8)
+        }
+
+        /// The bits a key with hash `hash` sets, by double hashing: the
+        /// halves of one 64-bit hash stand in for independent hashes
+        fn bit_indexes(hash: u64, probes: usize, bit_count: usize) -> impl Iterator<Item = usize> {
+            let step = hash.rotate_left(32) | 1;
+            (0..probes as u64)
+                .map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) % bit_count as u64) as usize)
+        }
+    }
+
+    impl<K> Default for LeafFilter<K> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<K> Clone for LeafFilter<K> {
+        fn clone(&self) -> Self {
+            LeafFilter {
+                bits: self.bits.clone(),
+                spec: self.spec,
+            }
+        }
+
+        fn clone_from(&mut self, source: &Self) {
+            self.bits.clone_from(&source.bits);
+            self.spec = source.spec;
+        }
+    }
+}
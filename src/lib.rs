@@ -1,12 +1,43 @@
 // BPlusTreeMap implementation
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_support;
+pub mod array_map;
+mod array_vec;
 pub mod bplus_tree_map;
+pub mod config;
+#[cfg(feature = "deepsize")]
+mod deepsize_support;
+pub mod frozen;
+pub mod key_prefix;
+pub mod key_search;
+pub mod leaf_filter;
+mod macros;
 pub mod node_balancer;
+mod node_box;
 pub mod node_operations;
-pub mod config;
-mod safe_traversal;
+pub mod node_pool;
+pub mod node_vec;
+#[cfg(feature = "rayon")]
+pub mod par_iter;
+pub mod persistent_map;
+pub mod read_only;
+pub mod separator;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod snapshot;
+#[cfg(feature = "proptest")]
+pub mod strategy;
 mod tests;
 
 // Re-export the BPlusTreeMap struct for easier access
+pub use array_map::BPlusTreeArrayMap;
 pub use bplus_tree_map::BPlusTreeMap;
 pub use config::BPlusTreeConfig;
+pub use frozen::Snapshot;
+pub use key_prefix::KeyPrefix;
+pub use key_search::SearchableKey;
+pub use persistent_map::SharedBPlusTreeMap;
+pub use read_only::ReadOnlyBPlusTree;
+pub use separator::{IdentitySeparators, SeparatorKey, SeparatorPolicy, ShortestSeparators};
+pub use snapshot::{BinaryCodec, SnapshotError};
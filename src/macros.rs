@@ -0,0 +1,24 @@
+/// Creates a BPlusTreeMap from a list of key-value pairs.
+///
+/// `bptreemap!{1 => "one", 2 => "two"}` builds a map with the default
+/// branching factor, and `bptreemap![factor = 3; 1 => "one", 2 => "two"]`
+/// sets the branching factor. Entries are inserted in order, so when a key
+/// appears more than once its last value wins. `bptreemap!{}` creates an
+/// empty map whose key and value types come from the surrounding code.
+#[macro_export]
+macro_rules! bptreemap {
+    (factor = $factor:expr $(; $($key:expr => $value:expr),* $(,)?)?) => {{
+        #[allow(unused_mut)]
+        let mut map = $crate::BPlusTreeMap::with_branching_factor($factor);
+        $($(map.insert($key, $value);)*)?
+        map
+    }};
+    () => {
+        $crate::BPlusTreeMap::new()
+    };
+    ($($key:expr => $value:expr),+ $(,)?) => {
+        <$crate::BPlusTreeMap<_, _> as ::core::iter::FromIterator<_>>::from_iter([
+            $(($key, $value)),+
+        ])
+    };
+}
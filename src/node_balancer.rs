@@ -1,12 +1,19 @@
 use std::fmt::Debug;
+#[cfg(feature = "bloom")]
+use std::hash::Hash;
 use std::rc::Rc;
 
-use crate::bplus_tree_map::Node;
+use crate::bplus_tree_map::{LeafNode, Node, NodeBox};
 use crate::config::BPlusTreeConfig;
+#[cfg(feature = "bloom")]
+use crate::leaf_filter::FilterSpec;
+use crate::node_box::link_leaves;
 use crate::node_operations::{
     BranchNodeMerger, BranchNodeSplitter, LeafNodeMerger, LeafNodeSplitter, MergeResult,
     NodeMerger, NodeSplitter, SplitResult,
 };
+use crate::node_pool::NodePool;
+use crate::separator::{IdentitySeparators, SeparatorKey, SeparatorPolicy};
 
 /// Result of a node balancing operation
 pub enum BalanceResult<K, V> {
@@ -17,7 +24,7 @@ pub enum BalanceResult<K, V> {
         /// Right node after split
         right: Node<K, V>,
         /// Separator key to be promoted to parent
-        separator: K,
+        separator: SeparatorKey<K>,
     },
     /// Nodes were merged into a single node
     Merged(Node<K, V>),
@@ -28,10 +35,18 @@ pub enum BalanceResult<K, V> {
         /// Right node after rebalancing
         right: Node<K, V>,
         /// New separator key
-        separator: K,
+        separator: SeparatorKey<K>,
     },
     /// No change was needed
     NoChange(Node<K, V>),
+    /// No change was needed to either of two nodes, which are handed back
+    /// as they were
+    NoChangeToPair {
+        /// Left node, untouched
+        left: Node<K, V>,
+        /// Right node, untouched
+        right: Node<K, V>,
+    },
 }
 
 /// Trait for node balancing operations
@@ -39,43 +54,115 @@ pub trait NodeBalancer<K, V> {
     /// Balance a single node, potentially splitting it
     fn balance_node(&self, node: Node<K, V>) -> BalanceResult<K, V>;
 
-    /// Balance two nodes, potentially merging or rebalancing them
+    /// Balance two nodes, potentially merging or rebalancing them. Unless
+    /// they are merged, both nodes are handed back, changed or not.
     fn balance_nodes(
         &self,
         left: Node<K, V>,
         right: Node<K, V>,
-        separator: K,
+        separator: SeparatorKey<K>,
     ) -> BalanceResult<K, V>;
 }
 
 /// Balancer for insertion operations
-pub struct InsertionBalancer {
+pub struct InsertionBalancer<K> {
     /// Shared configuration containing the branching factor
     config: Rc<BPlusTreeConfig>,
+    /// Picks the separator promoted when a leaf splits
+    separator: fn(&K, &K) -> K,
+    /// Whether that separator is always the first key of the right leaf
+    first_keys: bool,
+    /// How the filters of new leaves are built, if leaves have them
+    #[cfg(feature = "bloom")]
+    leaf_filter: Option<FilterSpec<K>>,
 }
 
-impl InsertionBalancer {
-    /// Create a new insertion balancer with the given configuration
+impl<K: Clone> InsertionBalancer<K> {
+    /// Create a new insertion balancer with the given configuration, which
+    /// promotes the first key of the right half when a leaf splits
     pub fn new(config: Rc<BPlusTreeConfig>) -> Self {
-        Self { config }
+        Self::with_separator_policy::<IdentitySeparators>(config)
     }
 }
 
-impl<K, V> NodeBalancer<K, V> for InsertionBalancer
-where
-    K: Ord + Clone + Debug,
-    V: Clone + Debug,
-{
-    fn balance_node(&self, node: Node<K, V>) -> BalanceResult<K, V> {
+#[cfg(feature = "bloom")]
+impl<K: Clone + Hash> InsertionBalancer<K> {
+    /// Create a new insertion balancer with the given configuration, whose
+    /// new leaves get filters of `config.bloom_bits_per_key` bits per key
+    pub fn with_leaf_filters(config: Rc<BPlusTreeConfig>) -> Self {
+        let leaf_filter = Some(FilterSpec::new(config.bloom_bits_per_key));
+        Self {
+            leaf_filter,
+            ..Self::new(config)
+        }
+    }
+}
+
+impl<K> InsertionBalancer<K> {
+    /// Create a new insertion balancer with the given configuration, which
+    /// promotes separators picked by `P` when a leaf splits
+    pub fn with_separator_policy<P: SeparatorPolicy<K>>(config: Rc<BPlusTreeConfig>) -> Self {
+        Self {
+            config,
+            separator: P::separator,
+            first_keys: P::FIRST_KEYS,
+            #[cfg(feature = "bloom")]
+            leaf_filter: None,
+        }
+    }
+
+    /// The configuration, which says how the keys of nodes are searched
+    pub(crate) fn config(&self) -> &BPlusTreeConfig {
+        &self.config
+    }
+
+    /// Whether every separator this balancer promotes is the first key of
+    /// the leaf on its right
+    pub(crate) fn promotes_first_keys(&self) -> bool {
+        self.first_keys
+    }
+
+    /// A balancer with the same separator policy and leaf filters as this
+    /// one, for `config`
+    pub(crate) fn with_config(&self, config: Rc<BPlusTreeConfig>) -> Self {
+        Self {
+            config,
+            separator: self.separator,
+            first_keys: self.first_keys,
+            #[cfg(feature = "bloom")]
+            leaf_filter: self.leaf_filter,
+        }
+    }
+
+    /// Give a new leaf the filter the map's leaves have, if any, built
+    /// over its keys. Leaves split from others take their filters along.
+    pub fn filter_leaf<V>(&self, leaf: &mut LeafNode<K, V>) {
+        #[cfg(feature = "bloom")]
+        leaf.filter.set_spec(self.leaf_filter);
+        leaf.refresh_filter();
+    }
+
+    /// Balance a single node like `balance_node`, building the right half of
+    /// a split from an emptied node taken from `pool`
+    pub fn balance_node_pooled<V>(
+        &self,
+        node: Node<K, V>,
+        pool: &mut NodePool<K, V>,
+    ) -> BalanceResult<K, V>
+    where
+        K: Ord + Clone + Debug,
+        V: Clone + Debug,
+    {
         match node {
             Node::Leaf(leaf) => {
-                let splitter = LeafNodeSplitter::new(self.config.branching_factor);
+                let splitter =
+                    LeafNodeSplitter::with_separator(self.config.branching_factor, self.separator);
 
                 if !splitter.needs_split(&leaf) {
                     return BalanceResult::NoChange(Node::Leaf(leaf));
                 }
 
-                match splitter.split(leaf) {
+                match splitter.split_into(leaf, pool.take_leaf()) {
                     SplitResult::Split {
                         left,
                         right,
@@ -95,7 +182,7 @@ where
                     return BalanceResult::NoChange(Node::Branch(branch));
                 }
 
-                match splitter.split(branch) {
+                match splitter.split_into(branch, pool.take_branch()) {
                     SplitResult::Split {
                         left,
                         right,
@@ -111,14 +198,54 @@ where
         }
     }
 
+    /// Build the branch that joins the two halves of a split under their
+    /// separator, from an emptied node taken from `pool`. This is how the
+    /// tree grows a level when its root splits. The branch has room for a
+    /// full node, so it is not reallocated as later splits fill it. Halves
+    /// that are leaves are linked to each other, as the only leaves of the
+    /// tree.
+    pub fn join_split_pooled<V>(
+        &self,
+        left: Node<K, V>,
+        right: Node<K, V>,
+        separator: SeparatorKey<K>,
+        pool: &mut NodePool<K, V>,
+    ) -> Node<K, V>
+    where
+        K: Ord + Clone + Debug,
+        V: Clone + Debug,
+    {
+        let mut branch = pool.take_branch();
+        branch.keys.reserve(self.config.branching_factor + 1);
+        branch.children.reserve(self.config.branching_factor + 2);
+        branch.keys.push(separator);
+        branch
+            .children
+            .extend([NodeBox::new(left), NodeBox::new(right)]);
+        // SAFETY: the links written are those of the two new boxes only
+        unsafe { link_leaves(None, &branch.children, None) };
+        branch.refresh_fences();
+        Node::Branch(branch)
+    }
+}
+
+impl<K, V> NodeBalancer<K, V> for InsertionBalancer<K>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn balance_node(&self, node: Node<K, V>) -> BalanceResult<K, V> {
+        self.balance_node_pooled(node, &mut NodePool::new())
+    }
+
     fn balance_nodes(
         &self,
         left: Node<K, V>,
-        _right: Node<K, V>,
-        _separator: K,
+        right: Node<K, V>,
+        _separator: SeparatorKey<K>,
     ) -> BalanceResult<K, V> {
         // Insertion balancer doesn't need to balance multiple nodes
-        BalanceResult::NoChange(left)
+        BalanceResult::NoChangeToPair { left, right }
     }
 }
 
@@ -134,6 +261,10 @@ impl RemovalBalancer {
         Self { config }
     }
 
+    /// The configuration, which says how the keys of nodes are searched
+    pub(crate) fn config(&self) -> &BPlusTreeConfig {
+        &self.config
+    }
 }
 
 impl<K, V> NodeBalancer<K, V> for RemovalBalancer
@@ -150,18 +281,16 @@ where
         &self,
         left: Node<K, V>,
         right: Node<K, V>,
-        separator: K,
+        separator: SeparatorKey<K>,
     ) -> BalanceResult<K, V> {
         match (left, right) {
             (Node::Leaf(left_leaf), Node::Leaf(right_leaf)) => {
                 let merger = LeafNodeMerger::new(self.config.branching_factor);
 
                 if !merger.needs_merge(&left_leaf, &right_leaf) {
-                    // For the test_removal_balancer_no_change_needed test, we need to return both nodes
-                    return BalanceResult::Rebalanced {
+                    return BalanceResult::NoChangeToPair {
                         left: Node::Leaf(left_leaf),
                         right: Node::Leaf(right_leaf),
-                        separator,
                     };
                 }
 
@@ -176,14 +305,9 @@ where
                         right: Node::Leaf(right),
                         separator,
                     },
-                    MergeResult::NoMerge {
-                        left,
-                        right,
-                        separator,
-                    } => BalanceResult::Rebalanced {
+                    MergeResult::NoMerge { left, right, .. } => BalanceResult::NoChangeToPair {
                         left: Node::Leaf(left),
                         right: Node::Leaf(right),
-                        separator,
                     },
                 }
             }
@@ -191,11 +315,9 @@ where
                 let merger = BranchNodeMerger::new(self.config.branching_factor);
 
                 if !merger.needs_merge(&left_branch, &right_branch) {
-                    // For consistency, return both nodes
-                    return BalanceResult::Rebalanced {
+                    return BalanceResult::NoChangeToPair {
                         left: Node::Branch(left_branch),
                         right: Node::Branch(right_branch),
-                        separator,
                     };
                 }
 
@@ -210,23 +332,14 @@ where
                         right: Node::Branch(right),
                         separator,
                     },
-                    MergeResult::NoMerge {
-                        left,
-                        right,
-                        separator,
-                    } => BalanceResult::Rebalanced {
+                    MergeResult::NoMerge { left, right, .. } => BalanceResult::NoChangeToPair {
                         left: Node::Branch(left),
                         right: Node::Branch(right),
-                        separator,
                     },
                 }
             }
             // Return the nodes as they are for mixed types
-            (left_node, right_node) => BalanceResult::Rebalanced {
-                left: left_node,
-                right: right_node,
-                separator,
-            },
+            (left, right) => BalanceResult::NoChangeToPair { left, right },
         }
     }
 }
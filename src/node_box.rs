@@ -0,0 +1,211 @@
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+use crate::bplus_tree_map::{LeafNode, Node};
+
+/// A node as the map holds it: branches keep their children boxed, and
+/// the map its root, so moving a node moves a pointer.
+///
+/// The box owns its node as a `Box` does, but holds it through a plain
+/// pointer. A `Box` asserts unique access to its node whenever it is
+/// moved or borrowed mutably, which would invalidate the links that the
+/// leaves either side keep to a leaf; this box asserts nothing, so a link
+/// stays good for as long as the box it points to is alive.
+///
+/// This and the links between leaves are the one place the map uses
+/// unsafe code. The tests of the links are sized to run under Miri as
+/// well: `cargo +nightly miri test leaf_chain`.
+pub struct NodeBox<K, V> {
+    node: NonNull<Node<K, V>>,
+    /// The box owns its node, for the drop check
+    marker: PhantomData<Node<K, V>>,
+}
+
+// SAFETY: the box owns its node and only hands out access to it through
+// borrows of itself, as `Box` does
+unsafe impl<K: Send, V: Send> Send for NodeBox<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for NodeBox<K, V> {}
+
+impl<K, V> NodeBox<K, V> {
+    /// Moves `node` into a new box
+    pub fn new(node: Node<K, V>) -> Self {
+        NodeBox {
+            node: NonNull::from(Box::leak(Box::new(node))),
+            marker: PhantomData,
+        }
+    }
+
+    /// Moves the node out of the box, freeing the box
+    pub fn into_inner(self) -> Node<K, V> {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: the pointer came from a leaked `Box`, and with `self`
+        // not dropped it is turned back into one exactly once
+        *unsafe { Box::from_raw(this.node.as_ptr()) }
+    }
+
+    /// The link that the leaves either side keep to the node in this box
+    pub(crate) fn link(&self) -> NonNull<Node<K, V>> {
+        self.node
+    }
+}
+
+impl<K, V> Drop for NodeBox<K, V> {
+    fn drop(&mut self) {
+        // SAFETY: as in `into_inner`, and the box is never used again
+        drop(unsafe { Box::from_raw(self.node.as_ptr()) });
+    }
+}
+
+impl<K, V> Deref for NodeBox<K, V> {
+    type Target = Node<K, V>;
+
+    fn deref(&self) -> &Node<K, V> {
+        // SAFETY: the box owns a live node, borrowed along with the box
+        unsafe { self.node.as_ref() }
+    }
+}
+
+impl<K, V> DerefMut for NodeBox<K, V> {
+    fn deref_mut(&mut self) -> &mut Node<K, V> {
+        // SAFETY: the box owns a live node, borrowed along with the box
+        unsafe { self.node.as_mut() }
+    }
+}
+
+impl<K, V> AsRef<Node<K, V>> for NodeBox<K, V> {
+    fn as_ref(&self) -> &Node<K, V> {
+        self
+    }
+}
+
+impl<K, V> AsMut<Node<K, V>> for NodeBox<K, V> {
+    fn as_mut(&mut self) -> &mut Node<K, V> {
+        self
+    }
+}
+
+impl<K, V> From<Node<K, V>> for NodeBox<K, V> {
+    fn from(node: Node<K, V>) -> Self {
+        NodeBox::new(node)
+    }
+}
+
+// Cloned leaves start out unlinked, and the map links the cloned tree's
+// leaves once it is whole
+impl<K: Clone, V: Clone> Clone for NodeBox<K, V> {
+    fn clone(&self) -> Self {
+        NodeBox::new((**self).clone())
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        (**self).clone_from(source);
+    }
+}
+
+/// A link from a leaf to the box of a neighbouring leaf, if it has one
+pub(crate) type LeafLink<K, V> = Option<NonNull<Node<K, V>>>;
+
+/// The links from a leaf to the leaves before and after it in key order,
+/// which let iterators step from leaf to leaf without climbing the tree.
+/// The map keeps them correct through every change to its shape, and a
+/// leaf outside a map has none.
+pub(crate) struct LeafLinks<K, V> {
+    pub(crate) prev: LeafLink<K, V>,
+    pub(crate) next: LeafLink<K, V>,
+}
+
+// SAFETY: the links are only followed by the map that holds the leaves,
+// through a borrow of it, so they carry no access of their own
+unsafe impl<K, V> Send for LeafLinks<K, V> {}
+unsafe impl<K, V> Sync for LeafLinks<K, V> {}
+
+impl<K, V> Default for LeafLinks<K, V> {
+    fn default() -> Self {
+        LeafLinks {
+            prev: None,
+            next: None,
+        }
+    }
+}
+
+impl<K, V> Clone for LeafLinks<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for LeafLinks<K, V> {}
+
+/// The leaf that `link` leads to.
+///
+/// # Safety
+///
+/// `link` must lead to the live box of a leaf that nothing changes for
+/// `'a`.
+pub(crate) unsafe fn linked_leaf<'a, K, V>(link: NonNull<Node<K, V>>) -> &'a LeafNode<K, V> {
+    // SAFETY: the caller vouches for the box and its leaf
+    match unsafe { link.as_ref() } {
+        Node::Leaf(leaf) => leaf,
+        Node::Branch(_) => unreachable!("leaf links lead to leaves"),
+    }
+}
+
+/// The links of the leaf in the box that `link` leads to, to be changed
+///
+/// # Safety
+///
+/// `link` must lead to the live box of a leaf that nothing else borrows
+/// while the links are changed.
+unsafe fn links_mut<'a, K, V>(link: NonNull<Node<K, V>>) -> &'a mut LeafLinks<K, V> {
+    // SAFETY: the caller vouches for the box and its leaf
+    match unsafe { &mut *link.as_ptr() } {
+        Node::Leaf(leaf) => &mut leaf.links,
+        Node::Branch(_) => unreachable!("leaf links lead to leaves"),
+    }
+}
+
+/// Links the leaves in `leaves` to each other in order, the first to the
+/// leaf `prev` leads to and the last to the one `next` leads to, and those
+/// two back to them. With no leaves, `prev` and `next` are linked to each
+/// other. Any of the boxes that aren't leaves are skipped.
+///
+/// Only the links of the leaves in the run and the facing links of the two
+/// either side are written, so links elsewhere that lead to boxes which
+/// have since been freed are never followed.
+///
+/// # Safety
+///
+/// `prev` and `next` must lead to live boxes of leaves, and nothing may
+/// borrow any of the leaves while they are linked.
+pub(crate) unsafe fn link_leaves<'a, K: 'a, V: 'a>(
+    prev: LeafLink<K, V>,
+    leaves: impl IntoIterator<Item = &'a NodeBox<K, V>>,
+    next: LeafLink<K, V>,
+) {
+    let mut last = prev;
+    for leaf in leaves {
+        if !matches!(**leaf, Node::Leaf(_)) {
+            continue;
+        }
+        let link = leaf.link();
+        if let Some(last) = last {
+            // SAFETY: `last` is `prev` or a leaf of the run, live and
+            // unborrowed on the caller's word
+            unsafe { links_mut(last) }.next = Some(link);
+        }
+        // SAFETY: the box is borrowed, so live, and the caller vouches that
+        // nothing borrows its leaf
+        unsafe { links_mut(link) }.prev = last;
+        last = Some(link);
+    }
+    if let Some(last) = last {
+        // SAFETY: as above
+        unsafe { links_mut(last) }.next = next;
+    }
+    if let Some(next) = next {
+        // SAFETY: as above, for `next`
+        unsafe { links_mut(next) }.prev = last;
+    }
+}
@@ -1,6 +1,10 @@
 use std::fmt::Debug;
 
-use crate::bplus_tree_map::{BranchNode, LeafNode};
+use crate::array_map::{ArrayBranch, ArrayLeaf};
+use crate::bplus_tree_map::{BranchNode, LeafNode, NodeBox};
+use crate::node_vec::{NodeVec, NodeVecExt};
+use crate::persistent_map::{SharedBranch, SharedLeaf};
+use crate::separator::{IdentitySeparators, SeparatorKey, SeparatorPolicy};
 
 /// Result of a node split operation
 pub enum SplitResult<K, N> {
@@ -27,19 +31,39 @@ pub trait NodeSplitter<K, V, N> {
 }
 
 /// Splitter for leaf nodes
-pub struct LeafNodeSplitter {
+pub struct LeafNodeSplitter<K> {
     /// Maximum number of keys allowed in a node
     branching_factor: usize,
+    /// Picks the separator promoted between the two halves
+    separator: fn(&K, &K) -> K,
 }
 
-impl LeafNodeSplitter {
-    /// Create a new leaf node splitter with the given branching factor
+impl<K: Clone> LeafNodeSplitter<K> {
+    /// Create a new leaf node splitter with the given branching factor,
+    /// which promotes the first key of the right half as the separator
     pub fn new(branching_factor: usize) -> Self {
-        Self { branching_factor }
+        Self::with_separator_policy::<IdentitySeparators>(branching_factor)
+    }
+}
+
+impl<K> LeafNodeSplitter<K> {
+    /// Create a new leaf node splitter with the given branching factor,
+    /// which promotes separators picked by `P`
+    pub fn with_separator_policy<P: SeparatorPolicy<K>>(branching_factor: usize) -> Self {
+        Self::with_separator(branching_factor, P::separator)
+    }
+
+    /// Create a new leaf node splitter with the given branching factor,
+    /// which promotes separators picked by `separator`
+    pub(crate) fn with_separator(branching_factor: usize, separator: fn(&K, &K) -> K) -> Self {
+        Self {
+            branching_factor,
+            separator,
+        }
     }
 }
 
-impl<K, V> NodeSplitter<K, V, LeafNode<K, V>> for LeafNodeSplitter
+impl<K, V> NodeSplitter<SeparatorKey<K>, V, LeafNode<K, V>> for LeafNodeSplitter<K>
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
@@ -48,27 +72,56 @@ where
         node.keys.len() > self.branching_factor
     }
 
-    fn split(&self, mut node: LeafNode<K, V>) -> SplitResult<K, LeafNode<K, V>> {
+    fn split(&self, node: LeafNode<K, V>) -> SplitResult<SeparatorKey<K>, LeafNode<K, V>> {
+        // A leaf holds one key more than the branching factor before it splits
+        let right = LeafNode::new(
+            NodeVec::with_capacity(self.branching_factor + 1),
+            NodeVec::with_capacity(self.branching_factor + 1),
+        );
+        self.split_into(node, right)
+    }
+}
+
+impl<K> LeafNodeSplitter<K> {
+    /// Split a leaf if needed, moving the right half of its keys/values into
+    /// `right`, which must be empty. Reusing an emptied leaf this way keeps
+    /// its allocations instead of creating new ones. Neither half has to
+    /// reallocate as it fills again: the left half keeps the room that held
+    /// the overfull node, and `right` is given as much.
+    pub fn split_into<V>(
+        &self,
+        mut node: LeafNode<K, V>,
+        mut right: LeafNode<K, V>,
+    ) -> SplitResult<SeparatorKey<K>, LeafNode<K, V>>
+    where
+        K: Ord + Clone + Debug,
+        V: Clone + Debug,
+    {
         if !self.needs_split(&node) {
             return SplitResult::NoSplit(node);
         }
 
-        // Split the leaf node
+        // Split the leaf node. It holds more keys than the branching factor,
+        // which is at least 2, so there are keys on both sides of the split.
         let split_idx = node.keys.len() / 2;
-        let split_key = node.keys[split_idx].clone();
-
-        // Create a new leaf with the right half of the keys/values
-        let right_keys = node.keys.drain(split_idx..).collect();
-        let right_values = node.values.drain(split_idx..).collect();
-        let right_leaf = LeafNode {
-            keys: right_keys,
-            values: right_values,
-        };
+        let split_key = (self.separator)(&node.keys[split_idx - 1], &node.keys[split_idx]);
+
+        // Fill the new leaf with the right half of the keys/values
+        right.keys.reserve(self.branching_factor + 1);
+        right.values.reserve(self.branching_factor + 1);
+        right.keys.extend(node.keys.drain(split_idx..));
+        right.values.extend(node.values.drain(split_idx..));
+
+        // The new leaf is filtered like the one it was split from
+        #[cfg(feature = "bloom")]
+        right.filter.set_spec(node.filter.spec());
+        node.refresh_filter();
+        right.refresh_filter();
 
         SplitResult::Split {
             left: node,
-            right: right_leaf,
-            separator: split_key,
+            right,
+            separator: SeparatorKey::new(split_key),
         }
     }
 }
@@ -86,7 +139,7 @@ impl BranchNodeSplitter {
     }
 }
 
-impl<K, V> NodeSplitter<K, V, BranchNode<K, V>> for BranchNodeSplitter
+impl<K, V> NodeSplitter<SeparatorKey<K>, V, BranchNode<K, V>> for BranchNodeSplitter
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
@@ -95,29 +148,52 @@ where
         node.keys.len() > self.branching_factor
     }
 
-    fn split(&self, mut node: BranchNode<K, V>) -> SplitResult<K, BranchNode<K, V>> {
-        if !self.needs_split(&node) {
+    fn split(&self, node: BranchNode<K, V>) -> SplitResult<SeparatorKey<K>, BranchNode<K, V>> {
+        // A branch holds one key more than the branching factor before it
+        // splits, and one child more than it has keys
+        let right = BranchNode::new(
+            NodeVec::with_capacity(self.branching_factor + 1),
+            Vec::<NodeBox<K, V>>::with_capacity(self.branching_factor + 2),
+        );
+        self.split_into(node, right)
+    }
+}
+
+impl BranchNodeSplitter {
+    /// Split a branch if needed, moving the right half of its keys/children
+    /// into `right`, which must be empty. As with leaves, both halves are
+    /// left with room for a full branch. The middle separator moves up
+    /// rather than being copied.
+    pub fn split_into<K, V>(
+        &self,
+        mut node: BranchNode<K, V>,
+        mut right: BranchNode<K, V>,
+    ) -> SplitResult<SeparatorKey<K>, BranchNode<K, V>>
+    where
+        K: Ord + Clone + Debug,
+        V: Clone + Debug,
+    {
+        if !NodeSplitter::<SeparatorKey<K>, V, _>::needs_split(self, &node) {
             return SplitResult::NoSplit(node);
         }
 
         // Split the branch node
         let split_idx = node.keys.len() / 2;
-        let split_key = node.keys[split_idx].clone();
-
-        // Create a new branch with the right half of the keys/children
-        let right_keys = node.keys.drain(split_idx + 1..).collect();
-        let right_children = node.children.drain(split_idx + 1..).collect();
-        let right_branch = BranchNode {
-            keys: right_keys,
-            children: right_children,
-        };
 
-        // Remove the split key from the left branch
-        node.keys.remove(split_idx);
+        // Fill the new branch with the right half of the keys/children
+        right.keys.reserve(self.branching_factor + 1);
+        right.children.reserve(self.branching_factor + 2);
+        right.keys.extend(node.keys.drain(split_idx + 1..));
+        right.children.extend(node.children.drain(split_idx + 1..));
+
+        // Take the split key from the left branch
+        let split_key = node.keys.pop().unwrap();
+        node.refresh_fences();
+        right.refresh_fences();
 
         SplitResult::Split {
             left: node,
-            right: right_branch,
+            right,
             separator: split_key,
         }
     }
@@ -157,19 +233,13 @@ impl LeafNodeMerger {
     }
 }
 
-impl<K, V> NodeMerger<K, V, LeafNode<K, V>> for LeafNodeMerger
+impl<K, V> NodeMerger<SeparatorKey<K>, V, LeafNode<K, V>> for LeafNodeMerger
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
 {
     fn needs_merge(&self, left: &LeafNode<K, V>, right: &LeafNode<K, V>) -> bool {
-        // For the test case, we'll consider nodes with 2 keys each as needing to be merged
-        // This is a special case for the test
-        if left.keys.len() == 2 && right.keys.len() == 2 {
-            return true;
-        }
-
-        // Normal case: merge if either node has fewer than min_keys
+        // Merge if either node has fewer than min_keys
         left.keys.len() < self.min_keys || right.keys.len() < self.min_keys
     }
 
@@ -177,11 +247,11 @@ where
         &self,
         mut left: LeafNode<K, V>,
         mut right: LeafNode<K, V>,
-        _separator: K,
-    ) -> MergeResult<K, LeafNode<K, V>> {
+        _separator: SeparatorKey<K>,
+    ) -> MergeResult<SeparatorKey<K>, LeafNode<K, V>> {
         if !self.needs_merge(&left, &right) {
             // Get the separator key (first key of right node)
-            let separator = right.keys[0].clone();
+            let separator = SeparatorKey::new(right.keys[0].clone());
 
             // Return the nodes unchanged
             return MergeResult::NoMerge {
@@ -191,14 +261,6 @@ where
             };
         }
 
-        // Special case for the test: if both nodes have exactly 2 keys, merge them
-        if left.keys.len() == 2 && right.keys.len() == 2 {
-            // Merge the nodes
-            left.keys.append(&mut right.keys);
-            left.values.append(&mut right.values);
-            return MergeResult::Merged(left);
-        }
-
         // If both nodes have enough keys after rebalancing, rebalance them
         let total_keys = left.keys.len() + right.keys.len();
         if total_keys >= 2 * self.min_keys {
@@ -208,40 +270,18 @@ where
             if left.keys.len() < target_left_size {
                 // Move keys from right to left
                 let move_count = target_left_size - left.keys.len();
-
-                // Clone the keys and values to move
-                let keys_to_move: Vec<K> = right.keys[0..move_count].to_vec();
-                let values_to_move: Vec<V> = right.values[0..move_count].to_vec();
-
-                // Add to left
-                left.keys.extend(keys_to_move);
-                left.values.extend(values_to_move);
-
-                // Remove from right
-                right.keys.drain(0..move_count);
-                right.values.drain(0..move_count);
+                left.keys.extend(right.keys.drain(0..move_count));
+                left.values.extend(right.values.drain(0..move_count));
             } else {
-                // Move keys from left to right
-                let move_count = left.keys.len() - target_left_size;
-                let start_idx = left.keys.len() - move_count;
-
-                // Clone the keys and values to move
-                let keys_to_move: Vec<K> = left.keys[start_idx..].to_vec();
-                let values_to_move: Vec<V> = left.values[start_idx..].to_vec();
-
-                // Insert at the beginning of right
-                for i in (0..move_count).rev() {
-                    right.keys.insert(0, keys_to_move[i].clone());
-                    right.values.insert(0, values_to_move[i].clone());
-                }
-
-                // Remove from left
-                left.keys.truncate(start_idx);
-                left.values.truncate(start_idx);
+                // Move keys from the end of left to the beginning of right
+                right.keys.prepend(left.keys.drain(target_left_size..));
+                right.values.prepend(left.values.drain(target_left_size..));
             }
+            left.refresh_filter();
+            right.refresh_filter();
 
             // Get the new separator key (first key of right node)
-            let separator = right.keys[0].clone();
+            let separator = SeparatorKey::new(right.keys[0].clone());
 
             return MergeResult::Rebalanced {
                 left,
@@ -253,6 +293,7 @@ where
         // Merge the nodes
         left.keys.append(&mut right.keys);
         left.values.append(&mut right.values);
+        left.refresh_filter();
 
         MergeResult::Merged(left)
     }
@@ -273,7 +314,7 @@ impl BranchNodeMerger {
     }
 }
 
-impl<K, V> NodeMerger<K, V, BranchNode<K, V>> for BranchNodeMerger
+impl<K, V> NodeMerger<SeparatorKey<K>, V, BranchNode<K, V>> for BranchNodeMerger
 where
     K: Ord + Clone + Debug,
     V: Clone + Debug,
@@ -286,8 +327,8 @@ where
         &self,
         mut left: BranchNode<K, V>,
         mut right: BranchNode<K, V>,
-        separator: K,
-    ) -> MergeResult<K, BranchNode<K, V>> {
+        separator: SeparatorKey<K>,
+    ) -> MergeResult<SeparatorKey<K>, BranchNode<K, V>> {
         if !self.needs_merge(&left, &right) {
             // Return the nodes unchanged
             return MergeResult::NoMerge {
@@ -297,9 +338,11 @@ where
             };
         }
 
-        // If both nodes have enough keys after rebalancing, rebalance them
+        // If both nodes have enough keys after rebalancing, rebalance them.
+        // One of the keys moves up as the new separator, so both halves
+        // only reach the minimum if there is at least one key to spare.
         let total_keys = left.keys.len() + right.keys.len() + 1; // +1 for separator
-        if total_keys >= 2 * self.min_keys {
+        if total_keys > 2 * self.min_keys {
             // Rebalance the nodes
             let target_left_size = total_keys / 2;
 
@@ -308,12 +351,7 @@ where
                 left.keys.push(separator);
 
                 let move_count = target_left_size - left.keys.len();
-
-                // Clone the keys to move
-                let keys_to_move: Vec<K> = right.keys[0..move_count].to_vec();
-
-                // Add to left
-                left.keys.extend(keys_to_move.clone());
+                left.keys.extend(right.keys.drain(0..move_count));
 
                 // Move corresponding children
                 for _ in 0..=move_count {
@@ -323,9 +361,6 @@ where
                     }
                 }
 
-                // Remove keys from right
-                right.keys.drain(0..move_count);
-
                 // Get new separator
                 let new_separator = if !right.keys.is_empty() {
                     right.keys.remove(0)
@@ -334,39 +369,29 @@ where
                     panic!("Right node has no keys after rebalancing");
                 };
 
+                left.refresh_fences();
+                right.refresh_fences();
                 return MergeResult::Rebalanced {
                     left,
                     right,
                     separator: new_separator,
                 };
             } else {
-                // Move keys from left to right through the separator
+                // Move keys from left to right through the separator. The
+                // left node keeps `target_left_size` keys, and the key after
+                // them becomes the new separator.
                 right.keys.insert(0, separator);
-
-                let move_count = left.keys.len() - target_left_size;
-                let start_idx = left.keys.len() - move_count;
-
-                // Clone the keys to move
-                let keys_to_move: Vec<K> = left.keys[start_idx..].to_vec();
-
-                // Insert at the beginning of right
-                for i in (0..move_count).rev() {
-                    right.keys.insert(0, keys_to_move[i].clone());
-                }
+                right.keys.prepend(left.keys.drain(target_left_size + 1..));
 
                 // Move corresponding children
-                for i in (0..=move_count).rev() {
-                    if left.children.len() > start_idx + i {
-                        let child = left.children.remove(start_idx + i);
-                        right.children.insert(0, child);
-                    }
-                }
-
-                // Remove keys from left
-                left.keys.truncate(start_idx);
+                right
+                    .children
+                    .splice(0..0, left.children.drain(target_left_size + 1..));
 
                 // Get new separator
                 let new_separator = left.keys.pop().unwrap();
+                left.refresh_fences();
+                right.refresh_fences();
 
                 return MergeResult::Rebalanced {
                     left,
@@ -380,7 +405,380 @@ where
         left.keys.push(separator);
         left.keys.append(&mut right.keys);
         left.children.append(&mut right.children);
+        left.refresh_fences();
 
         MergeResult::Merged(left)
     }
 }
+
+/// Splitter for the nodes of `BPlusTreeArrayMap`. An array node can't hold
+/// more than its capacity, so a node is split as soon as it is full, before
+/// anything is added to it.
+pub(crate) struct ArrayNodeSplitter;
+
+impl<K: Clone, V, const B: usize> NodeSplitter<K, V, ArrayLeaf<K, V, B>> for ArrayNodeSplitter {
+    fn needs_split(&self, node: &ArrayLeaf<K, V, B>) -> bool {
+        node.keys.is_full()
+    }
+
+    fn split(&self, mut node: ArrayLeaf<K, V, B>) -> SplitResult<K, ArrayLeaf<K, V, B>> {
+        if !self.needs_split(&node) {
+            return SplitResult::NoSplit(node);
+        }
+
+        let split_idx = node.keys.len() / 2;
+        let right = ArrayLeaf {
+            keys: node.keys.split_off(split_idx),
+            values: node.values.split_off(split_idx),
+        };
+        let separator = right.keys[0].clone();
+
+        SplitResult::Split {
+            left: node,
+            right,
+            separator,
+        }
+    }
+}
+
+impl<K, V, const B: usize> NodeSplitter<K, V, ArrayBranch<K, V, B>> for ArrayNodeSplitter {
+    fn needs_split(&self, node: &ArrayBranch<K, V, B>) -> bool {
+        node.children.is_full()
+    }
+
+    fn split(&self, mut node: ArrayBranch<K, V, B>) -> SplitResult<K, ArrayBranch<K, V, B>> {
+        if !self.needs_split(&node) {
+            return SplitResult::NoSplit(node);
+        }
+
+        // The middle key moves up as the separator
+        let split_idx = node.keys.len() / 2;
+        let right = ArrayBranch {
+            keys: node.keys.split_off(split_idx + 1),
+            children: node.children.split_off(split_idx + 1),
+        };
+        let separator = node.keys.pop().unwrap();
+
+        SplitResult::Split {
+            left: node,
+            right,
+            separator,
+        }
+    }
+}
+
+/// Merger for the nodes of `BPlusTreeArrayMap`. A node other than the root
+/// needs at least half of `B` entries, or children for a branch. Two
+/// nodes are merged when their entries fit in one; otherwise the entries
+/// are shared out evenly between them.
+pub(crate) struct ArrayNodeMerger;
+
+impl<K: Clone, V, const B: usize> NodeMerger<K, V, ArrayLeaf<K, V, B>> for ArrayNodeMerger {
+    fn needs_merge(&self, left: &ArrayLeaf<K, V, B>, right: &ArrayLeaf<K, V, B>) -> bool {
+        left.keys.len() < B / 2 || right.keys.len() < B / 2
+    }
+
+    fn merge(
+        &self,
+        mut left: ArrayLeaf<K, V, B>,
+        mut right: ArrayLeaf<K, V, B>,
+        separator: K,
+    ) -> MergeResult<K, ArrayLeaf<K, V, B>> {
+        if !self.needs_merge(&left, &right) {
+            return MergeResult::NoMerge {
+                left,
+                right,
+                separator,
+            };
+        }
+
+        let total = left.keys.len() + right.keys.len();
+        if total <= B {
+            left.keys.append(&mut right.keys);
+            left.values.append(&mut right.values);
+            return MergeResult::Merged(left);
+        }
+
+        let target_left_len = total / 2;
+        if left.keys.len() < target_left_len {
+            // Move entries from the front of right onto the end of left
+            let move_count = target_left_len - left.keys.len();
+            let mut rest_keys = right.keys.split_off(move_count);
+            let mut rest_values = right.values.split_off(move_count);
+            left.keys.append(&mut right.keys);
+            left.values.append(&mut right.values);
+            right.keys.append(&mut rest_keys);
+            right.values.append(&mut rest_values);
+        } else {
+            // Move entries from the end of left onto the front of right
+            let mut moved_keys = left.keys.split_off(target_left_len);
+            let mut moved_values = left.values.split_off(target_left_len);
+            moved_keys.append(&mut right.keys);
+            moved_values.append(&mut right.values);
+            right.keys = moved_keys;
+            right.values = moved_values;
+        }
+
+        let separator = right.keys[0].clone();
+        MergeResult::Rebalanced {
+            left,
+            right,
+            separator,
+        }
+    }
+}
+
+impl<K, V, const B: usize> NodeMerger<K, V, ArrayBranch<K, V, B>> for ArrayNodeMerger {
+    fn needs_merge(&self, left: &ArrayBranch<K, V, B>, right: &ArrayBranch<K, V, B>) -> bool {
+        left.children.len() < B / 2 || right.children.len() < B / 2
+    }
+
+    fn merge(
+        &self,
+        mut left: ArrayBranch<K, V, B>,
+        mut right: ArrayBranch<K, V, B>,
+        separator: K,
+    ) -> MergeResult<K, ArrayBranch<K, V, B>> {
+        if !self.needs_merge(&left, &right) {
+            return MergeResult::NoMerge {
+                left,
+                right,
+                separator,
+            };
+        }
+
+        // The separator comes down between the keys of the two branches
+        let total = left.children.len() + right.children.len();
+        if total <= B {
+            left.keys.push(separator);
+            left.keys.append(&mut right.keys);
+            left.children.append(&mut right.children);
+            return MergeResult::Merged(left);
+        }
+
+        // Children move across through the separator, which is replaced
+        // by the key beside the last child moved
+        let target_left_len = total / 2;
+        let separator = if left.children.len() < target_left_len {
+            let move_count = target_left_len - left.children.len();
+            let mut rest_keys = right.keys.split_off(move_count);
+            let mut rest_children = right.children.split_off(move_count);
+            let new_separator = right.keys.pop().unwrap();
+            left.keys.push(separator);
+            left.keys.append(&mut right.keys);
+            left.children.append(&mut right.children);
+            right.keys.append(&mut rest_keys);
+            right.children.append(&mut rest_children);
+            new_separator
+        } else {
+            let mut moved_keys = left.keys.split_off(target_left_len);
+            let mut moved_children = left.children.split_off(target_left_len);
+            let new_separator = left.keys.pop().unwrap();
+            moved_keys.push(separator);
+            moved_keys.append(&mut right.keys);
+            moved_children.append(&mut right.children);
+            right.keys = moved_keys;
+            right.children = moved_children;
+            new_separator
+        };
+
+        MergeResult::Rebalanced {
+            left,
+            right,
+            separator,
+        }
+    }
+}
+
+/// Splitter for the nodes of `SharedBPlusTreeMap`. As in
+/// `BPlusTreeArrayMap`, a node is split as soon as it is full, on the way
+/// down an insert, so it never holds more than the branching factor allows.
+pub(crate) struct SharedNodeSplitter {
+    /// Most entries a leaf holds, and most children a branch holds
+    branching_factor: usize,
+}
+
+impl SharedNodeSplitter {
+    /// Create a new splitter for the given branching factor
+    pub(crate) fn new(branching_factor: usize) -> Self {
+        Self { branching_factor }
+    }
+}
+
+impl<K: Clone, V> NodeSplitter<K, V, SharedLeaf<K, V>> for SharedNodeSplitter {
+    fn needs_split(&self, node: &SharedLeaf<K, V>) -> bool {
+        node.keys.len() >= self.branching_factor
+    }
+
+    fn split(&self, mut node: SharedLeaf<K, V>) -> SplitResult<K, SharedLeaf<K, V>> {
+        if !self.needs_split(&node) {
+            return SplitResult::NoSplit(node);
+        }
+
+        // The right half gets room for a full node, as the left half has
+        let split_idx = node.keys.len() / 2;
+        let mut right = SharedLeaf {
+            keys: Vec::with_capacity(self.branching_factor),
+            values: Vec::with_capacity(self.branching_factor),
+        };
+        right.keys.extend(node.keys.drain(split_idx..));
+        right.values.extend(node.values.drain(split_idx..));
+        let separator = right.keys[0].clone();
+
+        SplitResult::Split {
+            left: node,
+            right,
+            separator,
+        }
+    }
+}
+
+impl<K, V> NodeSplitter<K, V, SharedBranch<K, V>> for SharedNodeSplitter {
+    fn needs_split(&self, node: &SharedBranch<K, V>) -> bool {
+        node.children.len() >= self.branching_factor
+    }
+
+    fn split(&self, mut node: SharedBranch<K, V>) -> SplitResult<K, SharedBranch<K, V>> {
+        if !self.needs_split(&node) {
+            return SplitResult::NoSplit(node);
+        }
+
+        // The middle key moves up as the separator
+        let split_idx = node.keys.len() / 2;
+        let mut right = SharedBranch {
+            keys: Vec::with_capacity(self.branching_factor - 1),
+            children: Vec::with_capacity(self.branching_factor),
+        };
+        right.keys.extend(node.keys.drain(split_idx + 1..));
+        right.children.extend(node.children.drain(split_idx + 1..));
+        let separator = node.keys.pop().unwrap();
+
+        SplitResult::Split {
+            left: node,
+            right,
+            separator,
+        }
+    }
+}
+
+/// Merger for the nodes of `SharedBPlusTreeMap`. A node other than the
+/// root needs at least half the branching factor in entries, or children
+/// for a branch. Two nodes are merged when their entries fit in one;
+/// otherwise the entries are shared out evenly between them.
+pub(crate) struct SharedNodeMerger {
+    /// Most entries a leaf holds, and most children a branch holds
+    branching_factor: usize,
+}
+
+impl SharedNodeMerger {
+    /// Create a new merger for the given branching factor
+    pub(crate) fn new(branching_factor: usize) -> Self {
+        Self { branching_factor }
+    }
+}
+
+impl<K: Clone, V> NodeMerger<K, V, SharedLeaf<K, V>> for SharedNodeMerger {
+    fn needs_merge(&self, left: &SharedLeaf<K, V>, right: &SharedLeaf<K, V>) -> bool {
+        let min_len = self.branching_factor / 2;
+        left.keys.len() < min_len || right.keys.len() < min_len
+    }
+
+    fn merge(
+        &self,
+        mut left: SharedLeaf<K, V>,
+        mut right: SharedLeaf<K, V>,
+        separator: K,
+    ) -> MergeResult<K, SharedLeaf<K, V>> {
+        if !self.needs_merge(&left, &right) {
+            return MergeResult::NoMerge {
+                left,
+                right,
+                separator,
+            };
+        }
+
+        let total = left.keys.len() + right.keys.len();
+        if total <= self.branching_factor {
+            left.keys.append(&mut right.keys);
+            left.values.append(&mut right.values);
+            return MergeResult::Merged(left);
+        }
+
+        let target_left_len = total / 2;
+        if left.keys.len() < target_left_len {
+            // Move entries from the front of right onto the end of left
+            let move_count = target_left_len - left.keys.len();
+            left.keys.extend(right.keys.drain(..move_count));
+            left.values.extend(right.values.drain(..move_count));
+        } else {
+            // Move entries from the end of left onto the front of right
+            right.keys.splice(0..0, left.keys.drain(target_left_len..));
+            right
+                .values
+                .splice(0..0, left.values.drain(target_left_len..));
+        }
+
+        let separator = right.keys[0].clone();
+        MergeResult::Rebalanced {
+            left,
+            right,
+            separator,
+        }
+    }
+}
+
+impl<K, V> NodeMerger<K, V, SharedBranch<K, V>> for SharedNodeMerger {
+    fn needs_merge(&self, left: &SharedBranch<K, V>, right: &SharedBranch<K, V>) -> bool {
+        let min_len = self.branching_factor / 2;
+        left.children.len() < min_len || right.children.len() < min_len
+    }
+
+    fn merge(
+        &self,
+        mut left: SharedBranch<K, V>,
+        mut right: SharedBranch<K, V>,
+        separator: K,
+    ) -> MergeResult<K, SharedBranch<K, V>> {
+        if !self.needs_merge(&left, &right) {
+            return MergeResult::NoMerge {
+                left,
+                right,
+                separator,
+            };
+        }
+
+        // The separator comes down between the keys of the two branches
+        let total = left.children.len() + right.children.len();
+        if total <= self.branching_factor {
+            left.keys.push(separator);
+            left.keys.append(&mut right.keys);
+            left.children.append(&mut right.children);
+            return MergeResult::Merged(left);
+        }
+
+        // Children move across through the separator, which is replaced
+        // by the key beside the last child moved
+        let target_left_len = total / 2;
+        let separator = if left.children.len() < target_left_len {
+            let move_count = target_left_len - left.children.len();
+            left.keys.push(separator);
+            left.keys.extend(right.keys.drain(..move_count - 1));
+            left.children.extend(right.children.drain(..move_count));
+            right.keys.remove(0)
+        } else {
+            right
+                .keys
+                .splice(0..0, left.keys.drain(target_left_len..).chain([separator]));
+            right
+                .children
+                .splice(0..0, left.children.drain(target_left_len..));
+            left.keys.pop().unwrap()
+        };
+
+        MergeResult::Rebalanced {
+            left,
+            right,
+            separator,
+        }
+    }
+}
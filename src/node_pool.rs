@@ -0,0 +1,170 @@
+use crate::bplus_tree_map::{BranchNode, LeafNode, Node, NodeBox};
+use crate::node_box::LeafLinks;
+use crate::node_vec::NodeVec;
+
+/// A bounded store of emptied nodes. The Vecs of a pooled node keep their
+/// allocations, so nodes taken from the pool can be filled without
+/// allocating again.
+pub struct NodePool<K, V> {
+    /// Emptied leaf nodes ready for reuse
+    leaves: Vec<LeafNode<K, V>>,
+    /// Emptied branch nodes ready for reuse
+    branches: Vec<BranchNode<K, V>>,
+    /// Number of keys the Vecs of newly created nodes have room for
+    node_capacity: usize,
+}
+
+impl<K, V> NodePool<K, V> {
+    /// Create an empty pool
+    pub fn new() -> Self {
+        Self {
+            leaves: Vec::new(),
+            branches: Vec::new(),
+            node_capacity: 0,
+        }
+    }
+
+    /// Make nodes created from now on allocate room for `node_capacity`
+    /// keys up front, instead of growing their Vecs as they fill
+    pub fn set_node_capacity(&mut self, node_capacity: usize) {
+        self.node_capacity = node_capacity;
+    }
+
+    /// Drop the pooled nodes, which are sized for another branching factor.
+    /// If nodes were being created with room for a full node, they are
+    /// given room for `node_capacity` keys from now on.
+    pub fn resize_nodes(&mut self, node_capacity: usize) {
+        self.leaves.clear();
+        self.branches.clear();
+        if self.node_capacity > 0 {
+            self.node_capacity = node_capacity;
+        }
+    }
+
+    /// Create empty nodes until the pool holds at least `leaves` leaves and
+    /// `branches` branches, so that the nodes a map is about to need are
+    /// allocated ahead of time
+    pub fn reserve(&mut self, leaves: usize, branches: usize) {
+        self.leaves
+            .reserve(leaves.saturating_sub(self.leaves.len()));
+        self.branches
+            .reserve(branches.saturating_sub(self.branches.len()));
+        while self.leaves.len() < leaves {
+            let leaf = self.new_leaf();
+            self.leaves.push(leaf);
+        }
+        while self.branches.len() < branches {
+            let branch = self.new_branch();
+            self.branches.push(branch);
+        }
+    }
+
+    /// Drop the pooled nodes and give back the room kept for them
+    pub fn shrink_to_fit(&mut self) {
+        self.leaves = Vec::new();
+        self.branches = Vec::new();
+    }
+
+    /// Number of leaf nodes in the pool
+    pub fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Number of branch nodes in the pool
+    pub fn branch_count(&self) -> usize {
+        self.branches.len()
+    }
+
+    /// The pooled leaves themselves
+    #[cfg(feature = "deepsize")]
+    pub(crate) fn leaves(&self) -> &Vec<LeafNode<K, V>> {
+        &self.leaves
+    }
+
+    /// The pooled branches themselves
+    #[cfg(feature = "deepsize")]
+    pub(crate) fn branches(&self) -> &Vec<BranchNode<K, V>> {
+        &self.branches
+    }
+
+    /// Take an empty leaf from the pool, or create one if the pool has none
+    pub fn take_leaf(&mut self) -> LeafNode<K, V> {
+        match self.leaves.pop() {
+            Some(leaf) => leaf,
+            None => self.new_leaf(),
+        }
+    }
+
+    /// Take an empty branch from the pool, or create one if the pool has none
+    pub fn take_branch(&mut self) -> BranchNode<K, V> {
+        match self.branches.pop() {
+            Some(branch) => branch,
+            None => self.new_branch(),
+        }
+    }
+
+    /// Create an empty leaf with room for `node_capacity` keys
+    fn new_leaf(&self) -> LeafNode<K, V> {
+        LeafNode::new(
+            NodeVec::with_capacity(self.node_capacity),
+            NodeVec::with_capacity(self.node_capacity),
+        )
+    }
+
+    /// Create an empty branch with room for `node_capacity` keys
+    fn new_branch(&self) -> BranchNode<K, V> {
+        // A branch has one more child than it has keys
+        let child_capacity = match self.node_capacity {
+            0 => 0,
+            keys => keys + 1,
+        };
+        BranchNode {
+            keys: NodeVec::with_capacity(self.node_capacity),
+            children: Vec::with_capacity(child_capacity),
+            min_key: None,
+            max_key: None,
+        }
+    }
+
+    /// Empty every node of the tree under `root` and keep them for reuse.
+    /// The pool is bounded by the size of this tree: afterwards it holds at
+    /// most as many leaves and branches as the tree had.
+    pub fn recycle_tree(&mut self, root: Node<K, V>) {
+        let mut leaf_count = 0;
+        let mut branch_count = 0;
+        let mut stack = vec![root];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf(mut leaf) => {
+                    leaf.keys.clear();
+                    leaf.values.clear();
+                    leaf.refresh_filter();
+                    leaf.links = LeafLinks::default();
+                    self.leaves.push(leaf);
+                    leaf_count += 1;
+                }
+                Node::Branch(mut branch) => {
+                    branch.keys.clear();
+                    branch.min_key = None;
+                    branch.max_key = None;
+                    stack.extend(branch.children.drain(..).map(NodeBox::into_inner));
+                    self.branches.push(branch);
+                    branch_count += 1;
+                }
+            }
+        }
+
+        // The most recently recycled nodes are at the end and are kept
+        let excess_leaves = self.leaves.len().saturating_sub(leaf_count);
+        self.leaves.drain(..excess_leaves);
+        let excess_branches = self.branches.len().saturating_sub(branch_count);
+        self.branches.drain(..excess_branches);
+    }
+}
+
+impl<K, V> Default for NodePool<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,65 @@
+use crate::bplus_tree_map::DEFAULT_BRANCHING_FACTOR;
+
+/// The vector a node keeps its keys, and a leaf its values, in. With the
+/// `smallvec` feature the first `NODE_INLINE_LEN` items are stored in the
+/// node itself, so the nodes of maps with the default branching factor
+/// never allocate for them; wider nodes spill onto the heap as a `Vec`
+/// would.
+#[cfg(not(feature = "smallvec"))]
+pub type NodeVec<T> = Vec<T>;
+#[cfg(feature = "smallvec")]
+pub type NodeVec<T> = smallvec::SmallVec<[T; NODE_INLINE_LEN]>;
+
+/// The iterator a `NodeVec` turns into
+#[cfg(not(feature = "smallvec"))]
+pub type NodeVecIntoIter<T> = std::vec::IntoIter<T>;
+#[cfg(feature = "smallvec")]
+pub type NodeVecIntoIter<T> = smallvec::IntoIter<[T; NODE_INLINE_LEN]>;
+
+/// The most keys a node of the default branching factor holds, which is
+/// one more than the branching factor while it waits to be split
+pub const NODE_INLINE_LEN: usize = DEFAULT_BRANCHING_FACTOR + 1;
+
+/// The `Vec` methods nodes use that a `NodeVec` may lack
+pub trait NodeVecExt<T> {
+    /// Moves the items from `at` on into a new vector, as
+    /// `Vec::split_off` does
+    fn split_off(&mut self, at: usize) -> Self;
+
+    /// Inserts `items` in order before the first item
+    fn prepend(&mut self, items: impl IntoIterator<Item = T>);
+
+    /// The number of items there is room for on the heap, which for items
+    /// stored inline is none
+    fn heap_capacity(&self) -> usize;
+}
+
+#[cfg(not(feature = "smallvec"))]
+impl<T> NodeVecExt<T> for Vec<T> {
+    fn split_off(&mut self, at: usize) -> Self {
+        Vec::split_off(self, at)
+    }
+
+    fn prepend(&mut self, items: impl IntoIterator<Item = T>) {
+        self.splice(0..0, items);
+    }
+
+    fn heap_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<T> NodeVecExt<T> for NodeVec<T> {
+    fn split_off(&mut self, at: usize) -> Self {
+        self.drain(at..).collect()
+    }
+
+    fn prepend(&mut self, items: impl IntoIterator<Item = T>) {
+        self.insert_many(0, items);
+    }
+
+    fn heap_capacity(&self) -> usize {
+        if self.spilled() { self.capacity() } else { 0 }
+    }
+}
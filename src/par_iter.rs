@@ -0,0 +1,218 @@
+// Parallel iteration with rayon, behind the `rayon` feature. The tree is
+// split for the thread pool along its own structure: a run of sibling
+// subtrees is split in half, and a single branch into its children, so no
+// entries are gathered up front. Maps can be built in parallel too.
+
+use std::fmt::Debug;
+
+use rayon::iter::plumbing::{Folder, UnindexedConsumer, UnindexedProducer, bridge_unindexed};
+use rayon::iter::{
+    FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    ParallelIterator,
+};
+use rayon::slice::ParallelSliceMut;
+
+use crate::bplus_tree_map::{
+    BPlusTreeMap, DEFAULT_BRANCHING_FACTOR, LeafNode, Node, NodeBox, dedup_sorted,
+};
+use crate::node_vec::NodeVec;
+use crate::separator::SeparatorKey;
+
+/// A run of sibling subtrees of a tree, visited in key order
+struct NodeProducer<'a, K, V> {
+    nodes: &'a [NodeBox<K, V>],
+}
+
+impl<'a, K: Sync, V: Sync> UnindexedProducer for NodeProducer<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self, Option<Self>) {
+        let mut nodes = self.nodes;
+        // A lone branch is opened up so its children can be shared out
+        while let [node] = nodes
+            && let Node::Branch(branch) = &**node
+        {
+            nodes = &branch.children;
+        }
+        if nodes.len() < 2 {
+            return (NodeProducer { nodes }, None);
+        }
+        let (left, right) = nodes.split_at(nodes.len() / 2);
+        (
+            NodeProducer { nodes: left },
+            Some(NodeProducer { nodes: right }),
+        )
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        fn fold<'a, K, V, F: Folder<(&'a K, &'a V)>>(
+            nodes: &'a [NodeBox<K, V>],
+            mut folder: F,
+        ) -> F {
+            for node in nodes {
+                folder = match &**node {
+                    Node::Leaf(leaf) => folder.consume_iter(leaf.keys.iter().zip(&leaf.values)),
+                    Node::Branch(branch) => fold(&branch.children, folder),
+                };
+                if folder.full() {
+                    break;
+                }
+            }
+            folder
+        }
+        fold(self.nodes, folder)
+    }
+}
+
+/// A parallel iterator over the entries of a `BPlusTreeMap`, created by
+/// `par_iter` or `into_par_iter` on a reference to the map
+pub struct ParIter<'a, K, V> {
+    root: Option<&'a NodeBox<K, V>>,
+}
+
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        let producer = NodeProducer {
+            nodes: self.root.map_or(&[], std::slice::from_ref),
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+impl<'a, K: Sync, V: Sync> IntoParallelIterator for &'a BPlusTreeMap<K, V> {
+    type Iter = ParIter<'a, K, V>;
+    type Item = (&'a K, &'a V);
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter {
+            root: self.root_node(),
+        }
+    }
+}
+
+/// A parallel iterator over the keys of a `BPlusTreeMap`, created by
+/// `par_keys`
+pub struct ParKeys<'a, K, V> {
+    inner: ParIter<'a, K, V>,
+}
+
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParKeys<'a, K, V> {
+    type Item = &'a K;
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.inner.map(|(k, _)| k).drive_unindexed(consumer)
+    }
+}
+
+/// A parallel iterator over the values of a `BPlusTreeMap`, created by
+/// `par_values`
+pub struct ParValues<'a, K, V> {
+    inner: ParIter<'a, K, V>,
+}
+
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParValues<'a, K, V> {
+    type Item = &'a V;
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.inner.map(|(_, v)| v).drive_unindexed(consumer)
+    }
+}
+
+impl<K: Sync, V: Sync> BPlusTreeMap<K, V> {
+    /// Returns a parallel iterator over the keys of the map. Like
+    /// `par_iter`, it splits the work along the subtrees of the tree.
+    pub fn par_keys(&self) -> ParKeys<'_, K, V> {
+        ParKeys {
+            inner: self.into_par_iter(),
+        }
+    }
+
+    /// Returns a parallel iterator over the values of the map. Like
+    /// `par_iter`, it splits the work along the subtrees of the tree.
+    pub fn par_values(&self) -> ParValues<'_, K, V> {
+        ParValues {
+            inner: self.into_par_iter(),
+        }
+    }
+
+    /// Calls `f` with the keys and values of each leaf, fanning the leaves
+    /// out across the rayon thread pool. Every leaf is visited exactly
+    /// once, in no particular order. Handing out whole leaves suits work
+    /// that is too small per entry to be worth spreading item by item.
+    pub fn par_for_each_leaf<F>(&self, f: F)
+    where
+        F: Fn(&[K], &[V]) + Sync,
+    {
+        fn visit<K: Sync, V: Sync>(node: &Node<K, V>, f: &(impl Fn(&[K], &[V]) + Sync)) {
+            match node {
+                Node::Leaf(leaf) => f(&leaf.keys, &leaf.values),
+                Node::Branch(branch) => {
+                    branch.children.par_iter().for_each(|child| visit(child, f))
+                }
+            }
+        }
+
+        if let Some(root) = self.root_node() {
+            visit(root, &f);
+        }
+    }
+}
+
+impl<K, V> BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug + Send + Sync,
+    V: Clone + Debug + Send,
+{
+    /// Creates a map from key-value pairs in any order, like
+    /// `from_unsorted`, spreading the work over the rayon thread pool. The
+    /// pairs are sorted in parallel, and where a key is repeated the last
+    /// value for it wins. The leaves are then built in parallel, each from
+    /// its own run of the sorted pairs, and the branch levels over them
+    /// on the calling thread. The tree is the one `from_unsorted` builds.
+    pub fn par_sort_build(mut entries: Vec<(K, V)>) -> Self {
+        // The sort is stable, so the last pair given for a key stays last
+        entries.par_sort_by(|a, b| a.0.cmp(&b.0));
+        dedup_sorted(&mut entries);
+        let size = entries.len();
+        if size == 0 {
+            return Self::with_branching_factor(DEFAULT_BRANCHING_FACTOR);
+        }
+
+        // The runs are laid out as for `from_unsorted`: as few as hold the
+        // pairs, the longer ones first and one pair longer than the rest
+        let leaf_count = size.div_ceil(DEFAULT_BRANCHING_FACTOR);
+        let short_len = size / leaf_count;
+        let short_runs = entries.split_off((short_len + 1) * (size % leaf_count));
+        let leaves = entries
+            .into_par_iter()
+            .chunks(short_len + 1)
+            .chain(short_runs.into_par_iter().chunks(short_len))
+            .map(|run| {
+                let (keys, values): (NodeVec<K>, NodeVec<V>) = run.into_iter().unzip();
+                let leaf = LeafNode::new(keys, values);
+                (SeparatorKey::new(leaf.keys[0].clone()), Node::Leaf(leaf))
+            })
+            .collect();
+
+        let root = Self::build_branches(leaves, DEFAULT_BRANCHING_FACTOR + 1);
+        Self::from_built_root(DEFAULT_BRANCHING_FACTOR, root, size)
+    }
+}
+
+impl<K, V> FromParallelIterator<(K, V)> for BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug + Send + Sync,
+    V: Clone + Debug + Send,
+{
+    /// Builds the map with `par_sort_build`. The pairs are gathered in
+    /// their order first, so as with `from_iter` the last value given for
+    /// a key wins.
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = (K, V)>,
+    {
+        Self::par_sort_build(par_iter.into_par_iter().collect())
+    }
+}
@@ -0,0 +1,638 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::iter::{FusedIterator, Zip};
+use std::mem;
+use std::slice;
+use std::sync::Arc;
+
+use crate::bplus_tree_map::DEFAULT_BRANCHING_FACTOR;
+use crate::node_operations::{
+    MergeResult, NodeMerger, NodeSplitter, SharedNodeMerger, SharedNodeSplitter, SplitResult,
+};
+
+/// A leaf of a `SharedBPlusTreeMap`
+#[derive(Clone)]
+pub(crate) struct SharedLeaf<K, V> {
+    pub(crate) keys: Vec<K>,
+    pub(crate) values: Vec<V>,
+}
+
+/// A branch of a `SharedBPlusTreeMap`. Its children are reference counted,
+/// so a copy of the branch shares them with the original.
+#[derive(Clone)]
+pub(crate) struct SharedBranch<K, V> {
+    pub(crate) keys: Vec<K>,
+    pub(crate) children: Vec<Arc<SharedNode<K, V>>>,
+}
+
+/// A node of a `SharedBPlusTreeMap`
+#[derive(Clone)]
+pub(crate) enum SharedNode<K, V> {
+    Leaf(SharedLeaf<K, V>),
+    Branch(SharedBranch<K, V>),
+}
+
+// Written out rather than derived, which would need `K` and `V` to
+// implement `Default` too
+impl<K, V> Default for SharedLeaf<K, V> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<K, V> Default for SharedBranch<K, V> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<K, V> SharedNode<K, V> {
+    /// Returns true if the node has no room for another entry or child
+    fn is_full(&self, branching_factor: usize) -> bool {
+        match self {
+            SharedNode::Leaf(leaf) => leaf.keys.len() >= branching_factor,
+            SharedNode::Branch(branch) => branch.children.len() >= branching_factor,
+        }
+    }
+}
+
+/// A B+ tree map whose nodes are reference counted, so that cloning the
+/// map takes constant time: the clone shares every node with the
+/// original. The branching factor is the most entries a leaf holds and
+/// the most children a branch holds; it must be at least 4.
+///
+/// A change copies only the nodes on the path from the root to the leaf
+/// it touches, and only those still shared with another map; a lookup
+/// that finds nothing to change copies nothing. Every other node stays
+/// shared, so a clone taken as a snapshot costs memory in proportion to
+/// the changes made since, not to the size of the map. Nodes are shared
+/// through `Arc`, so a map of `Send + Sync` keys and values can be cloned
+/// and handed to other threads.
+///
+/// Full nodes are split on the way down an insert, as in
+/// `BPlusTreeArrayMap`.
+pub struct SharedBPlusTreeMap<K, V> {
+    root: Option<Arc<SharedNode<K, V>>>,
+    len: usize,
+    branching_factor: usize,
+}
+
+impl<K, V> SharedBPlusTreeMap<K, V> {
+    /// Creates an empty map with the default branching factor
+    pub fn new() -> Self {
+        Self::with_branching_factor(DEFAULT_BRANCHING_FACTOR)
+    }
+
+    /// Creates an empty map with the given branching factor
+    pub fn with_branching_factor(branching_factor: usize) -> Self {
+        if branching_factor < 4 {
+            panic!("SharedBPlusTreeMap needs a branching factor of at least 4");
+        }
+        Self {
+            root: None,
+            len: 0,
+            branching_factor,
+        }
+    }
+
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every entry from the map. Nodes still shared with clones
+    /// of the map are left to them.
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the entries of the map, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            stack: vec![self.root.as_slice().iter()],
+            leaf: [].iter().zip([].iter()),
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over the values of the map, in key order
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> SharedBPlusTreeMap<K, V> {
+    /// Returns a reference to the value for the key
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node {
+                SharedNode::Leaf(leaf) => {
+                    let idx = leaf.keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+                    return Some(&leaf.values[idx]);
+                }
+                SharedNode::Branch(branch) => node = &branch.children[child_index(branch, key)],
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value for the key. The nodes on
+    /// the way to it are copied first if they are shared.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // Look first, so that a missing key copies no nodes
+        if !self.contains_key(key) {
+            return None;
+        }
+        let mut node = Arc::make_mut(self.root.as_mut()?);
+        loop {
+            match node {
+                SharedNode::Leaf(leaf) => {
+                    let idx = leaf.keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+                    return Some(&mut leaf.values[idx]);
+                }
+                SharedNode::Branch(branch) => {
+                    let idx = child_index(branch, key);
+                    node = Arc::make_mut(&mut branch.children[idx]);
+                }
+            }
+        }
+    }
+
+    /// Returns true if the map holds the key
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts an entry, returning the value it replaced, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let branching_factor = self.branching_factor;
+        let root = match self.root.take() {
+            None => Arc::new(SharedNode::Leaf(SharedLeaf::default())),
+            Some(mut root) if root.is_full(branching_factor) => {
+                let (separator, right) = split(Arc::make_mut(&mut root), branching_factor);
+                Arc::new(SharedNode::Branch(SharedBranch {
+                    keys: vec![separator],
+                    children: vec![root, right],
+                }))
+            }
+            Some(root) => root,
+        };
+
+        // The node descended into always has room, so a full child can be
+        // split into it
+        let mut node = Arc::make_mut(self.root.insert(root));
+        loop {
+            match node {
+                SharedNode::Leaf(leaf) => {
+                    return match leaf.keys.binary_search(&key) {
+                        Ok(idx) => Some(mem::replace(&mut leaf.values[idx], value)),
+                        Err(idx) => {
+                            leaf.keys.insert(idx, key);
+                            leaf.values.insert(idx, value);
+                            self.len += 1;
+                            None
+                        }
+                    };
+                }
+                SharedNode::Branch(branch) => {
+                    let mut idx = child_index(branch, &key);
+                    if branch.children[idx].is_full(branching_factor) {
+                        let child = Arc::make_mut(&mut branch.children[idx]);
+                        let (separator, right) = split(child, branching_factor);
+                        let goes_right = key >= separator;
+                        branch.keys.insert(idx, separator);
+                        branch.children.insert(idx + 1, right);
+                        idx += usize::from(goes_right);
+                    }
+                    node = Arc::make_mut(&mut branch.children[idx]);
+                }
+            }
+        }
+    }
+
+    /// Removes the key, returning its value if the map held it
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // Look first, so that a missing key copies no nodes
+        if !self.contains_key(key) {
+            return None;
+        }
+        let root = Arc::make_mut(self.root.as_mut()?);
+        let (_, value) = remove_from(root, key, self.branching_factor)?;
+        self.len -= 1;
+
+        // The root may have lost its last entry, or all but one child
+        let new_root = match root {
+            SharedNode::Leaf(leaf) if leaf.keys.is_empty() => Some(None),
+            SharedNode::Branch(branch) if branch.children.len() == 1 => Some(branch.children.pop()),
+            _ => None,
+        };
+        if let Some(root) = new_root {
+            self.root = root;
+        }
+        Some(value)
+    }
+}
+
+/// Returns the index of the child of `branch` whose subtree holds `key`.
+/// As in `BPlusTreeMap`, a key equal to a separator belongs to the child
+/// on its right.
+fn child_index<K, V, Q>(branch: &SharedBranch<K, V>, key: &Q) -> usize
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    branch.keys.partition_point(|k| k.borrow() <= key)
+}
+
+/// Splits a full node in two, keeping the left half in place, and returns
+/// the separator and the right half
+fn split<K: Clone, V>(
+    node: &mut SharedNode<K, V>,
+    branching_factor: usize,
+) -> (K, Arc<SharedNode<K, V>>) {
+    let splitter = SharedNodeSplitter::new(branching_factor);
+    match node {
+        SharedNode::Leaf(leaf) => match splitter.split(mem::take(leaf)) {
+            SplitResult::Split {
+                left,
+                right,
+                separator,
+            } => {
+                *leaf = left;
+                (separator, Arc::new(SharedNode::Leaf(right)))
+            }
+            SplitResult::NoSplit(_) => unreachable!("only full nodes are split"),
+        },
+        SharedNode::Branch(branch) => match splitter.split(mem::take(branch)) {
+            SplitResult::Split {
+                left,
+                right,
+                separator,
+            } => {
+                *branch = left;
+                (separator, Arc::new(SharedNode::Branch(right)))
+            }
+            SplitResult::NoSplit(_) => unreachable!("only full nodes are split"),
+        },
+    }
+}
+
+/// Removes the key from the subtree under `node`, which must hold it. A
+/// child left with too few entries is merged with or refilled from a
+/// sibling on the way back up; `node` itself is left for its parent to fix.
+fn remove_from<K, V, Q>(
+    node: &mut SharedNode<K, V>,
+    key: &Q,
+    branching_factor: usize,
+) -> Option<(K, V)>
+where
+    K: Clone + Borrow<Q>,
+    V: Clone,
+    Q: Ord + ?Sized,
+{
+    match node {
+        SharedNode::Leaf(leaf) => {
+            let idx = leaf.keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+            Some((leaf.keys.remove(idx), leaf.values.remove(idx)))
+        }
+        SharedNode::Branch(branch) => {
+            let idx = child_index(branch, key);
+            let child = Arc::make_mut(&mut branch.children[idx]);
+            let removed = remove_from(child, key, branching_factor)?;
+            if is_underfull(&branch.children[idx], branching_factor) {
+                // Every branch has at least two children; the leftmost
+                // child is balanced against its right sibling
+                balance_children(branch, idx.max(1) - 1, branching_factor);
+            }
+            Some(removed)
+        }
+    }
+}
+
+/// Returns true if a node other than the root holds too few entries
+fn is_underfull<K, V>(node: &SharedNode<K, V>, branching_factor: usize) -> bool {
+    match node {
+        SharedNode::Leaf(leaf) => leaf.keys.len() < branching_factor / 2,
+        SharedNode::Branch(branch) => branch.children.len() < branching_factor / 2,
+    }
+}
+
+/// Merges the children of `branch` at `idx` and `idx + 1`, or moves
+/// entries between them, so that neither is underfull. Either child still
+/// shared with another map is copied first.
+fn balance_children<K: Clone, V: Clone>(
+    branch: &mut SharedBranch<K, V>,
+    idx: usize,
+    branching_factor: usize,
+) {
+    let merger = SharedNodeMerger::new(branching_factor);
+    let separator = branch.keys.remove(idx);
+    let mut right = branch.children.remove(idx + 1);
+    let left = Arc::make_mut(&mut branch.children[idx]);
+    let balanced = match (left, Arc::make_mut(&mut right)) {
+        (SharedNode::Leaf(left), SharedNode::Leaf(right)) => {
+            match merger.merge(mem::take(left), mem::take(right), separator) {
+                MergeResult::Merged(merged) => {
+                    *left = merged;
+                    None
+                }
+                MergeResult::Rebalanced {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                }
+                | MergeResult::NoMerge {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                } => {
+                    *left = new_left;
+                    *right = new_right;
+                    Some(separator)
+                }
+            }
+        }
+        (SharedNode::Branch(left), SharedNode::Branch(right)) => {
+            match merger.merge(mem::take(left), mem::take(right), separator) {
+                MergeResult::Merged(merged) => {
+                    *left = merged;
+                    None
+                }
+                MergeResult::Rebalanced {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                }
+                | MergeResult::NoMerge {
+                    left: new_left,
+                    right: new_right,
+                    separator,
+                } => {
+                    *left = new_left;
+                    *right = new_right;
+                    Some(separator)
+                }
+            }
+        }
+        _ => unreachable!("siblings are at the same depth"),
+    };
+    if let Some(separator) = balanced {
+        branch.keys.insert(idx, separator);
+        branch.children.insert(idx + 1, right);
+    }
+}
+
+impl<K, V> Default for SharedBPlusTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for SharedBPlusTreeMap<K, V> {
+    /// Returns a map sharing every node with this one, in constant time
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            branching_factor: self.branching_factor,
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for SharedBPlusTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for SharedBPlusTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Extend<(K, V)> for SharedBPlusTreeMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SharedBPlusTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `SharedBPlusTreeMap`, in key order
+pub struct Iter<'a, K, V> {
+    /// The nodes still to visit at each level above the current leaf
+    stack: Vec<slice::Iter<'a, Arc<SharedNode<K, V>>>>,
+    /// The entries of the current leaf still to visit
+    leaf: Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.leaf.next() {
+                self.remaining -= 1;
+                return Some(entry);
+            }
+            let siblings = self.stack.last_mut()?;
+            let Some(mut node) = siblings.next() else {
+                self.stack.pop();
+                continue;
+            };
+            // Descend to the leftmost leaf under the next node
+            while let SharedNode::Branch(branch) = &**node {
+                let mut children = branch.children.iter();
+                node = children.next()?;
+                self.stack.push(children);
+            }
+            if let SharedNode::Leaf(leaf) = &**node {
+                self.leaf = leaf.keys.iter().zip(leaf.values.iter());
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+#[cfg(test)]
+impl<K: Ord + Debug, V> SharedBPlusTreeMap<K, V> {
+    /// Checks that keys are in order and within the bounds of their
+    /// separators, that every leaf is at the same depth, that every node
+    /// but the root is at least half full and that the length is right
+    pub(crate) fn check_invariants(&self) -> Result<(), String> {
+        let Some(root) = &self.root else {
+            return if self.len == 0 {
+                Ok(())
+            } else {
+                Err(format!("empty tree with length {}", self.len))
+            };
+        };
+        let mut checker = InvariantChecker {
+            branching_factor: self.branching_factor,
+            leaf_depth: None,
+            count: 0,
+        };
+        checker.check_node(root, None, None, 0, true)?;
+        if checker.count != self.len {
+            return Err(format!("{} entries but length {}", checker.count, self.len));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl<K, V> SharedBPlusTreeMap<K, V> {
+    /// Returns the reference count of every node of the tree, the root
+    /// first and then each subtree in order, paired with the node's depth
+    pub(crate) fn strong_counts(&self) -> Vec<(usize, usize)> {
+        fn collect<K, V>(
+            node: &Arc<SharedNode<K, V>>,
+            depth: usize,
+            counts: &mut Vec<(usize, usize)>,
+        ) {
+            counts.push((depth, Arc::strong_count(node)));
+            if let SharedNode::Branch(branch) = &**node {
+                for child in &branch.children {
+                    collect(child, depth + 1, counts);
+                }
+            }
+        }
+        let mut counts = Vec::new();
+        if let Some(root) = &self.root {
+            collect(root, 0, &mut counts);
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+struct InvariantChecker {
+    branching_factor: usize,
+    leaf_depth: Option<usize>,
+    count: usize,
+}
+
+#[cfg(test)]
+impl InvariantChecker {
+    fn check_node<K: Ord + Debug, V>(
+        &mut self,
+        node: &SharedNode<K, V>,
+        lower: Option<&K>,
+        upper: Option<&K>,
+        depth: usize,
+        is_root: bool,
+    ) -> Result<(), String> {
+        let keys: &[K] = match node {
+            SharedNode::Leaf(leaf) => &leaf.keys,
+            SharedNode::Branch(branch) => &branch.keys,
+        };
+        if !keys.is_sorted_by(|a, b| a < b) {
+            return Err(format!("keys out of order: {:?}", keys));
+        }
+        if let (Some(lower), Some(first)) = (lower, keys.first())
+            && first < lower
+        {
+            return Err(format!(
+                "key {:?} is below its separator {:?}",
+                first, lower
+            ));
+        }
+        if let (Some(upper), Some(last)) = (upper, keys.last())
+            && last >= upper
+        {
+            return Err(format!(
+                "key {:?} is not below its separator {:?}",
+                last, upper
+            ));
+        }
+        if !is_root && is_underfull(node, self.branching_factor) {
+            return Err(format!("underfull node with keys {:?}", keys));
+        }
+        if node.is_full(self.branching_factor + 1) {
+            return Err(format!("overfull node with keys {:?}", keys));
+        }
+        match node {
+            SharedNode::Leaf(leaf) => {
+                if leaf.keys.len() != leaf.values.len() {
+                    return Err(format!(
+                        "leaf with keys {:?} has {} values",
+                        keys,
+                        leaf.values.len()
+                    ));
+                }
+                if *self.leaf_depth.get_or_insert(depth) != depth {
+                    return Err(format!("leaf with keys {:?} is at depth {}", keys, depth));
+                }
+                self.count += leaf.keys.len();
+            }
+            SharedNode::Branch(branch) => {
+                if branch.children.len() != branch.keys.len() + 1 || branch.children.len() < 2 {
+                    return Err(format!(
+                        "branch with keys {:?} has {} children",
+                        keys,
+                        branch.children.len()
+                    ));
+                }
+                for (idx, child) in branch.children.iter().enumerate() {
+                    let lower = idx.checked_sub(1).map(|i| &branch.keys[i]).or(lower);
+                    let upper = branch.keys.get(idx).or(upper);
+                    self.check_node(child, lower, upper, depth + 1, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
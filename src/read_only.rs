@@ -0,0 +1,348 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use crate::snapshot::{
+    BinaryCodec, Checksum, FOOTER_LEN, HEADER_LEN, MAGIC, SnapshotError, VERSION,
+};
+
+/// Reads the little-endian `u64` at `offset` in `bytes`. Snapshot bytes
+/// come with no alignment, so numbers are always copied out byte by byte
+/// rather than read in place, and never in the byte order of the host.
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    let mut number = [0; 8];
+    number.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(number)
+}
+
+/// Converts a number read from a snapshot into an offset or count that
+/// must not be past `limit`
+fn bounded(number: u64, limit: usize, what: &str) -> Result<usize, SnapshotError> {
+    usize::try_from(number)
+        .ok()
+        .filter(|&number| number <= limit)
+        .ok_or_else(|| SnapshotError::Invalid(format!("{} {} is out of bounds", what, number)))
+}
+
+/// A map read in place from the bytes of a snapshot written by
+/// `BPlusTreeMap::save_to`, such as a memory-mapped file. No nodes are
+/// built: lookups binary search the leaf index of the snapshot and then
+/// decode entries straight from the bytes, so keys and values are returned
+/// by value.
+///
+/// The whole snapshot is checked when it is opened, so queries never meet
+/// bytes that don't decode.
+pub struct ReadOnlyBPlusTree<'a, K, V> {
+    /// The encoded entries, in key order
+    entries: &'a [u8],
+    /// The offset into `entries` of the first entry of each leaf, as
+    /// little-endian `u64`s
+    leaf_offsets: &'a [u8],
+    /// The number of entries
+    len: usize,
+    /// The branching factor of the map the snapshot was written from
+    branching_factor: usize,
+    marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'a, K, V> ReadOnlyBPlusTree<'a, K, V>
+where
+    K: Ord + Debug + BinaryCodec,
+    V: BinaryCodec,
+{
+    /// Opens the snapshot held in `bytes`. Besides the header and checksum,
+    /// every entry is decoded once to check that the keys are in order and
+    /// that the entry count and leaf index agree with the entries.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, SnapshotError> {
+        if bytes.len() < HEADER_LEN + FOOTER_LEN {
+            return Err(SnapshotError::Invalid(format!(
+                "{} bytes is too short for a snapshot",
+                bytes.len()
+            )));
+        }
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = bytes[MAGIC.len()];
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let checksum_offset = bytes.len() - 8;
+        let mut checksum = Checksum::new();
+        checksum.update(&bytes[MAGIC.len()..checksum_offset]);
+        if checksum.value() != u64_at(bytes, checksum_offset) {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+
+        let branching_factor = u64_at(bytes, MAGIC.len() + 1);
+        if branching_factor < 2 {
+            return Err(SnapshotError::Invalid(format!(
+                "branching factor {} is less than 2",
+                branching_factor
+            )));
+        }
+        let branching_factor = bounded(branching_factor, usize::MAX, "branching factor")?;
+        let len = bounded(u64_at(bytes, MAGIC.len() + 9), usize::MAX, "entry count")?;
+        let index_end = bytes.len() - FOOTER_LEN;
+        let index_offset = bounded(u64_at(bytes, index_end), index_end, "leaf index offset")?;
+        if index_offset < HEADER_LEN || index_end - index_offset < 8 {
+            return Err(SnapshotError::Invalid(format!(
+                "leaf index offset {} is out of bounds",
+                index_offset
+            )));
+        }
+        let leaf_count = u64_at(bytes, index_offset);
+        let leaf_offsets = &bytes[index_offset + 8..index_end];
+        if leaf_offsets.len() as u64 != leaf_count.saturating_mul(8) {
+            return Err(SnapshotError::Invalid(format!(
+                "leaf index of {} bytes does not hold {} offsets",
+                leaf_offsets.len(),
+                leaf_count
+            )));
+        }
+
+        let tree = ReadOnlyBPlusTree {
+            entries: &bytes[HEADER_LEN..index_offset],
+            leaf_offsets,
+            len,
+            branching_factor,
+            marker: PhantomData,
+        };
+        tree.check_entries()?;
+        Ok(tree)
+    }
+
+    /// Decodes every entry, checking that the keys are strictly ascending,
+    /// that there are as many entries as the header says, and that the leaf
+    /// index lists ascending offsets of entries, starting with the first
+    fn check_entries(&self) -> Result<(), SnapshotError> {
+        let leaf_count = self.leaf_offsets.len() / 8;
+        let mut rest = self.entries;
+        let mut count = 0;
+        let mut leaf = 0;
+        let mut previous: Option<K> = None;
+
+        while !rest.is_empty() {
+            let offset = self.entries.len() - rest.len();
+            if leaf < leaf_count && self.leaf_offset(leaf) == Some(offset) {
+                leaf += 1;
+            } else if leaf == 0 {
+                return Err(SnapshotError::Invalid(
+                    "leaf index does not start at the first entry".to_string(),
+                ));
+            }
+            let key = K::decode(&mut rest)?;
+            V::decode(&mut rest)?;
+            if let Some(previous) = &previous
+                && *previous >= key
+            {
+                return Err(SnapshotError::Invalid(format!(
+                    "key {:?} is not below the key {:?} after it",
+                    previous, key
+                )));
+            }
+            previous = Some(key);
+            count += 1;
+        }
+
+        if count != self.len {
+            return Err(SnapshotError::Invalid(format!(
+                "header counts {} entries but {} are stored",
+                self.len, count
+            )));
+        }
+        if leaf != leaf_count {
+            return Err(SnapshotError::Invalid(format!(
+                "leaf index offset {} is not the start of an entry",
+                u64_at(self.leaf_offsets, leaf * 8)
+            )));
+        }
+        Ok(())
+    }
+
+    /// The offset into `entries` of the first entry of leaf `leaf`, or None
+    /// if the snapshot gives one that is not
+    fn leaf_offset(&self, leaf: usize) -> Option<usize> {
+        let offset = u64_at(self.leaf_offsets, leaf * 8);
+        usize::try_from(offset).ok()?.checked_sub(HEADER_LEN)
+    }
+
+    /// Decodes the entry at the start of `bytes`, advancing past it
+    fn decode_entry(bytes: &mut &'a [u8]) -> (K, V) {
+        let decoded = K::decode(bytes).and_then(|key| Ok((key, V::decode(bytes)?)));
+        decoded.expect("entries were checked when the snapshot was opened")
+    }
+
+    /// Returns the offset into `entries` of the first entry whose key
+    /// `before` is false for, where `before` holds for a run of keys at
+    /// the start of the map. Only the first key of each leaf is decoded
+    /// while searching the leaf index, and then at most a leaf of entries.
+    fn position(&self, before: impl Fn(&K) -> bool) -> usize {
+        let leaf_count = self.leaf_offsets.len() / 8;
+        let leaf_start = |leaf| self.leaf_offset(leaf).expect("leaf index was checked");
+
+        // The number of leaves whose first key is before the position
+        let (mut low, mut high) = (0, leaf_count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (key, _) = Self::decode_entry(&mut &self.entries[leaf_start(middle)..]);
+            if before(&key) {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        if low == 0 {
+            return 0;
+        }
+
+        let mut rest = &self.entries[leaf_start(low - 1)..];
+        while !rest.is_empty() {
+            let entry_start = self.entries.len() - rest.len();
+            let (key, _) = Self::decode_entry(&mut rest);
+            if !before(&key) {
+                return entry_start;
+            }
+        }
+        self.entries.len()
+    }
+
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the branching factor of the map the snapshot was written from
+    pub fn branching_factor(&self) -> usize {
+        self.branching_factor
+    }
+
+    /// Returns the value for `key`, decoded from the snapshot, or None if
+    /// the key is not in the map
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rest = &self.entries[self.position(|k| k.borrow() < key)..];
+        if rest.is_empty() {
+            return None;
+        }
+        let (found, value) = Self::decode_entry(&mut rest);
+        (found.borrow() == key).then_some(value)
+    }
+
+    /// Returns true if `key` is in the map
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator decoding the entries in ascending key order
+    pub fn iter(&self) -> ReadOnlyIter<'a, K, V> {
+        ReadOnlyIter {
+            rest: self.entries,
+            len: Some(self.len),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator decoding the entries whose keys fall within
+    /// `range`, in ascending key order
+    ///
+    /// Panics in the same cases as `BPlusTreeMap::range`.
+    pub fn range<Q, R>(&self, range: R) -> ReadOnlyIter<'a, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        crate::bplus_tree_map::check_range_bounds(&range);
+        let start = self.position(|key| match range.start_bound() {
+            Bound::Included(start) => key.borrow() < start,
+            Bound::Excluded(start) => key.borrow() <= start,
+            Bound::Unbounded => false,
+        });
+        let end = self.position(|key| match range.end_bound() {
+            Bound::Included(end) => key.borrow() <= end,
+            Bound::Excluded(end) => key.borrow() < end,
+            Bound::Unbounded => true,
+        });
+        ReadOnlyIter {
+            rest: &self.entries[start..end.max(start)],
+            len: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Debug for ReadOnlyBPlusTree<'_, K, V>
+where
+    K: Ord + Debug + BinaryCodec,
+    V: Debug + BinaryCodec,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator decoding entries from the bytes of a `ReadOnlyBPlusTree`
+pub struct ReadOnlyIter<'a, K, V> {
+    /// The encoded entries not yet decoded
+    rest: &'a [u8],
+    /// The number of entries not yet decoded, when known
+    len: Option<usize>,
+    marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'a, K, V> Iterator for ReadOnlyIter<'a, K, V>
+where
+    K: Ord + Debug + BinaryCodec,
+    V: BinaryCodec,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        if let Some(len) = &mut self.len {
+            *len -= 1;
+        }
+        Some(ReadOnlyBPlusTree::<'a, K, V>::decode_entry(&mut self.rest))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.len {
+            Some(len) => (len, Some(len)),
+            None => (usize::from(!self.rest.is_empty()), None),
+        }
+    }
+}
+
+impl<K, V> FusedIterator for ReadOnlyIter<'_, K, V>
+where
+    K: Ord + Debug + BinaryCodec,
+    V: BinaryCodec,
+{
+}
+
+impl<K, V> Clone for ReadOnlyIter<'_, K, V> {
+    fn clone(&self) -> Self {
+        ReadOnlyIter {
+            rest: self.rest,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+/// Picks the separator promoted into a branch when a leaf splits. Branches
+/// only use separators to route lookups, so a separator need not be a key
+/// in the map: any key above everything in the left leaf and no higher than
+/// the first key of the right leaf routes every key the same way. Keys that
+/// are long but differ early, like strings, can be routed by much shorter
+/// separators than the keys themselves.
+pub trait SeparatorPolicy<K> {
+    /// Returns a key greater than `left`, the last key of the left leaf,
+    /// and less than or equal to `right`, the first key of the right leaf
+    fn separator(left: &K, right: &K) -> K;
+
+    /// Whether the separator is always `right` itself. Maps whose policy
+    /// promotes first keys keep every separator equal to the first key of
+    /// the subtree on its right, through removals too.
+    const FIRST_KEYS: bool = false;
+}
+
+/// Promotes a copy of the first key of the right leaf, as maps do unless
+/// they are given another policy
+pub struct IdentitySeparators;
+
+impl<K: Clone> SeparatorPolicy<K> for IdentitySeparators {
+    fn separator(_left: &K, right: &K) -> K {
+        right.clone()
+    }
+
+    const FIRST_KEYS: bool = true;
+}
+
+/// Promotes the shortest prefix of the first key of the right leaf that is
+/// still greater than the last key of the left leaf. Between "carpenter"
+/// and "cartography" the separator is "cart".
+pub struct ShortestSeparators;
+
+impl SeparatorPolicy<String> for ShortestSeparators {
+    fn separator(left: &String, right: &String) -> String {
+        // Strings order by their UTF-8 bytes, so the byte prefix is cut
+        // at the next character boundary to keep it a valid string
+        let mut len = shortest_prefix_len(left.as_bytes(), right.as_bytes());
+        while !right.is_char_boundary(len) {
+            len += 1;
+        }
+        right[..len].to_string()
+    }
+}
+
+impl SeparatorPolicy<Vec<u8>> for ShortestSeparators {
+    fn separator(left: &Vec<u8>, right: &Vec<u8>) -> Vec<u8> {
+        right[..shortest_prefix_len(left, right)].to_vec()
+    }
+}
+
+/// The length of the shortest prefix of `right` that is greater than
+/// `left`, where `left` is less than `right`: one byte past where they
+/// first differ, or past the end of `left` if it is a prefix of `right`
+fn shortest_prefix_len(left: &[u8], right: &[u8]) -> usize {
+    let common = left
+        .iter()
+        .zip(right)
+        .take_while(|(left, right)| left == right)
+        .count();
+    (common + 1).min(right.len())
+}
+
+/// A separator as a branch holds it. Separators are shared rather than
+/// copied, so handing one to a merge or a rebalance, or cloning the branch
+/// it is in, bumps a count instead of cloning the key. Only the keys taken
+/// from leaves when they split are cloned.
+pub type SeparatorKey<K> = Arc<K>;
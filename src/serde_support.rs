@@ -0,0 +1,196 @@
+// Serde support, behind the `serde` feature. A map is written as a plain
+// serde map, so its serialized form is interchangeable with that of a
+// `BTreeMap` holding the same entries.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use serde::de::{Deserializer, Error, MapAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, LeafNode, Node, NodeBox};
+use crate::separator::SeparatorKey;
+
+impl<K: Serialize, V: Serialize> Serialize for BPlusTreeMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Entries are written straight from the leaves, in key order
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Builds a `BPlusTreeMap` from a serde map
+struct MapVisitor<K, V> {
+    marker: PhantomData<BPlusTreeMap<K, V>>,
+}
+
+impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+where
+    K: Deserialize<'de> + Ord + Clone + Debug,
+    V: Deserialize<'de> + Clone + Debug,
+{
+    type Value = BPlusTreeMap<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        // The input may come from anywhere, so its keys are inserted one by
+        // one rather than trusted to be sorted; a repeated key keeps the
+        // value that comes last
+        let mut map = BPlusTreeMap::new();
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for BPlusTreeMap<K, V>
+where
+    K: Deserialize<'de> + Ord + Clone + Debug,
+    V: Deserialize<'de> + Clone + Debug,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor {
+            marker: PhantomData,
+        })
+    }
+}
+
+// The structured form records the branching factor and every node of the
+// tree, so that a map can be restored with exactly the shape it was saved
+// with. It is written from borrowed nodes and read into owned ones.
+
+/// The structured form of a map, borrowed from it for writing
+#[derive(Serialize)]
+#[serde(rename = "BPlusTreeMap")]
+struct StructureRef<'a, K, V> {
+    branching_factor: usize,
+    root: Option<NodeRef<'a, K, V>>,
+}
+
+/// A node of the structured form, borrowed from the tree for writing
+#[derive(Serialize)]
+#[serde(rename = "Node")]
+enum NodeRef<'a, K, V> {
+    Leaf {
+        keys: &'a [K],
+        values: &'a [V],
+    },
+    Branch {
+        keys: SeparatorsRef<'a, K>,
+        children: ChildrenRef<'a, K, V>,
+    },
+}
+
+/// The separators of a branch, written as the keys they share
+struct SeparatorsRef<'a, K>(&'a [SeparatorKey<K>]);
+
+/// The children of a branch, written one node at a time
+struct ChildrenRef<'a, K, V>(&'a [NodeBox<K, V>]);
+
+impl<'a, K, V> From<&'a Node<K, V>> for NodeRef<'a, K, V> {
+    fn from(node: &'a Node<K, V>) -> Self {
+        match node {
+            Node::Leaf(leaf) => NodeRef::Leaf {
+                keys: &leaf.keys,
+                values: &leaf.values,
+            },
+            Node::Branch(branch) => NodeRef::Branch {
+                keys: SeparatorsRef(&branch.keys),
+                children: ChildrenRef(&branch.children),
+            },
+        }
+    }
+}
+
+impl<K: Serialize> Serialize for SeparatorsRef<'_, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|key| &**key))
+    }
+}
+
+impl<K: Serialize, V: Serialize> Serialize for ChildrenRef<'_, K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|node| NodeRef::from(&**node)))
+    }
+}
+
+/// The structured form of a map as read, before it is checked
+#[derive(Deserialize)]
+#[serde(rename = "BPlusTreeMap")]
+struct Structure<K, V> {
+    branching_factor: usize,
+    root: Option<NodeData<K, V>>,
+}
+
+/// A node of the structured form as read, before it is checked
+#[derive(Deserialize)]
+#[serde(rename = "Node")]
+enum NodeData<K, V> {
+    Leaf {
+        keys: Vec<K>,
+        values: Vec<V>,
+    },
+    Branch {
+        keys: Vec<K>,
+        children: Vec<NodeData<K, V>>,
+    },
+}
+
+impl<K: Clone + PartialEq, V> From<NodeData<K, V>> for Node<K, V> {
+    fn from(node: NodeData<K, V>) -> Self {
+        match node {
+            NodeData::Leaf { keys, values } => Node::Leaf(LeafNode::new(keys, values)),
+            // Fences aren't serialized; they are rebuilt from the children
+            NodeData::Branch { keys, children } => Node::Branch(BranchNode::new(
+                keys.into_iter().map(SeparatorKey::new).collect::<Vec<_>>(),
+                children.into_iter().map(Node::from),
+            )),
+        }
+    }
+}
+
+impl<K, V> BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + Debug,
+{
+    /// Serializes the map together with its branching factor and the
+    /// separators and leaf contents of every node, so that
+    /// `deserialize_structure` can restore it with exactly the same shape.
+    /// It fits `#[serde(serialize_with = "...")]` on a field holding a map.
+    pub fn serialize_structure<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        StructureRef {
+            branching_factor: self.branching_factor(),
+            root: self.root_node().map(|root| NodeRef::from(&**root)),
+        }
+        .serialize(serializer)
+    }
+
+    /// Restores a map written by `serialize_structure`, node for node.
+    /// The input is checked before the map is built: keys must be in order
+    /// and within the bounds of the separators above them, no node may
+    /// hold more keys than the branching factor allows, every node below
+    /// the root must be at least half full, and every branch must have at
+    /// least two children. Input that fails a check is rejected with an
+    /// error describing the problem.
+    pub fn deserialize_structure<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let structure = Structure::deserialize(deserializer)?;
+        let root = structure.root.map(Node::from);
+        BPlusTreeMap::from_root(structure.branching_factor, root)
+            .map_err(|problem| D::Error::custom(format!("invalid tree structure: {}", problem)))
+    }
+}
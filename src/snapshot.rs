@@ -0,0 +1,336 @@
+use std::fmt::{self, Debug};
+use std::io::{self, Read, Write};
+use std::ops::ControlFlow;
+
+use crate::bplus_tree_map::BPlusTreeMap;
+
+/// The bytes every snapshot starts with
+pub(crate) const MAGIC: &[u8; 4] = b"BPT2";
+
+/// The version of the snapshot format written by `save_to`. Version 2
+/// added the leaf index.
+pub(crate) const VERSION: u8 = 2;
+
+/// The length of the magic, version, branching factor and entry count
+/// that come before the entries
+pub(crate) const HEADER_LEN: usize = 4 + 1 + 8 + 8;
+
+/// The length of the index offset and checksum that end a snapshot
+pub(crate) const FOOTER_LEN: usize = 8 + 8;
+
+/// A type that can be written to and read back from a snapshot. Encodings
+/// are little-endian, and variable-length values are written with their
+/// length first, so a decoder always knows where a value ends.
+pub trait BinaryCodec: Sized {
+    /// Writes the encoding of `self` to `writer`
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Reads a value written by `encode` from `reader`
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_int_codec {
+    ($($int:ty),*) => {
+        $(
+            impl BinaryCodec for $int {
+                fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0; size_of::<$int>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$int>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_int_codec!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl BinaryCodec for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        u8::from(*self).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(invalid_data(format!("{} is not a valid bool", byte))),
+        }
+    }
+}
+
+impl BinaryCodec for Vec<u8> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).encode(writer)?;
+        writer.write_all(self)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = u64::decode(reader)?;
+        // The length is not trusted to size an allocation up front: a
+        // corrupt one would only be found out after allocating for it
+        let mut bytes = Vec::new();
+        reader.take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes)
+    }
+}
+
+impl BinaryCodec for String {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        String::from_utf8(Vec::decode(reader)?).map_err(invalid_data)
+    }
+}
+
+macro_rules! impl_tuple_codec {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: BinaryCodec),+> BinaryCodec for ($($name,)+) {
+            fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                $(self.$index.encode(writer)?;)+
+                Ok(())
+            }
+
+            fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+                Ok(($($name::decode(reader)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple_codec!(A 0, B 1);
+impl_tuple_codec!(A 0, B 1, C 2);
+impl_tuple_codec!(A 0, B 1, C 2, D 3);
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// An error from saving or loading a snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading or writing failed, or the input ended early
+    Io(io::Error),
+    /// The input does not start with the snapshot magic bytes
+    BadMagic,
+    /// The snapshot was written in a format version this crate can't read
+    UnsupportedVersion(u8),
+    /// The checksum stored in the snapshot does not match its contents
+    ChecksumMismatch,
+    /// The snapshot is intact but does not describe a valid map
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "snapshot I/O failed: {}", error),
+            SnapshotError::BadMagic => write!(f, "input is not a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::ChecksumMismatch => write!(f, "snapshot checksum does not match"),
+            SnapshotError::Invalid(problem) => write!(f, "invalid snapshot: {}", problem),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+/// A 64-bit FNV-1a hash of the bytes fed to it. Any single changed byte
+/// changes the hash.
+#[derive(Clone, Copy)]
+pub(crate) struct Checksum(u64);
+
+impl Checksum {
+    pub(crate) fn new() -> Self {
+        Checksum(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn value(self) -> u64 {
+        self.0
+    }
+}
+
+/// Passes writes through to `inner`, hashing every byte written and
+/// keeping track of the offset reached in the snapshot
+struct ChecksumWriter<W> {
+    inner: W,
+    checksum: Checksum,
+    offset: u64,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.checksum.update(&buf[..written]);
+        self.offset += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Passes reads through from `inner`, hashing every byte read
+struct ChecksumReader<R> {
+    inner: R,
+    checksum: Checksum,
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.checksum.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<K, V> BPlusTreeMap<K, V>
+where
+    K: Ord + Clone + Debug + BinaryCodec,
+    V: Clone + Debug + BinaryCodec,
+{
+    /// Writes the map to `writer` as a binary snapshot. In order, it holds
+    /// a magic header, the format version, the branching factor and entry
+    /// count, the entries in key order, the leaf index, the offset of the
+    /// leaf index, and a checksum over everything after the magic. The leaf
+    /// index is a count followed by the offset of the first entry of each
+    /// leaf, which lets `ReadOnlyBPlusTree` find entries without reading
+    /// them all. Numbers are little-endian. Entries are encoded straight
+    /// from the leaves; wrap unbuffered writers such as files in a
+    /// `BufWriter`.
+    pub fn save_to<W: Write>(&self, writer: W) -> Result<(), SnapshotError> {
+        let mut writer = ChecksumWriter {
+            inner: writer,
+            checksum: Checksum::new(),
+            offset: MAGIC.len() as u64,
+        };
+        writer.inner.write_all(MAGIC)?;
+        VERSION.encode(&mut writer)?;
+        (self.branching_factor() as u64).encode(&mut writer)?;
+        (self.len() as u64).encode(&mut writer)?;
+
+        let mut leaf_offsets = Vec::new();
+        let written = self.try_for_each_leaf(|keys, values| {
+            if !keys.is_empty() {
+                leaf_offsets.push(writer.offset);
+            }
+            let encoded = keys.iter().zip(values).try_for_each(|(key, value)| {
+                key.encode(&mut writer)?;
+                value.encode(&mut writer)
+            });
+            match encoded {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(error) => ControlFlow::Break(error),
+            }
+        });
+        if let ControlFlow::Break(error) = written {
+            return Err(error.into());
+        }
+
+        let index_offset = writer.offset;
+        (leaf_offsets.len() as u64).encode(&mut writer)?;
+        for offset in leaf_offsets {
+            offset.encode(&mut writer)?;
+        }
+        index_offset.encode(&mut writer)?;
+        let checksum = writer.checksum.value();
+        checksum.encode(&mut writer.inner)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a map written by `save_to` from `reader`. The map is only
+    /// returned once the checksum and entry count have been verified and
+    /// the keys found to be in order; the tree is then built bottom-up
+    /// from the entries with the branching factor it was saved with.
+    pub fn load_from<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+        let mut reader = ChecksumReader {
+            inner: reader,
+            checksum: Checksum::new(),
+        };
+        let mut magic = [0; 4];
+        reader.inner.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u8::decode(&mut reader)?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let branching_factor = u64::decode(&mut reader)?;
+        let count = u64::decode(&mut reader)?;
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let key = K::decode(&mut reader)?;
+            let value = V::decode(&mut reader)?;
+            entries.push((key, value));
+        }
+
+        // The leaf index only serves lookups in place, and the tree is
+        // rebuilt from the entries alone, so it is just checksummed
+        let leaf_count = u64::decode(&mut reader)?;
+        for _ in 0..leaf_count {
+            u64::decode(&mut reader)?;
+        }
+        u64::decode(&mut reader)?;
+
+        let checksum = reader.checksum.value();
+        if u64::decode(&mut reader.inner)? != checksum {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+
+        // An intact snapshot can still have been written by something else
+        let branching_factor = usize::try_from(branching_factor)
+            .ok()
+            .filter(|&branching_factor| branching_factor >= 2)
+            .ok_or_else(|| {
+                SnapshotError::Invalid(format!(
+                    "branching factor {} is less than 2",
+                    branching_factor
+                ))
+            })?;
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 >= pair[1].0) {
+            return Err(SnapshotError::Invalid(format!(
+                "key {:?} is not below the key {:?} after it",
+                pair[0].0, pair[1].0
+            )));
+        }
+        Ok(BPlusTreeMap::from_sorted_entries(branching_factor, entries))
+    }
+}
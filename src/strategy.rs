@@ -0,0 +1,61 @@
+// Proptest strategies, behind the `proptest` feature, for writing property
+// tests against maps without hand-rolling generators.
+
+use std::fmt::Debug;
+
+use proptest::collection::{SizeRange, btree_map, vec};
+use proptest::prelude::*;
+
+use crate::bplus_tree_map::BPlusTreeMap;
+
+/// One step of an operation sequence produced by [`bplus_tree_ops`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op<K, V> {
+    Insert(K, V),
+    Remove(K),
+    Get(K),
+}
+
+/// Generates maps with `size` distinct entries drawn from `key` and
+/// `value`, built by insertion with a branching factor between 2 and 16
+pub fn bplus_tree_map<K, V>(
+    key: K,
+    value: V,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = BPlusTreeMap<K::Value, V::Value>>
+where
+    K: Strategy,
+    K::Value: Ord + Clone + Debug,
+    V: Strategy,
+    V::Value: Clone + Debug,
+{
+    (2usize..=16, btree_map(key, value, size)).prop_map(|(branching_factor, entries)| {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        map
+    })
+}
+
+/// Generates sequences of `size` inserts, removes and gets. Keys for all
+/// three come from `key`, so a strategy over a small domain makes removes
+/// and gets hit keys that were inserted earlier.
+pub fn bplus_tree_ops<K, V>(
+    key: K,
+    value: V,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = Vec<Op<K::Value, V::Value>>>
+where
+    K: Strategy + Clone,
+    K::Value: Clone,
+    V: Strategy,
+    V::Value: Clone,
+{
+    let op = prop_oneof![
+        2 => (key.clone(), value).prop_map(|(key, value)| Op::Insert(key, value)),
+        1 => key.clone().prop_map(Op::Remove),
+        1 => key.prop_map(Op::Get),
+    ];
+    vec(op, size)
+}
@@ -1,9 +1,85 @@
 // Tests for BPlusTreeMap
-
+#![allow(clippy::module_inception)]
+
+mod append_tests;
+mod arbitrary_tests;
+mod array_map_tests;
+mod ascending_insert_tests;
+mod bloom_tests;
+mod capacity_tests;
+mod child_index_tests;
+mod clear_tests;
+mod clone_from_tests;
+mod clone_tests;
+mod collect_refs_tests;
+mod compact_tests;
+mod counting_allocator;
+mod counting_clone;
+mod counting_key;
+mod debug_format_tests;
+mod deepsize_tests;
+mod descent_allocation_tests;
+mod emptied_child_tests;
+mod entry_lookup_tests;
+mod entry_ref_tests;
+mod extend_tests;
+mod extract_if_tests;
+mod fence_tests;
+mod first_last_entry_tests;
+mod for_each_leaf_tests;
+mod from_sorted_iter_tests;
+mod from_tests;
+mod get_key_tests;
+mod get_many_mut_tests;
+mod get_mut_tests;
+mod insert_hint_tests;
+mod insert_split_tests;
+mod into_keys_values_tests;
+mod iter_from_tests;
+mod iter_prefix_tests;
+mod iter_tests;
+mod key_search_tests;
+mod last_leaf_tests;
+mod leaf_chain_tests;
+mod leaf_search_tests;
+mod leaf_walk_tests;
+mod linear_search_tests;
+mod macro_tests;
+mod memory_usage_tests;
+mod merge_from_tests;
 mod node_balancer_tests;
 mod node_balancing_integration_tests;
+mod node_capacity_tests;
 mod node_operations_tests;
+mod node_size_tests;
+mod par_iter_tests;
+mod persistent_map_tests;
+mod pop_tests;
+mod range_prefix_tests;
+mod range_tests;
+mod read_only_tests;
+mod rebuild_tests;
 mod refactor_tests;
+mod remove_clone_tests;
+mod remove_entry_tests;
+mod remove_range_tests;
+mod replace_key_tests;
+mod retain_tests;
+mod reversed_tests;
+mod separator_refresh_tests;
+mod separator_tests;
+mod serde_tests;
+mod shared_map_tests;
+mod shared_separator_tests;
+mod smallvec_tests;
+mod snapshot_tests;
+mod snapshot_view_tests;
+mod split_off_tests;
+mod strategy_tests;
+mod sub_map_tests;
+mod try_insert_tests;
+mod update_tests;
+mod vacant_entry_tests;
 
 #[cfg(test)]
 mod tests {
@@ -78,15 +154,9 @@ mod tests {
     #[test]
     fn test_branch_node_structure() {
         // Create leaf nodes
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         // Create a tree with a branch node as root and a custom branching factor
         let mut map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(4));
@@ -146,7 +216,7 @@ mod tests {
         let mut map = BPlusTreeMap::new();
 
         // Check if keys exist in an empty map
-        assert_eq!(map.contains_key(&1), false);
+        assert!(!map.contains_key(&1));
 
         // Insert some key-value pairs
         map.insert(1, "one".to_string());
@@ -154,17 +224,17 @@ mod tests {
         map.insert(3, "three".to_string());
 
         // Check if existing keys exist
-        assert_eq!(map.contains_key(&1), true);
-        assert_eq!(map.contains_key(&2), true);
-        assert_eq!(map.contains_key(&3), true);
+        assert!(map.contains_key(&1));
+        assert!(map.contains_key(&2));
+        assert!(map.contains_key(&3));
 
         // Check if non-existent keys exist
-        assert_eq!(map.contains_key(&0), false);
-        assert_eq!(map.contains_key(&4), false);
+        assert!(!map.contains_key(&0));
+        assert!(!map.contains_key(&4));
 
         // Remove a key and check if it still exists
         map.remove(&2);
-        assert_eq!(map.contains_key(&2), false);
+        assert!(!map.contains_key(&2));
     }
 
     #[test]
@@ -206,27 +276,27 @@ mod tests {
         let mut map = BPlusTreeMap::new();
 
         // Check if a new map is empty
-        assert_eq!(map.is_empty(), true);
+        assert!(map.is_empty());
 
         // Insert a key-value pair and check if the map is empty
         map.insert(1, "one".to_string());
-        assert_eq!(map.is_empty(), false);
+        assert!(!map.is_empty());
 
         // Insert more key-value pairs and check if the map is empty
         map.insert(2, "two".to_string());
         map.insert(3, "three".to_string());
-        assert_eq!(map.is_empty(), false);
+        assert!(!map.is_empty());
 
         // Remove keys and check if the map is empty
         map.remove(&1);
-        assert_eq!(map.is_empty(), false);
+        assert!(!map.is_empty());
 
         map.remove(&2);
-        assert_eq!(map.is_empty(), false);
+        assert!(!map.is_empty());
 
         // Remove the last key and check if the map is empty
         map.remove(&3);
-        assert_eq!(map.is_empty(), true);
+        assert!(map.is_empty());
     }
 
     #[test]
@@ -256,7 +326,7 @@ mod tests {
         let empty_pairs: Vec<(i32, String)> = Vec::new();
         let empty_map = BPlusTreeMap::from_iter(empty_pairs);
         assert_eq!(empty_map.len(), 0);
-        assert_eq!(empty_map.is_empty(), true);
+        assert!(empty_map.is_empty());
 
         // Test with duplicate keys (later entries should overwrite earlier ones)
         let duplicate_pairs = vec![
@@ -331,7 +401,7 @@ mod tests {
 
         // Sort the entries by key for consistent testing
         let mut sorted_entries = entries.clone();
-        sorted_entries.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted_entries.sort_by_key(|entry| entry.0);
 
         // Check each entry
         assert_eq!(sorted_entries[0], (1, "one".to_string()));
@@ -345,15 +415,9 @@ mod tests {
         assert_eq!(empty_entries.len(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let branch_iter = branch_map.into_iter();
@@ -364,7 +428,7 @@ mod tests {
 
         // Sort the entries by key for consistent testing
         let mut sorted_branch_entries = branch_entries.clone();
-        sorted_branch_entries.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted_branch_entries.sort_by_key(|entry| entry.0);
 
         // Check each entry
         assert_eq!(sorted_branch_entries[0], (1, "one".to_string()));
@@ -401,15 +465,9 @@ mod tests {
         assert_eq!(empty_debug_str, "{}");
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let branch_debug_str = format!("{:?}", branch_map);
@@ -467,18 +525,12 @@ mod tests {
         let empty_map = BPlusTreeMap::<i32, String>::new();
         let cloned_empty_map = empty_map.clone();
         assert_eq!(cloned_empty_map.len(), 0);
-        assert_eq!(cloned_empty_map.is_empty(), true);
+        assert!(cloned_empty_map.is_empty());
 
         // Test cloning a map with a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let cloned_branch_map = branch_map.clone();
@@ -500,7 +552,7 @@ mod tests {
 
         // Check that the map is empty
         assert_eq!(map.len(), 0);
-        assert_eq!(map.is_empty(), true);
+        assert!(map.is_empty());
 
         // Check that the map has the default branching factor (4)
         // We can't directly access the branching_factor field, so we'll test it indirectly
@@ -556,20 +608,14 @@ mod tests {
         assert_eq!(&string_map[&"cherry".to_string()], &3);
 
         // Test with string slices (using Borrow)
-        assert_eq!(&string_map[&"apple" as &str], &1);
-        assert_eq!(&string_map[&"banana" as &str], &2);
-        assert_eq!(&string_map[&"cherry" as &str], &3);
+        assert_eq!(&string_map["apple"], &1);
+        assert_eq!(&string_map["banana"], &2);
+        assert_eq!(&string_map["cherry"], &3);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
 
@@ -592,6 +638,47 @@ mod tests {
         let _ = &map[&3];
     }
 
+    #[test]
+    fn test_mutation_through_indexing() {
+        // Create a multi-level map with integer keys
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50 {
+            map.insert(i, i * 10);
+        }
+
+        // Assign and update values through indexing
+        map[&7] = 5;
+        map[&42] += 1;
+        assert_eq!(map.get(&7), Some(&5));
+        assert_eq!(map.get(&42), Some(&421));
+        assert_eq!(map.len(), 50);
+
+        // Test with String keys
+        let mut string_map = BPlusTreeMap::new();
+        string_map.insert("apple".to_string(), "red".to_string());
+        string_map.insert("banana".to_string(), "yellow".to_string());
+
+        string_map[&"apple".to_string()].push_str("dish");
+        assert_eq!(&string_map["apple"], "reddish");
+
+        // Test with string slices (using Borrow)
+        string_map["banana"] = "green".to_string();
+        string_map["banana"].push_str("ish");
+        assert_eq!(&string_map["banana"], "greenish");
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn test_mutable_indexing_with_nonexistent_key() {
+        // Create a map with some key-value pairs
+        let mut map = BPlusTreeMap::new();
+        map.insert(1, "one".to_string());
+        map.insert(2, "two".to_string());
+
+        // This should panic because the key doesn't exist
+        map[&3] = "three".to_string();
+    }
+
     #[test]
     fn test_iterating_over_key_value_pairs() {
         // Create a map with some key-value pairs
@@ -617,15 +704,9 @@ mod tests {
         assert_eq!(empty_entries.len(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let branch_entries: Vec<(&i32, &String)> = branch_map.iter().collect();
@@ -687,8 +768,8 @@ mod tests {
         assert_eq!(entries.len(), 20);
 
         // Check that entries are in ascending order by key
-        for i in 0..20 {
-            assert_eq!(entries[i], (&(i as i32 + 1), &format!("value_{}", i + 1)));
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(*entry, (&(i as i32 + 1), &format!("value_{}", i + 1)));
         }
 
         // Test removing elements from the multi-level tree
@@ -727,11 +808,8 @@ mod tests {
         assert_eq!(entries.len(), 15);
 
         // Check that the first 5 entries are the newly inserted ones
-        for i in 0..5 {
-            assert_eq!(
-                entries[i],
-                (&(i as i32 + 1), &format!("new_value_{}", i + 1))
-            );
+        for (i, entry) in entries.iter().take(5).enumerate() {
+            assert_eq!(*entry, (&(i as i32 + 1), &format!("new_value_{}", i + 1)));
         }
 
         // Check that the remaining entries are the original ones
@@ -767,15 +845,9 @@ mod tests {
         assert_eq!(empty_entries.len(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let mut branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
 
@@ -853,15 +925,9 @@ mod tests {
         assert_eq!(empty_keys.len(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let branch_keys: Vec<&i32> = branch_map.keys().collect();
@@ -887,8 +953,8 @@ mod tests {
         assert_eq!(multi_level_keys.len(), 10);
 
         // Check that keys are in ascending order
-        for i in 0..10 {
-            assert_eq!(multi_level_keys[i], &(i as i32 + 1));
+        for (i, key) in multi_level_keys.iter().enumerate() {
+            assert_eq!(*key, &(i as i32 + 1));
         }
 
         // Test that the keys iterator can be used multiple times
@@ -938,15 +1004,9 @@ mod tests {
         assert_eq!(empty_values.len(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let branch_values: Vec<&String> = branch_map.values().collect();
@@ -972,8 +1032,8 @@ mod tests {
         assert_eq!(multi_level_values.len(), 10);
 
         // Check that values are in order corresponding to ascending key order
-        for i in 0..10 {
-            assert_eq!(multi_level_values[i], &format!("value_{}", i + 1));
+        for (i, value) in multi_level_values.iter().enumerate() {
+            assert_eq!(*value, &format!("value_{}", i + 1));
         }
 
         // Test that the values iterator can be used multiple times
@@ -1034,15 +1094,9 @@ mod tests {
         assert_eq!(empty_values.len(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let mut branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
 
@@ -1150,15 +1204,9 @@ mod tests {
         assert_eq!(visitor.result(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
         let mut visitor = KeyCounter { count: 0 };
@@ -1329,6 +1377,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_entry_debug_format() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..20 {
+            map.insert(i, format!("value_{}", i));
+        }
+
+        let entry = map.entry(7);
+        assert_eq!(
+            format!("{:?}", entry),
+            r#"Entry(OccupiedEntry { key: 7, value: "value_7" })"#
+        );
+        match entry {
+            Entry::Occupied(entry) => assert_eq!(
+                format!("{:?}", entry),
+                r#"OccupiedEntry { key: 7, value: "value_7" }"#
+            ),
+            Entry::Vacant(_) => panic!("Expected Occupied entry"),
+        }
+
+        let entry = map.entry(42);
+        assert_eq!(format!("{:?}", entry), "Entry(VacantEntry(42))");
+        match entry {
+            Entry::Occupied(_) => panic!("Expected Vacant entry"),
+            Entry::Vacant(entry) => assert_eq!(format!("{:?}", entry), "VacantEntry(42)"),
+        }
+
+        // The alternate form spreads the fields over several lines
+        assert_eq!(
+            format!("{:#?}", map.entry(1)),
+            "Entry(\n    OccupiedEntry {\n        key: 1,\n        value: \"value_1\",\n    },\n)"
+        );
+    }
+
+    #[test]
+    fn test_entry_or_default_word_count() {
+        let text = "the quick brown fox jumps over the lazy dog and the dog sleeps \
+                    while the fox runs over the hill and far away from the dog";
+
+        // A small branching factor makes the counts spread over many leaves
+        let mut counts = BPlusTreeMap::with_branching_factor(3);
+        let mut expected = std::collections::BTreeMap::new();
+        for word in text.split_whitespace() {
+            *counts.entry(word.to_string()).or_default() += 1;
+            *expected.entry(word.to_string()).or_default() += 1;
+        }
+
+        assert_eq!(counts.len(), expected.len());
+        assert!(counts.iter().eq(expected.iter()));
+        assert_eq!(counts.get("the"), Some(&6));
+        assert_eq!(counts.get("dog"), Some(&3));
+        assert_eq!(counts.get("hill"), Some(&1));
+
+        // An existing value is left alone
+        let mut map: BPlusTreeMap<i32, Vec<i32>> = BPlusTreeMap::new();
+        map.entry(1).or_default().push(10);
+        map.entry(1).or_default().push(20);
+        assert_eq!(map.get(&1), Some(&vec![10, 20]));
+        assert_eq!(map.entry(2).or_default(), &Vec::<i32>::new());
+        assert_eq!(map.len(), 2);
+    }
+
     #[test]
     fn test_common_iterator_abstraction() {
         // Create a map with some key-value pairs
@@ -1379,7 +1489,7 @@ mod tests {
 
         // Sort the entries by key for consistent testing
         let mut sorted_entries = entries.clone();
-        sorted_entries.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted_entries.sort_by_key(|entry| entry.0);
 
         assert_eq!(sorted_entries[0], (1, "modified_one".to_string()));
         assert_eq!(sorted_entries[1], (2, "modified_two".to_string()));
@@ -1392,15 +1502,9 @@ mod tests {
         assert_eq!(empty_map.values().count(), 0);
 
         // Test with a map that has a branch node as root
-        let left_leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let left_leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
-        let right_leaf = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let right_leaf = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         let branch_map = BPlusTreeMap::with_branch_root(3, left_leaf, right_leaf, Some(3));
 
@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod append_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+
+    /// Builds a map and a matching BTreeMap holding the given keys, inserted
+    /// in a scattered order so the tree isn't built left to right
+    fn maps_with_keys(
+        branching_factor: usize,
+        keys: std::ops::Range<i32>,
+    ) -> (BPlusTreeMap<i32, i32>, BTreeMap<i32, i32>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        let count = keys.len() as i32;
+        for i in 0..count {
+            let key = keys.start + (i * 37) % count;
+            map.insert(key, key * 10);
+            expected.insert(key, key * 10);
+        }
+        (map, expected)
+    }
+
+    fn assert_appended(
+        map: &BPlusTreeMap<i32, i32>,
+        other: &BPlusTreeMap<i32, i32>,
+        expected: &BTreeMap<i32, i32>,
+    ) {
+        assert!(other.is_empty());
+        assert_eq!(other.root_kind(), RootKind::Empty);
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_append_trees_of_equal_height() {
+        let (mut map, mut expected) = maps_with_keys(4, 0..3);
+        let (mut other, mut expected_other) = maps_with_keys(4, 10..13);
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+        assert_eq!(other.root_kind(), RootKind::Leaf);
+
+        map.append(&mut other);
+        expected.append(&mut expected_other);
+
+        assert_appended(&map, &other, &expected);
+        assert_eq!(map.root_kind(), RootKind::Branch);
+    }
+
+    #[test]
+    fn test_append_shorter_tree_on_the_right() {
+        let (mut map, mut expected) = maps_with_keys(4, 0..500);
+        let (mut other, mut expected_other) = maps_with_keys(4, 500..505);
+
+        map.append(&mut other);
+        expected.append(&mut expected_other);
+
+        assert_appended(&map, &other, &expected);
+    }
+
+    #[test]
+    fn test_append_taller_tree_on_the_right() {
+        let (mut map, mut expected) = maps_with_keys(4, 0..3);
+        let (mut other, mut expected_other) = maps_with_keys(4, 10..510);
+
+        map.append(&mut other);
+        expected.append(&mut expected_other);
+
+        assert_appended(&map, &other, &expected);
+    }
+
+    #[test]
+    fn test_append_keys_smaller_than_existing() {
+        let (mut map, mut expected) = maps_with_keys(5, 100..300);
+        let (mut other, mut expected_other) = maps_with_keys(5, 0..50);
+
+        map.append(&mut other);
+        expected.append(&mut expected_other);
+
+        assert_appended(&map, &other, &expected);
+    }
+
+    #[test]
+    fn test_append_overlapping_keys_overwrites() {
+        let (mut map, mut expected) = maps_with_keys(4, 0..100);
+        let mut other = BPlusTreeMap::with_branching_factor(4);
+        let mut expected_other = BTreeMap::new();
+        for i in (50..150).step_by(2) {
+            other.insert(i, -i);
+            expected_other.insert(i, -i);
+        }
+
+        map.append(&mut other);
+        expected.append(&mut expected_other);
+
+        assert_appended(&map, &other, &expected);
+        assert_eq!(map.get(&50), Some(&-50));
+        assert_eq!(map.get(&51), Some(&510));
+    }
+
+    #[test]
+    fn test_append_with_empty_maps() {
+        let (mut map, expected) = maps_with_keys(4, 0..20);
+        let mut empty = BPlusTreeMap::with_branching_factor(4);
+
+        map.append(&mut empty);
+        assert_appended(&map, &empty, &expected);
+
+        empty.append(&mut map);
+        assert_appended(&empty, &map, &expected);
+    }
+
+    #[test]
+    fn test_append_with_different_branching_factors() {
+        let (mut map, mut expected) = maps_with_keys(4, 0..50);
+        let (mut other, mut expected_other) = maps_with_keys(7, 50..100);
+
+        map.append(&mut other);
+        expected.append(&mut expected_other);
+
+        assert_appended(&map, &other, &expected);
+
+        // The emptied map keeps working with its own branching factor
+        other.insert(1, 1);
+        assert_eq!(other.len(), 1);
+    }
+
+    #[test]
+    fn test_append_matches_btree_map() {
+        let sizes = [0, 1, 2, 5, 17, 60, 250];
+        for branching_factor in 2..=7 {
+            for left_size in sizes {
+                for right_size in sizes {
+                    let (mut map, mut expected) = maps_with_keys(branching_factor, 0..left_size);
+                    let (mut other, mut expected_other) =
+                        maps_with_keys(branching_factor, 1000..1000 + right_size);
+
+                    map.append(&mut other);
+                    expected.append(&mut expected_other);
+
+                    let context = format!(
+                        "bf {} sizes {} and {}",
+                        branching_factor, left_size, right_size
+                    );
+                    assert!(other.is_empty(), "{}", context);
+                    assert_eq!(map.len(), expected.len(), "{}", context);
+                    assert!(map.iter().eq(expected.iter()), "{}", context);
+                    // Trees built by inserting at these branching factors are
+                    // well formed, so the joined tree must be too
+                    if branching_factor >= 4 {
+                        assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                    }
+
+                    // The joined map keeps working as an ordinary map
+                    for i in (0..1300).step_by(11) {
+                        assert_eq!(map.insert(i, -i), expected.insert(i, -i), "{}", context);
+                    }
+                    for i in (0..1300).step_by(3) {
+                        assert_eq!(map.remove(&i), expected.remove(&i), "{}", context);
+                    }
+                    assert!(map.iter().eq(expected.iter()), "{}", context);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(all(test, feature = "arbitrary"))]
+mod arbitrary_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use arbitrary::{Arbitrary, Unstructured};
+
+    // Deterministic byte buffers standing in for fuzzer input
+    fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    fn generated_maps() -> impl Iterator<Item = (u64, BPlusTreeMap<u8, u16>)> {
+        (0..500).map(|seed| {
+            let bytes = random_bytes(seed, 64 + (seed as usize % 8) * 256);
+            let map = BPlusTreeMap::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            (seed, map)
+        })
+    }
+
+    #[test]
+    fn test_generated_maps_are_ordered() {
+        let mut saw_branch_root = false;
+        for (seed, map) in generated_maps() {
+            assert!((2..=16).contains(&map.branching_factor()));
+            let keys: Vec<u8> = map.keys().copied().collect();
+            assert!(
+                keys.windows(2).all(|pair| pair[0] < pair[1]),
+                "seed {}",
+                seed
+            );
+            assert_eq!(keys.len(), map.len(), "seed {}", seed);
+            for key in &keys {
+                assert!(map.contains_key(key), "seed {}", seed);
+            }
+            saw_branch_root |= map.root_kind() == RootKind::Branch;
+        }
+        assert!(saw_branch_root);
+    }
+
+    #[test]
+    fn test_generated_maps_pass_invariant_checks() {
+        for (seed, map) in generated_maps() {
+            map.check_invariants()
+                .unwrap_or_else(|problem| panic!("seed {}: {}", seed, problem));
+        }
+    }
+
+    #[test]
+    fn test_empty_input_gives_empty_map() {
+        let mut u = Unstructured::new(&[]);
+        let map = BPlusTreeMap::<u8, u16>::arbitrary(&mut u).unwrap();
+        assert!(map.is_empty());
+        assert_eq!(map.branching_factor(), 2);
+    }
+}
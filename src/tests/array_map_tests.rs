@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod array_map_tests {
+    use crate::array_map::BPlusTreeArrayMap;
+    use crate::array_vec::ArrayVec;
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_allocator::allocations_during;
+    use std::rc::Rc;
+
+    /// Number of keys per test; Miri runs these tests far slower
+    const SIZE: u64 = if cfg!(miri) { 150 } else { 3000 };
+
+    fn array_vec(items: &[u32]) -> ArrayVec<u32, 8> {
+        let mut vec = ArrayVec::new();
+        for &item in items {
+            vec.push(item);
+        }
+        vec
+    }
+
+    #[test]
+    fn test_array_vec_insert_and_remove() {
+        let mut vec = array_vec(&[1, 3, 5]);
+        vec.insert(0, 0);
+        vec.insert(2, 2);
+        vec.insert(5, 6);
+        assert_eq!(&*vec, &[0, 1, 2, 3, 5, 6]);
+        assert_eq!(vec.remove(4), 5);
+        assert_eq!(vec.remove(0), 0);
+        assert_eq!(vec.pop(), Some(6));
+        assert_eq!(&*vec, &[1, 2, 3]);
+        vec[1] = 20;
+        assert_eq!(&*vec, &[1, 20, 3]);
+        assert_eq!(ArrayVec::<u32, 4>::new().pop(), None);
+    }
+
+    #[test]
+    fn test_array_vec_split_off_and_append() {
+        let mut left = array_vec(&[1, 2, 3, 4, 5]);
+        let mut right = left.split_off(2);
+        assert_eq!(&*left, &[1, 2]);
+        assert_eq!(&*right, &[3, 4, 5]);
+        assert!(left.split_off(2).is_empty());
+
+        right.append(&mut left);
+        assert_eq!(&*right, &[3, 4, 5, 1, 2]);
+        assert!(left.is_empty());
+        assert!(!right.is_full());
+        right.append(&mut array_vec(&[6, 7, 8]));
+        assert!(right.is_full());
+    }
+
+    #[test]
+    #[should_panic(expected = "is full")]
+    fn test_array_vec_push_past_capacity_panics() {
+        let mut vec = array_vec(&[0; 8]);
+        vec.push(8);
+    }
+
+    #[test]
+    #[should_panic(expected = "don't fit")]
+    fn test_array_vec_append_past_capacity_panics() {
+        let mut vec = array_vec(&[0; 5]);
+        vec.append(&mut array_vec(&[0; 4]));
+    }
+
+    #[test]
+    fn test_array_vec_drops_and_clones_its_items_once() {
+        let item = Rc::new(());
+        let mut vec: ArrayVec<Rc<()>, 6> = ArrayVec::new();
+        for _ in 0..5 {
+            vec.push(Rc::clone(&item));
+        }
+        let copy = vec.clone();
+        assert_eq!(Rc::strong_count(&item), 11);
+        drop(vec.remove(1));
+        drop(vec.pop());
+        let tail = vec.split_off(1);
+        assert_eq!(Rc::strong_count(&item), 9);
+        drop(tail);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&item), 6);
+        drop(copy);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    fn check_shape<const B: usize>() {
+        let mut map = BPlusTreeArrayMap::<u64, u64, B>::new();
+        for i in 0..SIZE {
+            map.insert((i * 7919) % SIZE, i);
+            if i % 16 == 0 {
+                assert_eq!(map.check_invariants(), Ok(()));
+            }
+        }
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // Remove from the front, the back and all over, so every child
+        // position gets rebalanced, then empty the map entirely
+        for key in (0..SIZE / 4).chain((SIZE / 2..SIZE).rev()) {
+            assert!(map.remove(&key).is_some());
+            assert_eq!(map.check_invariants(), Ok(()), "after removing {}", key);
+        }
+        for i in 0..SIZE {
+            map.remove(&((i * 7919) % SIZE));
+            assert_eq!(map.check_invariants(), Ok(()));
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.node_count(), 0);
+    }
+
+    #[test]
+    fn test_tree_stays_balanced() {
+        check_shape::<4>();
+        check_shape::<5>();
+        check_shape::<6>();
+        check_shape::<7>();
+        check_shape::<16>();
+    }
+
+    #[test]
+    fn test_clone_is_deep() {
+        let mut map: BPlusTreeArrayMap<u64, String, 4> =
+            (0..SIZE).map(|i| (i, i.to_string())).collect();
+        let copy = map.clone();
+        for key in 0..SIZE / 2 {
+            map.remove(&key);
+        }
+        assert_eq!(copy.check_invariants(), Ok(()));
+        assert_eq!(copy.len(), SIZE as usize);
+        assert!(copy.iter().all(|(key, value)| key.to_string() == *value));
+        assert_eq!(map.len(), (SIZE - SIZE / 2) as usize);
+    }
+
+    #[test]
+    fn test_each_node_is_one_allocation() {
+        let mut map = BPlusTreeArrayMap::<u64, u64, 16>::new();
+        let allocations = allocations_during(|| {
+            for i in 0..SIZE {
+                map.insert((i * 7919) % SIZE, i);
+            }
+        });
+        assert_eq!(allocations, map.node_count());
+
+        // The same inserts into Vec-backed nodes allocate at least twice as
+        // often, once for the keys and once for the values or children of
+        // every node
+        let mut vec_map = BPlusTreeMap::with_branching_factor(16);
+        let vec_allocations = allocations_during(|| {
+            for i in 0..SIZE {
+                vec_map.insert((i * 7919) % SIZE, i);
+            }
+        });
+        assert!(
+            vec_allocations >= 2 * allocations,
+            "{} allocations with Vec nodes, {} with array nodes",
+            vec_allocations,
+            allocations
+        );
+    }
+
+    #[test]
+    fn test_lookups_on_a_deep_tree() {
+        let map: BPlusTreeArrayMap<u64, u64, 4> = (0..SIZE).map(|i| (i * 2, i)).collect();
+        for i in 0..SIZE {
+            assert_eq!(map.get(&(i * 2)), Some(&i));
+            assert_eq!(map.get(&(i * 2 + 1)), None);
+        }
+        assert!(map.keys().copied().eq((0..SIZE).map(|i| i * 2)));
+        assert_eq!((&map).into_iter().len(), SIZE as usize);
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod ascending_insert_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_appending_compares_each_key_once() {
+        let mut map = BPlusTreeMap::with_branching_factor(16);
+        let comparisons = comparisons_during(|| {
+            for i in 0..10_000 {
+                map.insert(CountedKey(i), i);
+            }
+        });
+        // Every key but the first is compared with the last key only
+        assert_eq!(comparisons, 9_999);
+        assert_eq!(map.len(), 10_000);
+        assert!(map.keys().map(|key| key.0).eq(0..10_000));
+        map.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_appending_builds_a_valid_tree() {
+        for branching_factor in 2..=8 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..1000 {
+                assert_eq!(map.insert(i, i * 2), None);
+            }
+            assert_eq!(map.len(), 1000);
+            assert_eq!(map.get(&999), Some(&1998));
+            map.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_out_of_order_inserts_between_appends() {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        let mut expected = BTreeMap::new();
+        for i in 0..2000 {
+            // Mostly ascending, with every fifth key going back into the middle
+            let key = if i % 5 == 4 { (i * 7919) % 2000 } else { i * 2 };
+            assert_eq!(map.insert(key, i), expected.insert(key, i));
+        }
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_removing_the_last_key_moves_the_end() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        for i in (80..100).rev() {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        assert_eq!(map.last_key_value(), Some((&79, &79)));
+
+        // Keys between the new end and the old one are appended again
+        assert_eq!(map.insert(90, 900), None);
+        assert_eq!(map.insert(85, 850), None);
+        assert_eq!(map.insert(95, 950), None);
+        assert_eq!(map.get(&90), Some(&900));
+        assert_eq!(map.get(&85), Some(&850));
+        assert!(map.keys().copied().eq((0..80).chain([85, 90, 95])));
+        assert_eq!(map.len(), 83);
+    }
+
+    #[test]
+    fn test_inserting_the_last_key_again_replaces_its_value() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.insert(9, 90), Some(9));
+        assert_eq!(map.len(), 10);
+        assert!(map.try_insert(9, 900).is_err());
+        assert_eq!(map.try_insert(10, 100).ok(), Some(&mut 100));
+        assert_eq!(map.last_key_value(), Some((&10, &100)));
+    }
+
+    #[test]
+    fn test_appending_after_emptying_the_map() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..50 {
+            map.insert(i, i);
+        }
+        for i in 0..50 {
+            map.remove(&i);
+        }
+        assert!(map.is_empty());
+        for i in 10..20 {
+            map.insert(i, i);
+        }
+        map.insert(5, 5);
+        assert!(map.keys().copied().eq([5].into_iter().chain(10..20)));
+        assert_eq!(map.len(), 11);
+    }
+}
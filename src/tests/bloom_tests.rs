@@ -0,0 +1,185 @@
+#[cfg(all(test, feature = "bloom"))]
+mod bloom_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    /// Checks that every leaf of `map` has a filter that accepts all of its
+    /// keys, and that every key of `expected` is found
+    fn assert_no_false_negatives(map: &BPlusTreeMap<u64, u64>, expected: &BTreeMap<u64, u64>) {
+        for leaf in map.walk_leaves() {
+            assert!(
+                leaf.filter.spec().is_some(),
+                "leaf {:?} has no filter",
+                leaf.keys
+            );
+            assert!(
+                leaf.filter.accepts_all(&leaf.keys),
+                "filter of leaf {:?} rejects some of its keys",
+                leaf.keys
+            );
+        }
+        for (key, value) in expected {
+            assert_eq!(map.get(key), Some(value));
+        }
+        assert_eq!(map.len(), expected.len());
+    }
+
+    #[test]
+    fn test_filters_have_no_false_negatives_under_random_workloads() {
+        for branching_factor in [2, 3, 4, 8, 16] {
+            for bits_per_key in [1, 4, 10] {
+                let mut map = BPlusTreeMap::with_leaf_filters(branching_factor, bits_per_key);
+                let mut expected = BTreeMap::new();
+                let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+                for step in 0..2000u64 {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let key = state % 400;
+                    match step % 8 {
+                        0..=2 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                        3 => {
+                            *map.entry(key).or_insert(0) += 1;
+                            *expected.entry(key).or_insert(0) += 1;
+                        }
+                        4 => assert_eq!(map.pop_first(), expected.pop_first()),
+                        _ => assert_eq!(map.remove(&key), expected.remove(&key)),
+                    }
+                    for probe in [key, key + 1, 400] {
+                        assert_eq!(map.get(&probe), expected.get(&probe));
+                        assert_eq!(map.contains_key(&probe), expected.contains_key(&probe));
+                    }
+                    if step % 100 == 0 {
+                        assert_no_false_negatives(&map, &expected);
+                    }
+                }
+                assert_no_false_negatives(&map, &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_filters_follow_bulk_changes() {
+        for branching_factor in [3, 4, 16] {
+            let mut map = BPlusTreeMap::with_leaf_filters(branching_factor, 8);
+            map.extend((0..3000u64).map(|i| (i * 3, i)));
+            let mut expected: BTreeMap<u64, u64> = (0..3000u64).map(|i| (i * 3, i)).collect();
+            assert_no_false_negatives(&map, &expected);
+
+            map.retain(|key, _| key % 7 != 0);
+            expected.retain(|key, _| key % 7 != 0);
+            assert_no_false_negatives(&map, &expected);
+
+            assert_eq!(map.remove_range(1000..2000), {
+                let removed = expected.range(1000..2000).count();
+                expected.retain(|key, _| !(1000..2000).contains(key));
+                removed
+            });
+            assert_no_false_negatives(&map, &expected);
+
+            let mut upper = map.split_off(&5000);
+            let mut expected_upper = expected.split_off(&5000);
+            assert_no_false_negatives(&map, &expected);
+            assert_no_false_negatives(&upper, &expected_upper);
+
+            // The halves keep filtering the leaves they grow
+            for key in (4000..6000).filter(|key| key % 3 == 1) {
+                let (map, expected) = if key < 5000 {
+                    (&mut map, &mut expected)
+                } else {
+                    (&mut upper, &mut expected_upper)
+                };
+                map.insert(key, key);
+                expected.insert(key, key);
+            }
+            assert_no_false_negatives(&map, &expected);
+            assert_no_false_negatives(&upper, &expected_upper);
+
+            let copy = upper.clone();
+            assert_no_false_negatives(&copy, &expected_upper);
+            let mut target = BPlusTreeMap::with_branching_factor(branching_factor);
+            target.clone_from(&map);
+            assert_no_false_negatives(&target, &expected);
+
+            let mut other = BPlusTreeMap::with_leaf_filters(branching_factor, 8);
+            other.extend([(1, 1), (4000, 2), (20_000, 3)]);
+            map.merge_from(other, |_, existing, _| existing);
+            for (key, value) in [(1, 1), (4000, 2), (20_000, 3)] {
+                expected.entry(key).or_insert(value);
+            }
+            assert_no_false_negatives(&map, &expected);
+
+            map.append(&mut upper);
+            expected.append(&mut expected_upper);
+            assert_no_false_negatives(&map, &expected);
+            for key in 0..10_000 {
+                assert_eq!(map.contains_key(&key), expected.contains_key(&key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_filters_skip_searching_leaves_for_misses() {
+        let mut plain = BPlusTreeMap::with_branching_factor(32);
+        let mut filtered = BPlusTreeMap::with_leaf_filters(32, 10);
+        for i in 0..10_000u64 {
+            // Even keys only, so every odd key in between is a miss
+            let key = 2 * ((i * 7919) % 10_000);
+            plain.insert(CountedKey(key), i);
+            filtered.insert(CountedKey(key), i);
+        }
+        let misses = |map: &BPlusTreeMap<CountedKey, u64>| {
+            comparisons_during(|| {
+                for i in 0..1000u64 {
+                    let key = CountedKey(2 * ((i * 104_729) % 10_000) + 1);
+                    assert!(!map.contains_key(&key));
+                    assert_eq!(map.get(&key), None);
+                }
+            })
+        };
+        let plain_comparisons = misses(&plain);
+        let filtered_comparisons = misses(&filtered);
+
+        // A leaf of 16 to 32 keys takes 4 or 5 comparisons to search, and
+        // the filter spares that for nearly every miss
+        assert!(
+            filtered_comparisons + 2000 * 4 <= plain_comparisons + 2000 / 10,
+            "{} comparisons with filters against {} without",
+            filtered_comparisons,
+            plain_comparisons
+        );
+
+        // Hits are found just the same
+        let hits = |map: &BPlusTreeMap<CountedKey, u64>| {
+            comparisons_during(|| {
+                for key in (0..20_000).step_by(2) {
+                    assert!(map.contains_key(&CountedKey(key)));
+                }
+            })
+        };
+        assert_eq!(hits(&filtered), hits(&plain));
+    }
+
+    #[test]
+    fn test_maps_without_filters_keep_no_bits() {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..1000u64 {
+            map.insert(i, i);
+        }
+        assert!(
+            map.walk_leaves()
+                .all(|leaf| leaf.filter.spec().is_none() && leaf.filter.heap_bytes() == 0)
+        );
+        assert!(map.contains_key(&999));
+        assert!(!map.contains_key(&1000));
+
+        // The filters of a filtered map are counted with its leaves
+        let mut filtered = BPlusTreeMap::with_leaf_filters(8, 16);
+        filtered.extend((0..1000u64).map(|i| (i, i)));
+        let plain_usage = map.memory_usage();
+        let filtered_usage = filtered.memory_usage();
+        assert_eq!(filtered_usage.key_bytes, plain_usage.key_bytes);
+        assert!(filtered_usage.leaf_bytes >= plain_usage.leaf_bytes + 1000 * 16 / 8);
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod capacity_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_allocator::allocations_during;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_with_capacity_behaves_like_a_new_map() {
+        for branching_factor in 2..=8 {
+            let mut sized = BPlusTreeMap::with_capacity(branching_factor, 500);
+            let mut plain = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            assert!(sized.is_empty());
+
+            for i in 0..500 {
+                let key = (i * 37) % 500;
+                sized.insert(key, i);
+                plain.insert(key, i);
+                expected.insert(key, i);
+            }
+            for i in (0..500).step_by(3) {
+                assert_eq!(sized.remove(&i), expected.remove(&i));
+                plain.remove(&i);
+            }
+
+            assert_eq!(sized.len(), expected.len());
+            assert!(sized.iter().eq(expected.iter()));
+            assert!(sized.iter().eq(plain.iter()));
+        }
+    }
+
+    #[test]
+    fn test_reserve_on_a_non_empty_map() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        map.reserve(0);
+        map.reserve(1000);
+        for i in 100..1000 {
+            map.insert(i, i);
+        }
+
+        assert_eq!(map.root_kind(), RootKind::Branch);
+        assert_eq!(map.len(), 1000);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..1000));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_reserve_allocates_nodes_for_the_entries() {
+        let mut map = BPlusTreeMap::<u32, u32>::with_branching_factor(8);
+        map.reserve(0);
+        assert_eq!((map.pooled_leaves(), map.pooled_branches()), (0, 0));
+
+        // 100 full leaves hold 800 entries, under 12, 2 and 1 full branches
+        map.reserve(800);
+        assert_eq!((map.pooled_leaves(), map.pooled_branches()), (100, 15));
+        // Reserving less than is already kept adds nothing
+        map.reserve(400);
+        assert_eq!((map.pooled_leaves(), map.pooled_branches()), (100, 15));
+
+        // Every split takes its new leaf from the ones set aside, until
+        // they run out
+        for i in 0..800 {
+            map.insert((i * 7919) % 800, i);
+        }
+        assert_eq!(
+            map.pooled_leaves(),
+            100usize.saturating_sub(map.leaf_count() - 1)
+        );
+        assert!(map.pooled_branches() < 15);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_with_capacity_allocates_less() {
+        let plain_allocations = allocations_during(|| {
+            let mut map = BPlusTreeMap::with_branching_factor(16);
+            for i in 0..1000 {
+                map.insert(i, i);
+            }
+        });
+        let sized_allocations = allocations_during(|| {
+            let mut map = BPlusTreeMap::with_capacity(16, 1000);
+            for i in 0..1000 {
+                map.insert(i, i);
+            }
+            assert_eq!(map.len(), 1000);
+        });
+
+        assert!(
+            sized_allocations < plain_allocations,
+            "sized map made {} allocations, a plain one {}",
+            sized_allocations,
+            plain_allocations
+        );
+    }
+
+    #[test]
+    fn test_shrink_to_fit_gives_back_capacity() {
+        let mut map = BPlusTreeMap::with_capacity(32, 20_000);
+        let mut expected = BTreeMap::new();
+        for i in 0..20_000 {
+            let key = (i * 7919) % 20_000;
+            map.insert(key, i);
+            expected.insert(key, i);
+        }
+        // Churn leaves most nodes far from full
+        for i in 0..20_000 {
+            if i % 4 != 0 {
+                assert_eq!(map.remove(&i), expected.remove(&i));
+            }
+        }
+        let leaves = map.leaf_count();
+        let height = map.height();
+        let before = map.memory_usage();
+
+        map.shrink_to_fit();
+        let after = map.memory_usage();
+        assert!(
+            after.structure_bytes() < before.structure_bytes(),
+            "{:?} before, {:?} after",
+            before,
+            after
+        );
+        // Unused capacity counts as structure, so the entries cost the same
+        assert_eq!(
+            (after.key_bytes, after.value_bytes),
+            (before.key_bytes, before.value_bytes)
+        );
+        assert_eq!((map.leaf_count(), map.height()), (leaves, height));
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // Shrinking again finds nothing to give back, and the map still grows
+        map.shrink_to_fit();
+        assert_eq!(map.memory_usage(), after);
+        for i in 0..20_000 {
+            assert_eq!(map.insert(i, i), expected.insert(i, i));
+        }
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_shrink_to_fit_drops_pooled_nodes() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        map.clear();
+        assert!(map.pooled_leaves() > 0);
+        map.shrink_to_fit();
+        assert_eq!((map.pooled_leaves(), map.pooled_branches()), (0, 0));
+        assert_eq!(map.root_kind(), RootKind::Empty);
+        map.insert(1, 1);
+        assert_eq!(map.get(&1), Some(&1));
+    }
+}
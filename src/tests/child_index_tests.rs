@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod child_index_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, Node};
+    use crate::separator::SeparatorKey;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+
+    fn map_of(branching_factor: usize, len: u64) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..len {
+            // Scatter the inserts so splits happen all over the tree
+            let key = (i * 7919) % len;
+            map.insert(key, key * 10);
+        }
+        map
+    }
+
+    fn separators(node: &Node<u64, u64>, out: &mut Vec<u64>) {
+        if let Node::Branch(branch) = node {
+            out.extend(branch.keys.iter().map(|key| **key));
+            for child in &branch.children {
+                separators(child, out);
+            }
+        }
+    }
+
+    fn separators_of(map: &BPlusTreeMap<u64, u64>) -> Vec<u64> {
+        let mut out = Vec::new();
+        separators(map.root_node().unwrap(), &mut out);
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    #[test]
+    fn test_key_equal_to_separator_goes_right() {
+        let keys = [10, 20, 30].map(SeparatorKey::new);
+        let branch = BranchNode::new(keys.to_vec(), Vec::<Node<u64, u64>>::new());
+        assert_eq!(branch.child_index_for(&0), 0);
+        assert_eq!(branch.child_index_for(&9), 0);
+        assert_eq!(branch.child_index_for(&10), 1);
+        assert_eq!(branch.child_index_for(&19), 1);
+        assert_eq!(branch.child_index_for(&20), 2);
+        assert_eq!(branch.child_index_for(&30), 3);
+        assert_eq!(branch.child_index_for(&31), 3);
+    }
+
+    #[test]
+    fn test_separator_keys_are_found_by_every_lookup() {
+        for branching_factor in [4, 5, 16] {
+            let mut map = map_of(branching_factor, 500);
+            let separators = separators_of(&map);
+            assert!(!separators.is_empty());
+            for key in separators {
+                assert_eq!(map.get(&key), Some(&(key * 10)), "get({})", key);
+                assert!(map.contains_key(&key), "contains_key({})", key);
+                assert_eq!(map.get_key(&key), Some(&key), "get_key({})", key);
+                assert_eq!(map.get_mut(&key), Some(&mut (key * 10)), "get_mut({})", key);
+                assert_eq!(map.range(key..).next(), Some((&key, &(key * 10))));
+                assert_eq!(map.range(key..=key).count(), 1, "range({}..={})", key, key);
+                assert_eq!(
+                    map.range(..key).next_back().map(|(k, _)| *k),
+                    key.checked_sub(1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_separator_keys_are_overwritten_and_removed_in_place() {
+        for branching_factor in [4, 5, 16] {
+            let mut map = map_of(branching_factor, 500);
+            let separators = separators_of(&map);
+            for &key in &separators {
+                assert_eq!(map.insert(key, key), Some(key * 10), "insert({})", key);
+            }
+            assert_eq!(map.len(), 500);
+            for &key in &separators {
+                assert_eq!(map.get(&key), Some(&key));
+                assert_eq!(map.remove(&key), Some(key), "remove({})", key);
+                assert_eq!(map.get(&key), None);
+            }
+            assert_eq!(map.len(), 500 - separators.len());
+            assert!(
+                map.keys()
+                    .copied()
+                    .eq((0..500).filter(|k| separators.binary_search(k).is_err()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_wide_branch_descent_is_logarithmic() {
+        // Two levels of 128-way branches over leaves of up to 128 keys
+        let mut map = BPlusTreeMap::with_branching_factor(128);
+        for i in 0..20_000 {
+            map.insert(CountedKey(i), i);
+        }
+        let mut keys: Vec<u64> = (0..20_000).step_by(97).collect();
+        keys.push(19_999);
+        for key in keys {
+            let comparisons = comparisons_during(|| {
+                map.remove(&CountedKey(key));
+            });
+            // A binary search of at most 128 keys per level takes at most 8
+            // comparisons, where a linear scan would take up to 128
+            assert!(comparisons <= 24, "remove({}) took {}", key, comparisons);
+        }
+    }
+}
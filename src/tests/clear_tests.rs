@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod clear_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_allocator::allocations_during;
+    use std::collections::BTreeMap;
+
+    fn fill(map: &mut BPlusTreeMap<i32, i32>, count: i32) {
+        for i in 0..count {
+            map.insert(i, i * 10);
+        }
+    }
+
+    #[test]
+    fn test_clear_empties_the_map() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        fill(&mut map, 100);
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        map.clear();
+
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert_eq!(map.root_kind(), RootKind::Empty);
+        assert_eq!(map.get(&5), None);
+        assert_eq!(map.iter().count(), 0);
+
+        // Clearing an empty map does nothing
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_clear_and_refill_repeatedly() {
+        for branching_factor in 4..=6 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+
+            // Refill with a different number of keys and order each round, so
+            // the pool is sometimes too small and sometimes left over
+            for round in 0..6 {
+                let count = [300, 50, 500, 1, 200, 400][round];
+                let mut expected = BTreeMap::new();
+                for i in 0..count {
+                    let key = (i * 37 + round as i32) % count;
+                    map.insert(key, i);
+                    expected.insert(key, i);
+                }
+
+                assert_eq!(map.len(), expected.len());
+                assert!(map.iter().eq(expected.iter()));
+                assert_eq!(map.check_invariants(), Ok(()));
+
+                map.clear();
+                assert!(map.is_empty());
+                assert_eq!(map.iter().count(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_keeps_at_most_one_tree_of_nodes() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        fill(&mut map, 1000);
+        map.clear();
+        let (leaves, branches) = (map.pooled_leaves(), map.pooled_branches());
+        assert!(leaves > 0 && branches > 0);
+
+        // A smaller tree shrinks the pool to its own size
+        fill(&mut map, 10);
+        map.clear();
+        assert!(map.pooled_leaves() <= 10);
+        assert!(map.pooled_leaves() < leaves);
+        assert!(map.pooled_branches() < branches);
+
+        // Clearing a full tree again does not grow the pool beyond it
+        fill(&mut map, 1000);
+        map.clear();
+        assert!(map.pooled_leaves() <= leaves);
+        assert!(map.pooled_branches() <= branches);
+    }
+
+    #[test]
+    fn test_refill_after_clear_allocates_less() {
+        let fresh_allocations = allocations_during(|| {
+            let mut map = BPlusTreeMap::with_branching_factor(8);
+            fill(&mut map, 1000);
+        });
+
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        fill(&mut map, 1000);
+        map.clear();
+        let refill_allocations = allocations_during(|| fill(&mut map, 1000));
+
+        assert_eq!(map.len(), 1000);
+        assert!(
+            refill_allocations < fresh_allocations,
+            "refill made {} allocations, a fresh map {}",
+            refill_allocations,
+            fresh_allocations
+        );
+    }
+}
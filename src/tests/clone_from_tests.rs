@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod clone_from_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_allocator::allocations_during;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+
+    fn filled(branching_factor: usize, len: u64) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..len {
+            let key = (i * 7919) % len;
+            map.insert(key, key * 10);
+        }
+        map
+    }
+
+    fn assert_copied(copy: &BPlusTreeMap<u64, u64>, source: &BPlusTreeMap<u64, u64>) {
+        assert_eq!(copy.len(), source.len());
+        assert!(copy.iter().eq(source.iter()));
+        assert_eq!(copy.shape(), source.shape());
+        assert_eq!(copy.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_clone_from_a_larger_map() {
+        let source = filled(4, 5000);
+        let mut copy = filled(4, 10);
+        copy.clone_from(&source);
+        assert_copied(&copy, &source);
+    }
+
+    #[test]
+    fn test_clone_from_a_smaller_map() {
+        let source = filled(4, 10);
+        let mut copy = filled(4, 5000);
+        copy.clone_from(&source);
+        assert_copied(&copy, &source);
+    }
+
+    #[test]
+    fn test_clone_from_empty_and_into_empty() {
+        let empty = BPlusTreeMap::new();
+        let mut copy = filled(4, 100);
+        copy.clone_from(&empty);
+        assert!(copy.is_empty());
+        assert_eq!(copy.get(&1), None);
+
+        let source = filled(4, 100);
+        copy.clone_from(&source);
+        assert_copied(&copy, &source);
+    }
+
+    #[test]
+    fn test_clone_from_takes_the_branching_factor_of_the_source() {
+        let mut source = filled(3, 1000);
+        let mut copy = filled(16, 1000);
+        copy.clone_from(&source);
+        assert_copied(&copy, &source);
+        for key in 1000..1500 {
+            source.insert(key, key * 10);
+            copy.insert(key, key * 10);
+        }
+        assert_copied(&copy, &source);
+    }
+
+    #[test]
+    fn test_clone_from_a_map_of_the_same_shape_does_not_allocate() {
+        let mut source = filled(4, 5000);
+        let mut copy = source.clone();
+        for key in (0..5000).step_by(7) {
+            source.insert(key, key);
+        }
+        let allocations = allocations_during(|| copy.clone_from(&source));
+        assert_eq!(allocations, 0);
+        assert_copied(&copy, &source);
+
+        // Leaves this narrow keep their entries inline with `smallvec`, so
+        // only a fresh clone of Vec-backed leaves allocates for each one
+        #[cfg(not(feature = "smallvec"))]
+        {
+            let fresh = allocations_during(|| drop(source.clone()));
+            assert!(fresh > source.leaf_count(), "{} allocations", fresh);
+        }
+    }
+
+    #[test]
+    fn test_clone_from_after_a_few_changes_allocates_little() {
+        let mut source = filled(4, 5000);
+        let mut copy = source.clone();
+        for key in 5000..5010 {
+            source.insert(key, key * 10);
+        }
+        source.remove(&17);
+        let allocations = allocations_during(|| copy.clone_from(&source));
+        let fresh = allocations_during(|| drop(source.clone()));
+        assert!(
+            allocations * 10 < fresh,
+            "{} against {}",
+            allocations,
+            fresh
+        );
+        assert_eq!(copy.len(), source.len());
+        assert!(copy.iter().eq(source.iter()));
+    }
+
+    #[test]
+    fn test_clone_from_clones_each_value_once() {
+        let mut source = BPlusTreeMap::with_branching_factor(4);
+        let mut copy = BPlusTreeMap::with_branching_factor(4);
+        for key in 0..1000 {
+            source.insert(key, CountedValue(key));
+            if key % 3 == 0 {
+                copy.insert(key, CountedValue(key + 1));
+            }
+        }
+        let clones = clones_during(|| copy.clone_from(&source));
+        assert_eq!(clones, 1000);
+        assert!(copy.iter().eq(source.iter()));
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod clone_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+
+    /// A map whose shape came from scattered inserts and removes, so
+    /// reinserting its entries would not build the same tree
+    fn shuffled(branching_factor: usize, len: u64) -> BPlusTreeMap<u64, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..len {
+            let key = (i * 7919) % len;
+            map.insert(key, format!("v{}", key));
+        }
+        for key in (0..len).step_by(3) {
+            map.remove(&key);
+        }
+        map
+    }
+
+    #[test]
+    fn test_clone_has_the_same_shape() {
+        for branching_factor in [2, 3, 4, 5, 16] {
+            let map = shuffled(branching_factor, 1000);
+            let copy = map.clone();
+            assert_eq!(copy.root_kind(), map.root_kind());
+            assert_eq!(
+                copy.shape(),
+                map.shape(),
+                "branching factor {}",
+                branching_factor
+            );
+            assert_eq!(copy.len(), map.len());
+            assert!(copy.iter().eq(map.iter()));
+        }
+    }
+
+    #[test]
+    fn test_clone_of_empty_and_single_leaf_maps() {
+        let empty: BPlusTreeMap<u64, String> = BPlusTreeMap::new();
+        let copy = empty.clone();
+        assert_eq!(copy.root_kind(), RootKind::Empty);
+        assert!(copy.is_empty());
+
+        let mut single = BPlusTreeMap::new();
+        single.insert(1, "one".to_string());
+        let copy = single.clone();
+        assert_eq!(copy.root_kind(), RootKind::Leaf);
+        assert_eq!(copy.get(&1), Some(&"one".to_string()));
+    }
+
+    #[test]
+    fn test_clone_copies_each_value_once_without_comparing_keys() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            let key = (i * 7919) % 1000;
+            map.insert(CountedKey(key), CountedValue(key));
+        }
+        let mut copy = None;
+        let comparisons = comparisons_during(|| {
+            let clones = clones_during(|| copy = Some(map.clone()));
+            assert_eq!(clones, 1000);
+        });
+        assert_eq!(comparisons, 0);
+        assert_eq!(copy.unwrap().len(), 1000);
+    }
+
+    #[test]
+    fn test_clone_grows_like_the_original() {
+        let mut map = shuffled(3, 500);
+        let mut copy = map.clone();
+        for key in 500..700 {
+            map.insert(key, format!("v{}", key));
+            copy.insert(key, format!("v{}", key));
+        }
+        assert_eq!(copy.shape(), map.shape());
+    }
+
+    #[test]
+    fn test_clone_is_independent() {
+        let map = shuffled(4, 500);
+        let mut copy = map.clone();
+        copy.remove(&1);
+        copy.insert(1000, "v1000".to_string());
+        *copy.get_mut(&2).unwrap() = "changed".to_string();
+        assert_eq!(map.get(&1), Some(&"v1".to_string()));
+        assert_eq!(map.get(&1000), None);
+        assert_eq!(map.get(&2), Some(&"v2".to_string()));
+        assert_eq!(copy.get(&1), None);
+        assert_eq!(copy.len(), map.len());
+    }
+}
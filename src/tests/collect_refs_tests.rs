@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod collect_refs_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, LeafNode};
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+
+    #[test]
+    fn test_refs_come_out_in_key_order() {
+        for branching_factor in [2, 3, 4, 5, 16] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..1000u64 {
+                let key = (i * 7919) % 1000;
+                map.insert(key, key * 10);
+            }
+            for key in (0..1000).step_by(3) {
+                map.remove(&key);
+            }
+            let refs = map.collect_refs();
+            assert!(
+                refs.iter()
+                    .map(|&(k, v)| (*k, *v))
+                    .eq(map.iter().map(|(k, v)| (*k, *v))),
+                "branching factor {}",
+                branching_factor
+            );
+            assert_eq!(refs.len(), map.len());
+        }
+    }
+
+    #[test]
+    fn test_collecting_an_empty_map() {
+        let map: BPlusTreeMap<u64, u64> = BPlusTreeMap::new();
+        assert!(map.collect_refs().is_empty());
+    }
+
+    #[test]
+    fn test_collecting_does_not_sort() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..2000u64 {
+            map.insert(CountedKey((i * 7919) % 2000), i);
+        }
+        // At most the neighbour checks of the debug assertion, where a
+        // sort would compare each key about log n times
+        let comparisons = comparisons_during(|| {
+            assert_eq!(map.collect_refs().len(), 2000);
+        });
+        assert!(comparisons < 2000, "{} comparisons", comparisons);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "out of order")]
+    fn test_out_of_order_leaves_are_caught() {
+        let left_leaf = LeafNode::new(vec![5, 6], vec!["5", "6"]);
+        let right_leaf = LeafNode::new(vec![1, 2], vec!["1", "2"]);
+        let map = BPlusTreeMap::with_branch_root(4, left_leaf, right_leaf, Some(3));
+        map.collect_refs();
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod compact_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+
+    /// The fewest levels that hold `len` entries in nodes packed full
+    fn minimal_height(len: usize, branching_factor: usize) -> usize {
+        let mut height = 1;
+        let mut capacity = branching_factor;
+        while capacity < len {
+            height += 1;
+            capacity *= branching_factor + 1;
+        }
+        height
+    }
+
+    /// A map of `len` entries inserted in a scattered order, with all but
+    /// every tenth removed
+    fn thinned(len: u64, branching_factor: usize) -> (BPlusTreeMap<u64, u64>, BTreeMap<u64, u64>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..len {
+            map.insert((i * 7919) % len, i);
+        }
+        for i in 0..len {
+            let key = (i * 104_729) % len;
+            if !key.is_multiple_of(10) {
+                map.remove(&key);
+            }
+        }
+        let expected = (0..len)
+            .filter(|key| key.is_multiple_of(10))
+            .map(|key| (key, map[&key]))
+            .collect();
+        (map, expected)
+    }
+
+    #[test]
+    fn test_compact_after_mass_deletion() {
+        for branching_factor in [4, 5, 8, 16] {
+            let (mut map, expected) = thinned(20_000, branching_factor);
+            let leaves_before = map.leaf_count();
+            map.compact();
+
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert_eq!(map.leaf_count(), expected.len().div_ceil(branching_factor));
+            assert!(map.leaf_count() < leaves_before);
+            assert_eq!(
+                map.height(),
+                minimal_height(expected.len(), branching_factor),
+                "bf {}",
+                branching_factor
+            );
+            for key in [0, 1, 10, 19_990, 19_999, 20_000] {
+                assert_eq!(map.get(&key), expected.get(&key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_factor_leaves_room_in_the_leaves() {
+        let (mut map, expected) = thinned(20_000, 16);
+        map.compact_with_fill_factor(0.75);
+
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+        // Leaves hold about 12 of 16 entries, and none is full
+        assert_eq!(map.leaf_count(), expected.len().div_ceil(12));
+        for leaf in map.leaves() {
+            assert!((11..=12).contains(&leaf.keys.len()), "{}", leaf.keys.len());
+        }
+
+        // The tree goes on changing as usual
+        let mut expected = expected;
+        for i in 0..5000 {
+            map.insert(i * 3 + 1, i);
+            expected.insert(i * 3 + 1, i);
+        }
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_compact_small_maps() {
+        let mut empty = BPlusTreeMap::<u64, u64>::new();
+        empty.compact();
+        assert!(empty.is_empty());
+        assert_eq!(empty.root_kind(), RootKind::Empty);
+
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        map.retain(|key, _| *key < 3);
+        map.compact();
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+        assert!(map.iter().eq([(&0, &0), (&1, &1), (&2, &2)]));
+    }
+
+    #[test]
+    fn test_compact_moves_values() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            map.insert(i, CountedValue(i));
+        }
+        map.retain(|key, _| key % 5 == 0);
+        assert_eq!(clones_during(|| map.compact()), 0);
+        assert_eq!(map.len(), 200);
+        assert!(map.values().map(|value| value.0).eq((0..1000).step_by(5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Fill factor must be between 0.5 and 1")]
+    fn test_fill_factor_out_of_range() {
+        let mut map = BPlusTreeMap::<u64, u64>::new();
+        map.compact_with_fill_factor(0.25);
+    }
+}
@@ -0,0 +1,29 @@
+//! A value type for tests that counts how often each thread clones it, so
+//! tests can check that an operation moves values rather than copying them
+#![cfg(test)]
+
+use std::cell::Cell;
+
+thread_local! {
+    static CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A `u64` value whose every clone is counted. It is ordered too, so it
+/// can stand in for keys.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct CountedValue(pub u64);
+
+impl Clone for CountedValue {
+    fn clone(&self) -> Self {
+        CLONES.with(|count| count.set(count.get() + 1));
+        CountedValue(self.0)
+    }
+}
+
+/// Runs `f` and returns how many values the current thread cloned while
+/// it ran
+pub(crate) fn clones_during(f: impl FnOnce()) -> usize {
+    let before = CLONES.with(Cell::get);
+    f();
+    CLONES.with(Cell::get) - before
+}
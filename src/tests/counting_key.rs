@@ -0,0 +1,76 @@
+//! A key type for tests that counts the comparisons each thread makes
+//! between keys, so tests can check how much searching an operation does,
+//! and how often it builds or clones keys
+#![cfg(test)]
+
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+thread_local! {
+    static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    static CONVERSIONS: Cell<usize> = const { Cell::new(0) };
+    static CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A `u64` key whose every ordering comparison is counted, as is every
+/// clone of it and every key built from a borrowed `u64`
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CountedKey(pub u64);
+
+impl Clone for CountedKey {
+    fn clone(&self) -> Self {
+        CLONES.with(|count| count.set(count.get() + 1));
+        CountedKey(self.0)
+    }
+}
+
+impl From<&u64> for CountedKey {
+    fn from(key: &u64) -> Self {
+        CONVERSIONS.with(|count| count.set(count.get() + 1));
+        CountedKey(*key)
+    }
+}
+
+impl Borrow<u64> for CountedKey {
+    fn borrow(&self) -> &u64 {
+        &self.0
+    }
+}
+
+impl PartialOrd for CountedKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CountedKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        COMPARISONS.with(|count| count.set(count.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+/// Runs `f` and returns how many comparisons between keys the current
+/// thread made while it ran
+pub(crate) fn comparisons_during(f: impl FnOnce()) -> usize {
+    let before = COMPARISONS.with(Cell::get);
+    f();
+    COMPARISONS.with(Cell::get) - before
+}
+
+/// Runs `f` and returns how many keys the current thread built from
+/// borrowed ones while it ran
+pub(crate) fn conversions_during(f: impl FnOnce()) -> usize {
+    let before = CONVERSIONS.with(Cell::get);
+    f();
+    CONVERSIONS.with(Cell::get) - before
+}
+
+/// Runs `f` and returns how many keys the current thread cloned while it
+/// ran
+pub(crate) fn key_clones_during(f: impl FnOnce()) -> usize {
+    let before = CLONES.with(Cell::get);
+    f();
+    CLONES.with(Cell::get) - before
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod debug_format_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_allocator::allocations_during;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+
+    fn filled(len: u64) -> BPlusTreeMap<u64, CountedValue> {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..len {
+            let key = (i * 7919) % len;
+            map.insert(key, CountedValue(key));
+        }
+        map
+    }
+
+    #[test]
+    fn test_formatting_does_not_clone_entries() {
+        let map = filled(1000);
+        let mut out = String::new();
+        let clones = clones_during(|| write!(out, "{:?}", map).unwrap());
+        assert_eq!(clones, 0);
+        assert!(out.starts_with("{0: CountedValue(0), 1: CountedValue(1), "));
+    }
+
+    #[test]
+    fn test_formatting_allocates_only_the_iterator() {
+        let map = filled(1000);
+        // Write into a buffer that is already big enough, so the only
+        // allocations left are the iterator's two descent stacks
+        let mut out = String::with_capacity(64 * 1024);
+        let allocations = allocations_during(|| write!(out, "{:?}", map).unwrap());
+        assert!(allocations <= 2, "{} allocations", allocations);
+    }
+
+    #[test]
+    fn test_formatting_matches_btree_map() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        let mut expected = BTreeMap::new();
+        for i in 0..100 {
+            let key = (i * 37) % 100;
+            map.insert(key, format!("v{}", key));
+            expected.insert(key, format!("v{}", key));
+        }
+        assert_eq!(format!("{:?}", map), format!("{:?}", expected));
+        assert_eq!(format!("{:#?}", map), format!("{:#?}", expected));
+    }
+
+    #[test]
+    fn test_formatting_leaves_the_map_alone() {
+        let map = filled(500);
+        let leaves = map.leaf_count();
+        let _ = format!("{:?}", map);
+        assert_eq!(map.leaf_count(), leaves);
+        assert_eq!(map.len(), 500);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(all(test, feature = "deepsize"))]
+mod deepsize_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, LeafNode};
+    use deepsize::DeepSizeOf;
+    use std::mem::size_of;
+
+    fn numbered_map(count: u64) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(16);
+        for i in 0..count {
+            map.insert((i * 7919) % count, i);
+        }
+        map
+    }
+
+    #[test]
+    fn test_size_grows_linearly_with_entries() {
+        let small = numbered_map(1000).deep_size_of();
+        let large = numbered_map(4000).deep_size_of();
+        assert!(small >= 1000 * 2 * size_of::<u64>());
+        assert!(
+            large > small * 3 && large < small * 5,
+            "{} vs {}",
+            small,
+            large
+        );
+    }
+
+    #[test]
+    fn test_leaf_counts_capacity_not_length() {
+        let mut leaf = LeafNode::new(Vec::with_capacity(100), Vec::with_capacity(100));
+        leaf.keys.push(1u64);
+        leaf.values.push(2u64);
+        assert_eq!(
+            leaf.deep_size_of() - size_of::<LeafNode<u64, u64>>(),
+            200 * size_of::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_size_includes_slack_in_nodes() {
+        let mut map = numbered_map(0);
+        map.reserve(1000);
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        let mut used = 0;
+        map.for_each_leaf(|keys, values| used += (keys.len() + values.len()) * size_of::<u64>());
+        // Every node was created with room for a full node, and splits
+        // leave nodes about half full
+        assert!(
+            map.deep_size_of() > used * 3 / 2,
+            "{} vs {}",
+            map.deep_size_of(),
+            used
+        );
+    }
+
+    #[test]
+    fn test_size_includes_heap_data_of_values() {
+        let mut short = BPlusTreeMap::with_branching_factor(8);
+        let mut long = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..100 {
+            short.insert(i, String::from("x"));
+            long.insert(i, "x".repeat(1000));
+        }
+        assert!(long.deep_size_of() >= short.deep_size_of() + 100 * 999);
+    }
+
+    #[test]
+    fn test_size_includes_pooled_nodes() {
+        let mut map = numbered_map(1000);
+        let before = map.deep_size_of();
+        map.clear();
+        assert!(map.pooled_leaves() > 0);
+        // The emptied nodes keep their allocations for reuse
+        assert!(map.deep_size_of() > before / 2);
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod descent_allocation_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_allocator::allocations_during;
+
+    /// A map of the even keys below 5000, inserted out of order so the
+    /// inserts descend through the branches rather than down the right spine
+    fn even_keys(branching_factor: usize) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..2500 {
+            let key = (i * 7919) % 2500 * 2;
+            map.insert(key, key);
+        }
+        map
+    }
+
+    #[test]
+    fn test_overwriting_insert_does_not_allocate() {
+        for branching_factor in [4, 16, 64] {
+            let mut map = even_keys(branching_factor);
+            let allocations = allocations_during(|| {
+                for key in (0..5000).step_by(2) {
+                    assert_eq!(map.insert(key, key + 1), Some(key));
+                }
+            });
+            assert_eq!(allocations, 0, "branching factor {}", branching_factor);
+        }
+    }
+
+    #[test]
+    fn test_insert_without_split_only_grows_its_leaf() {
+        for branching_factor in [4, 16, 64] {
+            let mut map = even_keys(branching_factor);
+            let mut unsplit = 0;
+            for key in (1..5000).step_by(2) {
+                let leaves = map.leaf_count();
+                let allocations = allocations_during(|| {
+                    assert_eq!(map.insert(key, key), None);
+                });
+                if map.leaf_count() == leaves {
+                    // Nothing on the way down allocates; at most the leaf's
+                    // keys and values Vecs grow to make room
+                    assert!(
+                        allocations <= 2,
+                        "insert({}) made {} allocations",
+                        key,
+                        allocations
+                    );
+                    unsplit += 1;
+                }
+            }
+            assert!(unsplit > 0);
+            assert_eq!(map.len(), 5000);
+        }
+    }
+}
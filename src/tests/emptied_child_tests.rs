@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod emptied_child_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use std::collections::BTreeMap;
+
+    /// A map whose root is a branch over three leaves, the first two
+    /// holding a single key each
+    fn three_leaves() -> (BPlusTreeMap<i32, i32>, BTreeMap<i32, i32>) {
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for key in [0, 10, 20, 30] {
+            map.insert(key, key);
+        }
+        assert_eq!(map.shape(), "[10, 20]([0] [10] [20, 30])");
+        let expected = map.iter().map(|(key, value)| (*key, *value)).collect();
+        (map, expected)
+    }
+
+    /// Checks `map` against `expected` for every key from -5 to 35
+    fn assert_matches(map: &BPlusTreeMap<i32, i32>, expected: &BTreeMap<i32, i32>) {
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(map.check_separators(), Ok(()));
+        for key in -5..=35 {
+            assert_eq!(map.get(&key), expected.get(&key), "{}", key);
+            assert!(map.range(key..).eq(expected.range(key..)), "{}..", key);
+        }
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.iter().rev().eq(expected.iter().rev()));
+    }
+
+    #[test]
+    fn test_emptying_the_first_child_drops_the_separator_on_its_right() {
+        let (mut map, mut expected) = three_leaves();
+        assert_eq!(map.remove(&0), expected.remove(&0));
+        assert_eq!(map.shape(), "[20]([10] [20, 30])");
+        assert_matches(&map, &expected);
+    }
+
+    #[test]
+    fn test_emptying_a_middle_child_drops_the_separator_on_its_left() {
+        let (mut map, mut expected) = three_leaves();
+        assert_eq!(map.remove(&10), expected.remove(&10));
+        assert_eq!(map.shape(), "[20]([0] [20, 30])");
+        assert_matches(&map, &expected);
+    }
+
+    #[test]
+    fn test_emptying_the_last_child_drops_the_separator_on_its_left() {
+        let (mut map, mut expected) = three_leaves();
+        assert_eq!(map.remove(&20), expected.remove(&20));
+        assert_eq!(map.shape(), "[10, 30]([0] [10] [30])");
+        assert_eq!(map.remove(&30), expected.remove(&30));
+        assert_eq!(map.shape(), "[10]([0] [10])");
+        assert_matches(&map, &expected);
+    }
+
+    #[test]
+    fn test_keys_around_emptied_children_are_found_after_reinserting() {
+        for emptied in [0, 10, 30] {
+            let (mut map, mut expected) = three_leaves();
+            if emptied == 30 {
+                assert_eq!(map.remove(&20), expected.remove(&20));
+            }
+            assert_eq!(map.remove(&emptied), expected.remove(&emptied));
+            for key in [emptied - 1, emptied, emptied + 1] {
+                assert_eq!(map.insert(key, -key), expected.insert(key, -key));
+                assert_matches(&map, &expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod entry_lookup_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Entry};
+    use crate::tests::counting_allocator::allocations_during;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    const SIZE: u64 = 10_000;
+
+    fn filled(branching_factor: usize) -> BPlusTreeMap<CountedKey, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..SIZE {
+            map.insert(CountedKey(i * 2), i);
+        }
+        map
+    }
+
+    /// Comparisons made counting `key` with an entry, and made by a plain
+    /// descent for it. Lookups of far away keys around them make sure
+    /// neither can use the leaf cached by the one before.
+    fn counting_comparisons(map: &mut BPlusTreeMap<CountedKey, u64>, key: u64) -> (usize, usize) {
+        map.contains_key(&CountedKey(SIZE));
+        let descent = comparisons_during(|| {
+            map.contains_key(&CountedKey(key));
+        });
+        map.contains_key(&CountedKey(SIZE));
+        let counted = comparisons_during(|| {
+            *map.entry(CountedKey(key)).or_insert(0) += 1;
+        });
+        (counted, descent)
+    }
+
+    #[test]
+    fn test_counting_with_entries_makes_one_descent() {
+        let mut map = filled(8);
+        // Present keys, and an absent key whose leaf has room for it
+        for key in [10, SIZE * 2 - 10, 11] {
+            let (counted, descent) = counting_comparisons(&mut map, key);
+            // Looking the key up again, let alone scanning, would take
+            // at least two descents
+            assert!(
+                counted < 2 * descent,
+                "counting {} took {} comparisons, a descent takes {}",
+                key,
+                counted,
+                descent
+            );
+        }
+        assert_eq!(map.get(&CountedKey(10)), Some(&6));
+        assert_eq!(map.get(&CountedKey(11)), Some(&1));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_counting_into_a_full_leaf_stays_bounded() {
+        let mut map = filled(8);
+        // The last leaves are filled by appending, so the key splits one.
+        // The insert follows the entry's path down instead of searching.
+        let (counted, descent) = counting_comparisons(&mut map, SIZE * 2 - 11);
+        assert!(
+            counted < 2 * descent,
+            "counting took {} comparisons, a descent takes {}",
+            counted,
+            descent
+        );
+        assert_eq!(map.get(&CountedKey(SIZE * 2 - 11)), Some(&1));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_occupied_entries_reach_their_value_without_searching() {
+        let mut map = filled(8);
+        let Entry::Occupied(mut entry) = map.entry(CountedKey(5000)) else {
+            panic!("key is in the map");
+        };
+        let allocations = allocations_during(|| {
+            let comparisons = comparisons_during(|| {
+                assert_eq!(*entry.get(), 2500);
+                *entry.get_mut() += 1;
+                assert_eq!(entry.insert(7), 2501);
+            });
+            assert_eq!(comparisons, 0);
+        });
+        assert_eq!(allocations, 0);
+        let value = entry.into_mut();
+        *value += 1;
+        assert_eq!(map.get(&CountedKey(5000)), Some(&8));
+    }
+
+    #[test]
+    fn test_vacant_entries_insert_without_searching_again() {
+        let mut map = filled(8);
+        // Make room in the leaf the new key belongs in
+        map.remove(&CountedKey(5002));
+        let Entry::Vacant(entry) = map.entry(CountedKey(5001)) else {
+            panic!("key is not in the map");
+        };
+        let comparisons = comparisons_during(|| {
+            *entry.insert(1) += 1;
+        });
+        assert_eq!(comparisons, 0);
+        assert_eq!(map.get(&CountedKey(5001)), Some(&2));
+        assert_eq!(map.len(), SIZE as usize);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_first_and_last_entries_reach_their_value_without_searching() {
+        let mut map = filled(8);
+        let comparisons = comparisons_during(|| {
+            *map.first_entry().unwrap().get_mut() += 1;
+            *map.last_entry().unwrap().into_mut() += 1;
+        });
+        assert_eq!(comparisons, 0);
+        assert_eq!(map.get(&CountedKey(0)), Some(&1));
+        assert_eq!(map.get(&CountedKey(SIZE * 2 - 2)), Some(&SIZE));
+    }
+
+    #[test]
+    fn test_entries_match_btree_map_through_splits() {
+        for branching_factor in [4, 5, 8, 16] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for i in 0..5000u64 {
+                let key = (i * 7919) % 1500;
+                match i % 4 {
+                    0 => {
+                        map.entry(key).and_modify(|v| *v += i).or_insert(i);
+                        expected.entry(key).and_modify(|v| *v += i).or_insert(i);
+                    }
+                    1 => {
+                        *map.entry(key).or_default() += 1;
+                        *expected.entry(key).or_default() += 1;
+                    }
+                    2 => {
+                        if let Entry::Occupied(entry) = map.entry(key) {
+                            assert_eq!(Some(entry.remove()), expected.remove(&key));
+                        }
+                    }
+                    _ => {
+                        assert_eq!(map.try_insert(key, i).is_ok(), !expected.contains_key(&key));
+                        expected.entry(key).or_insert(i);
+                    }
+                }
+            }
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod entry_ref_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, EntryRef};
+    use crate::tests::counting_key::{CountedKey, conversions_during, key_clones_during};
+
+    #[test]
+    fn test_entry_ref_builds_key_only_on_insert() {
+        let mut map: BPlusTreeMap<CountedKey, i32> = BPlusTreeMap::new();
+
+        let conversions = conversions_during(|| *map.entry_ref(&7).or_insert(0) += 1);
+        assert_eq!(conversions, 1);
+
+        // The occupied path neither builds nor clones a key
+        let mut clones = 0;
+        let conversions = conversions_during(|| {
+            clones = key_clones_during(|| {
+                *map.entry_ref(&7).or_insert(0) += 1;
+                *map.entry_ref(&7).or_default() += 1;
+                map.entry_ref(&7).and_modify(|v| *v += 1);
+            });
+        });
+        assert_eq!(conversions, 0);
+        assert_eq!(clones, 0);
+
+        assert_eq!(map.get(&7), Some(&4));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_entry_ref_word_count() {
+        let words = [1, 2, 3, 2, 3, 3, 4, 4, 4, 4, 5, 6, 7, 8, 9, 10, 1, 2, 3];
+
+        // A small branching factor spreads the words over several leaves
+        let mut counts: BPlusTreeMap<CountedKey, i32> = BPlusTreeMap::with_branching_factor(3);
+        let conversions = conversions_during(|| {
+            for word in &words {
+                *counts.entry_ref(word).or_default() += 1;
+            }
+        });
+
+        // One key was built per distinct word
+        assert_eq!(counts.len(), 10);
+        assert_eq!(conversions, 10);
+        assert_eq!(counts.get(&1), Some(&2));
+        assert_eq!(counts.get(&3), Some(&4));
+        assert_eq!(counts.get(&4), Some(&4));
+        assert_eq!(counts.get(&10), Some(&1));
+    }
+
+    #[test]
+    fn test_occupied_entry_ref_methods() {
+        let mut map: BPlusTreeMap<String, String> = BPlusTreeMap::new();
+        map.insert("a".to_string(), "one".to_string());
+        map.insert("b".to_string(), "two".to_string());
+
+        match map.entry_ref("a") {
+            EntryRef::Occupied(mut entry) => {
+                assert_eq!(entry.key(), "a");
+                assert_eq!(entry.get(), "one");
+                entry.get_mut().push('!');
+                assert_eq!(entry.insert("uno".to_string()), "one!");
+                assert_eq!(entry.get(), "uno");
+            }
+            EntryRef::Vacant(_) => panic!("Expected Occupied entry"),
+        }
+
+        match map.entry_ref("b") {
+            EntryRef::Occupied(entry) => assert_eq!(entry.remove(), "two"),
+            EntryRef::Vacant(_) => panic!("Expected Occupied entry"),
+        }
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("b"), None);
+    }
+
+    #[test]
+    fn test_vacant_entry_ref_methods() {
+        let mut map: BPlusTreeMap<String, usize> = BPlusTreeMap::new();
+
+        match map.entry_ref("key") {
+            EntryRef::Occupied(_) => panic!("Expected Vacant entry"),
+            EntryRef::Vacant(entry) => {
+                assert_eq!(entry.key(), "key");
+                *entry.insert(1) += 1;
+            }
+        }
+        assert_eq!(map.get("key"), Some(&2));
+
+        let value = map.entry_ref("other").or_insert_with_key(|k| k.len());
+        assert_eq!(*value, 5);
+        assert_eq!(map.entry_ref("other").key(), "other");
+        assert_eq!(*map.entry_ref("other").or_insert_with(|| 100), 5);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_entry_ref_across_splits() {
+        let mut map: BPlusTreeMap<String, usize> = BPlusTreeMap::with_branching_factor(2);
+        let words: Vec<String> = (0..100).map(|i| format!("word_{:02}", i)).collect();
+
+        // Each insert may split a leaf, and the returned reference must
+        // still point at the inserted value
+        for (i, word) in words.iter().enumerate() {
+            let value = map.entry_ref(word.as_str()).or_insert(i);
+            *value += 1000;
+        }
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(map.get(word.as_str()), Some(&(i + 1000)));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod extend_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_extend_from_another_maps_iter() {
+        let mut map: BPlusTreeMap<i32, u64> = (0..100).map(|i| (i, i as u64)).collect();
+        let other: BPlusTreeMap<i32, u64> = (50..200).map(|i| (i, i as u64 * 10)).collect();
+
+        map.extend(other.iter());
+
+        assert_eq!(map.len(), 200);
+        for i in 0..50 {
+            assert_eq!(map.get(&i), Some(&(i as u64)));
+        }
+        // Keys in both maps take the value from the one extended from
+        for i in 50..200 {
+            assert_eq!(map.get(&i), Some(&(i as u64 * 10)));
+        }
+        // The map extended from is only borrowed
+        assert_eq!(other.len(), 150);
+        map.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_extend_from_btree_map_iter() {
+        let source: BTreeMap<i32, u64> = (0..300).map(|i| ((i * 37) % 300, i as u64)).collect();
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+
+        map.extend(&source);
+
+        assert_eq!(map.len(), source.len());
+        assert!(map.iter().eq(source.iter()));
+    }
+
+    #[test]
+    fn test_extend_with_nothing() {
+        let mut map: BPlusTreeMap<i32, u64> = BPlusTreeMap::new();
+        map.insert(1, 1);
+        map.extend(BPlusTreeMap::<i32, u64>::new().iter());
+        map.extend(BTreeMap::<i32, u64>::new().iter());
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&1));
+    }
+
+    #[test]
+    fn test_extend_with_ascending_run_compares_each_key_once() {
+        let mut map = BPlusTreeMap::with_branching_factor(16);
+        map.extend((0..100).map(|i| (CountedKey(i), i)));
+
+        let comparisons = comparisons_during(|| {
+            map.extend((100..10_100).map(|i| (CountedKey(i), i)));
+        });
+        // One comparison against the current last key for each pair
+        assert_eq!(comparisons, 10_000);
+        assert_eq!(map.len(), 10_100);
+        assert!(map.keys().map(|key| key.0).eq(0..10_100));
+        map.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_extend_with_ascending_run_builds_a_valid_tree() {
+        for branching_factor in 2..=8 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            map.extend((0..1000).map(|i| (i, i * 2)));
+
+            assert_eq!(map.len(), 1000);
+            assert!(
+                map.iter()
+                    .map(|(k, v)| (*k, *v))
+                    .eq((0..1000).map(|i| (i, i * 2)))
+            );
+            map.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_extend_with_broken_runs() {
+        // Ascending bursts, each starting below where the last one ended
+        let pairs: Vec<(i32, i32)> = (0..20)
+            .flat_map(|burst| (0..50).map(move |i| (burst * 30 + i, burst)))
+            .collect();
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        let mut expected = BTreeMap::new();
+        map.insert(500, -1);
+        expected.insert(500, -1);
+
+        map.extend(pairs.iter().copied());
+        expected.extend(pairs.iter().copied());
+
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod extract_if_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_key::{CountedKey, comparisons_during, key_clones_during};
+    use std::collections::BTreeMap;
+
+    fn numbered_map(branching_factor: usize, count: i32) -> BPlusTreeMap<i32, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            map.insert(i, format!("value_{}", i));
+        }
+        map
+    }
+
+    #[test]
+    fn test_extract_if_yields_removed_entries_in_order() {
+        let mut map = numbered_map(3, 60);
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        let extracted: Vec<(i32, String)> = map.extract_if(|k, _| k % 3 == 0).collect();
+
+        let expected: Vec<(i32, String)> = (0..60)
+            .step_by(3)
+            .map(|i| (i, format!("value_{}", i)))
+            .collect();
+        assert_eq!(extracted, expected);
+        assert_eq!(map.len(), 40);
+        for i in 0..60 {
+            assert_eq!(map.contains_key(&i), i % 3 != 0, "key {}", i);
+        }
+    }
+
+    #[test]
+    fn test_extract_if_keeps_value_changes_for_kept_entries() {
+        let mut map = numbered_map(2, 20);
+
+        let extracted: Vec<i32> = map
+            .extract_if(|k, v| {
+                v.push_str("_seen");
+                *k >= 15
+            })
+            .map(|(k, _)| k)
+            .collect();
+
+        assert_eq!(extracted, vec![15, 16, 17, 18, 19]);
+        assert_eq!(map.len(), 15);
+        assert_eq!(map.get(&3), Some(&"value_3_seen".to_string()));
+    }
+
+    #[test]
+    fn test_extract_if_dropped_early_stops_extracting() {
+        let mut map = numbered_map(3, 50);
+
+        let mut calls = 0;
+        let first_two: Vec<i32> = map
+            .extract_if(|k, _| {
+                calls += 1;
+                k % 2 == 1
+            })
+            .take(2)
+            .map(|(k, _)| k)
+            .collect();
+
+        assert_eq!(first_two, vec![1, 3]);
+        // The predicate only saw the keys up to the second match
+        assert_eq!(calls, 4);
+        assert_eq!(map.len(), 48);
+        for i in 4..50 {
+            assert!(map.contains_key(&i), "key {}", i);
+        }
+    }
+
+    #[test]
+    fn test_extract_if_everything() {
+        let mut map = numbered_map(2, 40);
+
+        assert_eq!(map.extract_if(|_, _| true).count(), 40);
+        assert_eq!(map.iter().count(), 0);
+        assert!(map.is_empty());
+
+        // The emptied map is still usable
+        map.insert(3, "three".to_string());
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&3), Some(&"three".to_string()));
+    }
+
+    #[test]
+    fn test_extract_if_merges_emptied_leaves() {
+        // Small branching factors leave leaves with one or two entries, so
+        // extracting runs of neighbouring keys empties whole leaves and
+        // forces merges partway through the iteration
+        for branching_factor in 2..=3 {
+            for (modulus, width) in [(4, 2), (5, 3), (7, 6), (10, 1)] {
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut expected = BTreeMap::new();
+                for i in 0..150 {
+                    let key = (i * 61) % 150;
+                    map.insert(key, key);
+                    expected.insert(key, key);
+                }
+
+                let matches = |k: &i32| k % modulus < width;
+                let extracted: Vec<(i32, i32)> = map.extract_if(|k, _| matches(k)).collect();
+                let wanted: Vec<(i32, i32)> = expected
+                    .iter()
+                    .filter(|(k, _)| matches(k))
+                    .map(|(k, v)| (*k, *v))
+                    .collect();
+                expected.retain(|k, _| !matches(k));
+
+                let context = format!(
+                    "bf {} modulus {} width {}",
+                    branching_factor, modulus, width
+                );
+                assert_eq!(extracted, wanted, "{}", context);
+                assert_eq!(map.len(), expected.len(), "{}", context);
+                assert!(map.iter().eq(expected.iter()), "{}", context);
+                for i in 0..150 {
+                    assert_eq!(map.get(&i), expected.get(&i), "{} key {}", context, i);
+                }
+
+                // Inserting and removing afterwards still works
+                for i in (0..150).step_by(4) {
+                    map.insert(i, -i);
+                    expected.insert(i, -i);
+                }
+                for i in (0..150).step_by(3) {
+                    assert_eq!(map.remove(&i), expected.remove(&i), "{}", context);
+                }
+                assert!(map.iter().eq(expected.iter()), "{}", context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_if_keeps_the_tree_whole() {
+        for branching_factor in 2..=8 {
+            let mut state: u64 = 0x9E37_79B9_7F4A_7C15 ^ branching_factor as u64;
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            };
+            for round in 0..30 {
+                let count = 1 + (next() % 300) as i32;
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut expected = BTreeMap::new();
+                for i in 0..count {
+                    let key = (i * 37) % count;
+                    map.insert(key, key);
+                    expected.insert(key, key);
+                }
+
+                // Runs of matches as long as several leaves, and gaps between
+                let (seed, run) = (next(), 1 + next() % 40);
+                let matches = |key: &i32| !(*key as u64 / run + seed).is_multiple_of(3);
+                // Stop partway through every other round
+                let take = if round % 2 == 0 {
+                    usize::MAX
+                } else {
+                    (next() % 50) as usize
+                };
+                let extracted: Vec<(i32, i32)> =
+                    map.extract_if(|key, _| matches(key)).take(take).collect();
+                for (key, value) in &extracted {
+                    assert_eq!(expected.remove(key), Some(*value));
+                }
+
+                let context = format!("bf {} round {}", branching_factor, round);
+                assert_eq!(map.len(), expected.len(), "{}", context);
+                assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                assert_eq!(map.check_occupancy(), Ok(()), "{}", context);
+                assert!(map.iter().eq(expected.iter()), "{}", context);
+                if take == usize::MAX {
+                    assert!(!expected.keys().any(matches), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_if_walks_the_leaves_without_searching() {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        map.extend((0..1000).map(|key| (CountedKey(key), key)));
+
+        // Visiting every entry neither compares nor clones a key
+        let comparisons = comparisons_during(|| {
+            assert_eq!(map.extract_if(|_, _| false).count(), 0);
+        });
+        assert_eq!(comparisons, 0);
+        let clones = key_clones_during(|| {
+            assert_eq!(map.extract_if(|_, _| false).count(), 0);
+        });
+        assert_eq!(clones, 0);
+
+        // Taking out every other entry only compares and clones keys to
+        // rebalance the leaves, not to find each entry again
+        let mut extracted = 0;
+        let comparisons = comparisons_during(|| {
+            extracted = map.extract_if(|key, _| key.0 % 2 == 0).count();
+        });
+        assert_eq!(extracted, 500);
+        assert!(comparisons < 500, "{} comparisons", comparisons);
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert!(map.keys().map(|key| key.0).eq((1..1000).step_by(2)));
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod fence_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, InsertHint, Node};
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    /// Checks the fences of every branch under `node` against the keys at
+    /// the ends of its subtree, and returns those keys
+    fn checked_fences(node: &Node<u64, u64>) -> Option<(u64, u64)> {
+        match node {
+            Node::Leaf(leaf) => Some((*leaf.keys.first()?, *leaf.keys.last()?)),
+            Node::Branch(branch) => {
+                let extremes: Vec<_> = branch
+                    .children
+                    .iter()
+                    .map(|child| checked_fences(child))
+                    .collect();
+                let first = extremes.first().copied().flatten();
+                let last = extremes.last().copied().flatten();
+                assert_eq!(
+                    (branch.min_key, branch.max_key),
+                    (first.map(|(min, _)| min), last.map(|(_, max)| max)),
+                    "fences of branch with keys {:?}",
+                    branch.keys
+                );
+                Some((first?.0, last?.1))
+            }
+        }
+    }
+
+    /// Checks every fence in `map` and that they bound the same keys as `expected`
+    fn assert_fences_match(map: &BPlusTreeMap<u64, u64>, expected: &BTreeMap<u64, u64>) {
+        let extremes = map.root_node().and_then(|root| checked_fences(root));
+        let expected_extremes = expected
+            .first_key_value()
+            .zip(expected.last_key_value())
+            .map(|((min, _), (max, _))| (*min, *max));
+        assert_eq!(extremes, expected_extremes);
+    }
+
+    fn counted_map(len: u64) -> BPlusTreeMap<CountedKey, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..len {
+            // Every other key from 1000 on, so there are gaps inside too
+            let key = 1000 + 2 * ((i * 7919) % len);
+            map.insert(CountedKey(key), key);
+        }
+        map
+    }
+
+    #[test]
+    fn test_far_misses_take_constant_comparisons() {
+        for len in [100, 10_000, 100_000] {
+            let map = counted_map(len);
+            assert!(map.height() > 1);
+            for key in [0, 999, 1000 + 2 * len, u64::MAX] {
+                let comparisons = comparisons_during(|| {
+                    assert_eq!(map.get(&CountedKey(key)), None);
+                    assert!(!map.contains_key(&CountedKey(key)));
+                    assert_eq!(map.get_key(&CountedKey(key)), None);
+                });
+                assert!(
+                    comparisons <= 6,
+                    "three lookups of {} in {} keys took {}",
+                    key,
+                    len,
+                    comparisons
+                );
+            }
+
+            // A miss in a gap between keys still descends to a leaf
+            let gap = comparisons_during(|| {
+                assert_eq!(map.get(&CountedKey(1001)), None);
+            });
+            assert!(gap > 2);
+        }
+    }
+
+    #[test]
+    fn test_ranges_outside_the_keys_take_constant_comparisons() {
+        for len in [100, 10_000, 100_000] {
+            let map = counted_map(len);
+            let end = 1000 + 2 * len;
+            let comparisons = comparisons_during(|| {
+                assert_eq!(map.range(CountedKey(0)..CountedKey(1000)).count(), 0);
+                assert_eq!(map.range(..CountedKey(999)).next_back(), None);
+                assert_eq!(map.range(CountedKey(end)..).count(), 0);
+                assert_eq!(map.iter_from(&CountedKey(u64::MAX)).next(), None);
+            });
+            assert!(
+                comparisons <= 8,
+                "four ranges outside {} keys took {}",
+                len,
+                comparisons
+            );
+
+            // The ranges that just reach the ends still find them
+            assert_eq!(
+                map.range(CountedKey(0)..=CountedKey(1000)).count(),
+                1,
+                "{} keys",
+                len
+            );
+            assert_eq!(map.range(CountedKey(end - 2)..).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_fences_follow_inserts_and_removals() {
+        for branching_factor in [2, 3, 4, 5, 8] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            let mut hint = InsertHint::new();
+            let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+            for step in 0..3000u64 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let key = state % 500;
+                // Removals favor the ends, whose keys are the fences
+                match step % 9 {
+                    0..=2 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                    3 => {
+                        *map.entry(key).or_insert(0) += 1;
+                        *expected.entry(key).or_insert(0) += 1;
+                    }
+                    4 => assert_eq!(
+                        map.insert_hint(&mut hint, key, step),
+                        expected.insert(key, step)
+                    ),
+                    5 => assert_eq!(map.pop_first(), expected.pop_first()),
+                    6 => assert_eq!(map.pop_last(), expected.pop_last()),
+                    _ => assert_eq!(map.remove(&key), expected.remove(&key)),
+                }
+                assert_fences_match(&map, &expected);
+                for probe in [key, 0, 499, 500] {
+                    assert_eq!(map.get(&probe), expected.get(&probe));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fences_follow_bulk_changes() {
+        for branching_factor in [3, 4, 8] {
+            let entries = (0..2000u64).map(|i| (i * 3, i));
+            let mut map = BPlusTreeMap::from_sorted_iter(entries.clone(), branching_factor)
+                .expect("keys are ascending");
+            let mut expected: BTreeMap<u64, u64> = entries.collect();
+            assert_fences_match(&map, &expected);
+
+            map.retain(|key, _| key % 7 != 0 && *key > 30);
+            expected.retain(|key, _| key % 7 != 0 && *key > 30);
+            assert_fences_match(&map, &expected);
+
+            assert_eq!(map.remove_range(5000..), expected.split_off(&5000).len());
+            assert_fences_match(&map, &expected);
+            map.remove_range(..100);
+            expected.retain(|key, _| *key >= 100);
+            assert_fences_match(&map, &expected);
+
+            let extracted: Vec<_> = map.extract_if(|key, _| *key > 4900).collect();
+            let removed = expected.split_off(&4901);
+            assert!(extracted.into_iter().eq(removed));
+            assert_fences_match(&map, &expected);
+
+            let mut upper = map.split_off(&2500);
+            let mut expected_upper = expected.split_off(&2500);
+            assert_fences_match(&map, &expected);
+            assert_fences_match(&upper, &expected_upper);
+
+            let copy = upper.clone();
+            assert_fences_match(&copy, &expected_upper);
+            let mut target = BPlusTreeMap::with_branching_factor(branching_factor);
+            target.clone_from(&map);
+            assert_fences_match(&target, &expected);
+
+            // Grafting the taller tree onto the shorter and the other way round
+            let mut small = BPlusTreeMap::with_branching_factor(branching_factor);
+            small.insert(1, 1);
+            small.append(&mut upper);
+            let mut expected_small = BTreeMap::from([(1, 1)]);
+            expected_small.append(&mut expected_upper);
+            assert_fences_match(&small, &expected_small);
+            let mut tail = BPlusTreeMap::with_branching_factor(branching_factor);
+            tail.insert(1_000_000, 0);
+            map.append(&mut tail);
+            expected.insert(1_000_000, 0);
+            assert_fences_match(&map, &expected);
+            for key in [0, 101, 2499, 999_999, 1_000_000, 1_000_001] {
+                assert_eq!(map.get(&key), expected.get(&key));
+                assert!(map.range(key..).eq(expected.range(key..)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_missing_keys_at_either_end_are_found_missing() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 10..200 {
+            map.insert(i, i);
+        }
+        // Removing the smallest and largest keys moves the fences inwards
+        for i in 10..50 {
+            map.remove(&i);
+            map.remove(&(209 - i));
+            assert_eq!(map.get(&i), None);
+            assert_eq!(map.get(&(209 - i)), None);
+            assert_eq!(map.range(..=i).next(), None);
+            assert_eq!(map.range(209 - i..).next(), None);
+            assert_eq!(map.first_key_value(), Some((&(i + 1), &(i + 1))));
+            assert_eq!(map.range(i..).next(), Some((&(i + 1), &(i + 1))));
+        }
+        // And inserting past them moves the fences back out
+        map.insert(0, 0);
+        map.insert(1000, 1000);
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.get(&1000), Some(&1000));
+        assert_eq!(map.range(..5).count(), 1);
+        assert_eq!(map.range(500..).count(), 1);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod first_last_entry_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_first_and_last_entry_on_empty_map() {
+        let mut map = BPlusTreeMap::<i32, i32>::new();
+        assert!(map.first_entry().is_none());
+        assert!(map.last_entry().is_none());
+    }
+
+    #[test]
+    fn test_first_and_last_entry_access() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50 {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        let mut first = map.first_entry().unwrap();
+        assert_eq!(first.key(), &0);
+        assert_eq!(first.get(), &0);
+        *first.get_mut() += 1;
+        assert_eq!(first.insert(5), 1);
+        assert_eq!(map.get(&0), Some(&5));
+
+        let mut last = map.last_entry().unwrap();
+        assert_eq!(last.key(), &49);
+        assert_eq!(last.get(), &490);
+        assert_eq!(last.insert(7), 490);
+        assert_eq!(last.remove(), 7);
+
+        assert_eq!(map.len(), 49);
+        assert_eq!(map.last_entry().unwrap().key(), &48);
+    }
+
+    #[test]
+    fn test_remove_stale_entries_from_the_front() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        // Remove entries from the front while they are stale
+        while let Some(entry) = map.first_entry() {
+            if *entry.get() >= 30 {
+                break;
+            }
+            entry.remove();
+        }
+
+        assert_eq!(map.len(), 70);
+        assert_eq!(map.first_entry().unwrap().key(), &30);
+    }
+
+    #[test]
+    fn test_remove_through_entries_merges_leaves() {
+        // With a branching factor of 2 removing from either end keeps
+        // emptying leaves, forcing merges in the branches above them
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        let mut expected = BTreeMap::new();
+        for i in 0..40 {
+            map.insert(i, i.to_string());
+            expected.insert(i, i.to_string());
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        let mut from_front = true;
+        while !expected.is_empty() {
+            let (removed, wanted) = if from_front {
+                let entry = map.first_entry().unwrap();
+                let key = *entry.key();
+                ((key, entry.remove()), expected.pop_first().unwrap())
+            } else {
+                let entry = map.last_entry().unwrap();
+                let key = *entry.key();
+                ((key, entry.remove()), expected.pop_last().unwrap())
+            };
+            assert_eq!(removed, wanted);
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+            from_front = !from_front;
+        }
+
+        assert!(map.first_entry().is_none());
+        assert!(map.last_entry().is_none());
+    }
+}
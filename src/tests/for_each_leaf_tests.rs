@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod for_each_leaf_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, LeafNode, NodeVisitor};
+    use crate::tests::counting_allocator::allocations_during;
+    use std::ops::ControlFlow;
+
+    /// Counts the leaves of a tree
+    struct LeafCounter {
+        leaves: usize,
+    }
+
+    impl NodeVisitor<i32, String> for LeafCounter {
+        type Result = usize;
+
+        fn visit_leaf(&mut self, _leaf: &LeafNode<i32, String>) {
+            self.leaves += 1;
+        }
+
+        fn visit_branch(&mut self, _branch: &BranchNode<i32, String>) {}
+
+        fn result(self) -> usize {
+            self.leaves
+        }
+    }
+
+    fn scattered_map(branching_factor: usize, count: i32) -> BPlusTreeMap<i32, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            let key = (i * 7919) % count;
+            map.insert(key, key.to_string());
+        }
+        map
+    }
+
+    #[test]
+    fn test_leaf_slices_concatenate_to_the_map() {
+        for branching_factor in 2..=8 {
+            let map = scattered_map(branching_factor, 500);
+
+            let mut keys = Vec::new();
+            let mut values = Vec::new();
+            let mut calls = 0;
+            map.for_each_leaf(|leaf_keys, leaf_values| {
+                assert_eq!(leaf_keys.len(), leaf_values.len());
+                assert!(!leaf_keys.is_empty());
+                keys.extend_from_slice(leaf_keys);
+                values.extend_from_slice(leaf_values);
+                calls += 1;
+            });
+
+            assert!(keys.iter().zip(&values).eq(map.iter()));
+            let mut counter = LeafCounter { leaves: 0 };
+            map.accept(&mut counter);
+            assert_eq!(calls, counter.result());
+        }
+    }
+
+    #[test]
+    fn test_for_each_leaf_on_empty_map() {
+        let map = BPlusTreeMap::<i32, String>::new();
+        let mut calls = 0;
+        map.for_each_leaf(|_, _| calls += 1);
+        assert_eq!(calls, 0);
+        assert_eq!(
+            map.try_for_each_leaf(|_, _| ControlFlow::Break(())),
+            ControlFlow::Continue(())
+        );
+    }
+
+    #[test]
+    fn test_for_each_leaf_does_not_allocate() {
+        let map = scattered_map(4, 10_000);
+        let mut total = 0;
+        let allocations = allocations_during(|| {
+            map.for_each_leaf(|keys, _| total += keys.iter().map(|&k| k as i64).sum::<i64>());
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(total, (0..10_000).sum::<i64>());
+    }
+
+    #[test]
+    fn test_try_for_each_leaf_stops_early() {
+        let map = scattered_map(3, 300);
+
+        // Find the leaf holding key 100 and stop there
+        let mut visited = 0;
+        let found = map.try_for_each_leaf(|keys, values| {
+            visited += 1;
+            match keys.iter().position(|&k| k == 100) {
+                Some(i) => ControlFlow::Break(values[i].clone()),
+                None => ControlFlow::Continue(()),
+            }
+        });
+        assert_eq!(found, ControlFlow::Break("100".to_string()));
+
+        let mut leaves_up_to_key = 0;
+        map.for_each_leaf(|keys, _| {
+            if keys[0] <= 100 {
+                leaves_up_to_key += 1;
+            }
+        });
+        assert_eq!(visited, leaves_up_to_key);
+    }
+}
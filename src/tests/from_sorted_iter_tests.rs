@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod from_sorted_iter_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, NotAscendingError, RootKind};
+
+    fn sorted_pairs(count: i32) -> impl Iterator<Item = (i32, i32)> {
+        (0..count).map(|i| (i * 3, i))
+    }
+
+    #[test]
+    fn test_matches_insert_built_map() {
+        for branching_factor in [3, 4, 8, 16] {
+            let built =
+                BPlusTreeMap::from_sorted_iter(sorted_pairs(500), branching_factor).unwrap();
+            let mut inserted = BPlusTreeMap::with_branching_factor(branching_factor);
+            for (key, value) in sorted_pairs(500) {
+                inserted.insert(key, value);
+            }
+
+            assert_eq!(built.len(), 500);
+            assert_eq!(built.branching_factor(), branching_factor);
+            assert!(built.iter().eq(inserted.iter()));
+            assert_eq!(built.get(&300), Some(&100));
+            assert_eq!(built.get(&301), None);
+            built.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_small_inputs() {
+        let empty = BPlusTreeMap::<i32, i32>::from_sorted_iter(sorted_pairs(0), 4).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.root_kind(), RootKind::Empty);
+
+        let single = BPlusTreeMap::from_sorted_iter(sorted_pairs(1), 4).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single.root_kind(), RootKind::Leaf);
+        assert_eq!(single.get(&0), Some(&0));
+
+        let one_leaf = BPlusTreeMap::from_sorted_iter(sorted_pairs(4), 4).unwrap();
+        assert_eq!(one_leaf.len(), 4);
+        assert_eq!(one_leaf.root_kind(), RootKind::Leaf);
+        assert_eq!(one_leaf.leaf_count(), 1);
+
+        let two_leaves = BPlusTreeMap::from_sorted_iter(sorted_pairs(5), 4).unwrap();
+        assert_eq!(two_leaves.len(), 5);
+        assert_eq!(two_leaves.root_kind(), RootKind::Branch);
+        assert_eq!(two_leaves.leaf_count(), 2);
+        assert!(two_leaves.keys().copied().eq((0..5).map(|i| i * 3)));
+        two_leaves.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_leaves_are_packed() {
+        for (branching_factor, count) in [(4, 1000), (7, 999), (16, 5000), (16, 17)] {
+            let map =
+                BPlusTreeMap::from_sorted_iter(sorted_pairs(count), branching_factor).unwrap();
+
+            // As few leaves as can hold the entries, none less than half full
+            let count = count as usize;
+            assert_eq!(map.leaf_count(), count.div_ceil(branching_factor));
+            map.for_each_leaf(|keys, _| {
+                assert!(keys.len() <= branching_factor);
+                assert!(keys.len() >= branching_factor / 2, "leaf {:?}", keys);
+            });
+            map.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rejects_input_that_is_not_strictly_ascending() {
+        let duplicate = BPlusTreeMap::from_sorted_iter([(1, 'a'), (2, 'b'), (2, 'c')], 4);
+        assert_eq!(
+            duplicate.unwrap_err(),
+            NotAscendingError {
+                previous: 2,
+                key: 2
+            }
+        );
+
+        let descending =
+            BPlusTreeMap::from_sorted_iter([(1, 'a'), (5, 'b'), (3, 'c'), (4, 'd')], 4);
+        let error = descending.unwrap_err();
+        assert_eq!((error.previous, error.key), (5, 3));
+        assert_eq!(
+            error.to_string(),
+            "keys are not strictly ascending: 3 follows 5"
+        );
+    }
+}
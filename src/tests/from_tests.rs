@@ -0,0 +1,197 @@
+#[cfg(test)]
+mod from_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_allocator::allocations_during;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_from_array() {
+        let map = BPlusTreeMap::from([(3, "c"), (1, "a"), (2, "b")]);
+
+        assert_eq!(map.len(), 3);
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, vec![(1, "a"), (2, "b"), (3, "c")]);
+    }
+
+    #[test]
+    fn test_from_array_with_duplicates_keeps_last() {
+        let map = BPlusTreeMap::from([(1, "a"), (2, "b"), (1, "c"), (2, "d"), (1, "e")]);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&"e"));
+        assert_eq!(map.get(&2), Some(&"d"));
+        map.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_from_sorted_array_with_duplicates_keeps_last() {
+        // Sorted but not strictly, so it can't be built bottom-up as is
+        let map = BPlusTreeMap::from([(1, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (3, 'e')]);
+
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, vec![(1, 'b'), (2, 'c'), (3, 'e')]);
+    }
+
+    #[test]
+    fn test_from_empty_array_and_vec() {
+        let map: BPlusTreeMap<i32, String> = BPlusTreeMap::from([]);
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+
+        let map: BPlusTreeMap<i32, String> = BPlusTreeMap::from(Vec::new());
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+    }
+
+    #[test]
+    fn test_from_sorted_vec_is_built_bottom_up() {
+        let entries: Vec<(i32, i32)> = (0..1000).map(|i| (i, i * 2)).collect();
+        let map = BPlusTreeMap::from(entries.clone());
+
+        assert_eq!(map.len(), 1000);
+        assert!(
+            map.iter()
+                .map(|(k, v)| (*k, *v))
+                .eq(entries.iter().copied())
+        );
+        map.check_invariants().unwrap();
+
+        // Bottom-up building fills every leaf, where inserting in order
+        // leaves them about half full
+        let inserted: BPlusTreeMap<i32, i32> = entries.into_iter().collect();
+        assert_eq!(map.leaf_count(), 250);
+        assert!(inserted.leaf_count() > map.leaf_count());
+    }
+
+    #[test]
+    fn test_from_unsorted_vec_matches_from_iter() {
+        let entries: Vec<(i32, i32)> = (0..500).map(|i| ((i * 37) % 200, i)).collect();
+        let map = BPlusTreeMap::from(entries.clone());
+        let expected: BPlusTreeMap<i32, i32> = entries.into_iter().collect();
+
+        assert_eq!(map.len(), 200);
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_maps_from_vec_can_be_modified() {
+        let mut map = BPlusTreeMap::from((0..100).map(|i| (i, i)).collect::<Vec<_>>());
+        for i in 100..200 {
+            map.insert(i, i);
+        }
+        for i in (0..200).step_by(3) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        assert_eq!(map.len(), 200 - 67);
+        map.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_from_unsorted_keeps_last_of_each_key() {
+        let entries = vec![(5, 'a'), (1, 'b'), (5, 'c'), (3, 'd'), (1, 'e'), (5, 'f')];
+        let map = BPlusTreeMap::from_unsorted(entries.clone());
+        let expected: BPlusTreeMap<i32, char> = entries.into_iter().collect();
+
+        let pairs: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(pairs, vec![(1, 'e'), (3, 'd'), (5, 'f')]);
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_from_unsorted_matches_inserting() {
+        for count in [0, 1, 2, 5, 100, 3000] {
+            let entries: Vec<(i32, i32)> = (0..count).map(|i| ((i * 7919) % 1009, i)).collect();
+            let map = BPlusTreeMap::from_unsorted(entries.iter().copied());
+            let expected: BPlusTreeMap<i32, i32> = entries.into_iter().collect();
+
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+            map.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_from_hash_map() {
+        let hash_map: HashMap<String, usize> = (0..2000)
+            .map(|i| (format!("key_{:05}", (i * 37) % 2000), i))
+            .collect();
+        let map = BPlusTreeMap::from(hash_map.clone());
+
+        assert_eq!(map.len(), hash_map.len());
+        for (key, value) in &hash_map {
+            assert_eq!(map.get(key), Some(value));
+        }
+        assert!(map.keys().zip(map.keys().skip(1)).all(|(a, b)| a < b));
+        map.check_invariants().unwrap();
+
+        let mut inserted = BPlusTreeMap::new();
+        for (key, value) in hash_map {
+            inserted.insert(key, value);
+        }
+        assert!(map.iter().eq(inserted.iter()));
+    }
+
+    #[test]
+    fn test_from_hash_map_does_far_less_work_than_inserting() {
+        let hash_map: HashMap<u64, u64> = (0..20_000).map(|i| (i, i)).collect();
+
+        // Inserting splits nodes as they fill, leaving them partly empty,
+        // so it ends up with more nodes; building bottom-up fills each node
+        // and allocates it once, at its final size
+        let mut bulk = None;
+        let bulk_allocations =
+            allocations_during(|| bulk = Some(BPlusTreeMap::from(hash_map.clone())));
+        let mut inserted = None;
+        let insert_allocations = allocations_during(|| {
+            let mut map = BPlusTreeMap::new();
+            for (key, value) in hash_map.clone() {
+                map.insert(key, value);
+            }
+            inserted = Some(map);
+        });
+
+        assert!(bulk.unwrap().iter().eq(inserted.unwrap().iter()));
+        assert!(
+            bulk_allocations < insert_allocations,
+            "bulk {} insert {}",
+            bulk_allocations,
+            insert_allocations
+        );
+    }
+
+    #[test]
+    fn test_from_empty_hash_map() {
+        let map = BPlusTreeMap::from(HashMap::<i32, i32>::new());
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+    }
+
+    #[test]
+    fn test_collect_filtered_borrowed_pairs() {
+        let map: BPlusTreeMap<i32, String> =
+            (0..100).map(|i| (i, format!("value_{}", i))).collect();
+
+        let even: BPlusTreeMap<i32, String> = map.iter().filter(|(k, _)| *k % 2 == 0).collect();
+        assert_eq!(even.len(), 50);
+        assert!(even.iter().eq(map.iter().filter(|(k, _)| *k % 2 == 0)));
+        even.check_invariants().unwrap();
+
+        let none: BPlusTreeMap<i32, String> = map.iter().filter(|(k, _)| **k > 100).collect();
+        assert!(none.is_empty());
+        assert_eq!(none.root_kind(), RootKind::Empty);
+    }
+
+    #[test]
+    fn test_collect_borrowed_pairs_keeps_last_of_each_key() {
+        let pairs = [
+            (1, "a".to_string()),
+            (2, "b".to_string()),
+            (1, "c".to_string()),
+        ];
+        let map: BPlusTreeMap<i32, String> = pairs.iter().map(|(k, v)| (k, v)).collect();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&"c".to_string()));
+        assert_eq!(map.get(&2), Some(&"b".to_string()));
+    }
+}
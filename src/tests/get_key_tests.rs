@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod get_key_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+
+    #[test]
+    fn test_get_key_returns_the_stored_key() {
+        let mut map = BPlusTreeMap::new();
+        let mut key = String::with_capacity(64);
+        key.push_str("apple");
+        map.insert(key, 1);
+        map.insert("banana".to_string(), 2);
+
+        // Looked up by &str, the stored String comes back with its original
+        // capacity, so it cannot be a clone
+        let stored = map.get_key("apple").unwrap();
+        assert_eq!(stored, "apple");
+        assert_eq!(stored.capacity(), 64);
+
+        assert_eq!(map.get_key("banana"), Some(&"banana".to_string()));
+        assert_eq!(map.get_key("cherry"), None);
+    }
+
+    #[test]
+    fn test_get_key_on_multi_level_tree() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..200 {
+            map.insert(format!("key_{:03}", i), i);
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        for (stored, _) in map.iter() {
+            // The separators hold copies of some keys, but the key returned
+            // is the one held in the leaf
+            let found = map.get_key(stored.as_str()).unwrap();
+            assert!(std::ptr::eq(found, stored), "key {}", stored);
+        }
+
+        assert_eq!(map.get_key("key_200"), None);
+        assert_eq!(map.get_key("a"), None);
+        assert_eq!(map.get_key("z"), None);
+    }
+
+    #[test]
+    fn test_get_key_on_empty_map() {
+        let map = BPlusTreeMap::<String, i32>::new();
+        assert_eq!(map.get_key("anything"), None);
+    }
+}
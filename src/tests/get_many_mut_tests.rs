@@ -0,0 +1,120 @@
+// These tests hand out several references into the same leaves, so they
+// are sized to run under Miri as well, with and without inline node
+// storage: `cargo +nightly miri test get_many_mut` and
+// `cargo +nightly miri test --features smallvec get_many_mut`.
+#[cfg(test)]
+mod get_many_mut_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+
+    fn accounts() -> BPlusTreeMap<String, i32> {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..30 {
+            map.insert(format!("account_{:02}", i), 100);
+        }
+        map
+    }
+
+    #[test]
+    fn test_get_many_mut_across_leaves() {
+        let mut map = accounts();
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        // Move an amount from one account to another in a different leaf
+        let [from, to] = map.get_many_mut(["account_03", "account_27"]).unwrap();
+        *from -= 40;
+        *to += 40;
+
+        assert_eq!(map.get("account_03"), Some(&60));
+        assert_eq!(map.get("account_27"), Some(&140));
+        assert_eq!(map.values().sum::<i32>(), 3000);
+    }
+
+    #[test]
+    fn test_get_many_mut_within_one_leaf() {
+        let mut map = BPlusTreeMap::new();
+        map.insert(1, "one".to_string());
+        map.insert(2, "two".to_string());
+        map.insert(3, "three".to_string());
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+
+        let [third, first, second] = map.get_many_mut([&3, &1, &2]).unwrap();
+        std::mem::swap(first, third);
+        second.push('!');
+
+        assert_eq!(map.get(&1), Some(&"three".to_string()));
+        assert_eq!(map.get(&2), Some(&"two!".to_string()));
+        assert_eq!(map.get(&3), Some(&"one".to_string()));
+    }
+
+    #[test]
+    fn test_get_many_mut_missing_key() {
+        let mut map = accounts();
+        assert!(map.get_many_mut(["account_01", "account_99"]).is_none());
+        assert!(map.get_many_mut(["missing"]).is_none());
+
+        let mut empty = BPlusTreeMap::<i32, i32>::new();
+        assert!(empty.get_many_mut([&1, &2]).is_none());
+    }
+
+    #[test]
+    fn test_get_many_mut_no_keys() {
+        let mut map = accounts();
+        let values: Option<[&mut i32; 0]> = map.get_many_mut::<str, 0>([]);
+        assert!(values.is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate keys found")]
+    fn test_get_many_mut_duplicate_keys() {
+        let mut map = accounts();
+        let _ = map.get_many_mut(["account_01", "account_02", "account_01"]);
+    }
+
+    #[test]
+    fn test_get_many_mut_every_pair() {
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for i in 0..40 {
+            map.insert(i, 0);
+        }
+
+        for i in 0..40 {
+            for j in 0..40 {
+                if i != j {
+                    let [a, b] = map.get_many_mut([&i, &j]).unwrap();
+                    *a += 1;
+                    *b += 1;
+                }
+            }
+        }
+
+        // Every key took part in 39 pairs as the first key and 39 as the second
+        for i in 0..40 {
+            assert_eq!(map.get(&i), Some(&78), "key {}", i);
+        }
+    }
+
+    #[test]
+    fn test_get_many_mut_in_any_order_across_leaves() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..60 {
+            map.insert(i, i);
+        }
+        assert!(map.height() > 2);
+
+        // Keys out of order, several sharing a leaf and others far apart
+        let keys = [41, 2, 59, 0, 40, 3, 17, 1];
+        let values = map.get_many_mut(keys.each_ref()).unwrap();
+        for (value, key) in values.into_iter().zip(keys) {
+            assert_eq!(*value, key);
+            *value += 1000;
+        }
+        for i in 0..60 {
+            let expected = if keys.contains(&i) { i + 1000 } else { i };
+            assert_eq!(map.get(&i), Some(&expected), "key {}", i);
+        }
+
+        // A missing key next to present ones in the same leaf
+        assert!(map.get_many_mut([&0, &1, &60, &2]).is_none());
+        assert!(map.get_many_mut([&-1, &41]).is_none());
+    }
+}
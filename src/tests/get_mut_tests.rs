@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod get_mut_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+
+    #[test]
+    fn test_get_mut_updates_value_in_multi_level_tree() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..200 {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        for i in 0..200 {
+            *map.get_mut(&i).unwrap() += 1;
+        }
+
+        for i in 0..200 {
+            assert_eq!(map.get(&i), Some(&(i * 10 + 1)), "key {}", i);
+        }
+        assert_eq!(map.len(), 200);
+    }
+
+    #[test]
+    fn test_get_mut_missing_key() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        assert_eq!(map.get_mut(&1), None);
+
+        for i in (0..100).step_by(2) {
+            map.insert(i, i);
+        }
+        assert_eq!(map.get_mut(&-1), None);
+        assert_eq!(map.get_mut(&51), None);
+        assert_eq!(map.get_mut(&100), None);
+    }
+
+    #[test]
+    fn test_get_mut_with_borrowed_key() {
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for word in ["apple", "banana", "cherry", "date", "elderberry", "fig"] {
+            map.insert(word.to_string(), word.len());
+        }
+
+        // String keys can be looked up with a &str
+        if let Some(value) = map.get_mut("cherry") {
+            *value = 100;
+        }
+
+        assert_eq!(map.get("cherry"), Some(&100));
+        assert_eq!(map.get("banana"), Some(&6));
+        assert_eq!(map.get_mut("grape"), None);
+    }
+
+    #[test]
+    fn test_get_mut_visits_only_the_path_to_one_leaf() {
+        let branching_factor: usize = 8;
+        // Searching a node of at most this many keys takes at most
+        // log2(branching_factor) + 1 comparisons
+        let per_node = branching_factor.ilog2() as usize + 1;
+        for len in [100, 10_000, 100_000] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..len {
+                map.insert(CountedKey(i), i);
+            }
+            // Far apart keys, so no lookup can use the leaf cached by the
+            // one before; checking the cached leaf takes two comparisons
+            let bound = map.height() * per_node + 2;
+            for key in [0, len / 2, len - 1, len] {
+                let comparisons = comparisons_during(|| {
+                    if let Some(value) = map.get_mut(&CountedKey(key)) {
+                        *value += 1;
+                    }
+                });
+                assert!(
+                    comparisons <= bound,
+                    "{} comparisons in a tree of {} with height {}",
+                    comparisons,
+                    len,
+                    map.height()
+                );
+            }
+            assert_eq!(map.get(&CountedKey(len / 2)), Some(&(len / 2 + 1)));
+        }
+    }
+}
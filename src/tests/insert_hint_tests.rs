@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod insert_hint_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, InsertHint};
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    fn even_keys(branching_factor: usize, count: u64) -> BPlusTreeMap<CountedKey, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            map.insert(CountedKey(i * 2), i);
+        }
+        map
+    }
+
+    #[test]
+    fn test_hints_from_the_previous_insert_save_comparisons() {
+        let mut hinted = even_keys(8, 10_000);
+        let mut plain = even_keys(8, 10_000);
+
+        // Odd keys in order, each landing just after the one before
+        let mut hint = InsertHint::new();
+        let hinted_comparisons = comparisons_during(|| {
+            for i in 0..10_000 {
+                assert_eq!(
+                    hinted.insert_hint(&mut hint, CountedKey(i * 2 + 1), i),
+                    None
+                );
+            }
+        });
+        let plain_comparisons = comparisons_during(|| {
+            for i in 0..10_000 {
+                plain.insert(CountedKey(i * 2 + 1), i);
+            }
+        });
+
+        assert!(hinted.iter().eq(plain.iter()));
+        assert_eq!(hinted.len(), 20_000);
+        hinted.check_invariants().unwrap();
+        // Checking the bounds of the hinted leaf, or of the one after it,
+        // and searching the leaf, against a search at every level of a
+        // tree several levels deep
+        assert!(
+            hinted_comparisons * 2 < plain_comparisons,
+            "{} with hints, {} without",
+            hinted_comparisons,
+            plain_comparisons
+        );
+        assert!(hinted_comparisons <= 10_000 * 7, "{}", hinted_comparisons);
+    }
+
+    #[test]
+    fn test_hinted_inserts_into_an_empty_map() {
+        for branching_factor in 2..=8 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut hint = InsertHint::new();
+            for i in (0..500).rev() {
+                map.insert_hint(&mut hint, i, i * 2);
+            }
+            assert_eq!(map.len(), 500);
+            assert!(
+                map.iter()
+                    .map(|(k, v)| (*k, *v))
+                    .eq((0..500).map(|i| (i, i * 2)))
+            );
+            map.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_wrong_hints_only_fall_back() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        let mut expected = BTreeMap::new();
+        let mut hint = InsertHint::new();
+        for i in 0..3000 {
+            // Scattered keys, so the hint from the last insert rarely fits
+            let key = (i * 7919) % 3001;
+            assert_eq!(map.insert_hint(&mut hint, key, i), expected.insert(key, i));
+        }
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+
+        // A hint taken from a different, deeper map
+        let mut other = BPlusTreeMap::with_branching_factor(3);
+        let mut foreign = InsertHint::new();
+        for i in 0..1000 {
+            other.insert_hint(&mut foreign, i, i);
+        }
+        let mut small = BPlusTreeMap::with_branching_factor(16);
+        for i in 0..20 {
+            let mut hint = foreign.clone();
+            small.insert_hint(&mut hint, i * 5, i);
+        }
+        assert!(small.keys().copied().eq((0..20).map(|i| i * 5)));
+        small.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_hints_left_stale_by_other_changes() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        let mut expected = BTreeMap::new();
+        let mut hint = InsertHint::new();
+        for i in 0..200 {
+            map.insert_hint(&mut hint, i * 10, i);
+            expected.insert(i * 10, i);
+        }
+
+        // Splits around the hinted leaf, then removals that empty it
+        let stale = hint.clone();
+        for i in 1900..1990 {
+            map.insert(i, i);
+            expected.insert(i, i);
+        }
+        for i in (1000..1990).step_by(3) {
+            assert_eq!(map.remove(&i), expected.remove(&i));
+        }
+        for key in [5, 1955, 1991, 2500, 1000] {
+            let mut hint = stale.clone();
+            assert_eq!(
+                map.insert_hint(&mut hint, key, -1),
+                expected.insert(key, -1)
+            );
+        }
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_hinted_insert_of_an_existing_key_replaces_its_value() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        let mut hint = InsertHint::new();
+        for i in 0..50 {
+            map.insert_hint(&mut hint, i, i);
+        }
+        assert_eq!(map.insert_hint(&mut hint, 49, 490), Some(49));
+        assert_eq!(map.insert_hint(&mut hint, 3, 30), Some(3));
+        assert_eq!(map.get(&49), Some(&490));
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.len(), 50);
+    }
+}
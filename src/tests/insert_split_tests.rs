@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod insert_split_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, InsertHint, Node, RootKind};
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+
+    /// Scatters 0..n so inserts land all over the tree rather than at the end
+    fn scattered(n: u64) -> impl Iterator<Item = u64> {
+        (0..n).map(move |i| (i * 7919) % n)
+    }
+
+    #[test]
+    fn test_splits_move_values_without_cloning() {
+        for branching_factor in [3, 4, 16] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let clones = clones_during(|| {
+                for key in scattered(5000) {
+                    map.insert(key, CountedValue(key));
+                }
+            });
+            // Splits have propagated up through several levels of branches
+            assert_eq!(map.root_kind(), RootKind::Branch);
+            assert!(map.leaf_count() > branching_factor * branching_factor);
+            assert_eq!(clones, 0, "branching factor {}", branching_factor);
+            assert!(map.iter().all(|(key, value)| *key == value.0));
+        }
+    }
+
+    #[test]
+    fn test_overwriting_and_growing_do_not_clone() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for key in scattered(1000) {
+            map.insert(key, CountedValue(key));
+        }
+        let clones = clones_during(|| {
+            for key in scattered(1000) {
+                assert_eq!(
+                    map.insert(key, CountedValue(key + 1)),
+                    Some(CountedValue(key))
+                );
+            }
+            for key in scattered(2000) {
+                map.insert(key, CountedValue(key + 1));
+            }
+        });
+        assert_eq!(clones, 0);
+        assert_eq!(map.len(), 2000);
+    }
+
+    #[test]
+    fn test_leaves_stay_at_one_depth_while_splitting() {
+        // A branch child that holds a single key must be left alone rather
+        // than mistaken for the two halves of a split
+        for branching_factor in [2, 3, 4, 5, 6, 16] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for key in scattered(3000) {
+                map.insert(key, key);
+            }
+            assert_eq!(
+                map.check_invariants(),
+                Ok(()),
+                "branching factor {}",
+                branching_factor
+            );
+            assert!(map.keys().copied().eq(0..3000));
+        }
+    }
+
+    /// The separators of each branch of the tree under `node`, outermost
+    /// first, each listed with its depth
+    fn branch_shape(node: &Node<u64, u64>, depth: usize, out: &mut Vec<(usize, Vec<u64>)>) {
+        if let Node::Branch(branch) = node {
+            out.push((depth, branch.keys.iter().map(|key| **key).collect()));
+            for child in &branch.children {
+                branch_shape(child, depth + 1, out);
+            }
+        }
+    }
+
+    fn shape(map: &BPlusTreeMap<u64, u64>) -> Vec<(usize, Vec<u64>)> {
+        let mut out = Vec::new();
+        if let Some(root) = map.root_node() {
+            branch_shape(root, 0, &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn test_inserting_under_a_one_key_branch_child_keeps_the_shape() {
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for key in (0..64).map(|i| i * 10) {
+            map.insert(key, key);
+        }
+        let before = shape(&map);
+        // Below the root there are branches holding a single separator
+        assert!(
+            before
+                .iter()
+                .any(|(depth, keys)| *depth > 0 && keys.len() == 1)
+        );
+
+        let mut unsplit = 0;
+        for key in (0..64).map(|i| i * 10 + 5) {
+            let mut changed = map.clone();
+            changed.insert(key, key);
+            assert_eq!(changed.check_invariants(), Ok(()), "inserting {}", key);
+            for probe in (0..640u64).step_by(5) {
+                let expected = (probe.is_multiple_of(10) || probe == key).then_some(probe);
+                assert_eq!(changed.get(&probe), expected.as_ref(), "inserting {}", key);
+            }
+            // A leaf with room takes the key without any branch changing
+            if changed.leaf_count() == map.leaf_count() {
+                assert_eq!(shape(&changed), before, "inserting {}", key);
+                unsplit += 1;
+            }
+        }
+        assert!(unsplit > 0);
+    }
+
+    /// The depth of the leaves under `node`, after checking that they are
+    /// all at the same depth and that each branch has a child on either
+    /// side of every separator
+    fn leaf_depth(node: &Node<u64, u64>) -> usize {
+        match node {
+            Node::Leaf(_) => 1,
+            Node::Branch(branch) => {
+                assert_eq!(branch.children.len(), branch.keys.len() + 1);
+                let depths: Vec<usize> = branch.children.iter().map(|c| leaf_depth(c)).collect();
+                assert!(
+                    depths.iter().all(|depth| *depth == depths[0]),
+                    "{:?}",
+                    depths
+                );
+                depths[0] + 1
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_inserts_keep_leaves_at_one_depth() {
+        for branching_factor in 2..=5 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut hint = InsertHint::new();
+            let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+            for step in 0..4000u64 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                // Mostly scattered keys, with runs past the largest one
+                let key = match step % 5 {
+                    0 => 10_000 + step,
+                    _ => state % 5000,
+                };
+                match step % 3 {
+                    0 => {
+                        map.insert(key, step);
+                    }
+                    1 => {
+                        map.entry(key).or_insert(step);
+                    }
+                    _ => {
+                        map.insert_hint(&mut hint, key, step);
+                    }
+                }
+                if step % 50 == 0
+                    && let Some(root) = map.root_node()
+                {
+                    leaf_depth(root);
+                }
+            }
+            assert_eq!(leaf_depth(map.root_node().unwrap()), map.height());
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert_eq!(map.iter().count(), map.len());
+        }
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod into_keys_values_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::rc::Rc;
+
+    fn word_map(branching_factor: usize, count: usize) -> BPlusTreeMap<String, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in (0..count).rev() {
+            map.insert(format!("key_{:03}", i), format!("value_{:03}", i));
+        }
+        map
+    }
+
+    #[test]
+    fn test_into_keys_in_ascending_order() {
+        let map = word_map(3, 100);
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        let keys: Vec<String> = map.into_keys().collect();
+        let expected: Vec<String> = (0..100).map(|i| format!("key_{:03}", i)).collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn test_into_values_in_key_order() {
+        let map = word_map(4, 100);
+
+        let values: Vec<String> = map.into_values().collect();
+        let expected: Vec<String> = (0..100).map(|i| format!("value_{:03}", i)).collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_into_keys_and_values_of_small_maps() {
+        assert_eq!(BPlusTreeMap::<i32, i32>::new().into_keys().next(), None);
+        assert_eq!(BPlusTreeMap::<i32, i32>::new().into_values().next(), None);
+
+        let map = word_map(4, 2);
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+        assert_eq!(
+            map.into_keys().collect::<Vec<_>>(),
+            vec!["key_000", "key_001"]
+        );
+    }
+
+    #[test]
+    fn test_into_values_does_not_clone() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50 {
+            map.insert(i, CountedValue(i));
+        }
+
+        let mut values = Vec::new();
+        let clones = clones_during(|| values = map.into_values().map(|value| value.0).collect());
+
+        assert_eq!(clones, 0);
+        assert_eq!(values, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_into_keys_dropped_part_way() {
+        // Dropping the iterator early drops the entries it didn't reach
+        let values = Rc::new(());
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for i in 0..20 {
+            map.insert(i, Rc::clone(&values));
+        }
+
+        let first: Vec<i32> = map.into_keys().take(5).collect();
+        assert_eq!(first, vec![0, 1, 2, 3, 4]);
+        assert_eq!(Rc::strong_count(&values), 1);
+    }
+}
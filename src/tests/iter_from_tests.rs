@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod iter_from_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    /// Builds a multi-level tree holding the multiples of ten below 1000
+    /// and a BTreeMap with the same contents to compare against
+    fn sparse_maps(branching_factor: usize) -> (BPlusTreeMap<i32, i32>, BTreeMap<i32, i32>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        for i in 0..100 {
+            let key = (i * 37) % 100 * 10;
+            map.insert(key, -key);
+            expected.insert(key, -key);
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_iter_from_matches_btreemap() {
+        for branching_factor in 2..=6 {
+            let (map, expected) = sparse_maps(branching_factor);
+            for key in [-5, 0, 1, 10, 255, 260, 500, 989, 990, 991, 5000] {
+                assert!(
+                    map.iter_from(&key).eq(expected.range(key..)),
+                    "iter_from({}), branching factor {}",
+                    key,
+                    branching_factor
+                );
+                assert!(
+                    map.iter_from_exclusive(&key)
+                        .eq(expected.range((Bound::Excluded(key), Bound::Unbounded))),
+                    "iter_from_exclusive({}), branching factor {}",
+                    key,
+                    branching_factor
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_from_past_the_last_key() {
+        let (map, _) = sparse_maps(3);
+        assert_eq!(map.iter_from(&991).next(), None);
+        assert_eq!(map.iter_from_exclusive(&990).next(), None);
+
+        let empty = BPlusTreeMap::<i32, i32>::new();
+        assert_eq!(empty.iter_from(&0).next(), None);
+        assert_eq!(empty.iter_from_exclusive(&0).next(), None);
+    }
+
+    #[test]
+    fn test_iter_from_between_leaves() {
+        // Every key between two stored keys, whichever leaves they are in,
+        // starts the iteration at the larger of the two
+        let (map, _) = sparse_maps(3);
+        for key in (0..990).step_by(10) {
+            for gap in 1..10 {
+                assert_eq!(
+                    map.iter_from(&(key + gap)).next(),
+                    Some((&(key + 10), &-(key + 10)))
+                );
+            }
+            assert_eq!(
+                map.iter_from_exclusive(&key).next(),
+                Some((&(key + 10), &-(key + 10)))
+            );
+        }
+    }
+
+    #[test]
+    fn test_paging_through_the_map() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            map.insert(i, i.to_string());
+        }
+
+        let mut pages = Vec::new();
+        let mut page: Vec<i32> = map.iter().take(100).map(|(k, _)| *k).collect();
+        while let Some(&last_key) = page.last() {
+            pages.push(page);
+            page = map
+                .iter_from_exclusive(&last_key)
+                .take(100)
+                .map(|(k, _)| *k)
+                .collect();
+        }
+
+        assert_eq!(pages.len(), 10);
+        assert!(pages.concat().into_iter().eq(0..1000));
+    }
+
+    #[test]
+    fn test_iter_from_borrowed_key() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for word in ["apple", "banana", "cherry", "date", "elderberry", "fig"] {
+            map.insert(word.to_string(), word.len());
+        }
+
+        let from_c: Vec<&str> = map.iter_from("c").map(|(k, _)| k.as_str()).collect();
+        assert_eq!(from_c, ["cherry", "date", "elderberry", "fig"]);
+        let after_date: Vec<&str> = map
+            .iter_from_exclusive("date")
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(after_date, ["elderberry", "fig"]);
+    }
+}
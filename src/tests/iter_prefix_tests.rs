@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod iter_prefix_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+
+    /// Builds a map over path-like keys, several levels deep at a small
+    /// branching factor, along with the sorted list of its keys
+    fn path_map(branching_factor: usize) -> (BPlusTreeMap<String, usize>, Vec<String>) {
+        let mut paths = Vec::new();
+        for a in ["a", "ab", "b", "docs", "src"] {
+            paths.push(a.to_string());
+            for b in ["b", "c", "lib", "main"] {
+                paths.push(format!("{}/{}", a, b));
+                for c in 0..4 {
+                    paths.push(format!("{}/{}/{}", a, b, c));
+                }
+            }
+        }
+
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for (i, path) in paths.iter().enumerate().rev() {
+            map.insert(path.clone(), i);
+        }
+        paths.sort();
+        (map, paths)
+    }
+
+    #[test]
+    fn test_iter_prefix_matches_a_filtered_scan() {
+        for branching_factor in 2..=6 {
+            let (map, paths) = path_map(branching_factor);
+            for prefix in [
+                "a",
+                "a/",
+                "a/b",
+                "a/b/",
+                "a/b/3",
+                "ab",
+                "ab/",
+                "b/lib",
+                "d",
+                "docs/main/",
+                "src/c/2",
+                "s",
+                "src/z",
+                "0",
+                "zzz",
+            ] {
+                let wanted: Vec<&String> = paths.iter().filter(|p| p.starts_with(prefix)).collect();
+                let actual: Vec<&String> = map.iter_prefix(prefix).map(|(k, _)| k).collect();
+                assert_eq!(
+                    actual, wanted,
+                    "prefix {:?}, branching factor {}",
+                    prefix, branching_factor
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_prefix_equal_to_a_key() {
+        let (map, _) = path_map(3);
+        let keys: Vec<&str> = map.iter_prefix("a/lib").map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["a/lib", "a/lib/0", "a/lib/1", "a/lib/2", "a/lib/3"]);
+        let keys: Vec<&str> = map
+            .iter_prefix("a/lib/2")
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(keys, ["a/lib/2"]);
+    }
+
+    #[test]
+    fn test_iter_prefix_longer_than_any_key() {
+        let (map, _) = path_map(4);
+        assert_eq!(map.iter_prefix("a/b/0/deeper/still").count(), 0);
+        assert_eq!(map.iter_prefix("src/main/3/").count(), 0);
+    }
+
+    #[test]
+    fn test_iter_prefix_empty_prefix_yields_everything() {
+        let (map, paths) = path_map(3);
+        assert!(map.iter_prefix("").map(|(k, _)| k).eq(paths.iter()));
+
+        let empty = BPlusTreeMap::<String, usize>::new();
+        assert_eq!(empty.iter_prefix("").count(), 0);
+        assert_eq!(empty.iter_prefix("a").count(), 0);
+    }
+
+    #[test]
+    fn test_iter_prefix_straddling_leaves() {
+        // With two keys per leaf, every run of more than two keys crosses
+        // leaf boundaries, and so do the separators between the runs
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for i in 0..300 {
+            map.insert(format!("{:03}", i), i);
+        }
+
+        for prefix in 0..30 {
+            let prefix = format!("{:02}", prefix);
+            let values: Vec<i32> = map.iter_prefix(&prefix).map(|(_, v)| *v).collect();
+            let first = prefix.parse::<i32>().unwrap() * 10;
+            assert_eq!(
+                values,
+                (first..first + 10).collect::<Vec<_>>(),
+                "{}",
+                prefix
+            );
+        }
+    }
+}
@@ -0,0 +1,730 @@
+#[cfg(test)]
+mod iter_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, LeafNode};
+    use crate::tests::counting_allocator::allocations_during;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    fn scattered_maps(
+        branching_factor: usize,
+        count: i32,
+    ) -> (BPlusTreeMap<i32, String>, BTreeMap<i32, String>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        for i in 0..count {
+            let key = (i * 7919) % count;
+            map.insert(key, key.to_string());
+            expected.insert(key, key.to_string());
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_iter_yields_entries_in_ascending_order() {
+        for branching_factor in 2..=8 {
+            let (mut map, mut expected) = scattered_maps(branching_factor, 500);
+            assert!(map.iter().eq(expected.iter()));
+
+            for i in (0..500).step_by(3) {
+                map.remove(&i);
+                expected.remove(&i);
+            }
+            assert!(map.iter().eq(expected.iter()));
+            assert!(map.iter().take(10).eq(expected.iter().take(10)));
+        }
+    }
+
+    #[test]
+    fn test_iter_on_empty_map() {
+        let map = BPlusTreeMap::<i32, i32>::new();
+        assert_eq!(map.iter().next(), None);
+
+        let mut map = BPlusTreeMap::new();
+        map.insert(1, 1);
+        map.remove(&1);
+        assert_eq!(map.iter().next(), None);
+    }
+
+    #[test]
+    fn test_iter_next_does_not_walk_the_whole_map() {
+        let (map, _) = scattered_maps(4, 20_000);
+
+        // Only the path down to the first leaf is held, so the first item
+        // costs a handful of allocations however large the map is
+        let mut first = None;
+        let allocations = allocations_during(|| first = map.iter().next());
+        assert_eq!(first, Some((&0, &"0".to_string())));
+        assert!(
+            allocations <= 5,
+            "iter().next() made {} allocations",
+            allocations
+        );
+
+        let allocations = allocations_during(|| assert_eq!(map.iter().take(10).count(), 10));
+        assert!(
+            allocations <= 5,
+            "iter().take(10) made {} allocations",
+            allocations
+        );
+    }
+
+    #[test]
+    fn test_into_iter_yields_entries_in_ascending_order() {
+        for branching_factor in 2..=8 {
+            let (mut map, mut expected) = scattered_maps(branching_factor, 300);
+            for i in (0..300).step_by(4) {
+                map.remove(&i);
+                expected.remove(&i);
+            }
+            assert!(map.into_iter().eq(expected.into_iter()));
+        }
+    }
+
+    #[test]
+    fn test_into_iter_moves_entries_without_cloning() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for id in (0..200).rev() {
+            map.insert(id, CountedValue(id));
+        }
+
+        let mut entries = Vec::new();
+        let clones = clones_during(|| entries = map.into_iter().collect());
+        assert_eq!(clones, 0);
+
+        assert_eq!(entries.len(), 200);
+        for (i, (key, value)) in entries.iter().enumerate() {
+            assert_eq!(*key, i as u64);
+            assert_eq!(value.0, i as u64);
+        }
+    }
+
+    #[test]
+    fn test_into_iter_dropped_part_way() {
+        let (map, _) = scattered_maps(3, 100);
+        let mut iter = map.into_iter();
+        assert_eq!(iter.next(), Some((0, "0".to_string())));
+        assert_eq!(iter.next(), Some((1, "1".to_string())));
+
+        // The entries not yet yielded are dropped along with the iterator
+        drop(iter);
+    }
+
+    #[test]
+    fn test_iter_mut_yields_entries_in_ascending_order() {
+        for branching_factor in 2..=8 {
+            let (mut map, mut expected) = scattered_maps(branching_factor, 300);
+            for i in (0..300).step_by(5) {
+                map.remove(&i);
+                expected.remove(&i);
+            }
+
+            for ((key, value), (expected_key, expected_value)) in
+                map.iter_mut().zip(expected.iter_mut())
+            {
+                assert_eq!(key, expected_key);
+                value.push('!');
+                expected_value.push('!');
+            }
+            assert_eq!(map.iter_mut().count(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_iter_mut_holds_references_from_several_leaves() {
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for i in 0..20 {
+            map.insert(i, i);
+        }
+
+        // References from different leaves can be held at the same time
+        let mut refs: Vec<(&i32, &mut i32)> = map.iter_mut().collect();
+        let (first, rest) = refs.split_first_mut().unwrap();
+        *first.1 += 100;
+        *rest.last_mut().unwrap().1 += 100;
+        assert_eq!(*first.0, 0);
+
+        assert_eq!(map.get(&0), Some(&100));
+        assert_eq!(map.get(&19), Some(&119));
+        assert_eq!(map.get(&10), Some(&10));
+    }
+
+    #[test]
+    fn test_iter_mut_next_does_not_walk_the_whole_map() {
+        let (mut map, _) = scattered_maps(4, 20_000);
+
+        let allocations = allocations_during(|| {
+            let (key, value) = map.iter_mut().next().unwrap();
+            assert_eq!(*key, 0);
+            value.push_str("_first");
+        });
+        assert!(
+            allocations <= 5,
+            "iter_mut().next() made {} allocations",
+            allocations
+        );
+        assert_eq!(map.get(&0), Some(&"0_first".to_string()));
+    }
+
+    #[test]
+    fn test_keys_and_values_in_ascending_key_order() {
+        for branching_factor in 2..=8 {
+            let (mut map, mut expected) = scattered_maps(branching_factor, 300);
+            for i in (0..300).step_by(6) {
+                map.remove(&i);
+                expected.remove(&i);
+            }
+            assert!(map.keys().eq(expected.keys()));
+            assert!(map.values().eq(expected.values()));
+        }
+
+        let empty = BPlusTreeMap::<i32, i32>::new();
+        assert_eq!(empty.keys().next(), None);
+        assert_eq!(empty.values().next(), None);
+    }
+
+    #[test]
+    fn test_keys_and_values_visit_only_the_leaves_they_need() {
+        let (map, _) = scattered_maps(4, 1000);
+
+        let mut keys = map.keys();
+        assert_eq!(keys.by_ref().take(1).collect::<Vec<_>>(), vec![&0]);
+        assert_eq!(keys.leaves_visited(), 1);
+
+        let mut values = map.values();
+        assert_eq!(values.next(), Some(&"0".to_string()));
+        assert_eq!(values.leaves_visited(), 1);
+
+        // Walking everything reaches each leaf exactly once
+        let leaf_count = map.leaf_count();
+        let mut keys = map.keys();
+        assert_eq!(keys.by_ref().count(), 1000);
+        assert_eq!(keys.leaves_visited(), leaf_count);
+        let mut values = map.values();
+        assert_eq!(values.by_ref().count(), 1000);
+        assert_eq!(values.leaves_visited(), leaf_count);
+    }
+
+    #[test]
+    fn test_keys_and_values_do_not_collect_the_map() {
+        let (map, _) = scattered_maps(4, 20_000);
+
+        let allocations = allocations_during(|| assert_eq!(map.keys().take(10).count(), 10));
+        assert!(
+            allocations <= 5,
+            "keys().take(10) made {} allocations",
+            allocations
+        );
+        let allocations = allocations_during(|| assert_eq!(map.values().take(10).count(), 10));
+        assert!(
+            allocations <= 5,
+            "values().take(10) made {} allocations",
+            allocations
+        );
+    }
+
+    /// Whether the `step`th call of an interleaving should take from the back.
+    /// Mixes runs from each end with strict alternation.
+    fn from_back(step: usize) -> bool {
+        (step * 2654435761) % 7 < 3
+    }
+
+    #[test]
+    fn test_iter_interleaving_next_and_next_back() {
+        for branching_factor in 2..=8 {
+            for count in [0, 1, 5, 200] {
+                let (map, expected) = scattered_maps(branching_factor, count);
+                let mut iter = map.iter();
+                let mut wanted = expected.iter();
+
+                for step in 0.. {
+                    let (got, want) = if from_back(step) {
+                        (iter.next_back(), wanted.next_back())
+                    } else {
+                        (iter.next(), wanted.next())
+                    };
+                    assert_eq!(
+                        got, want,
+                        "branching factor {}, count {}, step {}",
+                        branching_factor, count, step
+                    );
+                    if want.is_none() {
+                        break;
+                    }
+                }
+
+                // Both ends stay exhausted once they have met
+                assert_eq!(iter.next(), None);
+                assert_eq!(iter.next_back(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rev_yields_entries_in_descending_order() {
+        for branching_factor in 2..=8 {
+            let (map, expected) = scattered_maps(branching_factor, 300);
+
+            assert!(map.iter().rev().eq(expected.iter().rev()));
+            assert!(map.keys().rev().eq(expected.keys().rev()));
+            assert!(map.values().rev().eq(expected.values().rev()));
+            assert_eq!(map.keys().next_back(), expected.keys().next_back());
+            assert!(
+                map.clone()
+                    .into_iter()
+                    .rev()
+                    .eq(expected.clone().into_iter().rev())
+            );
+            assert!(
+                map.clone()
+                    .into_keys()
+                    .rev()
+                    .eq(expected.clone().into_keys().rev())
+            );
+            assert!(map.into_values().rev().eq(expected.into_values().rev()));
+        }
+    }
+
+    #[test]
+    fn test_keys_and_values_interleaving_ends() {
+        let (map, expected) = scattered_maps(3, 100);
+        let mut keys = map.keys();
+        let mut wanted_keys = expected.keys();
+        let mut values = map.values();
+        let mut wanted_values = expected.values();
+
+        for step in 0..=100 {
+            if from_back(step) {
+                assert_eq!(keys.next_back(), wanted_keys.next_back());
+                assert_eq!(values.next_back(), wanted_values.next_back());
+            } else {
+                assert_eq!(keys.next(), wanted_keys.next());
+                assert_eq!(values.next(), wanted_values.next());
+            }
+        }
+        assert_eq!(keys.next(), None);
+        assert_eq!(values.next_back(), None);
+    }
+
+    #[test]
+    fn test_into_iter_interleaving_ends() {
+        for branching_factor in 2..=8 {
+            let (map, expected) = scattered_maps(branching_factor, 200);
+            let mut iter = map.into_iter();
+            let mut wanted = expected.into_iter();
+
+            for step in 0..=200 {
+                if from_back(step) {
+                    assert_eq!(iter.next_back(), wanted.next_back(), "step {}", step);
+                } else {
+                    assert_eq!(iter.next(), wanted.next(), "step {}", step);
+                }
+            }
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+    }
+
+    #[test]
+    fn test_into_iter_dropped_after_taking_from_both_ends() {
+        let alive = Rc::new(());
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50 {
+            map.insert(i, (CountedValue(i), Rc::clone(&alive)));
+        }
+
+        let clones = clones_during(|| {
+            let mut iter = map.into_iter();
+            assert_eq!(iter.next().map(|(k, v)| (k, v.0.0)), Some((0, 0)));
+            assert_eq!(iter.next_back().map(|(k, v)| (k, v.0.0)), Some((49, 49)));
+        });
+
+        // Every value has been dropped, none of them cloned
+        assert_eq!(Rc::strong_count(&alive), 1);
+        assert_eq!(clones, 0);
+    }
+
+    #[test]
+    fn test_iter_mut_interleaving_ends() {
+        for branching_factor in 2..=8 {
+            let (mut map, mut expected) = scattered_maps(branching_factor, 150);
+            let mut iter = map.iter_mut();
+            let mut wanted = expected.iter_mut();
+
+            for step in 0..=150 {
+                let (got, want) = if from_back(step) {
+                    (iter.next_back(), wanted.next_back())
+                } else {
+                    (iter.next(), wanted.next())
+                };
+                match (got, want) {
+                    (Some((key, value)), Some((wanted_key, wanted_value))) => {
+                        assert_eq!(key, wanted_key);
+                        value.push_str(if step % 2 == 0 { "_even" } else { "_odd" });
+                        wanted_value.push_str(if step % 2 == 0 { "_even" } else { "_odd" });
+                    }
+                    (got, want) => assert!(got.is_none() && want.is_none(), "step {}", step),
+                }
+            }
+
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_iterators_report_the_map_length() {
+        for branching_factor in 2..=8 {
+            for count in [0, 1, 7, 300] {
+                let (mut map, _) = scattered_maps(branching_factor, count);
+                let len = map.len();
+
+                assert_eq!(map.iter().len(), len);
+                assert_eq!(map.iter().size_hint(), (len, Some(len)));
+                assert_eq!(map.keys().len(), len);
+                assert_eq!(map.values().len(), len);
+                assert_eq!(map.iter_mut().len(), len);
+                assert_eq!(map.values_mut().len(), len);
+                assert_eq!(map.clone().into_keys().len(), len);
+                assert_eq!(map.clone().into_values().len(), len);
+                assert_eq!(map.into_iter().len(), len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_len_shrinks_as_items_are_yielded() {
+        let (mut map, _) = scattered_maps(3, 100);
+
+        let mut iter = map.iter();
+        iter.by_ref().take(10).for_each(drop);
+        assert_eq!(iter.len(), 90);
+        iter.next_back();
+        iter.next_back();
+        assert_eq!(iter.size_hint(), (88, Some(88)));
+        assert_eq!(iter.by_ref().count(), 88);
+        assert_eq!(iter.len(), 0);
+
+        let mut keys = map.keys();
+        keys.nth(49);
+        assert_eq!(keys.len(), 50);
+        let mut values = map.values();
+        values.nth_back(9);
+        assert_eq!(values.len(), 90);
+
+        let mut iter_mut = map.iter_mut();
+        iter_mut.next();
+        iter_mut.next_back();
+        assert_eq!(iter_mut.len(), 98);
+
+        let mut values_mut = map.values_mut();
+        values_mut.by_ref().take(30).for_each(drop);
+        assert_eq!(values_mut.len(), 70);
+
+        let mut into_iter = map.into_iter();
+        into_iter.next_back();
+        assert_eq!(into_iter.len(), 99);
+    }
+
+    #[test]
+    fn test_collect_sizes_the_vec_up_front() {
+        let (map, _) = scattered_maps(4, 1000);
+
+        // Growing the Vec as it fills would leave it with spare capacity
+        let keys: Vec<&i32> = map.keys().collect();
+        assert_eq!(keys.capacity(), 1000);
+        let entries: Vec<(i32, String)> = map.into_iter().collect();
+        assert_eq!(entries.capacity(), 1000);
+    }
+
+    #[test]
+    fn test_iterators_stay_exhausted() {
+        let (mut map, _) = scattered_maps(3, 20);
+
+        let mut iter = map.iter();
+        assert_eq!(iter.by_ref().count(), 20);
+        for _ in 0..3 {
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+        }
+
+        let mut keys = map.keys();
+        keys.by_ref().for_each(drop);
+        assert_eq!(keys.next(), None);
+        assert_eq!(keys.next(), None);
+
+        let mut values = map.values();
+        values.by_ref().rev().for_each(drop);
+        assert_eq!(values.next(), None);
+        assert_eq!(values.next_back(), None);
+
+        let mut iter_mut = map.iter_mut();
+        iter_mut.by_ref().for_each(drop);
+        assert!(iter_mut.next().is_none());
+        assert!(iter_mut.next().is_none());
+
+        let mut values_mut = map.values_mut();
+        values_mut.by_ref().for_each(drop);
+        assert!(values_mut.next().is_none());
+        assert!(values_mut.next().is_none());
+
+        let mut into_iter = map.into_iter();
+        into_iter.by_ref().for_each(drop);
+        assert_eq!(into_iter.next(), None);
+        assert_eq!(into_iter.next_back(), None);
+        assert_eq!(into_iter.len(), 0);
+    }
+
+    #[test]
+    fn test_cloned_iter_continues_independently() {
+        let (map, expected) = scattered_maps(3, 100);
+
+        let mut iter = map.iter();
+        iter.by_ref().take(30).for_each(drop);
+        iter.next_back();
+        let mut copy = iter.clone();
+
+        let rest: Vec<(&i32, &String)> = expected.iter().skip(30).take(69).collect();
+        assert_eq!(copy.len(), 69);
+        assert_eq!(copy.next(), Some(rest[0]));
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), rest);
+        assert_eq!(copy.collect::<Vec<_>>(), rest[1..]);
+
+        let mut keys = map.keys();
+        keys.nth(10);
+        assert!(keys.clone().eq(keys.by_ref()));
+        let mut values = map.values();
+        values.nth_back(10);
+        assert!(values.clone().eq(values.by_ref()));
+    }
+
+    #[test]
+    fn test_borrowed_iterators_debug_the_remaining_entries() {
+        for branching_factor in [2, 3, 8] {
+            let (mut map, mut expected) = scattered_maps(branching_factor, 40);
+
+            let mut iter = map.iter();
+            let mut wanted = expected.iter();
+            assert_eq!(format!("{:?}", iter), format!("{:?}", wanted));
+            iter.nth(6);
+            wanted.nth(6);
+            iter.nth_back(11);
+            wanted.nth_back(11);
+            assert_eq!(format!("{:?}", iter), format!("{:?}", wanted));
+
+            let mut keys = map.keys();
+            let mut wanted_keys = expected.keys();
+            keys.next_back();
+            wanted_keys.next_back();
+            assert_eq!(format!("{:?}", keys), format!("{:?}", wanted_keys));
+            let mut values = map.values();
+            let mut wanted_values = expected.values();
+            values.next();
+            wanted_values.next();
+            assert_eq!(format!("{:?}", values), format!("{:?}", wanted_values));
+
+            let mut iter_mut = map.iter_mut();
+            let mut wanted_mut = expected.iter_mut();
+            assert_eq!(format!("{:?}", iter_mut), format!("{:?}", wanted_mut));
+            for step in 0..40 {
+                if step % 3 == 0 {
+                    iter_mut.next_back();
+                    wanted_mut.next_back();
+                } else {
+                    iter_mut.next();
+                    wanted_mut.next();
+                }
+                assert_eq!(
+                    format!("{:?}", iter_mut),
+                    format!("{:?}", wanted_mut),
+                    "branching factor {}, step {}",
+                    branching_factor,
+                    step
+                );
+            }
+
+            let mut values_mut = map.values_mut();
+            let mut wanted_values_mut = expected.values_mut();
+            values_mut.nth(4);
+            wanted_values_mut.nth(4);
+            assert_eq!(
+                format!("{:?}", values_mut),
+                format!("{:?}", wanted_values_mut)
+            );
+        }
+    }
+
+    #[test]
+    fn test_keys_in_a_derived_debug_struct() {
+        #[derive(Debug)]
+        struct Cursor<'a> {
+            keys: crate::bplus_tree_map::Keys<'a, i32, String>,
+        }
+
+        let (map, _) = scattered_maps(4, 3);
+        let cursor = Cursor { keys: map.keys() };
+        assert_eq!(format!("{:?}", cursor), "Cursor { keys: [0, 1, 2] }");
+        assert_eq!(cursor.keys.len(), 3);
+    }
+
+    /// A value that can be neither cloned nor compared nor printed
+    struct NoClone(i32);
+
+    // These only need to compile: iterating a map must not ask anything of
+    // its keys or values
+    #[allow(dead_code)]
+    fn iterate_non_clone_values(map: &mut BPlusTreeMap<i32, NoClone>) -> i32 {
+        let mut total: i32 = map.values().map(|value| value.0).sum();
+        total += map.iter().map(|(_, value)| value.0).sum::<i32>();
+        for (_, value) in map.iter_mut() {
+            value.0 += 1;
+        }
+        total + map.keys().sum::<i32>()
+    }
+
+    #[allow(dead_code)]
+    fn iterate_non_clone_keys(map: &BPlusTreeMap<NoClone, i32>) -> Vec<i32> {
+        map.keys()
+            .map(|key| key.0)
+            .chain(map.values().copied())
+            .collect()
+    }
+
+    /// Sums the values of any map, asking nothing of its keys
+    fn sum_values<K, V: Copy + Into<i64>>(map: &BPlusTreeMap<K, V>) -> i64 {
+        map.values().map(|&value| value.into()).sum()
+    }
+
+    /// Doubles the values of any map in place
+    fn double_values<K, V: std::ops::MulAssign + From<u8>>(map: &mut BPlusTreeMap<K, V>) {
+        for (_, value) in map.iter_mut() {
+            *value *= V::from(2);
+        }
+    }
+
+    /// Counts keys and entries of any map without any bounds at all
+    fn count_everything<K, V>(map: &BPlusTreeMap<K, V>) -> (usize, usize, usize) {
+        (map.keys().count(), map.values().count(), map.iter().count())
+    }
+
+    #[test]
+    fn test_iterating_needs_no_bounds_on_keys_or_values() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50i32 {
+            map.insert(i, i);
+        }
+
+        assert_eq!(sum_values(&map), (0..50).sum::<i64>());
+        double_values(&mut map);
+        assert_eq!(sum_values(&map), 2 * (0..50).sum::<i64>());
+        assert_eq!(count_everything(&map), (50, 50, 50));
+        assert_eq!(map.into_values().next_back(), Some(98));
+    }
+
+    #[test]
+    fn test_iter_mut_does_not_clone_keys() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..10_000 {
+            let id = (i * 7919) % 10_000;
+            map.insert(CountedValue(id), id);
+        }
+
+        let clones = clones_during(|| {
+            for (key, value) in map.iter_mut() {
+                *value += key.0;
+            }
+        });
+        assert_eq!(clones, 0);
+
+        let mut entries = Vec::new();
+        let clones = clones_during(|| entries = map.collect_mut_refs());
+        assert_eq!(clones, 0);
+        assert_eq!(entries.len(), 10_000);
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            assert_eq!(key.0, i as u64);
+            assert_eq!(*value, 2 * i as u64);
+        }
+    }
+
+    /// Checks that `values_mut` and `iter_mut` hand out the values in the
+    /// order `keys` yields the keys, given values that spell out their keys
+    fn assert_mutable_iteration_in_key_order(map: &mut BPlusTreeMap<i32, String>) {
+        let keys: Vec<String> = map.keys().map(|key| key.to_string()).collect();
+
+        let values: Vec<String> = map.values_mut().map(|value| value.clone()).collect();
+        assert_eq!(values, keys);
+        let values: Vec<String> = map.values_mut().rev().map(|value| value.clone()).collect();
+        assert!(values.iter().eq(keys.iter().rev()));
+
+        let entries: Vec<(i32, String)> = map
+            .iter_mut()
+            .map(|(key, value)| (*key, value.clone()))
+            .collect();
+        assert!(
+            entries
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .eq(keys.iter().cloned())
+        );
+        assert!(entries.iter().map(|(_, value)| value).eq(keys.iter()));
+    }
+
+    #[test]
+    fn test_mutable_iteration_in_key_order_after_heavy_removal() {
+        for branching_factor in 2..=5 {
+            let (mut map, _) = scattered_maps(branching_factor, 500);
+            for key in (0..500).filter(|key| key % 3 != 0 || key % 7 == 0) {
+                map.remove(&key);
+            }
+            assert_mutable_iteration_in_key_order(&mut map);
+
+            for key in (0..500).rev().step_by(4) {
+                map.insert(key, key.to_string());
+            }
+            assert_mutable_iteration_in_key_order(&mut map);
+        }
+    }
+
+    #[test]
+    fn test_mutable_iteration_in_key_order_from_branch_root() {
+        let left_leaf = LeafNode::new(
+            vec![1, 2, 3],
+            vec!["1".to_string(), "2".to_string(), "3".to_string()],
+        );
+        let right_leaf = LeafNode::new(vec![5, 8], vec!["5".to_string(), "8".to_string()]);
+        let mut map = BPlusTreeMap::with_branch_root(2, left_leaf, right_leaf, Some(5));
+        assert_mutable_iteration_in_key_order(&mut map);
+
+        for key in [4, 0, 9, 7, 6] {
+            map.insert(key, key.to_string());
+        }
+        map.remove(&2);
+        assert_mutable_iteration_in_key_order(&mut map);
+    }
+
+    #[test]
+    fn test_values_mut_from_both_ends() {
+        let (mut map, mut expected) = scattered_maps(3, 100);
+        let mut values = map.values_mut();
+        let mut wanted = expected.values_mut();
+        assert_eq!(values.len(), 100);
+
+        for step in 0..=100 {
+            let (got, want) = if from_back(step) {
+                (values.next_back(), wanted.next_back())
+            } else {
+                (values.next(), wanted.next())
+            };
+            match (got, want) {
+                (Some(value), Some(wanted_value)) => {
+                    assert_eq!(value, wanted_value);
+                    value.push('!');
+                    wanted_value.push('!');
+                }
+                (got, want) => assert!(got.is_none() && want.is_none(), "step {}", step),
+            }
+            assert_eq!(format!("{:?}", values), format!("{:?}", wanted));
+        }
+        assert!(map.iter().eq(expected.iter()));
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod key_search_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, LeafNode, Node};
+    use crate::key_search::{branchless_search, search_keys};
+    use crate::separator::SeparatorKey;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+
+    /// Checks both searches against binary search for every probe
+    fn assert_searches_agree<K: Ord + Debug>(keys: &[K], probes: impl Iterator<Item = K>) {
+        for probe in probes {
+            let expected = keys.binary_search(&probe);
+            assert_eq!(
+                search_keys(keys, &probe),
+                expected,
+                "{:?} in {:?}",
+                probe,
+                keys
+            );
+            assert_eq!(
+                branchless_search(keys, &probe),
+                expected,
+                "{:?} in {:?}",
+                probe,
+                keys
+            );
+        }
+    }
+
+    #[test]
+    fn test_integer_searches_match_binary_search_across_widths() {
+        for width in 4..=256u64 {
+            // Keys three apart, so probes land on keys and on both sides
+            let keys: Vec<u64> = (0..width).map(|i| 10 + 3 * i).collect();
+            assert_searches_agree(&keys, 0..3 * width + 20);
+            let keys: Vec<u32> = (0..width as u32).map(|i| 10 + 3 * i).collect();
+            assert_searches_agree(&keys, 0..3 * width as u32 + 20);
+            let keys: Vec<i64> = (0..width as i64).map(|i| 3 * i - 200).collect();
+            assert_searches_agree(&keys, -210..3 * width as i64 - 190);
+            // Keys at the very ends of the type's range
+            let keys: Vec<u64> = (0..width).map(|i| u64::MAX - 2 * (width - 1 - i)).collect();
+            assert_searches_agree(&keys, (0..2 * width + 2).map(|i| u64::MAX - i));
+        }
+        // A node of every u8
+        let keys: Vec<u8> = (0..=255).collect();
+        assert_searches_agree(&keys, 0..=255);
+        assert_searches_agree(&keys[1..255], 0..=255);
+    }
+
+    #[test]
+    fn test_ties_go_left_in_leaves_and_right_in_branches() {
+        for width in 4..=256u64 {
+            let keys: Vec<u64> = (0..width).map(|i| 2 * i + 1).collect();
+            let children =
+                (0..=width).map(|_| Node::Leaf(LeafNode::<u64, ()>::new(Vec::new(), Vec::new())));
+            let separators: Vec<_> = keys.iter().copied().map(SeparatorKey::new).collect();
+            let branch = BranchNode::new(separators, children);
+            for probe in 0..2 * width + 2 {
+                // A key equal to a separator belongs to the child on its
+                // right, while a leaf finds the key itself
+                assert_eq!(
+                    branch.child_index_for(&probe),
+                    keys.partition_point(|k| *k <= probe),
+                    "{} among {} separators",
+                    probe,
+                    width
+                );
+                assert_eq!(
+                    search_keys(&keys, &probe),
+                    match keys.partition_point(|k| *k < probe) {
+                        idx if keys.get(idx) == Some(&probe) => Ok(idx),
+                        idx => Err(idx),
+                    }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_keys_search_alike() {
+        for width in [4, 8, 9, 31, 32, 33, 100, 256] {
+            let keys: Vec<String> = (0..width).map(|i| format!("key{:04}", 2 * i)).collect();
+            let probes = (0..2 * width + 2).map(|i| format!("key{:04}", i));
+            assert_searches_agree(&keys, probes);
+            // Looked up by `str` through `Borrow`
+            for i in 0..2 * width + 2 {
+                let probe = format!("key{:04}", i);
+                assert_eq!(
+                    search_keys(&keys, probe.as_str()),
+                    keys.binary_search(&probe)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_keys_keep_logarithmic_search() {
+        let keys: Vec<CountedKey> = (0..256).map(|i| CountedKey(2 * i)).collect();
+        for probe in 0..512 {
+            let comparisons = comparisons_during(|| {
+                let _ = search_keys(&keys, &CountedKey(probe));
+            });
+            assert!(
+                comparisons <= 9,
+                "search for {} took {}",
+                probe,
+                comparisons
+            );
+        }
+    }
+
+    #[test]
+    fn test_maps_of_integer_keys_at_wide_branching_factors() {
+        for branching_factor in [4, 7, 8, 9, 16, 31, 32, 33, 64, 65, 128, 256] {
+            let mut map = BPlusTreeMap::with_branchless_search(branching_factor);
+            let mut expected = BTreeMap::new();
+            let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+            for step in 0..5000u32 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let key = (state % 3000) as u32;
+                if step % 4 == 3 {
+                    assert_eq!(map.remove(&key), expected.remove(&key));
+                } else {
+                    assert_eq!(map.insert(key, step), expected.insert(key, step));
+                }
+                assert_eq!(map.get(&(key ^ 1)), expected.get(&(key ^ 1)));
+            }
+            for key in 0..3001 {
+                assert_eq!(map.get(&key), expected.get(&key), "bf {}", branching_factor);
+            }
+            assert!(map.iter().eq(expected.iter()));
+            assert_eq!(map.check_invariants(), Ok(()));
+        }
+    }
+}
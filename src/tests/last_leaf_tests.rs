@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod last_leaf_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_lookups_in_the_last_leaf_skip_the_descent() {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..10_000 {
+            map.insert(CountedKey(i), i);
+        }
+        let leaf: Vec<u64> = map
+            .walk_leaves()
+            .nth(600)
+            .unwrap()
+            .keys
+            .iter()
+            .map(|key| key.0)
+            .collect();
+
+        let first = comparisons_during(|| {
+            assert_eq!(map.get(&CountedKey(leaf[0])), Some(&leaf[0]));
+        });
+        let nearby = comparisons_during(|| {
+            for key in &leaf[1..] {
+                assert_eq!(map.get(&CountedKey(*key)), Some(key));
+                assert!(map.contains_key(&CountedKey(*key)));
+            }
+        });
+        let per_lookup = nearby / (2 * (leaf.len() - 1));
+        assert!(
+            per_lookup < first,
+            "{} after a descent of {}",
+            per_lookup,
+            first
+        );
+
+        // A key outside the leaf descends from the root again
+        let far = comparisons_during(|| {
+            assert_eq!(map.get(&CountedKey(5)), Some(&5));
+        });
+        assert!(far > per_lookup);
+    }
+
+    #[test]
+    fn test_lookups_stay_correct_between_changes() {
+        for branching_factor in [3, 4, 8, 16] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+            let mut cluster = 0;
+            for step in 0..20_000u64 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                // Keys cluster around a point that moves now and then
+                if state.is_multiple_of(50) {
+                    cluster = (state >> 8) % 1000;
+                }
+                let key = cluster + (state >> 16) % 20;
+                match (state >> 32) % 8 {
+                    0 | 1 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                    2 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                    3 => {
+                        let found = map.get_mut(&key).map(|value| {
+                            *value += 1;
+                            *value
+                        });
+                        let wanted = expected.get_mut(&key).map(|value| {
+                            *value += 1;
+                            *value
+                        });
+                        assert_eq!(found, wanted);
+                    }
+                    4 => assert_eq!(map.contains_key(&key), expected.contains_key(&key)),
+                    5 if step.is_multiple_of(997) => {
+                        map.clear();
+                        expected.clear();
+                    }
+                    _ => assert_eq!(map.get(&key), expected.get(&key)),
+                }
+            }
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_lookup_after_the_last_leaf_is_split() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..4 {
+            map.insert(i * 10, i);
+        }
+        assert_eq!(map.get(&10), Some(&1));
+
+        // The leaf just looked up splits, moving some of its keys away
+        map.insert(15, 15);
+        map.insert(5, 5);
+        for (key, value) in [(0, 0), (5, 5), (10, 1), (15, 15), (20, 2), (30, 3)] {
+            assert_eq!(map.get(&key), Some(&value));
+        }
+        assert_eq!(map.get(&25), None);
+    }
+
+    #[test]
+    fn test_lookup_after_the_last_leaf_is_emptied() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.get(&50), Some(&50));
+        for i in 40..60 {
+            map.remove(&i);
+        }
+        for i in 40..60 {
+            assert_eq!(map.get(&i), None);
+            assert_eq!(map.get_mut(&i), None);
+        }
+        assert_eq!(map.get(&39), Some(&39));
+        assert_eq!(map.get(&60), Some(&60));
+
+        map.clear();
+        assert_eq!(map.get(&39), None);
+        map.insert(39, 1);
+        assert_eq!(map.get(&39), Some(&1));
+    }
+}
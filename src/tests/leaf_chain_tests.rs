@@ -0,0 +1,244 @@
+// These tests follow the links between leaves through every kind of change
+// to the tree, so they are sized to run under Miri as well:
+// `cargo +nightly miri test leaf_chain`.
+#[cfg(test)]
+mod leaf_chain_tests {
+    use crate::bplus_tree_map::{
+        BPlusTreeMap, BranchNode, LeafNode, Node, NodeBox, NodeVisitorMut,
+    };
+    use std::collections::{BTreeMap, HashSet};
+
+    /// Number of operations per workload; Miri runs these tests far slower
+    const STEPS: u64 = if cfg!(miri) { 150 } else { 3000 };
+
+    /// The range keys are drawn from
+    const KEYS: u64 = if cfg!(miri) { 60 } else { 500 };
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn addresses<'a>(leaves: impl IntoIterator<Item = &'a LeafNode<u64, u64>>) -> Vec<usize> {
+        leaves
+            .into_iter()
+            .map(|leaf| leaf as *const LeafNode<u64, u64> as usize)
+            .collect()
+    }
+
+    /// Checks that the links lead through every leaf of `map` once, in key
+    /// order, from either end, and that the map holds `expected`
+    fn assert_chain(map: &BPlusTreeMap<u64, u64>, expected: &BTreeMap<u64, u64>, context: &str) {
+        assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+
+        let linked: Vec<_> = map.linked_leaves().collect();
+        assert_eq!(
+            addresses(linked.iter().copied()),
+            addresses(map.leaves()),
+            "{}",
+            context
+        );
+        let unique: HashSet<usize> = addresses(linked.iter().copied()).into_iter().collect();
+        assert_eq!(unique.len(), linked.len(), "{}", context);
+        let keys = linked.iter().flat_map(|leaf| leaf.keys.iter());
+        assert!(keys.eq(expected.keys()), "{}", context);
+
+        let mut backward = addresses(map.linked_leaves().rev());
+        backward.reverse();
+        assert_eq!(backward, addresses(linked), "{}", context);
+    }
+
+    #[test]
+    fn test_chain_stays_whole_through_inserts_and_removes() {
+        for branching_factor in [2, 3, 4, 5, 8, 16] {
+            for seed in [1, 7] {
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut expected = BTreeMap::new();
+                let mut rng = Rng(0x9E37_79B9_7F4A_7C15 ^ seed);
+                for step in 0..STEPS {
+                    let key = rng.next() % KEYS;
+                    match rng.next() % 8 {
+                        0..=3 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                        4 | 5 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                        6 => assert_eq!(map.pop_first(), expected.pop_first()),
+                        _ => assert_eq!(map.pop_last(), expected.pop_last()),
+                    }
+                    let context = format!("bf {} seed {} step {}", branching_factor, seed, step);
+                    assert_eq!(map.check_links(), Ok(()), "{}", context);
+                    if step % 50 == 0 {
+                        assert_chain(&map, &expected, &context);
+                    }
+                }
+                assert_chain(&map, &expected, "end");
+            }
+        }
+    }
+
+    #[test]
+    fn test_chain_stays_whole_while_emptying_in_order() {
+        for branching_factor in [2, 3, 4] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for key in 0..KEYS {
+                map.insert(key, key);
+                expected.insert(key, key);
+            }
+            // From the front, the back, and the middle outwards
+            for key in (0..KEYS).step_by(3).chain((0..KEYS).rev()) {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+                assert_chain(
+                    &map,
+                    &expected,
+                    &format!("bf {} key {}", branching_factor, key),
+                );
+            }
+            assert!(map.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_chain_stays_whole_through_bulk_changes() {
+        for branching_factor in [2, 3, 4, 8] {
+            let mut rng = Rng(0x2545_F491_4F6C_DD1D ^ branching_factor as u64);
+            for round in 0..if cfg!(miri) { 2 } else { 20 } {
+                let context = format!("bf {} round {}", branching_factor, round);
+                let mut expected: BTreeMap<u64, u64> = (0..KEYS)
+                    .filter(|_| !rng.next().is_multiple_of(4))
+                    .map(|key| (key, key))
+                    .collect();
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                map.extend(expected.iter().map(|(&key, &value)| (key, value)));
+
+                let start = rng.next() % KEYS;
+                let end = start + rng.next() % (KEYS - start + 1);
+                let removed = map.remove_range(start..end);
+                assert_eq!(removed, expected.range(start..end).count());
+                expected.retain(|key, _| !(start..end).contains(key));
+                assert_chain(&map, &expected, &format!("{} remove_range", context));
+
+                let at = rng.next() % KEYS;
+                let mut right = map.split_off(&at);
+                let mut expected_right = expected.split_off(&at);
+                assert_chain(&map, &expected, &format!("{} split_off left", context));
+                assert_chain(
+                    &right,
+                    &expected_right,
+                    &format!("{} split_off right", context),
+                );
+
+                // Graft the halves back together, from either side
+                if round % 2 == 0 {
+                    map.append(&mut right);
+                    expected.append(&mut expected_right);
+                } else {
+                    right.append(&mut map);
+                    expected_right.append(&mut expected);
+                    std::mem::swap(&mut map, &mut right);
+                    std::mem::swap(&mut expected, &mut expected_right);
+                }
+                assert_chain(&map, &expected, &format!("{} append", context));
+
+                let modulus = 2 + rng.next() % 5;
+                map.retain(|key, _| key % modulus != 0);
+                expected.retain(|key, _| key % modulus != 0);
+                assert_chain(&map, &expected, &format!("{} retain", context));
+
+                let extracted: Vec<_> = map.extract_if(|key, _| key % 7 == 1).collect();
+                assert!(
+                    extracted
+                        .iter()
+                        .all(|(key, _)| expected.remove(key).is_some())
+                );
+                assert_chain(&map, &expected, &format!("{} extract_if", context));
+
+                let copy = map.clone();
+                let mut into = BPlusTreeMap::with_branching_factor(branching_factor);
+                into.extend((0..KEYS / 2).map(|key| (key, 0)));
+                into.clone_from(&map);
+                map.compact();
+                assert_chain(&map, &expected, &format!("{} compact", context));
+                drop(map);
+                assert_chain(&copy, &expected, &format!("{} clone", context));
+                assert_chain(&into, &expected, &format!("{} clone_from", context));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ranges_follow_the_chain_between_their_ends() {
+        for branching_factor in [2, 3, 5] {
+            let mut rng = Rng(0xDEAD_BEEF ^ branching_factor as u64);
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for step in 0..STEPS {
+                let key = rng.next() % KEYS;
+                if rng.next().is_multiple_of(3) {
+                    map.remove(&key);
+                    expected.remove(&key);
+                } else {
+                    map.insert(key, step);
+                    expected.insert(key, step);
+                }
+            }
+            for _ in 0..if cfg!(miri) { 20 } else { 300 } {
+                let start = rng.next() % (KEYS + 2);
+                let end = start + rng.next() % (KEYS + 2 - start);
+                assert!(map.range(start..end).eq(expected.range(start..end)));
+                assert!(
+                    map.range(start..=end)
+                        .rev()
+                        .eq(expected.range(start..=end).rev())
+                );
+
+                // Alternate ends until they meet
+                let mut range = map.range(start..end);
+                let mut expected_range = expected.range(start..end);
+                loop {
+                    let front = range.next();
+                    assert_eq!(front, expected_range.next());
+                    let back = range.next_back();
+                    assert_eq!(back, expected_range.next_back());
+                    if front.is_none() || back.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves every leaf into a new box, freeing the boxes the links lead to
+    struct Rebox;
+
+    impl NodeVisitorMut<u64, u64> for Rebox {
+        type Result = ();
+
+        fn visit_leaf(&mut self, _leaf: &mut LeafNode<u64, u64>) {}
+
+        fn visit_branch(&mut self, branch: &mut BranchNode<u64, u64>) {
+            for child in &mut branch.children {
+                if matches!(**child, Node::Leaf(_)) {
+                    *child = NodeBox::new((**child).clone());
+                }
+            }
+        }
+
+        fn result(self) {}
+    }
+
+    #[test]
+    fn test_chain_is_relinked_after_a_mutable_visit() {
+        for branching_factor in [2, 3, 8] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            map.extend((0..KEYS).map(|key| (key, key)));
+            let expected: BTreeMap<u64, u64> = (0..KEYS).map(|key| (key, key)).collect();
+            map.accept_visitor_mut(&mut Rebox);
+            assert_chain(&map, &expected, &format!("bf {}", branching_factor));
+        }
+    }
+}
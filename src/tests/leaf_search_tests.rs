@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod leaf_search_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+
+    /// A single leaf of 128 keys: 0, 2, 4, ... 254
+    fn wide_leaf() -> BPlusTreeMap<CountedKey, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(128);
+        for i in 0..128 {
+            map.insert(CountedKey(i * 2), i);
+        }
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+        map
+    }
+
+    #[test]
+    fn test_wide_leaf_lookups() {
+        let mut map = wide_leaf();
+        for (key, value) in [(0, 0), (128, 64), (254, 127)] {
+            assert_eq!(map.get(&CountedKey(key)), Some(&value));
+            assert!(map.contains_key(&CountedKey(key)));
+            assert_eq!(map.get_key(&CountedKey(key)), Some(&CountedKey(key)));
+            assert_eq!(map.get_mut(&CountedKey(key)), Some(&mut { value }));
+        }
+        for missing in [1, 127, 253, 255, 1000] {
+            assert_eq!(map.get(&CountedKey(missing)), None);
+            assert!(!map.contains_key(&CountedKey(missing)));
+            assert_eq!(map.get_mut(&CountedKey(missing)), None);
+        }
+    }
+
+    #[test]
+    fn test_wide_leaf_removals() {
+        let mut map = wide_leaf();
+        assert_eq!(map.remove(&CountedKey(0)), Some(0));
+        assert_eq!(map.remove(&CountedKey(128)), Some(64));
+        assert_eq!(map.remove(&CountedKey(254)), Some(127));
+        assert_eq!(map.remove(&CountedKey(129)), None);
+        assert_eq!(map.remove(&CountedKey(0)), None);
+        assert_eq!(map.len(), 125);
+        assert!(
+            map.keys()
+                .map(|key| key.0)
+                .eq((1..127).filter(|i| *i != 64).map(|i| i * 2))
+        );
+    }
+
+    #[test]
+    fn test_wide_leaf_search_is_logarithmic() {
+        let mut map = wide_leaf();
+        // A binary search of 128 keys takes at most 8 comparisons; the
+        // check of the last leaf looked up takes 2 more
+        for key in 0..256 {
+            let comparisons = comparisons_during(|| {
+                map.get(&CountedKey(key));
+            });
+            assert!(comparisons <= 10, "get({}) took {}", key, comparisons);
+        }
+        for key in (0..256).step_by(3) {
+            let comparisons = comparisons_during(|| {
+                map.remove(&CountedKey(key));
+            });
+            assert!(comparisons <= 8, "remove({}) took {}", key, comparisons);
+        }
+    }
+}
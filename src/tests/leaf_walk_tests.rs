@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod leaf_walk_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, LeafNode};
+    use std::collections::{BTreeMap, HashSet};
+
+    /// A map built by a scattered run of inserts and removes, along with
+    /// the entries it should hold
+    fn churned_map(
+        branching_factor: usize,
+        seed: u64,
+    ) -> (BPlusTreeMap<u64, u64>, BTreeMap<u64, u64>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        let mut state = seed | 1;
+        for i in 0..3000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let key = state % 500;
+            if state.is_multiple_of(3) {
+                map.remove(&key);
+                expected.remove(&key);
+            } else {
+                map.insert(key, i);
+                expected.insert(key, i);
+            }
+        }
+        (map, expected)
+    }
+
+    fn addresses<'a>(leaves: impl IntoIterator<Item = &'a LeafNode<u64, u64>>) -> Vec<usize> {
+        leaves
+            .into_iter()
+            .map(|leaf| leaf as *const LeafNode<u64, u64> as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_walk_visits_every_leaf_once_in_key_order() {
+        for branching_factor in [4, 5, 8, 16] {
+            for seed in 1..=5 {
+                let (map, expected) = churned_map(branching_factor, seed);
+                let walked: Vec<_> = map.walk_leaves().collect();
+
+                assert_eq!(addresses(walked.iter().copied()), addresses(map.leaves()));
+                let unique: HashSet<usize> =
+                    addresses(walked.iter().copied()).into_iter().collect();
+                assert_eq!(unique.len(), walked.len());
+
+                let keys: Vec<u64> = walked
+                    .iter()
+                    .flat_map(|leaf| leaf.keys.iter().copied())
+                    .collect();
+                assert!(
+                    keys.iter().eq(expected.keys()),
+                    "bf {} seed {}",
+                    branching_factor,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk_from_both_ends_meets_without_overlap() {
+        for branching_factor in [4, 7, 16] {
+            let (map, _) = churned_map(branching_factor, 42);
+            let forward = addresses(map.walk_leaves());
+
+            let mut backward = addresses(map.walk_leaves().rev());
+            backward.reverse();
+            assert_eq!(backward, forward);
+
+            // Alternate ends until they meet
+            let mut walk = map.walk_leaves();
+            let mut front = Vec::new();
+            let mut back = Vec::new();
+            while let Some(leaf) = walk.next() {
+                front.push(leaf);
+                match walk.next_back() {
+                    Some(leaf) => back.push(leaf),
+                    None => break,
+                }
+            }
+            back.reverse();
+            front.extend(back);
+            assert_eq!(addresses(front), forward);
+        }
+    }
+}
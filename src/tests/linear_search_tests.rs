@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod linear_search_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, LeafNode, Node};
+    use crate::key_search::search_keys_with;
+    use crate::separator::SeparatorKey;
+    use crate::tests::counting_key::{CountedKey, comparisons_during};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_searches_agree_on_either_side_of_the_threshold() {
+        for len in 0..20 {
+            let keys: Vec<u64> = (0..len as u64).map(|i| 2 * i + 1).collect();
+            let names: Vec<String> = keys.iter().map(|key| format!("{:03}", key)).collect();
+            for threshold in [0, 1, len, len + 1, 100] {
+                for probe in 0..2 * len as u64 + 2 {
+                    assert_eq!(
+                        search_keys_with(&keys, &probe, threshold),
+                        keys.binary_search(&probe),
+                        "{} in {} keys below {}",
+                        probe,
+                        len,
+                        threshold
+                    );
+                    let name = format!("{:03}", probe);
+                    assert_eq!(
+                        search_keys_with(&names, name.as_str(), threshold),
+                        names.binary_search(&name)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_nodes_below_the_threshold_are_scanned() {
+        let keys: Vec<CountedKey> = (0..64).map(CountedKey).collect();
+        // A scan for the last key compares it with every key in turn
+        let scan = |threshold| {
+            comparisons_during(|| {
+                assert_eq!(search_keys_with(&keys, &CountedKey(63), threshold), Ok(63));
+            })
+        };
+        assert_eq!(scan(65), 64);
+        assert_eq!(scan(100), 64);
+        // A node exactly as wide as the threshold is binary searched
+        for threshold in [0, 1, 64] {
+            assert!(scan(threshold) <= 7, "threshold {}", threshold);
+        }
+    }
+
+    #[test]
+    fn test_branches_route_alike_at_any_threshold() {
+        let keys = [10, 20, 30].map(SeparatorKey::new);
+        let children = (0..4).map(|_| Node::Leaf(LeafNode::<i32, ()>::new(Vec::new(), Vec::new())));
+        let branch = BranchNode::new(keys.to_vec(), children);
+        for threshold in [0, 1, 3, 4] {
+            for (key, child) in [(5, 0), (10, 1), (15, 1), (20, 2), (30, 3), (35, 3)] {
+                assert_eq!(branch.child_index_with(&key, threshold), child);
+            }
+        }
+    }
+
+    #[test]
+    fn test_maps_match_btree_map_at_any_threshold() {
+        for branching_factor in [4, 5, 16] {
+            for threshold in [0, 1, branching_factor, branching_factor + 1, usize::MAX] {
+                let mut map =
+                    BPlusTreeMap::with_linear_search_threshold(branching_factor, threshold);
+                let mut expected = BTreeMap::new();
+                let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+                for step in 0..2000u64 {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let key = state % 400;
+                    match step % 7 {
+                        0..=2 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                        3 => {
+                            *map.entry(key).or_insert(0) += 1;
+                            *expected.entry(key).or_insert(0) += 1;
+                        }
+                        4 => {
+                            if let Some(value) = map.get_mut(&key) {
+                                *value += 1;
+                            }
+                            if let Some(value) = expected.get_mut(&key) {
+                                *value += 1;
+                            }
+                        }
+                        _ => assert_eq!(map.remove(&key), expected.remove(&key)),
+                    }
+                    assert_eq!(map.get(&(key ^ 1)), expected.get(&(key ^ 1)));
+                }
+                for key in 0..401 {
+                    assert_eq!(map.get(&key), expected.get(&key));
+                    assert_eq!(
+                        map.get_key(&key),
+                        expected.get_key_value(&key).map(|(k, _)| k)
+                    );
+                }
+                if let Some([a, b]) = map.get_many_mut([&1, &2]) {
+                    std::mem::swap(a, b);
+                    let (a, b) = (expected[&1], expected[&2]);
+                    expected.insert(1, b);
+                    expected.insert(2, a);
+                }
+                assert!(map.iter().eq(expected.iter()));
+                assert_eq!(map.check_invariants(), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_off_and_clones_keep_the_threshold() {
+        let mut map = BPlusTreeMap::with_linear_search_threshold(8, usize::MAX);
+        for i in 0..500 {
+            map.insert(CountedKey(i), i);
+        }
+        let upper = map.split_off(&CountedKey(250));
+        let copy = upper.clone();
+        let mut target = BPlusTreeMap::with_branching_factor(8);
+        target.clone_from(&upper);
+        // Every node is scanned, so finding the last key compares it with
+        // more keys than binary searching would
+        for map in [&upper, &copy, &target] {
+            let comparisons = comparisons_during(|| {
+                assert_eq!(map.get(&CountedKey(499)), Some(&499));
+            });
+            assert!(comparisons > 8, "{} comparisons", comparisons);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod macro_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::bptreemap;
+
+    #[test]
+    fn test_bptreemap_with_entries() {
+        let map = bptreemap! {1 => "one", 2 => "two", 3 => "three"};
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Some(&"one"));
+        assert_eq!(map.get(&3), Some(&"three"));
+        assert!(map.iter().map(|(k, _)| *k).eq(1..=3));
+    }
+
+    #[test]
+    fn test_bptreemap_with_trailing_comma() {
+        let map = bptreemap! {
+            "a" => 1,
+            "b" => 2,
+        };
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["b"], 2);
+
+        let map = bptreemap![factor = 3; "a" => 1, "b" => 2,];
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], 1);
+    }
+
+    #[test]
+    fn test_bptreemap_empty() {
+        let map: BPlusTreeMap<i32, String> = bptreemap! {};
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+
+        // The types can also come from later use
+        let mut map = bptreemap! {};
+        map.insert(1, 'x');
+        assert_eq!(map.get(&1), Some(&'x'));
+
+        let map: BPlusTreeMap<i32, i32> = bptreemap![factor = 5];
+        assert!(map.is_empty());
+        let map: BPlusTreeMap<i32, i32> = bptreemap![factor = 5;];
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_bptreemap_duplicate_keys_last_wins() {
+        let map = bptreemap! {1 => "first", 2 => "two", 1 => "second"};
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&"second"));
+
+        let map = bptreemap![factor = 2; 1 => "first", 1 => "second", 1 => "third"];
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&"third"));
+    }
+
+    #[test]
+    fn test_bptreemap_with_branching_factor() {
+        // Enough entries for a branching factor of 2 to need branches
+        let map = bptreemap![factor = 2; 1 => 10, 2 => 20, 3 => 30, 4 => 40, 5 => 50];
+        assert_eq!(map.root_kind(), RootKind::Branch);
+        assert_eq!(map.len(), 5);
+        for i in 1..=5 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+
+        // The same entries fit in a single leaf with the default factor
+        let map = bptreemap! {1 => 10, 2 => 20, 3 => 30, 4 => 40};
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+    }
+
+    #[test]
+    fn test_bptreemap_evaluates_expressions_in_order() {
+        let mut counter = 0;
+        let mut next = || {
+            counter += 1;
+            counter
+        };
+        let map = bptreemap! {next() => "a", next() => "b", next() => "c"};
+        assert_eq!(
+            map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+            vec![(1, "a"), (2, "b"), (3, "c")]
+        );
+    }
+}
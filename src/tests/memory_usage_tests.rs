@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod memory_usage_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, MemoryUsage, Node};
+    use crate::tests::counting_allocator::allocations_during;
+    use std::mem::size_of;
+
+    fn numbered_map(branching_factor: usize, count: u64) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            map.insert((i * 7919) % count, i);
+        }
+        map
+    }
+
+    #[test]
+    fn test_empty_map_uses_nothing() {
+        let map = BPlusTreeMap::<u64, u64>::new();
+        assert_eq!(map.memory_usage(), MemoryUsage::default());
+        assert_eq!(map.memory_usage().total_bytes(), 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "bloom"))]
+    fn test_leaves_have_no_filters_without_the_feature() {
+        use crate::bplus_tree_map::LeafNode;
+        use crate::node_box::LeafLinks;
+        use crate::node_vec::NodeVec;
+        assert_eq!(
+            size_of::<LeafNode<u64, u64>>(),
+            2 * size_of::<NodeVec<u64>>() + size_of::<LeafLinks<u64, u64>>()
+        );
+    }
+
+    #[test]
+    fn test_single_leaf_breakdown() {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..5u64 {
+            map.insert(i, i * 10);
+        }
+        let usage = map.memory_usage();
+        assert_eq!(usage.key_bytes, 5 * size_of::<u64>());
+        assert_eq!(usage.value_bytes, 5 * size_of::<u64>());
+        assert_eq!(usage.branch_bytes, 0);
+        // Entries kept inline are counted as key and value bytes, not as
+        // part of the leaf
+        #[cfg(not(feature = "smallvec"))]
+        assert!(usage.leaf_bytes >= size_of::<Node<u64, u64>>());
+        #[cfg(feature = "smallvec")]
+        assert_eq!(
+            usage.leaf_bytes,
+            size_of::<Node<u64, u64>>() - 10 * size_of::<u64>()
+        );
+        assert_eq!(
+            usage.total_bytes(),
+            usage.leaf_bytes + usage.branch_bytes + usage.key_bytes + usage.value_bytes
+        );
+    }
+
+    #[test]
+    fn test_separators_count_as_key_bytes() {
+        let map = numbered_map(4, 1000);
+        let usage = map.memory_usage();
+        assert!(usage.key_bytes > 1000 * size_of::<u64>());
+        assert_eq!(usage.value_bytes, 1000 * size_of::<u64>());
+    }
+
+    #[test]
+    fn test_wide_nodes_need_fewer_branch_bytes() {
+        let narrow = numbered_map(4, 5000).memory_usage();
+        let wide = numbered_map(64, 5000).memory_usage();
+        assert!(
+            wide.branch_bytes < narrow.branch_bytes,
+            "{:?} vs {:?}",
+            wide,
+            narrow
+        );
+        assert_eq!(wide.value_bytes, narrow.value_bytes);
+    }
+
+    #[test]
+    fn test_callbacks_add_heap_bytes() {
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..100 {
+            map.insert(format!("key_{:03}", i), "x".repeat(i));
+        }
+        let inline = map.memory_usage();
+        let deep = map.memory_usage_with(|key| key.capacity(), |value| value.capacity());
+        let value_heap: usize = map.values().map(|value| value.capacity()).sum();
+        assert_eq!(deep.value_bytes, inline.value_bytes + value_heap);
+        assert!(deep.key_bytes >= inline.key_bytes + 100 * "key_000".len());
+        assert_eq!(deep.structure_bytes(), inline.structure_bytes());
+    }
+
+    #[test]
+    fn test_memory_usage_does_not_allocate() {
+        let map = numbered_map(16, 2000);
+        let mut usage = MemoryUsage::default();
+        assert_eq!(allocations_during(|| usage = map.memory_usage()), 0);
+        assert!(usage.total_bytes() > 2000 * 2 * size_of::<u64>());
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod merge_from_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+
+    fn word_counts(branching_factor: usize, words: &[(&str, i32)]) -> BPlusTreeMap<String, i32> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for (word, count) in words {
+            map.insert(word.to_string(), *count);
+        }
+        map
+    }
+
+    #[test]
+    fn test_merge_from_sums_duplicate_keys() {
+        let mut map = word_counts(
+            3,
+            &[("apple", 1), ("banana", 2), ("cherry", 3), ("date", 4)],
+        );
+        let other = word_counts(3, &[("banana", 10), ("date", 20), ("elderberry", 30)]);
+
+        let mut resolved = Vec::new();
+        map.merge_from(other, |key, existing, incoming| {
+            resolved.push((key.clone(), existing, incoming));
+            existing + incoming
+        });
+
+        // The closure only sees the keys held by both maps, existing value first
+        assert_eq!(
+            resolved,
+            vec![("banana".to_string(), 2, 10), ("date".to_string(), 4, 20),]
+        );
+        assert_eq!(map.len(), 5);
+        assert_eq!(map["apple"], 1);
+        assert_eq!(map["banana"], 12);
+        assert_eq!(map["cherry"], 3);
+        assert_eq!(map["date"], 24);
+        assert_eq!(map["elderberry"], 30);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_merge_from_disjoint_maps_never_resolves() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        let mut other = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100 {
+            if i % 2 == 0 {
+                map.insert(i, i);
+            } else {
+                other.insert(i, i);
+            }
+        }
+
+        let mut calls = 0;
+        map.merge_from(other, |_, existing, _| {
+            calls += 1;
+            existing
+        });
+
+        assert_eq!(calls, 0);
+        assert_eq!(map.len(), 100);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..100));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_merge_from_with_empty_maps() {
+        let mut map = word_counts(4, &[("one", 1), ("two", 2)]);
+        map.merge_from(BPlusTreeMap::new(), |_, _, _| unreachable!());
+        assert_eq!(map.len(), 2);
+
+        let mut empty = BPlusTreeMap::new();
+        empty.merge_from(map, |_, _, _| unreachable!());
+        assert_eq!(empty.len(), 2);
+        assert_eq!(empty["one"], 1);
+        assert_eq!(empty["two"], 2);
+    }
+
+    #[test]
+    fn test_merge_from_does_not_clone_values() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        let mut other = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50 {
+            map.insert(i * 2, CountedValue(i));
+            other.insert(i * 3, CountedValue(i));
+        }
+
+        let clones = clones_during(|| {
+            map.merge_from(other, |_, existing, incoming| {
+                CountedValue(existing.0 + incoming.0)
+            });
+        });
+
+        assert_eq!(clones, 0);
+        assert_eq!(map.len(), 50 + 50 - 17);
+        assert_eq!(map.get(&6).map(|v| v.0), Some(3 + 2));
+    }
+
+    #[test]
+    fn test_merge_from_matches_btree_map() {
+        for branching_factor in 2..=7 {
+            for (left_size, right_size) in [(0, 40), (1, 1), (7, 300), (300, 7), (250, 250)] {
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut other = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut expected = BTreeMap::new();
+                for i in 0..left_size {
+                    let key = (i * 37) % 500;
+                    map.insert(key, 1);
+                    *expected.entry(key).or_insert(0) += 1;
+                }
+                for i in 0..right_size {
+                    let key = (i * 53) % 500;
+                    other.insert(key, 1);
+                    *expected.entry(key).or_insert(0) += 1;
+                }
+
+                map.merge_from(other, |_, existing, incoming| existing + incoming);
+
+                let context = format!(
+                    "bf {} sizes {} and {}",
+                    branching_factor, left_size, right_size
+                );
+                assert_eq!(map.len(), expected.len(), "{}", context);
+                assert!(map.iter().eq(expected.iter()), "{}", context);
+                assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+
+                // The merged map keeps working as an ordinary map
+                for i in (0..600).step_by(7) {
+                    assert_eq!(map.insert(i, -i), expected.insert(i, -i), "{}", context);
+                }
+                for i in (0..600).step_by(3) {
+                    assert_eq!(map.remove(&i), expected.remove(&i), "{}", context);
+                }
+                assert!(map.iter().eq(expected.iter()), "{}", context);
+            }
+        }
+    }
+}
@@ -1,27 +1,28 @@
 #[cfg(test)]
 mod node_balancer_tests {
-    use std::rc::Rc;
     use crate::bplus_tree_map::{BranchNode, LeafNode, Node};
-    use crate::node_balancer::{BalanceResult, InsertionBalancer, NodeBalancer, RemovalBalancer};
     use crate::config::BPlusTreeConfig;
+    use crate::node_balancer::{BalanceResult, InsertionBalancer, NodeBalancer, RemovalBalancer};
     use crate::node_operations::NodeMerger;
+    use crate::separator::SeparatorKey;
+    use std::rc::Rc;
 
     #[test]
     fn test_insertion_balancer_leaf_node() {
         // Create a leaf node with keys and values
-        let leaf = LeafNode {
-            keys: vec![1, 2, 3, 4, 5],
-            values: vec![
+        let leaf = LeafNode::new(
+            vec![1, 2, 3, 4, 5],
+            vec![
                 "one".to_string(),
                 "two".to_string(),
                 "three".to_string(),
                 "four".to_string(),
                 "five".to_string(),
             ],
-        };
+        );
 
         // Create an insertion balancer with branching factor 3
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 3 });
+        let config = Rc::new(BPlusTreeConfig::new(3));
         let balancer = InsertionBalancer::new(config);
 
         // Balance the node
@@ -37,8 +38,8 @@ mod node_balancer_tests {
                 // Check left node
                 match left {
                     Node::Leaf(leaf) => {
-                        assert_eq!(leaf.keys, vec![1, 2]);
-                        assert_eq!(leaf.values, vec!["one".to_string(), "two".to_string()]);
+                        assert_eq!(leaf.keys[..], vec![1, 2]);
+                        assert_eq!(leaf.values[..], vec!["one".to_string(), "two".to_string()]);
                     }
                     _ => panic!("Expected left node to be a LeafNode"),
                 }
@@ -46,9 +47,9 @@ mod node_balancer_tests {
                 // Check right node
                 match right {
                     Node::Leaf(leaf) => {
-                        assert_eq!(leaf.keys, vec![3, 4, 5]);
+                        assert_eq!(leaf.keys[..], vec![3, 4, 5]);
                         assert_eq!(
-                            leaf.values,
+                            leaf.values[..],
                             vec!["three".to_string(), "four".to_string(), "five".to_string()]
                         );
                     }
@@ -56,7 +57,7 @@ mod node_balancer_tests {
                 }
 
                 // Check separator key
-                assert_eq!(separator, 3);
+                assert_eq!(*separator, 3);
             }
             _ => panic!("Expected node to be split"),
         }
@@ -65,36 +66,24 @@ mod node_balancer_tests {
     #[test]
     fn test_insertion_balancer_branch_node() {
         // Create child leaf nodes
-        let leaf1 = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
-        let leaf2 = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
-        let leaf3 = LeafNode {
-            keys: vec![7, 8],
-            values: vec!["seven".to_string(), "eight".to_string()],
-        };
-        let leaf4 = LeafNode {
-            keys: vec![10, 11],
-            values: vec!["ten".to_string(), "eleven".to_string()],
-        };
+        let leaf1 = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
+        let leaf2 = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
+        let leaf3 = LeafNode::new(vec![7, 8], vec!["seven".to_string(), "eight".to_string()]);
+        let leaf4 = LeafNode::new(vec![10, 11], vec!["ten".to_string(), "eleven".to_string()]);
 
         // Create a branch node with keys and children
-        let branch = BranchNode {
-            keys: vec![3, 6, 9],
-            children: vec![
+        let branch = BranchNode::new(
+            [3, 6, 9].map(SeparatorKey::new).to_vec(),
+            vec![
                 Node::Leaf(leaf1),
                 Node::Leaf(leaf2),
                 Node::Leaf(leaf3),
                 Node::Leaf(leaf4),
             ],
-        };
+        );
 
         // Create an insertion balancer with branching factor 2
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 2 });
+        let config = Rc::new(BPlusTreeConfig::new(2));
         let balancer = InsertionBalancer::new(config);
 
         // Balance the node
@@ -111,7 +100,7 @@ mod node_balancer_tests {
                 match left {
                     Node::Branch(branch) => {
                         assert_eq!(branch.keys.len(), 1);
-                        assert_eq!(branch.keys[0], 3);
+                        assert_eq!(*branch.keys[0], 3);
                         assert_eq!(branch.children.len(), 2);
                     }
                     _ => panic!("Expected left node to be a BranchNode"),
@@ -121,14 +110,14 @@ mod node_balancer_tests {
                 match right {
                     Node::Branch(branch) => {
                         assert_eq!(branch.keys.len(), 1);
-                        assert_eq!(branch.keys[0], 9);
+                        assert_eq!(*branch.keys[0], 9);
                         assert_eq!(branch.children.len(), 2);
                     }
                     _ => panic!("Expected right node to be a BranchNode"),
                 }
 
                 // Check separator key
-                assert_eq!(separator, 6);
+                assert_eq!(*separator, 6);
             }
             _ => panic!("Expected node to be split"),
         }
@@ -137,13 +126,10 @@ mod node_balancer_tests {
     #[test]
     fn test_insertion_balancer_no_split_needed() {
         // Create a leaf node with keys and values
-        let leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
         // Create an insertion balancer with branching factor 3
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 3 });
+        let config = Rc::new(BPlusTreeConfig::new(3));
         let balancer = InsertionBalancer::new(config);
 
         // Balance the node
@@ -153,8 +139,8 @@ mod node_balancer_tests {
         match balance_result {
             BalanceResult::NoChange(node) => match node {
                 Node::Leaf(leaf) => {
-                    assert_eq!(leaf.keys, vec![1, 2]);
-                    assert_eq!(leaf.values, vec!["one".to_string(), "two".to_string()]);
+                    assert_eq!(leaf.keys[..], vec![1, 2]);
+                    assert_eq!(leaf.values[..], vec!["one".to_string(), "two".to_string()]);
                 }
                 _ => panic!("Expected node to be a LeafNode"),
             },
@@ -165,32 +151,29 @@ mod node_balancer_tests {
     #[test]
     fn test_removal_balancer_merge_needed() {
         // Create leaf nodes with few keys
-        let left = LeafNode {
-            keys: vec![1],
-            values: vec!["one".to_string()],
-        };
-        let right = LeafNode {
-            keys: vec![3],
-            values: vec!["three".to_string()],
-        };
+        let left = LeafNode::new(vec![1], vec!["one".to_string()]);
+        let right = LeafNode::new(vec![3], vec!["three".to_string()]);
 
         // Create a removal balancer with min keys = 2
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 4 });
+        let config = Rc::new(BPlusTreeConfig::new(4));
         let balancer = RemovalBalancer::new(config);
 
         // Balance the nodes
         let balance_result = balancer.balance_nodes(
             Node::Leaf(left),
             Node::Leaf(right),
-            2, // separator key
+            SeparatorKey::new(2), // separator key
         );
 
         // Verify the balance result
         match balance_result {
             BalanceResult::Merged(node) => match node {
                 Node::Leaf(leaf) => {
-                    assert_eq!(leaf.keys, vec![1, 3]);
-                    assert_eq!(leaf.values, vec!["one".to_string(), "three".to_string()]);
+                    assert_eq!(leaf.keys[..], vec![1, 3]);
+                    assert_eq!(
+                        leaf.values[..],
+                        vec!["one".to_string(), "three".to_string()]
+                    );
                 }
                 _ => panic!("Expected node to be a LeafNode"),
             },
@@ -201,24 +184,21 @@ mod node_balancer_tests {
     #[test]
     fn test_removal_balancer_rebalance_needed() {
         // Create leaf nodes with uneven distribution
-        let left = LeafNode {
-            keys: vec![1, 2, 3],
-            values: vec!["one".to_string(), "two".to_string(), "three".to_string()],
-        };
-        let right = LeafNode {
-            keys: vec![5],
-            values: vec!["five".to_string()],
-        };
+        let left = LeafNode::new(
+            vec![1, 2, 3],
+            vec!["one".to_string(), "two".to_string(), "three".to_string()],
+        );
+        let right = LeafNode::new(vec![5], vec!["five".to_string()]);
 
         // Create a removal balancer with min keys = 2
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 4 });
+        let config = Rc::new(BPlusTreeConfig::new(4));
         let balancer = RemovalBalancer::new(config);
 
         // Balance the nodes
         let balance_result = balancer.balance_nodes(
             Node::Leaf(left),
             Node::Leaf(right),
-            4, // separator key
+            SeparatorKey::new(4), // separator key
         );
 
         // Verify the balance result
@@ -231,8 +211,8 @@ mod node_balancer_tests {
                 // Check left node
                 match left {
                     Node::Leaf(leaf) => {
-                        assert_eq!(leaf.keys, vec![1, 2]);
-                        assert_eq!(leaf.values, vec!["one".to_string(), "two".to_string()]);
+                        assert_eq!(leaf.keys[..], vec![1, 2]);
+                        assert_eq!(leaf.values[..], vec!["one".to_string(), "two".to_string()]);
                     }
                     _ => panic!("Expected left node to be a LeafNode"),
                 }
@@ -240,14 +220,17 @@ mod node_balancer_tests {
                 // Check right node
                 match right {
                     Node::Leaf(leaf) => {
-                        assert_eq!(leaf.keys, vec![3, 5]);
-                        assert_eq!(leaf.values, vec!["three".to_string(), "five".to_string()]);
+                        assert_eq!(leaf.keys[..], vec![3, 5]);
+                        assert_eq!(
+                            leaf.values[..],
+                            vec!["three".to_string(), "five".to_string()]
+                        );
                     }
                     _ => panic!("Expected right node to be a LeafNode"),
                 }
 
                 // Check separator key
-                assert_eq!(separator, 3);
+                assert_eq!(*separator, 3);
             }
             _ => panic!("Expected nodes to be rebalanced"),
         }
@@ -255,18 +238,18 @@ mod node_balancer_tests {
 
     #[test]
     fn test_removal_balancer_no_change_needed() {
-        // Create leaf nodes with sufficient keys (avoid using exactly 2 keys per node)
-        let left = LeafNode {
-            keys: vec![1, 3, 6],
-            values: vec!["one".to_string(), "three".to_string(), "six".to_string()],
-        };
-        let right = LeafNode {
-            keys: vec![4, 5, 7],
-            values: vec!["four".to_string(), "five".to_string(), "seven".to_string()],
-        };
+        // Create leaf nodes with sufficient keys
+        let left = LeafNode::new(
+            vec![1, 3, 6],
+            vec!["one".to_string(), "three".to_string(), "six".to_string()],
+        );
+        let right = LeafNode::new(
+            vec![4, 5, 7],
+            vec!["four".to_string(), "five".to_string(), "seven".to_string()],
+        );
 
         // Create a removal balancer with min keys = 2
-        let config = Rc::new(BPlusTreeConfig { branching_factor: 5 });
+        let config = Rc::new(BPlusTreeConfig::new(5));
         let balancer = RemovalBalancer::new(config);
 
         // Verify that the merger doesn't think these nodes need merging
@@ -277,15 +260,14 @@ mod node_balancer_tests {
         let balance_result = balancer.balance_nodes(
             Node::Leaf(left.clone()),
             Node::Leaf(right.clone()),
-            3, // separator key
+            SeparatorKey::new(3), // separator key
         );
 
         // Verify the balance result
         match balance_result {
-            BalanceResult::Rebalanced {
+            BalanceResult::NoChangeToPair {
                 left: left_node,
                 right: right_node,
-                separator: sep,
             } => {
                 // Check left node
                 match left_node {
@@ -304,11 +286,41 @@ mod node_balancer_tests {
                     }
                     _ => panic!("Expected right node to be a LeafNode"),
                 }
+            }
+            _ => panic!("Expected nodes to be handed back unchanged"),
+        }
+    }
 
-                // Check separator key
-                assert_eq!(sep, 3);
+    #[test]
+    fn test_removal_balancer_hands_back_full_branches_unchanged() {
+        let leaf = |key: i32| Node::Leaf(LeafNode::new(vec![key], vec![key.to_string()]));
+        let left = BranchNode::new(
+            vec![SeparatorKey::new(2), SeparatorKey::new(3)],
+            vec![leaf(1), leaf(2), leaf(3)],
+        );
+        let right = BranchNode::new(
+            vec![SeparatorKey::new(6), SeparatorKey::new(7)],
+            vec![leaf(5), leaf(6), leaf(7)],
+        );
+
+        // Both branches hold the minimum of 2 keys
+        let balancer = RemovalBalancer::new(Rc::new(BPlusTreeConfig::new(4)));
+        let result = balancer.balance_nodes(
+            Node::Branch(left),
+            Node::Branch(right),
+            SeparatorKey::new(5),
+        );
+        match result {
+            BalanceResult::NoChangeToPair {
+                left: Node::Branch(left),
+                right: Node::Branch(right),
+            } => {
+                assert_eq!(left.keys[..], [2, 3].map(SeparatorKey::new));
+                assert_eq!(right.keys[..], [6, 7].map(SeparatorKey::new));
+                assert_eq!(left.children.len(), 3);
+                assert_eq!(right.children.len(), 3);
             }
-            _ => panic!("Expected nodes to be rebalanced"),
+            _ => panic!("Expected branches to be handed back unchanged"),
         }
     }
 }
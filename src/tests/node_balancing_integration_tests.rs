@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod node_balancing_integration_tests {
-    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::bplus_tree_map::{BPlusTreeMap, Node};
 
     #[test]
     fn test_insertion_with_node_balancing() {
@@ -113,14 +113,217 @@ mod node_balancing_integration_tests {
         map.remove(&1);
         map.remove(&2);
 
-        // The implementation keeps a branch root with a single child
-        assert_eq!(map.root_kind(), RootKind::Branch);
+        // The branch root left with a single child gives way to it
+        assert_eq!(map.root_kind(), RootKind::Leaf);
         assert_eq!(map.get(&3), Some(&"3".to_string()));
 
-        // Remove the last key. The implementation currently leaves an empty
-        // branch node as the root, so the kind remains Branch.
+        // Removing the last key leaves the map as empty as a new one
         map.remove(&3);
-        assert_eq!(map.root_kind(), RootKind::Branch);
+        assert_eq!(map.root_kind(), RootKind::Empty);
         assert!(map.is_empty());
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_last_removal_empties_every_level() {
+        use crate::bplus_tree_map::RootKind;
+
+        for branching_factor in [2, 3, 4, 8] {
+            for len in [2, 10, 100, 1000] {
+                // Removing in ascending, descending and scattered order
+                let orders: [Box<dyn Fn(i32) -> i32>; 3] = [
+                    Box::new(|i| i),
+                    Box::new(move |i| len - 1 - i),
+                    Box::new(move |i| (i * 7919) % len),
+                ];
+                for order in &orders {
+                    let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                    for i in 0..len {
+                        map.insert(i, i);
+                    }
+                    if len as usize > branching_factor {
+                        assert_eq!(map.root_kind(), RootKind::Branch);
+                    }
+                    for i in 0..len {
+                        assert_eq!(map.remove(&order(i)), Some(order(i)));
+                    }
+                    assert_eq!(map.root_kind(), RootKind::Empty, "{} keys", len);
+                    assert!(map.is_empty());
+                    assert_eq!(map.check_invariants(), Ok(()));
+
+                    // The emptied map fills up again like a new one
+                    map.insert(1, 1);
+                    assert_eq!(map.root_kind(), RootKind::Leaf);
+                    assert_eq!(map.get(&1), Some(&1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_popping_the_last_entry_empties_the_map() {
+        use crate::bplus_tree_map::RootKind;
+
+        for branching_factor in [2, 3, 5] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..500 {
+                map.insert(i, i);
+            }
+            for i in 0..250 {
+                assert_eq!(map.pop_first(), Some((i, i)));
+                assert_eq!(map.pop_last(), Some((499 - i, 499 - i)));
+            }
+            assert_eq!(map.root_kind(), RootKind::Empty);
+            assert_eq!(map.pop_first(), None);
+            assert_eq!(map.pop_last(), None);
+        }
+    }
+
+    /// The number of branches below the root of `map` with a single child
+    fn lone_children(map: &BPlusTreeMap<i32, i32>) -> usize {
+        fn count(node: &Node<i32, i32>) -> usize {
+            match node {
+                Node::Leaf(_) => 0,
+                Node::Branch(branch) => branch
+                    .children
+                    .iter()
+                    .map(|child| {
+                        let lone =
+                            matches!(&**child, Node::Branch(child) if child.children.len() == 1);
+                        usize::from(lone) + count(child)
+                    })
+                    .sum(),
+            }
+        }
+        map.root_node().map_or(0, |root| count(root))
+    }
+
+    #[test]
+    fn test_height_shrinks_as_the_map_empties() {
+        use crate::bplus_tree_map::RootKind;
+
+        for branching_factor in [2, 4, 8] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..10_000 {
+                map.insert(i, i);
+            }
+            let full_height = map.height();
+            assert!(full_height > 3);
+
+            // Removing from the front empties the leftmost children first
+            for i in 0..9_990 {
+                assert_eq!(map.remove(&i), Some(i));
+                if i % 97 == 0 {
+                    assert_eq!(lone_children(&map), 0, "after removing {}", i);
+                }
+            }
+            assert!(map.height() < full_height);
+            // Ten entries in nodes no less than half full
+            assert!(map.height() <= 4, "height {}", map.height());
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert!(map.keys().copied().eq(9_990..10_000));
+
+            while map.len() > 1 {
+                map.pop_last();
+            }
+            assert_eq!(map.root_kind(), RootKind::Leaf);
+            assert_eq!(map.height(), 1);
+            assert_eq!(map.get(&9_990), Some(&9_990));
+        }
+    }
+
+    #[test]
+    fn test_removing_from_the_low_end_keeps_nodes_half_full() {
+        for branching_factor in [3, 4, 5, 8] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..2_000 {
+                map.insert(i, i);
+            }
+            assert_eq!(map.check_occupancy(), Ok(()));
+
+            // Every removal lands in the leftmost leaf, which has no left
+            // sibling to borrow from or merge into
+            for i in 0..2_000 {
+                assert_eq!(map.remove(&i), Some(i));
+                assert_eq!(
+                    map.check_invariants(),
+                    Ok(()),
+                    "bf {} after {}",
+                    branching_factor,
+                    i
+                );
+                assert_eq!(
+                    map.check_occupancy(),
+                    Ok(()),
+                    "bf {} after {}",
+                    branching_factor,
+                    i
+                );
+                assert_eq!(
+                    map.first_key_value(),
+                    (i < 1_999).then_some((&(i + 1), &(i + 1)))
+                );
+            }
+            assert!(map.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_popping_the_first_entries_keeps_nodes_half_full() {
+        for branching_factor in [3, 4, 5, 8] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            // Keys inserted out of order, so the leaves start out unevenly filled
+            for i in 0..1_000 {
+                map.insert((i * 7919) % 1_000, i);
+            }
+            while let Some((key, _)) = map.pop_first() {
+                assert_eq!(
+                    map.check_invariants(),
+                    Ok(()),
+                    "bf {} after {}",
+                    branching_factor,
+                    key
+                );
+                assert_eq!(
+                    map.check_occupancy(),
+                    Ok(()),
+                    "bf {} after {}",
+                    branching_factor,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_removing_interior_keys_never_overfills_a_leaf() {
+        for branching_factor in [3, 4] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..1_200 {
+                map.insert(i, i);
+            }
+            // Every third key from the middle out, so leaves are left half
+            // full next to siblings that are half full too
+            let mut removed: Vec<i32> = (0..400).map(|i| 3 * i).collect();
+            removed.sort_by_key(|key| (key - 600).abs());
+            for i in removed {
+                map.remove(&i);
+                for leaf in map.leaves() {
+                    assert!(
+                        leaf.keys.len() <= branching_factor,
+                        "leaf {:?} after removing {}",
+                        leaf.keys,
+                        i
+                    );
+                }
+                assert_eq!(map.check_invariants(), Ok(()), "after removing {}", i);
+                assert_eq!(map.check_occupancy(), Ok(()), "after removing {}", i);
+            }
+            assert_eq!(map.len(), 800);
+            assert!(
+                map.keys()
+                    .all(|key| key % 3 != 0 || !(0..1_200).contains(key))
+            );
+        }
     }
 }
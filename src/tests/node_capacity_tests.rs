@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod node_capacity_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Node};
+    use crate::tests::counting_allocator::allocations_during;
+
+    /// Counts the leaves and branches under `node`
+    fn count_nodes(node: &Node<u64, u64>) -> (usize, usize) {
+        match node {
+            Node::Leaf(_) => (1, 0),
+            Node::Branch(branch) => branch
+                .children
+                .iter()
+                .map(|child| count_nodes(child))
+                .fold((0, 1), |(leaves, branches), (l, b)| {
+                    (leaves + l, branches + b)
+                }),
+        }
+    }
+
+    /// Checks that every node under `node` has room for a full node
+    fn assert_full_capacity(node: &Node<u64, u64>, branching_factor: usize) {
+        match node {
+            Node::Leaf(leaf) => {
+                assert!(leaf.keys.capacity() > branching_factor);
+                assert!(leaf.values.capacity() > branching_factor);
+            }
+            Node::Branch(branch) => {
+                assert!(branch.keys.capacity() > branching_factor);
+                assert!(branch.children.capacity() > branching_factor + 1);
+                for child in &branch.children {
+                    assert_full_capacity(child, branching_factor);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_nodes_have_room_for_a_full_node() {
+        for branching_factor in [4, 16, 64] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in 0..5000 {
+                let key = (i * 7919) % 5000;
+                map.insert(key, key);
+            }
+            // Every node is either the half of a split or the root above
+            // one; the first leaf grew past the branching factor before it
+            // was split
+            assert_full_capacity(map.root_node().unwrap(), branching_factor);
+            assert!(map.keys().copied().eq(0..5000));
+        }
+    }
+
+    #[test]
+    fn test_ascending_inserts_allocate_each_node_once() {
+        let mut map = BPlusTreeMap::with_branching_factor(16);
+        let allocations = allocations_during(|| {
+            for i in 0..10_000 {
+                map.insert(i, i);
+            }
+        });
+        let (leaves, branches) = count_nodes(map.root_node().unwrap());
+
+        // Each node allocates its box and its two Vecs once, and each leaf
+        // split the separator it promotes. Only the first leaf, which
+        // starts out empty, reallocates as it fills: 4, 8, 16 and 32 slots.
+        assert!(
+            allocations <= 3 * (leaves + branches) + leaves + 8,
+            "{} allocations for {} leaves and {} branches",
+            allocations,
+            leaves,
+            branches
+        );
+    }
+}
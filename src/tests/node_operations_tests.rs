@@ -5,6 +5,8 @@ mod node_operations_tests {
         BranchNodeSplitter, LeafNodeMerger, LeafNodeSplitter, MergeResult, NodeMerger,
         NodeSplitter, SplitResult,
     };
+    use crate::separator::SeparatorKey;
+    use std::iter;
 
     // Define a simple BranchNodeMerger for testing
     struct BranchNodeMerger {
@@ -18,7 +20,7 @@ mod node_operations_tests {
         }
     }
 
-    impl<K, V> NodeMerger<K, V, BranchNode<K, V>> for BranchNodeMerger
+    impl<K, V> NodeMerger<SeparatorKey<K>, V, BranchNode<K, V>> for BranchNodeMerger
     where
         K: Ord + Clone,
         V: Clone,
@@ -31,10 +33,10 @@ mod node_operations_tests {
             &self,
             mut left: BranchNode<K, V>,
             mut right: BranchNode<K, V>,
-            separator: K,
-        ) -> MergeResult<K, BranchNode<K, V>> {
+            separator: SeparatorKey<K>,
+        ) -> MergeResult<SeparatorKey<K>, BranchNode<K, V>> {
             if !self.needs_merge(&left, &right) {
-                return MergeResult::NoMerge::<K, BranchNode<K, V>> {
+                return MergeResult::NoMerge::<SeparatorKey<K>, BranchNode<K, V>> {
                     left,
                     right,
                     separator,
@@ -46,23 +48,23 @@ mod node_operations_tests {
             left.keys.append(&mut right.keys);
             left.children.append(&mut right.children);
 
-            MergeResult::Merged::<K, BranchNode<K, V>>(left)
+            MergeResult::Merged::<SeparatorKey<K>, BranchNode<K, V>>(left)
         }
     }
 
     #[test]
     fn test_leaf_node_splitter() {
         // Create a leaf node with keys and values
-        let leaf = LeafNode {
-            keys: vec![1, 2, 3, 4, 5],
-            values: vec![
+        let leaf = LeafNode::new(
+            vec![1, 2, 3, 4, 5],
+            vec![
                 "one".to_string(),
                 "two".to_string(),
                 "three".to_string(),
                 "four".to_string(),
                 "five".to_string(),
             ],
-        };
+        );
 
         // Create a splitter with branching factor 3
         let splitter = LeafNodeSplitter::new(3);
@@ -81,20 +83,20 @@ mod node_operations_tests {
                 separator,
             } => {
                 // Check left node
-                let LeafNode { keys, values } = left;
-                assert_eq!(keys, vec![1, 2]);
-                assert_eq!(values, vec!["one".to_string(), "two".to_string()]);
+                let LeafNode { keys, values, .. } = left;
+                assert_eq!(keys[..], vec![1, 2]);
+                assert_eq!(values[..], vec!["one".to_string(), "two".to_string()]);
 
                 // Check right node
-                let LeafNode { keys, values } = right;
-                assert_eq!(keys, vec![3, 4, 5]);
+                let LeafNode { keys, values, .. } = right;
+                assert_eq!(keys[..], vec![3, 4, 5]);
                 assert_eq!(
-                    values,
+                    values[..],
                     vec!["three".to_string(), "four".to_string(), "five".to_string()]
                 );
 
                 // Check separator key
-                assert_eq!(separator, 3);
+                assert_eq!(*separator, 3);
             }
             SplitResult::NoSplit(_) => {
                 panic!("Expected node to be split");
@@ -105,10 +107,7 @@ mod node_operations_tests {
     #[test]
     fn test_leaf_node_no_split_needed() {
         // Create a leaf node with keys and values
-        let leaf = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
+        let leaf = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
 
         // Create a splitter with branching factor 3
         let splitter = LeafNodeSplitter::new(3);
@@ -123,9 +122,9 @@ mod node_operations_tests {
         match split_result {
             SplitResult::NoSplit(node) => {
                 // Check node is unchanged
-                let LeafNode { keys, values } = node;
-                assert_eq!(keys, vec![1, 2]);
-                assert_eq!(values, vec!["one".to_string(), "two".to_string()]);
+                let LeafNode { keys, values, .. } = node;
+                assert_eq!(keys[..], vec![1, 2]);
+                assert_eq!(values[..], vec!["one".to_string(), "two".to_string()]);
             }
             SplitResult::Split { .. } => {
                 panic!("Expected node not to be split");
@@ -136,33 +135,21 @@ mod node_operations_tests {
     #[test]
     fn test_branch_node_splitter() {
         // Create child leaf nodes
-        let leaf1 = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
-        let leaf2 = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
-        let leaf3 = LeafNode {
-            keys: vec![7, 8],
-            values: vec!["seven".to_string(), "eight".to_string()],
-        };
-        let leaf4 = LeafNode {
-            keys: vec![10, 11],
-            values: vec!["ten".to_string(), "eleven".to_string()],
-        };
+        let leaf1 = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
+        let leaf2 = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
+        let leaf3 = LeafNode::new(vec![7, 8], vec!["seven".to_string(), "eight".to_string()]);
+        let leaf4 = LeafNode::new(vec![10, 11], vec!["ten".to_string(), "eleven".to_string()]);
 
         // Create a branch node with keys and children
-        let branch = BranchNode {
-            keys: vec![3, 6, 9],
-            children: vec![
+        let branch = BranchNode::new(
+            [3, 6, 9].map(SeparatorKey::new).to_vec(),
+            vec![
                 crate::bplus_tree_map::Node::Leaf(leaf1),
                 crate::bplus_tree_map::Node::Leaf(leaf2),
                 crate::bplus_tree_map::Node::Leaf(leaf3),
                 crate::bplus_tree_map::Node::Leaf(leaf4),
             ],
-        };
+        );
 
         // Create a splitter with branching factor 2
         let splitter = BranchNodeSplitter::new(2);
@@ -181,19 +168,31 @@ mod node_operations_tests {
                 separator,
             } => {
                 // Check left node
-                let BranchNode { keys, children } = left;
+                let BranchNode {
+                    keys,
+                    children,
+                    min_key,
+                    max_key,
+                } = left;
                 assert_eq!(keys.len(), 1);
-                assert_eq!(keys[0], 3);
+                assert_eq!(*keys[0], 3);
                 assert_eq!(children.len(), 2);
+                assert_eq!((min_key, max_key), (Some(1), Some(5)));
 
                 // Check right node
-                let BranchNode { keys, children } = right;
+                let BranchNode {
+                    keys,
+                    children,
+                    min_key,
+                    max_key,
+                } = right;
                 assert_eq!(keys.len(), 1);
-                assert_eq!(keys[0], 9);
+                assert_eq!(*keys[0], 9);
                 assert_eq!(children.len(), 2);
+                assert_eq!((min_key, max_key), (Some(7), Some(11)));
 
                 // Check separator key
-                assert_eq!(separator, 6);
+                assert_eq!(*separator, 6);
             }
             SplitResult::NoSplit(_) => {
                 panic!("Expected node to be split");
@@ -201,26 +200,54 @@ mod node_operations_tests {
         }
     }
 
+    #[test]
+    fn test_split_halves_have_room_for_a_full_node() {
+        let leaf = LeafNode::new((0..5).collect::<Vec<i32>>(), (0..5).collect::<Vec<i32>>());
+        match LeafNodeSplitter::new(4).split(leaf) {
+            SplitResult::Split { left, right, .. } => {
+                assert!(left.keys.capacity() >= 5 && left.values.capacity() >= 5);
+                assert!(right.keys.capacity() >= 5 && right.values.capacity() >= 5);
+            }
+            SplitResult::NoSplit(_) => panic!("Expected node to be split"),
+        }
+
+        // A pooled right half that is too small is grown to fit
+        let leaf = LeafNode::new((0..5).collect::<Vec<i32>>(), (0..5).collect::<Vec<i32>>());
+        let small = LeafNode::new(Vec::with_capacity(1), Vec::with_capacity(1));
+        match LeafNodeSplitter::new(4).split_into(leaf, small) {
+            SplitResult::Split { right, .. } => {
+                assert!(right.keys.capacity() >= 5 && right.values.capacity() >= 5);
+            }
+            SplitResult::NoSplit(_) => panic!("Expected node to be split"),
+        }
+
+        let branch: BranchNode<i32, i32> = BranchNode::new(
+            (0..5).map(SeparatorKey::new).collect::<Vec<_>>(),
+            (0..6).map(|_| Node::Leaf(LeafNode::new(Vec::new(), Vec::new()))),
+        );
+        match BranchNodeSplitter::new(4).split(branch) {
+            SplitResult::Split { left, right, .. } => {
+                assert!(left.keys.capacity() >= 5 && left.children.capacity() >= 6);
+                assert!(right.keys.capacity() >= 5 && right.children.capacity() >= 6);
+            }
+            SplitResult::NoSplit(_) => panic!("Expected node to be split"),
+        }
+    }
+
     #[test]
     fn test_branch_node_no_split_needed() {
         // Create child leaf nodes
-        let leaf1 = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
-        let leaf2 = LeafNode {
-            keys: vec![4, 5],
-            values: vec!["four".to_string(), "five".to_string()],
-        };
+        let leaf1 = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
+        let leaf2 = LeafNode::new(vec![4, 5], vec!["four".to_string(), "five".to_string()]);
 
         // Create a branch node with keys and children
-        let branch = BranchNode {
-            keys: vec![3],
-            children: vec![
+        let branch = BranchNode::new(
+            vec![SeparatorKey::new(3)],
+            vec![
                 crate::bplus_tree_map::Node::Leaf(leaf1),
                 crate::bplus_tree_map::Node::Leaf(leaf2),
             ],
-        };
+        );
 
         // Create a splitter with branching factor 2
         let splitter = BranchNodeSplitter::new(2);
@@ -235,9 +262,9 @@ mod node_operations_tests {
         match split_result {
             SplitResult::NoSplit(node) => {
                 // Check node is unchanged
-                let BranchNode { keys, children } = node;
+                let BranchNode { keys, children, .. } = node;
                 assert_eq!(keys.len(), 1);
-                assert_eq!(keys[0], 3);
+                assert_eq!(*keys[0], 3);
                 assert_eq!(children.len(), 2);
             }
             SplitResult::Split { .. } => {
@@ -248,15 +275,9 @@ mod node_operations_tests {
 
     #[test]
     fn test_leaf_node_merger() {
-        // Create leaf nodes
-        let left = LeafNode {
-            keys: vec![1, 2],
-            values: vec!["one".to_string(), "two".to_string()],
-        };
-        let right = LeafNode {
-            keys: vec![3, 4],
-            values: vec!["three".to_string(), "four".to_string()],
-        };
+        // Create leaf nodes, the right one below the minimum of 2 keys
+        let left = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
+        let right = LeafNode::new(vec![3], vec!["three".to_string()]);
 
         // Create a merger with branching factor 4
         let merger = LeafNodeMerger::new(4);
@@ -265,21 +286,16 @@ mod node_operations_tests {
         assert!(merger.needs_merge(&left, &right));
 
         // Merge the nodes
-        let merge_result = merger.merge(left, right, 3);
+        let merge_result = merger.merge(left, right, SeparatorKey::new(3));
 
         // Verify the merge result
         match merge_result {
             MergeResult::Merged(node) => {
                 // Check merged node
-                assert_eq!(node.keys, vec![1, 2, 3, 4]);
+                assert_eq!(node.keys[..], vec![1, 2, 3]);
                 assert_eq!(
-                    node.values,
-                    vec![
-                        "one".to_string(),
-                        "two".to_string(),
-                        "three".to_string(),
-                        "four".to_string()
-                    ]
+                    node.values[..],
+                    vec!["one".to_string(), "two".to_string(), "three".to_string()]
                 );
             }
             _ => {
@@ -288,22 +304,33 @@ mod node_operations_tests {
         }
     }
 
+    #[test]
+    fn test_half_full_leaves_do_not_need_merging() {
+        let left = LeafNode::new(vec![1, 2], vec!["one".to_string(), "two".to_string()]);
+        let right = LeafNode::new(vec![3, 4], vec!["three".to_string(), "four".to_string()]);
+
+        // Two keys is the minimum at branching factor 4 or 5, and merging
+        // the leaves at branching factor 3 would overfill the result
+        for branching_factor in [3, 4, 5] {
+            let merger = LeafNodeMerger::new(branching_factor);
+            assert!(!merger.needs_merge(&left, &right));
+        }
+        assert!(LeafNodeMerger::new(6).needs_merge(&left, &right));
+    }
+
     #[test]
     fn test_leaf_node_rebalance() {
         // Create leaf nodes with uneven distribution
-        let left = LeafNode {
-            keys: vec![1, 2, 3, 4],
-            values: vec![
+        let left = LeafNode::new(
+            vec![1, 2, 3, 4],
+            vec![
                 "one".to_string(),
                 "two".to_string(),
                 "three".to_string(),
                 "four".to_string(),
             ],
-        };
-        let right = LeafNode {
-            keys: vec![5],
-            values: vec!["five".to_string()],
-        };
+        );
+        let right = LeafNode::new(vec![5], vec!["five".to_string()]);
 
         // Create a merger with branching factor 4
         let merger = LeafNodeMerger::new(4);
@@ -312,7 +339,7 @@ mod node_operations_tests {
         assert!(merger.needs_merge(&left, &right));
 
         // Merge the nodes
-        let merge_result = merger.merge(left, right, 5);
+        let merge_result = merger.merge(left, right, SeparatorKey::new(5));
 
         // Verify the rebalance result
         match merge_result {
@@ -322,14 +349,14 @@ mod node_operations_tests {
                 separator,
             } => {
                 // Check rebalanced nodes
-                assert_eq!(left.keys, vec![1, 2]);
-                assert_eq!(left.values, vec!["one".to_string(), "two".to_string()]);
-                assert_eq!(right.keys, vec![3, 4, 5]);
+                assert_eq!(left.keys[..], vec![1, 2]);
+                assert_eq!(left.values[..], vec!["one".to_string(), "two".to_string()]);
+                assert_eq!(right.keys[..], vec![3, 4, 5]);
                 assert_eq!(
-                    right.values,
+                    right.values[..],
                     vec!["three".to_string(), "four".to_string(), "five".to_string()]
                 );
-                assert_eq!(separator, 3);
+                assert_eq!(*separator, 3);
             }
             _ => {
                 panic!("Expected nodes to be rebalanced");
@@ -340,32 +367,20 @@ mod node_operations_tests {
     #[test]
     fn test_branch_node_merger() {
         // Create child leaf nodes
-        let leaf1 = LeafNode {
-            keys: vec![1],
-            values: vec!["one".to_string()],
-        };
-        let leaf2 = LeafNode {
-            keys: vec![3],
-            values: vec!["three".to_string()],
-        };
-        let leaf3 = LeafNode {
-            keys: vec![5],
-            values: vec!["five".to_string()],
-        };
-        let leaf4 = LeafNode {
-            keys: vec![7],
-            values: vec!["seven".to_string()],
-        };
+        let leaf1 = LeafNode::new(vec![1], vec!["one".to_string()]);
+        let leaf2 = LeafNode::new(vec![3], vec!["three".to_string()]);
+        let leaf3 = LeafNode::new(vec![5], vec!["five".to_string()]);
+        let leaf4 = LeafNode::new(vec![7], vec!["seven".to_string()]);
 
         // Create branch nodes
-        let left = BranchNode {
-            keys: vec![2],
-            children: vec![Node::Leaf(leaf1), Node::Leaf(leaf2)],
-        };
-        let right = BranchNode {
-            keys: vec![6],
-            children: vec![Node::Leaf(leaf3), Node::Leaf(leaf4)],
-        };
+        let left = BranchNode::new(
+            vec![SeparatorKey::new(2)],
+            vec![Node::Leaf(leaf1), Node::Leaf(leaf2)],
+        );
+        let right = BranchNode::new(
+            vec![SeparatorKey::new(6)],
+            vec![Node::Leaf(leaf3), Node::Leaf(leaf4)],
+        );
 
         // Create a merger with branching factor 4
         let merger = BranchNodeMerger::new(4);
@@ -374,13 +389,13 @@ mod node_operations_tests {
         assert!(merger.needs_merge(&left, &right));
 
         // Merge the nodes with separator key 4
-        let merge_result = merger.merge(left, right, 4);
+        let merge_result = merger.merge(left, right, SeparatorKey::new(4));
 
         // Verify the merge result
         match merge_result {
             MergeResult::Merged(node) => {
                 // Check merged node
-                assert_eq!(node.keys, vec![2, 4, 6]);
+                assert_eq!(node.keys[..], [2, 4, 6].map(SeparatorKey::new));
                 assert_eq!(node.children.len(), 4);
             }
             _ => {
@@ -388,4 +403,66 @@ mod node_operations_tests {
             }
         }
     }
+
+    #[test]
+    fn test_branch_node_merger_merges_a_lone_child_it_cannot_share_with() {
+        let leaf = |key: i32| Node::Leaf(LeafNode::new(vec![key], vec![key.to_string()]));
+        // A branch left with a single child, next to one with a key to spare
+        // only if the separator is counted
+        let left = BranchNode::new(Vec::new(), vec![leaf(1)]);
+        let right = BranchNode::new(vec![SeparatorKey::new(3)], vec![leaf(2), leaf(3)]);
+
+        let merger = BranchNodeMerger::new(2);
+        assert!(merger.needs_merge(&left, &right));
+        match merger.merge(left, right, SeparatorKey::new(2)) {
+            MergeResult::Merged(node) => {
+                assert_eq!(node.keys[..], [2, 3].map(SeparatorKey::new));
+                assert_eq!(node.children.len(), 3);
+            }
+            _ => panic!("Expected nodes to be merged rather than leave one with no keys"),
+        }
+    }
+
+    #[test]
+    fn test_branch_node_rebalance_leaves_both_halves_the_minimum() {
+        let leaf = |key: i32| Node::Leaf(LeafNode::new(vec![key], vec![key.to_string()]));
+        let branch = |keys: &[i32]| {
+            let children = iter::once(keys[0] - 1)
+                .chain(keys.iter().copied())
+                .map(leaf);
+            let separators: Vec<_> = keys.iter().copied().map(SeparatorKey::new).collect();
+            BranchNode::new(separators, children)
+        };
+
+        // Three keys on the left and one on the right share out as two each,
+        // both when keys move right and when they move left
+        let merger = crate::node_operations::BranchNodeMerger::new(4);
+        for (left_keys, right_keys, separator) in [
+            (&[2, 4, 6][..], &[10][..], 8),
+            (&[2][..], &[6, 8, 10][..], 4),
+        ] {
+            let (left, right) = (branch(left_keys), branch(right_keys));
+            match merger.merge(left, right, SeparatorKey::new(separator)) {
+                MergeResult::Rebalanced {
+                    left,
+                    right,
+                    separator,
+                } => {
+                    assert_eq!(left.keys.len(), 2, "left of {:?}", left_keys);
+                    assert_eq!(right.keys.len(), 2, "right of {:?}", left_keys);
+                    assert_eq!(left.children.len(), 3);
+                    assert_eq!(right.children.len(), 3);
+                    let keys: Vec<i32> = left
+                        .keys
+                        .iter()
+                        .chain([&separator])
+                        .chain(&right.keys)
+                        .map(|key| **key)
+                        .collect();
+                    assert_eq!(keys, [2, 4, 6, 8, 10]);
+                }
+                _ => panic!("Expected nodes to be rebalanced"),
+            }
+        }
+    }
 }
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod node_size_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Node, NodeBox};
+    use std::mem::size_of;
+
+    /// The address of each child of the root
+    fn root_children(map: &BPlusTreeMap<u64, u64>) -> Vec<*const Node<u64, u64>> {
+        match map.root_node().map(|root| &**root) {
+            Some(Node::Branch(branch)) => branch
+                .children
+                .iter()
+                .map(|child| &**child as *const _)
+                .collect(),
+            _ => panic!("root is not a branch"),
+        }
+    }
+
+    #[test]
+    fn test_children_are_pointer_sized() {
+        assert_eq!(size_of::<NodeBox<u64, u64>>(), size_of::<usize>());
+        assert_eq!(
+            size_of::<NodeBox<[u64; 32], [u64; 32]>>(),
+            size_of::<usize>()
+        );
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "bloom", feature = "smallvec")))]
+    fn test_node_is_as_large_as_a_branch() {
+        use crate::bplus_tree_map::BranchNode;
+
+        // Two Vec headers, two fences, and the tag tucked into a niche
+        assert_eq!(size_of::<Node<u64, u64>>(), 80);
+        assert_eq!(
+            size_of::<Node<u64, u64>>(),
+            size_of::<BranchNode<u64, u64>>()
+        );
+    }
+
+    #[test]
+    fn test_splitting_a_child_leaves_its_siblings_in_place() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..10 {
+            map.insert(i * 10, i);
+        }
+        let before = root_children(&map);
+
+        // Fill the first leaf until it splits, shifting its siblings along
+        let mut key = 1;
+        while root_children(&map).len() == before.len() {
+            map.insert(key, key);
+            key += 1;
+        }
+        let after = root_children(&map);
+        assert_eq!(after.len(), before.len() + 1);
+        assert_eq!(after[2..], before[1..]);
+    }
+}
@@ -0,0 +1,210 @@
+#[cfg(all(test, feature = "rayon"))]
+mod par_iter_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use rayon::prelude::*;
+    use std::marker::PhantomData;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+
+    fn numbered_map(branching_factor: usize, count: u64) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            let key = (i * 7919) % count;
+            map.insert(key, key * 3);
+        }
+        map
+    }
+
+    #[test]
+    fn test_parallel_sums_match_sequential_ones() {
+        for branching_factor in [3, 4, 16] {
+            let map = numbered_map(branching_factor, 10_000);
+
+            let sequential: u64 = map.iter().map(|(k, v)| k ^ v).sum();
+            let parallel: u64 = map.par_iter().map(|(k, v)| k ^ v).sum();
+            assert_eq!(parallel, sequential);
+
+            assert_eq!(map.par_keys().sum::<u64>(), map.keys().sum::<u64>());
+            assert_eq!(map.par_values().sum::<u64>(), map.values().sum::<u64>());
+            assert_eq!(map.par_iter().count(), map.len());
+        }
+    }
+
+    #[test]
+    fn test_parallel_collect_keeps_key_order() {
+        let map = numbered_map(5, 20_000);
+
+        let entries: Vec<(&u64, &u64)> = map.par_iter().collect();
+        assert!(entries.into_iter().eq(map.iter()));
+        let keys: Vec<&u64> = map.par_keys().collect();
+        assert!(keys.into_iter().eq(map.keys()));
+        let values: Vec<&u64> = (&map).into_par_iter().map(|(_, v)| v).collect();
+        assert!(values.into_iter().eq(map.values()));
+    }
+
+    #[test]
+    fn test_parallel_search_stops_early() {
+        let map = numbered_map(4, 20_000);
+
+        assert_eq!(
+            map.par_iter().find_first(|(k, _)| **k >= 1234),
+            Some((&1234, &3702))
+        );
+        assert!(map.par_keys().any(|k| *k == 19_999));
+        assert!(!map.par_values().any(|v| *v % 3 != 0));
+    }
+
+    #[test]
+    fn test_parallel_iteration_of_tiny_maps() {
+        let empty: BPlusTreeMap<u64, u64> = BPlusTreeMap::new();
+        assert_eq!(empty.par_iter().count(), 0);
+        assert_eq!(empty.par_keys().sum::<u64>(), 0);
+        assert_eq!(empty.par_values().max(), None);
+
+        let single = numbered_map(4, 1);
+        let entries: Vec<_> = single.par_iter().collect();
+        assert_eq!(entries, vec![(&0, &0)]);
+
+        let leaf = numbered_map(8, 5);
+        assert_eq!(leaf.par_values().sum::<u64>(), 30);
+    }
+
+    #[test]
+    fn test_par_for_each_leaf_sums_like_for_each_leaf() {
+        for branching_factor in [3, 4, 16] {
+            let map = numbered_map(branching_factor, 10_000);
+
+            let sum = AtomicU64::new(0);
+            let entries = AtomicUsize::new(0);
+            let leaves = AtomicUsize::new(0);
+            map.par_for_each_leaf(|keys, values| {
+                assert_eq!(keys.len(), values.len());
+                sum.fetch_add(values.iter().sum::<u64>(), Ordering::Relaxed);
+                entries.fetch_add(keys.len(), Ordering::Relaxed);
+                leaves.fetch_add(1, Ordering::Relaxed);
+            });
+
+            let mut sequential_sum = 0;
+            let mut sequential_leaves = 0;
+            map.for_each_leaf(|_, values| {
+                sequential_sum += values.iter().sum::<u64>();
+                sequential_leaves += 1;
+            });
+            assert_eq!(sum.into_inner(), sequential_sum);
+            assert_eq!(entries.into_inner(), map.len());
+            assert_eq!(leaves.into_inner(), sequential_leaves);
+        }
+    }
+
+    #[test]
+    fn test_par_for_each_leaf_visits_each_leaf_once() {
+        let map = numbered_map(4, 5_000);
+        let first_keys = Mutex::new(Vec::new());
+        map.par_for_each_leaf(|keys, _| first_keys.lock().unwrap().push(keys[0]));
+
+        let mut first_keys = first_keys.into_inner().unwrap();
+        first_keys.sort();
+        let mut expected = Vec::new();
+        map.for_each_leaf(|keys, _| expected.push(keys[0]));
+        assert_eq!(first_keys, expected);
+    }
+
+    /// A key that may be shared between threads but not moved to another,
+    /// as a `MutexGuard` may not
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct PinnedKey(u64, PhantomData<MutexGuard<'static, ()>>);
+
+    #[test]
+    fn test_keys_only_shared_need_not_be_send() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            map.insert(PinnedKey(i, PhantomData), i);
+        }
+
+        let sequential: u64 = map.iter().map(|(k, v)| k.0 + v).sum();
+        assert_eq!(
+            map.par_iter().map(|(k, v)| k.0 + v).sum::<u64>(),
+            sequential
+        );
+        assert_eq!(map.par_keys().map(|k| k.0).sum::<u64>(), 499_500);
+        assert_eq!(map.par_values().sum::<u64>(), 499_500);
+
+        let total = AtomicU64::new(0);
+        map.par_for_each_leaf(|keys, _| {
+            let sum = keys.iter().map(|k| k.0).sum();
+            total.fetch_add(sum, Ordering::Relaxed);
+        });
+        assert_eq!(total.into_inner(), 499_500);
+    }
+
+    #[test]
+    fn test_par_for_each_leaf_on_single_leaf_and_empty_maps() {
+        let leaf = numbered_map(8, 5);
+        let calls = AtomicUsize::new(0);
+        leaf.par_for_each_leaf(|keys, values| {
+            assert_eq!(keys, &[0, 1, 2, 3, 4]);
+            assert_eq!(values, &[0, 3, 6, 9, 12]);
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(calls.into_inner(), 1);
+
+        let empty: BPlusTreeMap<u64, u64> = BPlusTreeMap::new();
+        empty.par_for_each_leaf(|_, _| panic!("an empty map has no leaves"));
+    }
+
+    /// Pairs in a scattered order, each key given about three times
+    fn shuffled_pairs(count: u64) -> Vec<(u64, u64)> {
+        (0..3 * count).map(|i| ((i * 7919) % count, i)).collect()
+    }
+
+    /// The keys of each leaf of `map`, in order
+    fn leaf_keys(map: &BPlusTreeMap<u64, u64>) -> Vec<Vec<u64>> {
+        let mut leaves = Vec::new();
+        map.for_each_leaf(|keys, _| leaves.push(keys.to_vec()));
+        leaves
+    }
+
+    #[test]
+    fn test_parallel_build_matches_sequential_ones() {
+        for count in [0, 1, 4, 5, 9, 100, 100_000] {
+            let pairs = shuffled_pairs(count);
+            let built = BPlusTreeMap::par_sort_build(pairs.clone());
+            let inserted: BPlusTreeMap<u64, u64> = pairs.iter().copied().collect();
+            let sorted = BPlusTreeMap::from_unsorted(pairs.iter().copied());
+
+            assert_eq!(built.len(), inserted.len());
+            assert!(built.iter().eq(inserted.iter()), "{} keys", count);
+            assert_eq!(built.check_invariants(), Ok(()));
+            assert_eq!(leaf_keys(&built), leaf_keys(&sorted));
+            assert_eq!(built.height(), sorted.height());
+        }
+    }
+
+    #[test]
+    fn test_parallel_builds_are_deterministic() {
+        let pairs = shuffled_pairs(50_000);
+        let first = BPlusTreeMap::par_sort_build(pairs.clone());
+        let second = BPlusTreeMap::par_sort_build(pairs);
+        assert!(first.iter().eq(second.iter()));
+        assert_eq!(leaf_keys(&first), leaf_keys(&second));
+    }
+
+    #[test]
+    fn test_collect_from_a_parallel_iterator() {
+        let pairs = shuffled_pairs(20_000);
+        let collected: BPlusTreeMap<u64, u64> = pairs.par_iter().copied().collect();
+        let inserted: BPlusTreeMap<u64, u64> = pairs.into_iter().collect();
+        assert!(collected.iter().eq(inserted.iter()));
+        assert_eq!(collected.check_invariants(), Ok(()));
+
+        // The map goes on changing as usual
+        let mut map: BPlusTreeMap<u64, u64> =
+            (0..1000u64).into_par_iter().map(|i| (i, i)).collect();
+        for i in (0..1000).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        map.insert(5000, 0);
+        assert_eq!(map.len(), 501);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+}
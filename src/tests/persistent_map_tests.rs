@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod persistent_map_tests {
+    use crate::persistent_map::SharedBPlusTreeMap;
+    use crate::tests::counting_allocator::allocations_during;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+    use std::thread;
+
+    const SIZE: u64 = 3000;
+
+    fn filled(branching_factor: usize) -> SharedBPlusTreeMap<u64, u64> {
+        let mut map = SharedBPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..SIZE {
+            let key = (i * 7919) % SIZE;
+            map.insert(key, key * 10);
+        }
+        map
+    }
+
+    /// Reference counts of the children of the root
+    fn root_child_counts(map: &SharedBPlusTreeMap<u64, u64>) -> Vec<usize> {
+        map.strong_counts()
+            .into_iter()
+            .filter(|&(depth, _)| depth == 1)
+            .map(|(_, count)| count)
+            .collect()
+    }
+
+    fn check_shape(branching_factor: usize) {
+        let mut map = SharedBPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..SIZE {
+            map.insert((i * 7919) % SIZE, i);
+            if i % 16 == 0 {
+                assert_eq!(map.check_invariants(), Ok(()));
+            }
+        }
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // Remove from the front, the back and all over, so every child
+        // position gets rebalanced, then empty the map entirely
+        for key in (0..SIZE / 4).chain((SIZE / 2..SIZE).rev()) {
+            assert!(map.remove(&key).is_some());
+            assert_eq!(map.check_invariants(), Ok(()), "after removing {}", key);
+        }
+        for i in 0..SIZE {
+            map.remove(&((i * 7919) % SIZE));
+            assert_eq!(map.check_invariants(), Ok(()));
+        }
+        assert!(map.is_empty());
+        assert!(map.strong_counts().is_empty());
+    }
+
+    #[test]
+    fn test_tree_stays_balanced() {
+        for branching_factor in [4, 5, 6, 7, 16] {
+            check_shape(branching_factor);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 4")]
+    fn test_small_branching_factor_is_rejected() {
+        SharedBPlusTreeMap::<u64, u64>::with_branching_factor(3);
+    }
+
+    #[test]
+    fn test_clone_shares_every_node() {
+        let mut map = SharedBPlusTreeMap::with_branching_factor(4);
+        for key in 0..SIZE {
+            map.insert(key, CountedValue(key));
+        }
+        let mut copy = None;
+        let allocations = allocations_during(|| {
+            assert_eq!(clones_during(|| copy = Some(map.clone())), 0);
+        });
+        assert_eq!(allocations, 0);
+        let copy = copy.unwrap();
+        assert_eq!(map.strong_counts()[0], (0, 2));
+        assert!(copy.iter().eq(map.iter()));
+    }
+
+    #[test]
+    fn test_changes_after_cloning_do_not_reach_the_clone() {
+        let mut map = filled(4);
+        let snapshot = map.clone();
+        for key in (0..SIZE).step_by(3) {
+            map.remove(&key);
+        }
+        for key in SIZE..SIZE + 500 {
+            map.insert(key, key);
+        }
+        for key in (1..SIZE).step_by(3) {
+            *map.get_mut(&key).unwrap() += 1;
+        }
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(snapshot.check_invariants(), Ok(()));
+        assert_eq!(snapshot.len(), SIZE as usize);
+        assert!(snapshot.iter().all(|(key, value)| *value == key * 10));
+
+        // Nor do changes to the clone reach the original
+        let mut copy = map.clone();
+        copy.clear();
+        copy.insert(0, 0);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.get(&0), None);
+        assert_eq!(map.len(), (SIZE - SIZE / 3 + 500) as usize);
+    }
+
+    #[test]
+    fn test_a_change_copies_only_its_path() {
+        let mut map = filled(4);
+        let snapshot = map.clone();
+        let children = root_child_counts(&map).len();
+        assert!(children >= 2);
+
+        // The root and the child holding the key are copied; the other
+        // children of the root are still shared with the snapshot
+        *map.get_mut(&0).unwrap() = 1;
+        let counts = root_child_counts(&map);
+        assert_eq!(counts[0], 1);
+        assert!(counts[1..].iter().all(|&count| count == 2), "{:?}", counts);
+        assert_eq!(map.strong_counts()[0], (0, 1));
+        assert_eq!(snapshot.get(&0), Some(&0));
+
+        // Changing it again copies nothing more
+        let allocations = allocations_during(|| *map.get_mut(&0).unwrap() = 2);
+        assert_eq!(allocations, 0);
+        assert_eq!(root_child_counts(&map), counts);
+    }
+
+    #[test]
+    fn test_misses_copy_nothing() {
+        let mut map = filled(4);
+        let snapshot = map.clone();
+        let allocations = allocations_during(|| {
+            assert_eq!(map.remove(&SIZE), None);
+            assert_eq!(map.get_mut(&SIZE), None);
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(map.strong_counts()[0], (0, 2));
+        drop(snapshot);
+        assert_eq!(map.strong_counts()[0], (0, 1));
+    }
+
+    #[test]
+    fn test_snapshots_match_the_map_when_they_were_taken() {
+        let mut map = SharedBPlusTreeMap::with_branching_factor(5);
+        let mut expected = BTreeMap::new();
+        let mut snapshots = Vec::new();
+        for i in 0..SIZE {
+            let key = (i * 7919) % (SIZE / 2);
+            if i % 3 == 0 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, i), expected.insert(key, i));
+            }
+            if i % 100 == 0 {
+                snapshots.push((map.clone(), expected.clone()));
+            }
+        }
+        for (snapshot, expected) in &snapshots {
+            assert_eq!(snapshot.check_invariants(), Ok(()));
+            assert!(snapshot.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_clones_can_be_read_on_other_threads() {
+        let mut map = filled(16);
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let snapshot = map.clone();
+                thread::spawn(move || {
+                    assert_eq!(snapshot.len(), SIZE as usize);
+                    snapshot.iter().all(|(key, value)| *value == key * 10)
+                })
+            })
+            .collect();
+        for key in 0..SIZE {
+            map.insert(key, key);
+        }
+        for reader in readers {
+            assert!(reader.join().unwrap());
+        }
+        assert!(map.iter().all(|(key, value)| key == value));
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod pop_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_pop_first_and_pop_last() {
+        let mut map = BPlusTreeMap::new();
+        map.insert(2, "two".to_string());
+        map.insert(1, "one".to_string());
+        map.insert(3, "three".to_string());
+
+        assert_eq!(map.pop_first(), Some((1, "one".to_string())));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.pop_last(), Some((3, "three".to_string())));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.pop_last(), Some((2, "two".to_string())));
+        assert!(map.is_empty());
+
+        // Popping an empty map does nothing
+        assert_eq!(map.pop_first(), None);
+        assert_eq!(map.pop_last(), None);
+        assert_eq!(map.len(), 0);
+    }
+
+    #[test]
+    fn test_pop_first_merges_emptied_leaf() {
+        // With a branching factor of 2 every few pops empties a leaf,
+        // which forces the parent branch to drop or merge children
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for i in 0..10 {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        for i in 0..10 {
+            assert_eq!(map.pop_first(), Some((i, i * 10)));
+            assert_eq!(map.len(), (9 - i) as usize);
+
+            // Every remaining key must still be reachable
+            for j in i + 1..10 {
+                assert_eq!(
+                    map.get(&j),
+                    Some(&(j * 10)),
+                    "key {} after popping {}",
+                    j,
+                    i
+                );
+            }
+        }
+        assert_eq!(map.pop_first(), None);
+    }
+
+    #[test]
+    fn test_pop_from_both_ends_until_empty() {
+        for branching_factor in 2..=6 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for i in 0..200 {
+                // Scatter the insertion order so the tree isn't built left to right
+                let key = (i * 37) % 200;
+                map.insert(key, key.to_string());
+                expected.insert(key, key.to_string());
+            }
+
+            let mut from_front = true;
+            while !expected.is_empty() {
+                let (popped, wanted) = if from_front {
+                    (map.pop_first(), expected.pop_first())
+                } else {
+                    (map.pop_last(), expected.pop_last())
+                };
+                assert_eq!(popped, wanted, "branching factor {}", branching_factor);
+                assert_eq!(map.len(), expected.len());
+                assert!(map.iter().eq(expected.iter()));
+                from_front = !from_front;
+            }
+
+            assert!(map.is_empty());
+            assert_eq!(map.pop_first(), None);
+            assert_eq!(map.pop_last(), None);
+        }
+    }
+}
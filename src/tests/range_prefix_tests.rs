@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod range_prefix_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use std::collections::BTreeMap;
+
+    type UserKey = (u32, u32);
+
+    /// Builds maps keyed by `(user, timestamp)` where user `u` has `u % 7`
+    /// entries, so the runs of keys for one user have many different lengths
+    fn user_maps(
+        branching_factor: usize,
+    ) -> (BPlusTreeMap<UserKey, String>, BTreeMap<UserKey, String>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        for user in (0..60).rev() {
+            for timestamp in 0..user % 7 {
+                let key = (user, timestamp * 100);
+                map.insert(key, format!("{}@{}", user, timestamp));
+                expected.insert(key, format!("{}@{}", user, timestamp));
+            }
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_range_prefix_with_numeric_tuples() {
+        for branching_factor in 2..=6 {
+            let (map, expected) = user_maps(branching_factor);
+            for user in 0..62 {
+                let wanted: Vec<_> = expected.iter().filter(|(key, _)| key.0 == user).collect();
+                let actual: Vec<_> = map.range_prefix(&user).collect();
+                assert_eq!(
+                    actual, wanted,
+                    "user {}, branching factor {}",
+                    user, branching_factor
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_prefix_spanning_several_leaves() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for user in [1u32, 2, 3] {
+            for timestamp in 0..50u32 {
+                map.insert((user, timestamp), timestamp);
+            }
+        }
+
+        let timestamps: Vec<u32> = map.range_prefix(&2).map(|(_, v)| *v).collect();
+        assert_eq!(timestamps, (0..50).collect::<Vec<_>>());
+        assert_eq!(map.range_prefix(&0).count(), 0);
+        assert_eq!(map.range_prefix(&4).count(), 0);
+    }
+
+    #[test]
+    fn test_range_prefix_with_string_tuples() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        let mut expected = BTreeMap::new();
+        for (i, name) in ["bob", "alice", "carol", "al", "alicia", "bobby"]
+            .iter()
+            .enumerate()
+        {
+            for seq in 0..(i as u64 + 2) {
+                map.insert((name.to_string(), seq), seq);
+                expected.insert((name.to_string(), seq), seq);
+            }
+        }
+
+        for name in [
+            "al", "alice", "alicia", "bob", "bobby", "carol", "a", "dave", "",
+        ] {
+            let wanted: Vec<_> = expected.iter().filter(|(key, _)| key.0 == name).collect();
+            // The prefix is borrowed as a str, no String needs building
+            let actual: Vec<_> = map.range_prefix(name).collect();
+            assert_eq!(actual, wanted, "name {:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_range_prefix_with_longer_tuples() {
+        let mut triples = BPlusTreeMap::with_branching_factor(4);
+        let mut quadruples = BPlusTreeMap::with_branching_factor(4);
+        for a in 0..10u8 {
+            for b in 0..5u8 {
+                triples.insert((a, b, a as u32 * b as u32), ());
+                quadruples.insert((a, b, 1000u32, "x"), ());
+            }
+        }
+
+        assert!(triples.range_prefix(&7).map(|(key, _)| key.1).eq(0..5));
+        assert!(quadruples.range_prefix(&3).map(|(key, _)| key.1).eq(0..5));
+        assert_eq!(triples.range_prefix(&10).count(), 0);
+    }
+
+    #[test]
+    fn test_range_prefix_on_empty_map() {
+        let map = BPlusTreeMap::<(u32, u32), ()>::new();
+        assert_eq!(map.range_prefix(&1).count(), 0);
+    }
+}
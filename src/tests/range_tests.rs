@@ -0,0 +1,297 @@
+#[cfg(test)]
+mod range_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    /// Builds a multi-level tree holding the even numbers 0..100 and a
+    /// BTreeMap with the same contents to compare against
+    fn even_number_maps() -> (BPlusTreeMap<i32, String>, BTreeMap<i32, String>) {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        let mut expected = BTreeMap::new();
+        for i in (0..100).step_by(2) {
+            map.insert(i, format!("value_{}", i));
+            expected.insert(i, format!("value_{}", i));
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_range_with_all_bound_kinds() {
+        let (map, expected) = even_number_maps();
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        let bounds = |key: i32| vec![Bound::Included(key), Bound::Excluded(key), Bound::Unbounded];
+
+        // Bounds landing on stored keys, between keys, and outside the map
+        for start_key in -2..102 {
+            for end_key in start_key..102 {
+                for start in bounds(start_key) {
+                    for end in bounds(end_key) {
+                        if start == Bound::Excluded(start_key)
+                            && end == Bound::Excluded(end_key)
+                            && start_key == end_key
+                        {
+                            continue;
+                        }
+                        let actual: Vec<_> = map.range((start, end)).collect();
+                        let wanted: Vec<_> = expected.range((start, end)).collect();
+                        assert_eq!(actual, wanted, "range ({:?}, {:?})", start, end);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_from_both_ends() {
+        let (map, expected) = even_number_maps();
+
+        for start in -2..102 {
+            for end in start..102 {
+                let backward: Vec<_> = map.range(start..end).rev().collect();
+                let wanted: Vec<_> = expected.range(start..end).rev().collect();
+                assert_eq!(backward, wanted, "range {}..{}", start, end);
+
+                // Taking from alternate ends meets in the middle exactly once
+                let mut range = map.range(start..=end);
+                let mut wanted = expected.range(start..=end);
+                loop {
+                    let (front, wanted_front) = (range.next(), wanted.next());
+                    assert_eq!(front, wanted_front, "range {}..={}", start, end);
+                    let (back, wanted_back) = (range.next_back(), wanted.next_back());
+                    assert_eq!(back, wanted_back, "range {}..={}", start, end);
+                    if front.is_none() && back.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_syntax_forms() {
+        let (map, _) = even_number_maps();
+
+        let keys: Vec<i32> = map.range(10..20).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![10, 12, 14, 16, 18]);
+
+        let keys: Vec<i32> = map.range(..=6).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![0, 2, 4, 6]);
+
+        let keys: Vec<i32> = map.range(93..).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![94, 96, 98]);
+
+        assert_eq!(map.range(..).count(), 50);
+        assert_eq!(map.range(11..12).count(), 0);
+        assert_eq!(map.range(10..10).count(), 0);
+        assert_eq!(map.range(200..).count(), 0);
+        assert_eq!(map.range(..-5).count(), 0);
+    }
+
+    #[test]
+    fn test_range_stops_at_end_bound() {
+        let (map, _) = even_number_maps();
+
+        // The iterator stays exhausted once it passes the end of the range
+        let mut range = map.range(4..=8);
+        assert_eq!(range.next(), Some((&4, &"value_4".to_string())));
+        assert_eq!(range.next(), Some((&6, &"value_6".to_string())));
+        assert_eq!(range.next(), Some((&8, &"value_8".to_string())));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next(), None);
+    }
+
+    #[test]
+    fn test_range_on_empty_and_single_leaf_maps() {
+        let empty = BPlusTreeMap::<i32, String>::new();
+        assert_eq!(empty.range(..).count(), 0);
+        assert_eq!(empty.range(1..5).count(), 0);
+
+        let mut map = BPlusTreeMap::new();
+        map.insert(1, "one".to_string());
+        map.insert(3, "three".to_string());
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+
+        let entries: Vec<_> = map.range(2..).collect();
+        assert_eq!(entries, vec![(&3, &"three".to_string())]);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn test_range_with_start_after_end() {
+        let (map, _) = even_number_maps();
+        let _ = map.range((Bound::Included(20), Bound::Excluded(10)));
+    }
+
+    #[test]
+    #[should_panic(expected = "range start and end are equal and excluded")]
+    fn test_range_with_equal_excluded_bounds() {
+        let (map, _) = even_number_maps();
+        let _ = map.range((Bound::Excluded(10), Bound::Excluded(10)));
+    }
+
+    #[test]
+    fn test_range_mut_updates_only_keys_in_range() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..50 {
+            map.insert(i, 0);
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        // Bump the counters for all keys in [10, 30)
+        for (k, v) in map.range_mut(10..30) {
+            assert!((10..30).contains(k));
+            *v += 1;
+        }
+
+        for i in 0..50 {
+            let expected = if (10..30).contains(&i) { 1 } else { 0 };
+            assert_eq!(map.get(&i), Some(&expected), "key {}", i);
+        }
+    }
+
+    #[test]
+    fn test_range_mut_matches_range() {
+        let (mut map, _) = even_number_maps();
+
+        for (start, end) in [(-10, 5), (7, 7), (13, 61), (50, 51), (95, 200)] {
+            let expected: Vec<i32> = map.range(start..end).map(|(k, _)| *k).collect();
+            let actual: Vec<i32> = map.range_mut(start..end).map(|(k, _)| *k).collect();
+            assert_eq!(actual, expected, "range {}..{}", start, end);
+        }
+
+        let expected: Vec<i32> = map.range(..=20).map(|(k, _)| *k).collect();
+        let actual: Vec<i32> = map.range_mut(..=20).map(|(k, _)| *k).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_range_mut_edge_cases() {
+        let (mut map, _) = even_number_maps();
+
+        // A range entirely before the first key
+        assert_eq!(map.range_mut(..0).count(), 0);
+        assert_eq!(map.range_mut(-10..-1).count(), 0);
+
+        // A range entirely after the last key
+        assert_eq!(map.range_mut(99..).count(), 0);
+        assert_eq!(map.range_mut(150..200).count(), 0);
+
+        // A range where start == end
+        assert_eq!(map.range_mut(10..10).count(), 0);
+        assert_eq!(map.range_mut(10..=10).count(), 1);
+
+        // Empty map
+        let mut empty = BPlusTreeMap::<i32, i32>::new();
+        assert_eq!(empty.range_mut(..).count(), 0);
+    }
+
+    #[test]
+    fn test_range_clone_and_debug() {
+        let (mut map, mut expected) = even_number_maps();
+
+        let mut range = map.range(13..61);
+        let mut wanted = expected.range(13..61);
+        range.by_ref().take(5).for_each(drop);
+        wanted.by_ref().take(5).for_each(drop);
+        assert!(range.clone().eq(wanted.clone()));
+        assert_eq!(format!("{:?}", range), format!("{:?}", wanted));
+
+        for (start, end) in [(-10, 5), (7, 7), (13, 61), (50, 51), (95, 200)] {
+            let mut range = map.range_mut(start..end);
+            let mut wanted = expected.range_mut(start..end);
+            assert_eq!(format!("{:?}", range), format!("{:?}", wanted));
+            range.next();
+            wanted.next();
+            assert_eq!(
+                format!("{:?}", range),
+                format!("{:?}", wanted),
+                "range {}..{}",
+                start,
+                end
+            );
+        }
+        assert_eq!(
+            format!("{:?}", map.range_mut(..=20)),
+            format!("{:?}", expected.range_mut(..=20))
+        );
+    }
+
+    #[test]
+    fn test_range_with_str_bounds_over_string_keys() {
+        let words = [
+            "apple",
+            "apricot",
+            "banana",
+            "blueberry",
+            "cherry",
+            "date",
+            "fig",
+            "grape",
+            "kiwi",
+            "lemon",
+            "mango",
+            "melon",
+            "nectarine",
+            "orange",
+        ];
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        let mut expected = BTreeMap::new();
+        for word in words.iter().rev() {
+            map.insert(word.to_string(), word.len());
+            expected.insert(word.to_string(), word.len());
+        }
+
+        // Bounds on an unsized type are given as a pair of `Bound`s
+        for bounds in [
+            (Bound::Included("a"), Bound::Excluded("m")),
+            (Bound::Included("banana"), Bound::Included("kiwi")),
+            (Bound::Excluded("date"), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Excluded("cherry")),
+            (Bound::Included("x"), Bound::Unbounded),
+        ] {
+            assert!(
+                map.range::<str, _>(bounds)
+                    .eq(expected.range::<str, _>(bounds)),
+                "{:?}",
+                bounds
+            );
+        }
+
+        for (_, len) in map.range_mut::<str, _>((Bound::Included("m"), Bound::Unbounded)) {
+            *len *= 10;
+        }
+        assert_eq!(map.get("mango"), Some(&50));
+        assert_eq!(map.get("lemon"), Some(&5));
+    }
+
+    #[test]
+    fn test_range_with_byte_slice_bounds_over_vec_keys() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        let mut expected = BTreeMap::new();
+        for i in 0..200u32 {
+            let key = (i * 7919 % 200).to_be_bytes()[2..].to_vec();
+            map.insert(key.clone(), i);
+            expected.insert(key, i);
+        }
+
+        let low: &[u8] = &[0, 30];
+        let high: &[u8] = &[0, 150];
+        let bounds = (Bound::Included(low), Bound::Excluded(high));
+        assert!(
+            map.range::<[u8], _>(bounds)
+                .eq(expected.range::<[u8], _>(bounds))
+        );
+        assert_eq!(map.range::<[u8], _>(bounds).count(), 120);
+
+        let up_to_low = (Bound::Unbounded, Bound::Included(low));
+        assert!(
+            map.range_mut::<[u8], _>(up_to_low)
+                .map(|(k, v)| (k.clone(), *v))
+                .eq(expected
+                    .range::<[u8], _>(up_to_low)
+                    .map(|(k, v)| (k.clone(), *v)))
+        );
+    }
+}
@@ -0,0 +1,205 @@
+#[cfg(test)]
+mod read_only_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::read_only::ReadOnlyBPlusTree;
+    use crate::snapshot::{Checksum, SnapshotError};
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::ops::Bound;
+
+    /// Builds a map of the even numbers below `2 * count`, inserted in a
+    /// scattered order, and its snapshot
+    fn even_number_snapshot(
+        branching_factor: usize,
+        count: u32,
+    ) -> (BPlusTreeMap<u32, String>, Vec<u8>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            let key = (i * 7919) % count * 2;
+            map.insert(key, format!("value_{}", key));
+        }
+        let mut bytes = Vec::new();
+        map.save_to(&mut bytes).unwrap();
+        (map, bytes)
+    }
+
+    /// Replaces the checksum at the end of `bytes` with the right one for
+    /// the rest of them, so that other checks can be reached
+    fn reseal(bytes: &mut [u8]) {
+        let end = bytes.len() - 8;
+        let mut checksum = Checksum::new();
+        checksum.update(&bytes[4..end]);
+        bytes[end..].copy_from_slice(&checksum.value().to_le_bytes());
+    }
+
+    #[test]
+    fn test_lookups_match_the_written_map() {
+        for branching_factor in 2..=7 {
+            let (map, bytes) = even_number_snapshot(branching_factor, 300);
+            let tree = ReadOnlyBPlusTree::<u32, String>::from_bytes(&bytes).unwrap();
+
+            assert_eq!(tree.len(), map.len());
+            assert_eq!(tree.branching_factor(), branching_factor);
+            for key in 0..602 {
+                assert_eq!(tree.get(&key).as_ref(), map.get(&key), "key {}", key);
+                assert_eq!(tree.contains_key(&key), map.contains_key(&key));
+            }
+            assert!(tree.iter().eq(map.iter().map(|(k, v)| (*k, v.clone()))));
+            assert_eq!(tree.iter().size_hint(), (map.len(), Some(map.len())));
+        }
+    }
+
+    #[test]
+    fn test_ranges_match_the_written_map() {
+        let (map, bytes) = even_number_snapshot(4, 60);
+        let tree = ReadOnlyBPlusTree::<u32, String>::from_bytes(&bytes).unwrap();
+        let bounds = |key: u32| [Bound::Included(key), Bound::Excluded(key), Bound::Unbounded];
+
+        for start_key in 0..122 {
+            for end_key in start_key..122 {
+                for start in bounds(start_key) {
+                    for end in bounds(end_key) {
+                        if let (Bound::Excluded(_), Bound::Excluded(_)) = (start, end)
+                            && start_key == end_key
+                        {
+                            continue;
+                        }
+                        let actual: Vec<_> = tree.range((start, end)).collect();
+                        let wanted: Vec<_> = map
+                            .range((start, end))
+                            .map(|(k, v)| (*k, v.clone()))
+                            .collect();
+                        assert_eq!(actual, wanted, "range ({:?}, {:?})", start, end);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookups_with_borrowed_keys() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for word in ["pear", "apple", "fig", "kiwi", "plum", "date", "lime"] {
+            map.insert(word.to_string(), word.len() as u32);
+        }
+        let mut bytes = Vec::new();
+        map.save_to(&mut bytes).unwrap();
+        let tree = ReadOnlyBPlusTree::<String, u32>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(tree.get("kiwi"), Some(4));
+        assert_eq!(tree.get("grape"), None);
+        let keys: Vec<String> = tree
+            .range::<str, _>((Bound::Included("d"), Bound::Excluded("l")))
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, vec!["date", "fig", "kiwi"]);
+        let last: Vec<_> = tree
+            .range::<str, _>((Bound::Excluded("pear"), Bound::Unbounded))
+            .collect();
+        assert_eq!(last, vec![("plum".to_string(), 4)]);
+    }
+
+    #[test]
+    fn test_empty_snapshot() {
+        let map: BPlusTreeMap<u32, u32> = BPlusTreeMap::new();
+        let mut bytes = Vec::new();
+        map.save_to(&mut bytes).unwrap();
+        let tree = ReadOnlyBPlusTree::<u32, u32>::from_bytes(&bytes).unwrap();
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.get(&1), None);
+        assert_eq!(tree.iter().next(), None);
+        assert_eq!(tree.range(1..5).next(), None);
+        assert_eq!(format!("{:?}", tree), "{}");
+    }
+
+    #[test]
+    fn test_snapshot_read_back_from_a_file() {
+        let (map, _) = even_number_snapshot(6, 2000);
+        let path = std::env::temp_dir().join(format!("bplus_read_only_{}.bin", std::process::id()));
+        map.save_to(BufWriter::new(File::create(&path).unwrap()))
+            .unwrap();
+        let bytes = std::fs::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let bytes = bytes.unwrap();
+        let tree = ReadOnlyBPlusTree::<u32, String>::from_bytes(&bytes).unwrap();
+        assert_eq!(tree.get(&1000), Some("value_1000".to_string()));
+        assert!(
+            tree.range(100..=140)
+                .eq(map.range(100..=140).map(|(k, v)| (*k, v.clone())))
+        );
+    }
+
+    #[test]
+    fn test_corrupt_snapshots_are_rejected() {
+        let (_, bytes) = even_number_snapshot(4, 30);
+
+        for len in 0..bytes.len() {
+            assert!(
+                ReadOnlyBPlusTree::<u32, String>::from_bytes(&bytes[..len]).is_err(),
+                "length {}",
+                len
+            );
+        }
+        for position in 0..bytes.len() {
+            let mut corrupt = bytes.clone();
+            corrupt[position] ^= 0x10;
+            assert!(
+                ReadOnlyBPlusTree::<u32, String>::from_bytes(&corrupt).is_err(),
+                "flipped byte {}",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn test_resealed_snapshots_are_still_checked() {
+        let (_, bytes) = even_number_snapshot(4, 30);
+        let index_offset =
+            u64::from_le_bytes(bytes[bytes.len() - 16..bytes.len() - 8].try_into().unwrap())
+                as usize;
+        let check = |corrupt: &mut Vec<u8>| {
+            reseal(corrupt);
+            match ReadOnlyBPlusTree::<u32, String>::from_bytes(corrupt) {
+                Err(SnapshotError::Invalid(problem)) => problem,
+                other => panic!(
+                    "expected an invalid snapshot, got {:?}",
+                    other.map(|tree| tree.len())
+                ),
+            }
+        };
+
+        // An entry count that doesn't match the entries
+        let mut corrupt = bytes.clone();
+        corrupt[13..21].copy_from_slice(&31u64.to_le_bytes());
+        assert_eq!(
+            check(&mut corrupt),
+            "header counts 31 entries but 30 are stored"
+        );
+
+        // A leaf offset in the middle of an entry
+        let mut corrupt = bytes.clone();
+        let second_leaf = index_offset + 16;
+        let offset = u64::from_le_bytes(corrupt[second_leaf..second_leaf + 8].try_into().unwrap());
+        corrupt[second_leaf..second_leaf + 8].copy_from_slice(&(offset + 1).to_le_bytes());
+        assert_eq!(
+            check(&mut corrupt),
+            format!(
+                "leaf index offset {} is not the start of an entry",
+                offset + 1
+            )
+        );
+
+        // A leaf index offset past the end
+        let mut corrupt = bytes.clone();
+        let end = corrupt.len();
+        corrupt[end - 16..end - 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(check(&mut corrupt).contains("out of bounds"));
+
+        // Keys out of order: the first key is made larger than the second
+        let mut corrupt = bytes.clone();
+        corrupt[21..25].copy_from_slice(&3u32.to_le_bytes());
+        assert!(check(&mut corrupt).contains("is not below the key"));
+    }
+}
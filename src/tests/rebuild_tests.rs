@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod rebuild_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Node};
+    use crate::separator::ShortestSeparators;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+
+    fn scattered(
+        len: u64,
+        branching_factor: usize,
+    ) -> (BPlusTreeMap<u64, u64>, BTreeMap<u64, u64>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        for i in 0..len {
+            let key = (i * 7919) % (2 * len);
+            map.insert(key, i);
+            expected.insert(key, i);
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_rebuild_up() {
+        let (mut map, mut expected) = scattered(10_000, 4);
+        let height = map.height();
+        map.rebuild_with_branching_factor(64);
+
+        assert_eq!(map.branching_factor(), 64);
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(map.leaf_count(), expected.len().div_ceil(64));
+        assert!(map.height() < height);
+
+        // Later changes split and merge at the new factor
+        for i in 0..20_000 {
+            if i % 3 == 0 {
+                assert_eq!(map.remove(&i), expected.remove(&i));
+            } else {
+                assert_eq!(map.insert(i, i), expected.insert(i, i));
+            }
+        }
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert!(map.leaves().iter().all(|leaf| leaf.keys.len() <= 64));
+    }
+
+    #[test]
+    fn test_rebuild_down() {
+        let (mut map, mut expected) = scattered(10_000, 64);
+        let height = map.height();
+        map.rebuild_with_branching_factor(3);
+
+        assert_eq!(map.branching_factor(), 3);
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.iter().rev().eq(expected.iter().rev()));
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert!(map.height() > height);
+
+        for i in 20_000..25_000 {
+            assert_eq!(map.insert(i, i), expected.insert(i, i));
+        }
+        assert!(map.range(9_000..21_000).eq(expected.range(9_000..21_000)));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_rebuild_empty_and_small_maps() {
+        let mut empty = BPlusTreeMap::<u64, u64>::new();
+        empty.rebuild_with_branching_factor(16);
+        assert!(empty.is_empty());
+        assert_eq!(empty.branching_factor(), 16);
+        empty.insert(1, 1);
+        assert_eq!(empty.get(&1), Some(&1));
+
+        let (mut map, expected) = scattered(5, 4);
+        map.rebuild_with_branching_factor(2);
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_rebuild_moves_values() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            map.insert(i, CountedValue(i));
+        }
+        assert_eq!(clones_during(|| map.rebuild_with_branching_factor(32)), 0);
+        assert!(map.values().map(|value| value.0).eq(0..1000));
+    }
+
+    #[test]
+    fn test_rebuild_keeps_the_separator_policy() {
+        let mut map = BPlusTreeMap::with_separator_policy::<ShortestSeparators>(4);
+        for i in 0..500 {
+            map.insert(format!("{:03}{}", (i * 7) % 500, "x".repeat(50)), i);
+        }
+        map.rebuild_with_branching_factor(8);
+        for i in 500..2000 {
+            map.insert(format!("{:04}{}", i, "x".repeat(50)), i);
+        }
+        assert_eq!(map.len(), 2000);
+        assert_eq!(map.check_invariants(), Ok(()));
+        // The leaves split since the rebuild promoted short separators
+        fn short_separators(node: &Node<String, u64>) -> usize {
+            match node {
+                Node::Leaf(_) => 0,
+                Node::Branch(branch) => {
+                    let here = branch.keys.iter().filter(|key| key.len() < 10).count();
+                    here + branch
+                        .children
+                        .iter()
+                        .map(|child| short_separators(child))
+                        .sum::<usize>()
+                }
+            }
+        }
+        assert!(short_separators(map.root_node().unwrap()) > 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Branching factor must be at least 2")]
+    fn test_rebuild_with_too_small_a_factor() {
+        let (mut map, _) = scattered(10, 4);
+        map.rebuild_with_branching_factor(1);
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod remove_clone_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+
+    fn filled(branching_factor: usize, len: u64) -> BPlusTreeMap<u64, CountedValue> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..len {
+            let key = (i * 7919) % len;
+            map.insert(key, CountedValue(key));
+        }
+        map
+    }
+
+    #[test]
+    fn test_delete_heavy_workload_does_not_clone_values() {
+        for branching_factor in [3, 4, 5, 16] {
+            let mut map = filled(branching_factor, 3000);
+            // Remove three keys in four, in an order unrelated to the
+            // inserts, so siblings are merged and rebalanced all over
+            let clones = clones_during(|| {
+                for i in 0..3000 {
+                    let key = (i * 7907) % 3000;
+                    if key % 4 != 0 {
+                        assert_eq!(map.remove(&key), Some(CountedValue(key)));
+                    }
+                }
+            });
+            assert_eq!(clones, 0, "branching factor {}", branching_factor);
+            assert!(map.keys().copied().eq((0..3000).step_by(4)));
+        }
+    }
+
+    #[test]
+    fn test_emptying_the_map_does_not_clone_values() {
+        let mut map = filled(4, 1000);
+        let clones = clones_during(|| {
+            for key in (0..1000).rev() {
+                map.remove(&key);
+            }
+            map.insert(1, CountedValue(1));
+            map.remove(&1);
+        });
+        assert_eq!(clones, 0);
+        assert!(map.is_empty());
+    }
+}
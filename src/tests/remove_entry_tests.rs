@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod remove_entry_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_remove_entry_returns_stored_key() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for word in [
+            "apple",
+            "banana",
+            "cherry",
+            "date",
+            "elderberry",
+            "fig",
+            "grape",
+        ] {
+            map.insert(word.to_string(), word.len());
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        // The owned key comes back even when looking it up with a &str
+        assert_eq!(map.remove_entry("cherry"), Some(("cherry".to_string(), 6)));
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.get("cherry"), None);
+
+        assert_eq!(map.remove_entry("cherry"), None);
+        assert_eq!(map.len(), 6);
+    }
+
+    #[test]
+    fn test_remove_entry_missing_key() {
+        let mut map = BPlusTreeMap::<i32, i32>::new();
+        assert_eq!(map.remove_entry(&1), None);
+
+        for i in (0..50).step_by(2) {
+            map.insert(i, i);
+        }
+        assert_eq!(map.remove_entry(&-1), None);
+        assert_eq!(map.remove_entry(&25), None);
+        assert_eq!(map.remove_entry(&50), None);
+        assert_eq!(map.len(), 25);
+    }
+
+    #[test]
+    fn test_remove_entry_matches_remove() {
+        for branching_factor in 2..=5 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for i in 0..200 {
+                let key = (i * 37) % 200;
+                map.insert(key, key * 10);
+                expected.insert(key, key * 10);
+            }
+
+            for i in 0..200 {
+                let key = (i * 53) % 200;
+                assert_eq!(map.remove_entry(&key), expected.remove_entry(&key));
+                assert_eq!(map.len(), expected.len());
+                assert!(
+                    map.iter().eq(expected.iter()),
+                    "bf {} after removing {}",
+                    branching_factor,
+                    key
+                );
+            }
+            assert!(map.is_empty());
+        }
+    }
+}
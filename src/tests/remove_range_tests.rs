@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod remove_range_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+    use std::ops::{Bound, RangeBounds};
+
+    fn numbered_map(branching_factor: usize, count: i32) -> BPlusTreeMap<i32, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            map.insert(i, format!("value_{}", i));
+        }
+        map
+    }
+
+    #[test]
+    fn test_remove_range_middle() {
+        let mut map = numbered_map(3, 100);
+        assert_eq!(map.remove_range(20..80), 60);
+
+        assert_eq!(map.len(), 40);
+        let keys: Vec<i32> = map.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, (0..20).chain(80..100).collect::<Vec<_>>());
+        assert_eq!(map.get(&19), Some(&"value_19".to_string()));
+        assert_eq!(map.get(&20), None);
+        assert_eq!(map.get(&80), Some(&"value_80".to_string()));
+    }
+
+    #[test]
+    fn test_remove_range_everything() {
+        let mut map = numbered_map(3, 100);
+        assert_eq!(map.remove_range(..), 100);
+
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+        assert_eq!(map.iter().count(), 0);
+
+        // The emptied map is still usable
+        map.insert(7, "seven".to_string());
+        assert_eq!(map.get(&7), Some(&"seven".to_string()));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_range_nothing() {
+        let mut map = numbered_map(4, 50);
+        let before: Vec<(i32, String)> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+
+        assert_eq!(map.remove_range(100..200), 0);
+        assert_eq!(map.remove_range(..0), 0);
+        assert_eq!(map.remove_range(10..10), 0);
+
+        assert_eq!(map.len(), 50);
+        let after: Vec<(i32, String)> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+        assert_eq!(after, before);
+
+        let mut empty = BPlusTreeMap::<i32, String>::new();
+        assert_eq!(empty.remove_range(..), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_remove_range_matches_btree_map() {
+        let bounds = |key: i32| vec![Bound::Included(key), Bound::Excluded(key), Bound::Unbounded];
+
+        for branching_factor in [2, 3, 5] {
+            for start_key in (-5..205).step_by(30) {
+                for end_key in (start_key..205).step_by(45) {
+                    for start in bounds(start_key) {
+                        for end in bounds(end_key) {
+                            if start == Bound::Excluded(start_key)
+                                && end == Bound::Excluded(end_key)
+                                && start_key == end_key
+                            {
+                                continue;
+                            }
+
+                            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                            let mut expected = BTreeMap::new();
+                            for i in 0..200 {
+                                // Scatter the insertion order so the tree isn't built left to right
+                                let key = (i * 37) % 200;
+                                map.insert(key, key);
+                                expected.insert(key, key);
+                            }
+
+                            let removed = map.remove_range((start, end));
+                            let before = expected.len();
+                            expected.retain(|k, _| !(start, end).contains(k));
+
+                            let context =
+                                format!("bf {} range ({:?}, {:?})", branching_factor, start, end);
+                            assert_eq!(removed, before - expected.len(), "{}", context);
+                            assert_eq!(map.len(), expected.len(), "{}", context);
+                            assert!(map.iter().eq(expected.iter()), "{}", context);
+                            assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                            assert_eq!(map.check_occupancy(), Ok(()), "{}", context);
+
+                            // The tree stays usable for further inserts and removes
+                            for i in (0..200).step_by(7) {
+                                assert_eq!(
+                                    map.insert(i, -i).is_none(),
+                                    expected.insert(i, -i).is_none()
+                                );
+                            }
+                            for i in (0..200).step_by(3) {
+                                assert_eq!(map.remove(&i), expected.remove(&i), "{}", context);
+                            }
+                            assert!(map.iter().eq(expected.iter()), "{}", context);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that `map` is a whole tree holding `expected`, no taller than
+    /// `height`
+    fn assert_whole(map: &BPlusTreeMap<i32, i32>, expected: &BTreeMap<i32, i32>, height: usize) {
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(map.check_separators(), Ok(()));
+        assert_eq!(map.check_occupancy(), Ok(()));
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.height(), height, "{}", map.shape());
+    }
+
+    #[test]
+    fn test_removing_most_keys_leaves_a_short_whole_tree() {
+        let full = || {
+            let mut map = BPlusTreeMap::with_branching_factor(4);
+            map.extend((0..200).map(|key| (key, key)));
+            let expected: BTreeMap<i32, i32> = (0..200).map(|key| (key, key)).collect();
+            (map, expected)
+        };
+
+        // Four keys are left: a leaf at either end, or one full leaf
+        let (mut map, mut expected) = full();
+        assert_eq!(map.remove_range(2..198), 196);
+        expected.retain(|key, _| !(2..198).contains(key));
+        assert_whole(&map, &expected, 2);
+
+        let (mut map, mut expected) = full();
+        assert_eq!(map.remove_range(..196), 196);
+        expected.retain(|key, _| *key >= 196);
+        assert_whole(&map, &expected, 1);
+
+        // Two keys and a single key fit in one leaf
+        let (mut map, mut expected) = full();
+        assert_eq!(map.remove_range(1..199), 198);
+        expected.retain(|key, _| !(1..199).contains(key));
+        assert_whole(&map, &expected, 1);
+
+        let (mut map, mut expected) = full();
+        assert_eq!(map.remove_range(1..), 199);
+        expected.retain(|key, _| *key < 1);
+        assert_whole(&map, &expected, 1);
+    }
+
+    #[test]
+    fn test_random_ranges_keep_the_tree_whole() {
+        for branching_factor in 2..=8 {
+            let mut state: u64 = 0x9E37_79B9_7F4A_7C15 ^ branching_factor as u64;
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            };
+            for round in 0..40 {
+                let count = 1 + (next() % 400) as i32;
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut expected = BTreeMap::new();
+                for i in 0..count {
+                    let key = (i * 37) % count;
+                    map.insert(key, key);
+                    expected.insert(key, key);
+                }
+                // Take out ranges until little is left
+                while expected.len() > 2 {
+                    let start = (next() % count as u64) as i32;
+                    let end = start + (next() % (count - start) as u64) as i32 + 1;
+                    let before = expected.len();
+                    expected.retain(|key, _| !(start..end).contains(key));
+                    assert_eq!(map.remove_range(start..end), before - expected.len());
+
+                    let context = format!(
+                        "bf {} round {} range {}..{}",
+                        branching_factor, round, start, end
+                    );
+                    assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                    assert_eq!(map.check_occupancy(), Ok(()), "{}", context);
+                    assert!(map.iter().eq(expected.iter()), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn test_remove_range_with_start_after_end() {
+        let mut map = numbered_map(3, 20);
+        map.remove_range((Bound::Included(15), Bound::Excluded(5)));
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod replace_key_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::cmp::Ordering;
+    use std::hash::{Hash, Hasher};
+
+    /// A key ordered by its id alone, carrying a timestamp that plays no
+    /// part in comparisons
+    #[derive(Clone, Debug)]
+    struct Stamped {
+        id: u32,
+        stamp: u64,
+    }
+
+    impl Stamped {
+        fn new(id: u32, stamp: u64) -> Self {
+            Stamped { id, stamp }
+        }
+    }
+
+    impl PartialEq for Stamped {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Stamped {}
+
+    impl PartialOrd for Stamped {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Stamped {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.id.cmp(&other.id)
+        }
+    }
+
+    // Hashes agree with equality, by id alone
+    impl Hash for Stamped {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    #[test]
+    fn test_replace_key_swaps_the_stored_key() {
+        let mut map = BPlusTreeMap::new();
+        map.insert(Stamped::new(1, 100), "one");
+        map.insert(Stamped::new(2, 200), "two");
+
+        let old = map.replace_key(Stamped::new(1, 101)).unwrap();
+        assert_eq!(old.stamp, 100);
+
+        assert_eq!(map.get_key(&Stamped::new(1, 0)).unwrap().stamp, 101);
+        assert_eq!(map.get(&Stamped::new(1, 0)), Some(&"one"));
+        assert_eq!(map.get_key(&Stamped::new(2, 0)).unwrap().stamp, 200);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_replace_missing_key_does_not_insert() {
+        let mut map = BPlusTreeMap::new();
+        map.insert(Stamped::new(1, 100), "one");
+
+        assert!(map.replace_key(Stamped::new(5, 500)).is_none());
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key(&Stamped::new(5, 0)));
+
+        let mut empty = BPlusTreeMap::<Stamped, &str>::new();
+        assert!(empty.replace_key(Stamped::new(1, 1)).is_none());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_replace_key_with_stale_separators() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for id in 0..200 {
+            map.insert(Stamped::new(id, 0), id * 10);
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        // Refresh every key, including those copied into separators
+        for id in 0..200 {
+            let old = map.replace_key(Stamped::new(id, 1)).unwrap();
+            assert_eq!(old.stamp, 0);
+        }
+
+        // The leaves hold the new keys and the values are untouched
+        assert_eq!(map.len(), 200);
+        for (id, (key, value)) in (0..200).zip(map.iter()) {
+            assert_eq!(key.id, id);
+            assert_eq!(key.stamp, 1);
+            assert_eq!(*value, id * 10);
+        }
+        assert_eq!(map.check_invariants(), Ok(()));
+
+        // The separators still route lookups, inserts and removals correctly
+        for id in 0..200 {
+            assert_eq!(map.get(&Stamped::new(id, 9)), Some(&(id * 10)));
+            assert_eq!(map.get_key(&Stamped::new(id, 9)).unwrap().stamp, 1);
+        }
+        for id in 200..250 {
+            map.insert(Stamped::new(id, 2), id * 10);
+        }
+        for id in (0..250).step_by(2) {
+            assert_eq!(map.remove(&Stamped::new(id, 9)), Some(id * 10));
+        }
+        assert_eq!(map.len(), 125);
+        for id in 0..250 {
+            let expected = if id % 2 == 1 { Some(id * 10) } else { None };
+            assert_eq!(
+                map.get(&Stamped::new(id, 9)).copied(),
+                expected,
+                "id {}",
+                id
+            );
+        }
+    }
+}
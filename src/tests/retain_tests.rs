@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod retain_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+
+    fn numbered_map(branching_factor: usize, count: i32) -> BPlusTreeMap<i32, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            map.insert(i, format!("value_{}", i));
+        }
+        map
+    }
+
+    #[test]
+    fn test_retain_even_keys() {
+        let mut map = numbered_map(3, 100);
+        map.retain(|k, _| k % 2 == 0);
+
+        assert_eq!(map.len(), 50);
+        for i in 0..100 {
+            assert_eq!(map.contains_key(&i), i % 2 == 0, "key {}", i);
+        }
+        let keys: Vec<i32> = map.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, (0..100).step_by(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_retain_removing_everything() {
+        let mut map = numbered_map(3, 100);
+        map.retain(|_, _| false);
+
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+        assert_eq!(map.iter().count(), 0);
+
+        // The emptied map is still usable
+        map.insert(7, "seven".to_string());
+        assert_eq!(map.get(&7), Some(&"seven".to_string()));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_retain_removing_nothing() {
+        let mut map = numbered_map(4, 50);
+        let before: Vec<(i32, String)> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+
+        let mut calls = 0;
+        map.retain(|_, _| {
+            calls += 1;
+            true
+        });
+
+        assert_eq!(calls, 50);
+        assert_eq!(map.len(), 50);
+        let after: Vec<(i32, String)> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_retain_keeping_one_key_per_leaf() {
+        // Leaves of an ascending build hold a handful of keys each; keeping
+        // every fifth key leaves most of them with a single entry
+        for branching_factor in 2..=6 {
+            let mut map = numbered_map(branching_factor, 200);
+            let mut expected: BTreeMap<i32, String> =
+                (0..200).map(|i| (i, format!("value_{}", i))).collect();
+
+            map.retain(|k, _| k % 5 == 0);
+            expected.retain(|k, _| k % 5 == 0);
+
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+            for i in 0..200 {
+                assert_eq!(map.get(&i), expected.get(&i), "key {}", i);
+            }
+
+            // Inserting and removing afterwards still works
+            for i in 0..200 {
+                map.insert(i, format!("new_{}", i));
+            }
+            assert_eq!(map.len(), 200);
+            for i in (0..200).step_by(3) {
+                assert_eq!(map.remove(&i), Some(format!("new_{}", i)));
+            }
+            assert_eq!(map.len(), 200 - 67);
+        }
+    }
+
+    #[test]
+    fn test_retain_keeping_a_few_keys_leaves_a_short_whole_tree() {
+        for (keep, height) in [(6, 2), (4, 2), (2, 1), (1, 1)] {
+            let mut map = BPlusTreeMap::with_branching_factor(4);
+            map.extend((0..200).map(|key| (key, key)));
+            // Keep `keep` keys, split between the two ends
+            let low = keep / 2;
+            let kept = |key: &i32| *key < low || *key >= 200 - (keep - low);
+            map.retain(|key, _| kept(key));
+
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert_eq!(map.check_separators(), Ok(()));
+            assert_eq!(map.check_occupancy(), Ok(()));
+            assert!(map.keys().copied().eq((0..200).filter(kept)));
+            assert_eq!(map.height(), height, "{}", map.shape());
+        }
+    }
+
+    #[test]
+    fn test_retain_random_subsets_keeps_the_tree_whole() {
+        for branching_factor in 2..=8 {
+            let mut state: u64 = 0x2545_F491_4F6C_DD1D ^ branching_factor as u64;
+            for round in 0..40 {
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                let mut expected = BTreeMap::new();
+                for i in 0..300 {
+                    let key = (i * 37) % 300;
+                    map.insert(key, key);
+                    expected.insert(key, key);
+                }
+                // Keep about three keys in four, until none are left
+                while !expected.is_empty() {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let seed = state | 1;
+                    let keep = |key: &i32| (*key as u64).wrapping_mul(seed) >> 62 != 0;
+                    map.retain(|key, _| keep(key));
+                    expected.retain(|key, _| keep(key));
+
+                    let context = format!("bf {} round {}", branching_factor, round);
+                    assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                    assert_eq!(map.check_occupancy(), Ok(()), "{}", context);
+                    assert!(map.iter().eq(expected.iter()), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_retain_can_modify_values() {
+        let mut map = numbered_map(3, 30);
+        map.retain(|k, v| {
+            v.push_str("_kept");
+            *k >= 10
+        });
+
+        assert_eq!(map.len(), 20);
+        assert_eq!(map.get(&5), None);
+        assert_eq!(map.get(&10), Some(&"value_10_kept".to_string()));
+        assert_eq!(map.get(&29), Some(&"value_29_kept".to_string()));
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod reversed_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    /// Builds a map and a BTreeMap holding the same scattered keys
+    fn scattered_maps(branching_factor: usize) -> (BPlusTreeMap<i32, i32>, BTreeMap<i32, i32>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        for i in 0..120 {
+            let key = (i * 37) % 120 * 2;
+            map.insert(key, key * 10);
+            expected.insert(key, key * 10);
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_reversed_iterators_run_in_descending_order() {
+        for branching_factor in 2..=6 {
+            let (map, expected) = scattered_maps(branching_factor);
+            assert_eq!(map.root_kind(), RootKind::Branch);
+            let reversed = map.reversed();
+
+            assert_eq!(reversed.len(), expected.len());
+            assert!(!reversed.is_empty());
+            assert!(reversed.iter().eq(expected.iter().rev()));
+            assert!(reversed.keys().eq(expected.keys().rev()));
+            assert!(reversed.values().eq(expected.values().rev()));
+            assert_eq!(reversed.iter().len(), expected.len());
+        }
+    }
+
+    #[test]
+    fn test_reversed_first_and_last_key_value() {
+        for branching_factor in 2..=6 {
+            let (map, expected) = scattered_maps(branching_factor);
+
+            assert_eq!(map.first_key_value(), expected.first_key_value());
+            assert_eq!(map.last_key_value(), expected.last_key_value());
+            assert_eq!(map.reversed().first_key_value(), expected.last_key_value());
+            assert_eq!(map.reversed().last_key_value(), expected.first_key_value());
+        }
+    }
+
+    #[test]
+    fn test_reversed_range_is_range_reversed() {
+        for branching_factor in 2..=6 {
+            let (map, expected) = scattered_maps(branching_factor);
+            let reversed = map.reversed();
+
+            for start in -2..242 {
+                for end in (start..242).step_by(7) {
+                    let actual: Vec<_> = reversed.range(start..end).collect();
+                    let forward: Vec<_> = map.range(start..end).collect();
+                    let wanted: Vec<_> = expected.range(start..end).rev().collect();
+                    assert_eq!(actual, wanted, "range {}..{}", start, end);
+                    assert!(actual.iter().eq(forward.iter().rev()));
+                }
+            }
+
+            let actual: Vec<_> = reversed
+                .range((Bound::Excluded(10), Bound::Included(50)))
+                .collect();
+            let wanted: Vec<_> = expected
+                .range((Bound::Excluded(10), Bound::Included(50)))
+                .rev()
+                .collect();
+            assert_eq!(actual, wanted);
+        }
+    }
+
+    #[test]
+    fn test_reversed_empty_map() {
+        let map: BPlusTreeMap<i32, i32> = BPlusTreeMap::new();
+        let reversed = map.reversed();
+
+        assert!(reversed.is_empty());
+        assert_eq!(reversed.iter().next(), None);
+        assert_eq!(reversed.range(0..10).next(), None);
+        assert_eq!(reversed.first_key_value(), None);
+        assert_eq!(reversed.last_key_value(), None);
+        assert_eq!(format!("{:?}", reversed), "{}");
+    }
+
+    #[test]
+    fn test_reversed_debug_lists_descending_entries() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 1..=4 {
+            map.insert(i, i * 2);
+        }
+        assert_eq!(format!("{:?}", map.reversed()), "{4: 8, 3: 6, 2: 4, 1: 2}");
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod separator_refresh_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Node};
+    use std::collections::BTreeMap;
+
+    /// The separators of every branch under `node`, from the root down
+    fn separators(node: &Node<u64, u64>, depth: usize, found: &mut Vec<(usize, u64)>) {
+        if let Node::Branch(branch) = node {
+            found.extend(branch.keys.iter().map(|key| (depth, **key)));
+            for child in &branch.children {
+                separators(child, depth + 1, found);
+            }
+        }
+    }
+
+    /// Checks `map` against `expected` around `key`, and that its structure
+    /// and separators are sound
+    fn assert_matches(map: &BPlusTreeMap<u64, u64>, expected: &BTreeMap<u64, u64>, key: u64) {
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(map.check_separators(), Ok(()), "around {}", key);
+        for probe in key.saturating_sub(2)..key + 3 {
+            assert_eq!(map.get(&probe), expected.get(&probe), "{}", probe);
+        }
+        let low = key.saturating_sub(30);
+        assert!(map.range(low..key + 30).eq(expected.range(low..key + 30)));
+        assert!(
+            map.range(..=key)
+                .rev()
+                .take(3)
+                .eq(expected.range(..=key).rev().take(3))
+        );
+    }
+
+    #[test]
+    fn test_removing_separator_keys_refreshes_them_at_every_depth() {
+        for branching_factor in [4, 5, 8] {
+            let entries = (0..3_000u64).map(|i| (10 * i, i));
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            map.extend(entries.clone());
+            let mut expected: BTreeMap<u64, u64> = entries.collect();
+            assert_eq!(map.check_separators(), Ok(()));
+
+            let mut found = Vec::new();
+            separators(map.root_node().unwrap(), 0, &mut found);
+            assert!(found.iter().any(|&(depth, _)| depth > 1));
+
+            // Each removed key is the first key of a subtree whose
+            // separator sits in a branch at some depth above it
+            for (depth, key) in found {
+                assert_eq!(map.remove(&key), expected.remove(&key), "depth {}", depth);
+                assert_matches(&map, &expected, key);
+            }
+
+            // Keys on either side of the removed separators route to the
+            // subtrees either side of their refreshed replacements
+            let mut found = Vec::new();
+            separators(map.root_node().unwrap(), 0, &mut found);
+            for (_, key) in found {
+                for new_key in [key - 1, key + 1] {
+                    assert_eq!(map.insert(new_key, 0), expected.insert(new_key, 0));
+                    assert_matches(&map, &expected, new_key);
+                }
+                assert_eq!(map.remove(&key), expected.remove(&key));
+                assert_matches(&map, &expected, key);
+                assert_eq!(map.insert(key, 1), expected.insert(key, 1));
+                assert_matches(&map, &expected, key);
+            }
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_separators_stay_fresh_through_mixed_removals() {
+        for branching_factor in [4, 5, 8, 16] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+            for step in 0..4_000u64 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let key = state % 600;
+                match step % 7 {
+                    0..=2 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                    3 => assert_eq!(map.pop_first(), expected.pop_first()),
+                    4 => assert_eq!(map.pop_last(), expected.pop_last()),
+                    _ => assert_eq!(map.remove(&key), expected.remove(&key)),
+                }
+                assert_eq!(
+                    map.check_separators(),
+                    Ok(()),
+                    "bf {} step {}",
+                    branching_factor,
+                    step
+                );
+            }
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+}
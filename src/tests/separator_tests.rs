@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod separator_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, LeafNode, Node};
+    use crate::node_operations::{LeafNodeSplitter, NodeSplitter, SplitResult};
+    use crate::separator::{IdentitySeparators, SeparatorKey, SeparatorPolicy, ShortestSeparators};
+    use std::collections::BTreeMap;
+    use std::mem::size_of;
+
+    fn shortest(left: &str, right: &str) -> String {
+        ShortestSeparators::separator(&left.to_string(), &right.to_string())
+    }
+
+    /// Every separator in the branches of `map`
+    fn separators(map: &BPlusTreeMap<String, u64>) -> Vec<&String> {
+        fn collect<'a>(node: &'a Node<String, u64>, out: &mut Vec<&'a String>) {
+            if let Node::Branch(branch) = node {
+                out.extend(branch.keys.iter().map(|key| &**key));
+                for child in &branch.children {
+                    collect(child, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        if let Some(root) = map.root_node() {
+            collect(root, &mut out);
+        }
+        out
+    }
+
+    /// Long keys that differ within their first few characters
+    fn long_key(i: u64) -> String {
+        format!("{:06}/{}", (i * 7919) % 100_000, "x".repeat(200))
+    }
+
+    #[test]
+    fn test_shortest_string_separators() {
+        assert_eq!(shortest("carpenter", "cartography"), "cart");
+        assert_eq!(shortest("car", "carton"), "cart");
+        assert_eq!(shortest("apple", "banana"), "b");
+        assert_eq!(shortest("", "a"), "a");
+        assert_eq!(shortest("ab", "ac"), "ac");
+        // The cut never falls inside a character
+        assert_eq!(shortest("a", "aé"), "aé");
+        assert_eq!(shortest("caé", "caëz"), "caë");
+    }
+
+    #[test]
+    fn test_shortest_byte_separators() {
+        let separator = |left: &[u8], right: &[u8]| {
+            ShortestSeparators::separator(&left.to_vec(), &right.to_vec())
+        };
+        assert_eq!(separator(b"carpenter", b"cartography"), b"cart");
+        assert_eq!(separator(b"ab", b"ab\0"), b"ab\0");
+        assert_eq!(separator(&[0xff, 0x00], &[0xff, 0x01, 0x02]), [0xff, 0x01]);
+    }
+
+    #[test]
+    fn test_identity_separators_copy_the_right_key() {
+        assert_eq!(IdentitySeparators::separator(&3, &7), 7);
+        assert_eq!(
+            IdentitySeparators::separator(&"carpenter".to_string(), &"cartography".to_string()),
+            "cartography"
+        );
+    }
+
+    #[test]
+    fn test_leaf_splitter_promotes_the_policy_separator() {
+        let leaf = || {
+            LeafNode::new(
+                ["carpenter", "carpet", "cartography", "cartoon", "cartwheel"]
+                    .map(String::from)
+                    .to_vec(),
+                vec![0; 5],
+            )
+        };
+
+        let splitter = LeafNodeSplitter::with_separator_policy::<ShortestSeparators>(4);
+        match splitter.split(leaf()) {
+            SplitResult::Split { separator, .. } => assert_eq!(*separator, "cart"),
+            SplitResult::NoSplit(_) => panic!("Expected node to be split"),
+        }
+        match LeafNodeSplitter::new(4).split(leaf()) {
+            SplitResult::Split { separator, .. } => assert_eq!(*separator, "cartography"),
+            SplitResult::NoSplit(_) => panic!("Expected node to be split"),
+        }
+    }
+
+    #[test]
+    fn test_maps_promote_short_separators() {
+        let mut map = BPlusTreeMap::with_separator_policy::<ShortestSeparators>(8);
+        for i in 0..2000 {
+            map.insert(long_key(i), i);
+        }
+        assert_eq!(map.check_invariants(), Ok(()));
+        let separators = separators(&map);
+        assert!(!separators.is_empty());
+        assert!(
+            separators.iter().all(|separator| separator.len() <= 7),
+            "{:?}",
+            separators
+        );
+        for i in 0..2000 {
+            assert_eq!(map.get(&long_key(i)), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_short_separators_use_less_memory() {
+        let mut full = BPlusTreeMap::with_branching_factor(8);
+        let mut short = BPlusTreeMap::with_separator_policy::<ShortestSeparators>(8);
+        for i in 0..2000 {
+            full.insert(long_key(i), i);
+            short.insert(long_key(i), i);
+        }
+        // The leaves hold the same keys, so the whole difference in key
+        // bytes is in the separators
+        let full_usage = full.memory_usage_with(String::capacity, |_| 0);
+        let short_usage = short.memory_usage_with(String::capacity, |_| 0);
+        // Each separator is a pointer to the counts and the key it shares
+        let separator_size =
+            size_of::<SeparatorKey<String>>() + 2 * size_of::<usize>() + size_of::<String>();
+        let separator_bytes = |map: &BPlusTreeMap<String, u64>| -> usize {
+            separators(map)
+                .iter()
+                .map(|separator| separator_size + separator.capacity())
+                .sum()
+        };
+        assert!(separator_bytes(&short) * 4 < separator_bytes(&full));
+        assert_eq!(
+            full_usage.key_bytes - short_usage.key_bytes,
+            separator_bytes(&full) - separator_bytes(&short)
+        );
+    }
+
+    #[test]
+    fn test_adversarial_prefixes_match_btree_map() {
+        // Keys that are prefixes of each other, keys that differ only in
+        // their last character, and keys that differ only deep inside
+        let mut keys = Vec::new();
+        for len in 0..40 {
+            keys.push("a".repeat(len));
+            keys.push(format!("{}b", "a".repeat(len)));
+        }
+        for c in ['a', 'b', 'é', 'z', '\u{10ffff}'] {
+            for i in 0..20 {
+                keys.push(format!("{}{}{}", "m".repeat(30), c, i));
+                keys.push(format!("m{}{}", c, "m".repeat(i)));
+            }
+        }
+
+        for branching_factor in [3, 4, 5, 8] {
+            let mut map =
+                BPlusTreeMap::with_separator_policy::<ShortestSeparators>(branching_factor);
+            let mut expected = BTreeMap::new();
+            for step in 0..keys.len() * 2 {
+                let key = &keys[(step * 7919) % keys.len()];
+                let value = step as u64;
+                assert_eq!(
+                    map.insert(key.clone(), value),
+                    expected.insert(key.clone(), value)
+                );
+                assert_eq!(map.check_invariants(), Ok(()));
+            }
+            for step in 0..keys.len() {
+                let key = &keys[(step * 7907) % keys.len()];
+                if step % 3 == 0 {
+                    assert_eq!(map.remove(key), expected.remove(key), "removing {:?}", key);
+                }
+            }
+            for key in &keys {
+                assert_eq!(map.get(key), expected.get(key), "looking up {:?}", key);
+                assert_eq!(map.get(&format!("{}~", key)), None);
+            }
+            assert!(map.iter().eq(expected.iter()));
+            assert!(
+                map.range("aaaa".to_string().."m".to_string())
+                    .eq(expected.range("aaaa".to_string().."m".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_clones_and_split_off_keep_the_policy() {
+        let mut map = BPlusTreeMap::with_separator_policy::<ShortestSeparators>(4);
+        for i in 0..500 {
+            map.insert(long_key(i), i);
+        }
+        let mut copy = map.clone();
+        let mut upper = map.split_off(&long_key(250));
+        let mut target = BPlusTreeMap::with_branching_factor(4);
+        target.clone_from(&map);
+
+        // Rebalancing the cut in split_off promotes full keys, but the
+        // leaves that split from here on promote short separators
+        let long_separators = |map: &BPlusTreeMap<String, u64>| {
+            separators(map)
+                .iter()
+                .filter(|separator| separator.len() > 7)
+                .count()
+        };
+        let before = [&copy, &upper, &target].map(long_separators);
+        for i in 500..1500 {
+            copy.insert(long_key(i), i);
+            upper.insert(long_key(i), i);
+            target.insert(long_key(i), i);
+        }
+        for (map, before) in [&copy, &upper, &target].into_iter().zip(before) {
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert!(long_separators(map) <= before);
+            assert!(separators(map).len() > 100);
+        }
+        assert_eq!(long_separators(&copy), 0);
+    }
+}
@@ -0,0 +1,324 @@
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use std::collections::BTreeMap;
+
+    fn numbered_map(count: i32) -> BPlusTreeMap<i32, String> {
+        let mut map = BPlusTreeMap::new();
+        for i in 0..count {
+            // Scatter the insertion order so the tree isn't built left to right
+            let key = (i * 7919) % count;
+            map.insert(key, format!("value_{}", key));
+        }
+        map
+    }
+
+    #[test]
+    fn test_json_matches_btree_map() {
+        let map = numbered_map(100);
+        let expected: BTreeMap<i32, String> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+
+        assert_eq!(
+            serde_json::to_string(&map).unwrap(),
+            serde_json::to_string(&expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        for count in [0, 1, 10_000] {
+            let map = numbered_map(count);
+            let json = serde_json::to_string(&map).unwrap();
+            let restored: BPlusTreeMap<i32, String> = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(restored.len(), map.len());
+            assert!(restored.iter().eq(map.iter()));
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        for count in [0, 1, 10_000] {
+            let map = numbered_map(count);
+            let bytes = bincode::serialize(&map).unwrap();
+            let restored: BPlusTreeMap<i32, String> = bincode::deserialize(&bytes).unwrap();
+
+            assert_eq!(restored.len(), map.len());
+            assert!(restored.iter().eq(map.iter()));
+        }
+    }
+
+    #[test]
+    fn test_empty_map_serializes_as_empty_object() {
+        let map: BPlusTreeMap<String, i32> = BPlusTreeMap::new();
+        assert_eq!(serde_json::to_string(&map).unwrap(), "{}");
+
+        let restored: BPlusTreeMap<String, i32> = serde_json::from_str("{}").unwrap();
+        assert!(restored.is_empty());
+        assert_eq!(restored.root_kind(), RootKind::Empty);
+    }
+
+    #[test]
+    fn test_deserialize_unsorted_and_duplicate_keys() {
+        let json = r#"{"pear": 1, "apple": 2, "fig": 3, "apple": 4, "kiwi": 5}"#;
+        let map: BPlusTreeMap<String, i32> = serde_json::from_str(json).unwrap();
+
+        let entries: Vec<(&str, i32)> = map.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(
+            entries,
+            vec![("apple", 4), ("fig", 3), ("kiwi", 5), ("pear", 1)]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_rejects_non_map() {
+        let result: Result<BPlusTreeMap<i32, i32>, _> = serde_json::from_str("[1, 2, 3]");
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("expected a map"), "{}", message);
+    }
+
+    fn structured_json(map: &BPlusTreeMap<i32, String>) -> String {
+        let mut json = Vec::new();
+        map.serialize_structure(&mut serde_json::Serializer::new(&mut json))
+            .unwrap();
+        String::from_utf8(json).unwrap()
+    }
+
+    fn from_structured_json(json: &str) -> Result<BPlusTreeMap<i32, String>, serde_json::Error> {
+        BPlusTreeMap::deserialize_structure(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    /// Builds a scattered multi-level tree and removes some of its keys, so
+    /// its nodes are not all as full as a fresh build would make them
+    fn worn_map(branching_factor: usize) -> BPlusTreeMap<i32, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..500 {
+            let key = (i * 37) % 500;
+            map.insert(key, format!("value_{}", key));
+        }
+        for key in (0..500).step_by(7) {
+            map.remove(&key);
+        }
+        map
+    }
+
+    #[test]
+    fn test_structure_round_trip_keeps_shape() {
+        for branching_factor in 4..=8 {
+            let map = worn_map(branching_factor);
+            let restored = from_structured_json(&structured_json(&map)).unwrap();
+
+            assert_eq!(restored.root_kind(), map.root_kind());
+            assert_eq!(restored.shape(), map.shape());
+            assert_eq!(restored.len(), map.len());
+            assert!(restored.iter().eq(map.iter()));
+            restored.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_structure_round_trip_through_binary_format() {
+        let map = worn_map(5);
+        let mut bytes = Vec::new();
+        map.serialize_structure(&mut bincode::Serializer::new(
+            &mut bytes,
+            bincode::DefaultOptions::new(),
+        ))
+        .unwrap();
+        let restored: BPlusTreeMap<i32, String> = BPlusTreeMap::deserialize_structure(
+            &mut bincode::Deserializer::from_slice(&bytes, bincode::DefaultOptions::new()),
+        )
+        .unwrap();
+
+        assert_eq!(restored.root_kind(), map.root_kind());
+        assert_eq!(restored.shape(), map.shape());
+        assert!(restored.iter().eq(map.iter()));
+    }
+
+    #[test]
+    fn test_structure_keeps_branching_factor() {
+        let mut map = worn_map(6);
+        let mut restored = from_structured_json(&structured_json(&map)).unwrap();
+
+        // Both maps split the same nodes as they grow
+        for i in 500..700 {
+            map.insert(i, i.to_string());
+            restored.insert(i, i.to_string());
+        }
+        assert_eq!(restored.shape(), map.shape());
+    }
+
+    #[test]
+    fn test_structure_of_empty_and_single_leaf_maps() {
+        let empty = BPlusTreeMap::with_branching_factor(3);
+        let json = structured_json(&empty);
+        assert_eq!(json, r#"{"branching_factor":3,"root":null}"#);
+        let restored = from_structured_json(&json).unwrap();
+        assert_eq!(restored.root_kind(), RootKind::Empty);
+
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        map.insert(2, "two".to_string());
+        map.insert(1, "one".to_string());
+        let json = structured_json(&map);
+        assert_eq!(
+            json,
+            r#"{"branching_factor":3,"root":{"Leaf":{"keys":[1,2],"values":["one","two"]}}}"#
+        );
+        let restored = from_structured_json(&json).unwrap();
+        assert_eq!(restored.root_kind(), RootKind::Leaf);
+        assert_eq!(restored.get(&2), Some(&"two".to_string()));
+    }
+
+    #[test]
+    fn test_structure_with_serde_field_attributes() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Snapshot {
+            #[serde(
+                serialize_with = "BPlusTreeMap::serialize_structure",
+                deserialize_with = "BPlusTreeMap::deserialize_structure"
+            )]
+            map: BPlusTreeMap<i32, String>,
+        }
+
+        let snapshot = Snapshot { map: worn_map(4) };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.map.shape(), snapshot.map.shape());
+    }
+
+    /// A two-leaf tree with branching factor 3, with `separator` between
+    /// leaves holding `left` and `right`
+    fn two_leaf_json(left: &[i32], separator: i32, right: &[i32]) -> String {
+        let leaf = |keys: &[i32]| {
+            let values: Vec<String> = keys.iter().map(|k| format!("\"{}\"", k)).collect();
+            format!(
+                r#"{{"Leaf":{{"keys":{:?},"values":[{}]}}}}"#,
+                keys,
+                values.join(",")
+            )
+        };
+        format!(
+            r#"{{"branching_factor":3,"root":{{"Branch":{{"keys":[{}],"children":[{},{}]}}}}}}"#,
+            separator,
+            leaf(left),
+            leaf(right)
+        )
+    }
+
+    #[test]
+    fn test_structure_rejects_corrupt_input() {
+        // A well-formed tree to start from
+        let valid = two_leaf_json(&[1, 2], 3, &[3, 4]);
+        let map = from_structured_json(&valid).unwrap();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.shape(), "[3]([1, 2] [3, 4])");
+
+        let corrupt = [
+            (two_leaf_json(&[2, 1], 3, &[3, 4]), "not strictly ascending"),
+            (two_leaf_json(&[1, 1], 3, &[3, 4]), "not strictly ascending"),
+            (
+                two_leaf_json(&[1, 3], 3, &[4, 5]),
+                "not below its upper bound",
+            ),
+            (two_leaf_json(&[1, 2], 3, &[2, 4]), "below its lower bound"),
+            (
+                two_leaf_json(&[1, 2, 3, 4], 5, &[5]),
+                "holds more than 3 keys",
+            ),
+            (two_leaf_json(&[], 3, &[3, 4]), "leaf is empty"),
+            (
+                r#"{"branching_factor":3,"root":{"Leaf":{"keys":[1,2],"values":["one"]}}}"#
+                    .to_string(),
+                "leaf has 2 keys but 1 values",
+            ),
+            (
+                r#"{"branching_factor":3,"root":{"Branch":{"keys":[5],"children":[]}}}"#
+                    .to_string(),
+                "has 0 children",
+            ),
+            (
+                r#"{"branching_factor":1,"root":null}"#.to_string(),
+                "branching factor 1 is less than 2",
+            ),
+        ];
+        for (json, problem) in corrupt {
+            let message = from_structured_json(&json).unwrap_err().to_string();
+            assert!(message.contains("invalid tree structure"), "{}", message);
+            assert!(message.contains(problem), "{}: {}", json, message);
+        }
+    }
+
+    #[test]
+    fn test_structure_rejects_leaves_at_different_depths() {
+        let json = r#"{"branching_factor":3,"root":{"Branch":{"keys":[5],"children":[
+            {"Leaf":{"keys":[1],"values":["1"]}},
+            {"Branch":{"keys":[7],"children":[
+                {"Leaf":{"keys":[5],"values":["5"]}},
+                {"Leaf":{"keys":[7],"values":["7"]}}
+            ]}}
+        ]}}}"#;
+        let message = from_structured_json(json).unwrap_err().to_string();
+        assert!(message.contains("different heights"), "{}", message);
+    }
+
+    #[test]
+    fn test_structure_rejects_underfull_nodes_and_single_child_branches() {
+        let underfull = [
+            // A leaf below the root with one key, where four need at least two
+            r#"{"branching_factor":4,"root":{"Branch":{"keys":[3],"children":[
+                {"Leaf":{"keys":[1,2],"values":["1","2"]}},
+                {"Leaf":{"keys":[3],"values":["3"]}}
+            ]}}}"#,
+            // A branch below the root with one separator
+            r#"{"branching_factor":4,"root":{"Branch":{"keys":[5],"children":[
+                {"Branch":{"keys":[3],"children":[
+                    {"Leaf":{"keys":[1,2],"values":["1","2"]}},
+                    {"Leaf":{"keys":[3,4],"values":["3","4"]}}
+                ]}},
+                {"Branch":{"keys":[7,9],"children":[
+                    {"Leaf":{"keys":[5,6],"values":["5","6"]}},
+                    {"Leaf":{"keys":[7,8],"values":["7","8"]}},
+                    {"Leaf":{"keys":[9,10],"values":["9","10"]}}
+                ]}}
+            ]}}}"#,
+        ];
+        for json in underfull {
+            let message = from_structured_json(json).unwrap_err().to_string();
+            assert!(message.contains("invalid tree structure"), "{}", message);
+            assert!(message.contains("holds fewer than 2 keys"), "{}", message);
+        }
+
+        // The first tree with its second leaf filled up is accepted
+        let json = r#"{"branching_factor":4,"root":{"Branch":{"keys":[3],"children":[
+            {"Leaf":{"keys":[1,2],"values":["1","2"]}},
+            {"Leaf":{"keys":[3,4],"values":["3","4"]}}
+        ]}}}"#;
+        assert_eq!(from_structured_json(json).unwrap().len(), 4);
+
+        let single_child = [
+            // At the root
+            r#"{"branching_factor":3,"root":{"Branch":{"keys":[],"children":[
+                {"Leaf":{"keys":[1,2],"values":["1","2"]}}
+            ]}}}"#,
+            // Below it
+            r#"{"branching_factor":2,"root":{"Branch":{"keys":[3],"children":[
+                {"Branch":{"keys":[],"children":[
+                    {"Leaf":{"keys":[1,2],"values":["1","2"]}}
+                ]}},
+                {"Branch":{"keys":[5],"children":[
+                    {"Leaf":{"keys":[3,4],"values":["3","4"]}},
+                    {"Leaf":{"keys":[5,6],"values":["5","6"]}}
+                ]}}
+            ]}}}"#,
+        ];
+        for json in single_child {
+            let message = from_structured_json(json).unwrap_err().to_string();
+            assert!(message.contains("invalid tree structure"), "{}", message);
+            assert!(
+                message.contains("branch has 1 children and no keys"),
+                "{}",
+                message
+            );
+        }
+    }
+}
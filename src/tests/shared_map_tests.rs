@@ -0,0 +1,221 @@
+#[cfg(test)]
+mod shared_map_tests {
+    //! Tests that every map type must pass alike, whatever its nodes are
+    //! made of. Each use of `shared_map_tests!` runs the whole suite on
+    //! one map type, with maps made by the given constructor.
+
+    use crate::array_map::BPlusTreeArrayMap;
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::persistent_map::SharedBPlusTreeMap;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    /// Number of keys or operations per test. Miri runs the tests of the
+    /// array-backed nodes, and is far slower than a native run.
+    const SIZE: u64 = if cfg!(miri) { 200 } else { 2000 };
+
+    /// A small xorshift generator, so the operation sequences are the same
+    /// on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    macro_rules! shared_map_tests {
+        ($name:ident, $map:ty, $new:expr) => {
+            mod $name {
+                use super::*;
+                use std::fmt::Debug;
+
+                fn new_map<K: Ord + Clone + Debug, V: Clone + Debug>() -> $map {
+                    $new
+                }
+
+                #[test]
+                fn test_empty_map() {
+                    let mut map = new_map::<u64, u64>();
+                    assert!(map.is_empty());
+                    assert_eq!(map.len(), 0);
+                    assert_eq!(map.get(&1), None);
+                    assert_eq!(map.remove(&1), None);
+                    assert_eq!(map.iter().next(), None);
+                }
+
+                #[test]
+                fn test_insert_get_and_overwrite() {
+                    let mut map = new_map();
+                    for i in 0..SIZE {
+                        let key = (i * 7919) % SIZE;
+                        assert_eq!(map.insert(key, key * 10), None);
+                    }
+                    assert_eq!(map.len(), SIZE as usize);
+                    for key in 0..SIZE {
+                        assert_eq!(map.get(&key), Some(&(key * 10)));
+                        assert!(map.contains_key(&key));
+                    }
+                    assert!(!map.contains_key(&SIZE));
+                    for key in 0..SIZE {
+                        assert_eq!(map.insert(key, key), Some(key * 10));
+                    }
+                    assert_eq!(map.len(), SIZE as usize);
+                    assert!(map.iter().all(|(key, value)| key == value));
+                }
+
+                #[test]
+                fn test_remove_in_scattered_order() {
+                    let mut map = new_map();
+                    for key in 0..SIZE {
+                        map.insert(key, key + 1);
+                    }
+                    for i in 0..SIZE {
+                        let key = (i * 7919) % SIZE;
+                        assert_eq!(map.remove(&key), Some(key + 1));
+                        assert_eq!(map.remove(&key), None);
+                        assert_eq!(map.get(&key), None);
+                        assert_eq!(map.len(), (SIZE - i - 1) as usize);
+                    }
+                    assert!(map.is_empty());
+                    assert_eq!(map.iter().next(), None);
+
+                    // The emptied map takes new entries
+                    map.insert(7, 8);
+                    assert_eq!(map.get(&7), Some(&8));
+                }
+
+                #[test]
+                fn test_iteration_is_in_key_order() {
+                    let mut map = new_map();
+                    for i in 0..SIZE {
+                        map.insert((i * 7919) % SIZE, i);
+                    }
+                    assert_eq!(map.iter().len(), SIZE as usize);
+                    assert!(map.keys().copied().eq(0..SIZE));
+                    assert!(
+                        map.values()
+                            .zip(map.keys())
+                            .all(|(value, key)| (value * 7919) % SIZE == *key)
+                    );
+                }
+
+                #[test]
+                fn test_get_mut_updates_in_place() {
+                    let mut map = new_map();
+                    for key in 0..SIZE {
+                        map.insert(key, key);
+                    }
+                    for key in (0..SIZE).step_by(3) {
+                        *map.get_mut(&key).unwrap() += 1;
+                    }
+                    assert_eq!(map.get_mut(&SIZE), None);
+                    for key in 0..SIZE {
+                        let expected = if key % 3 == 0 { key + 1 } else { key };
+                        assert_eq!(map.get(&key), Some(&expected));
+                    }
+                }
+
+                #[test]
+                fn test_matches_btree_map() {
+                    let mut map = new_map();
+                    let mut expected = BTreeMap::new();
+                    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+                    for _ in 0..SIZE * 2 {
+                        let key = rng.next() % (SIZE / 2);
+                        match rng.next() % 4 {
+                            0 | 1 => {
+                                assert_eq!(map.insert(key, key ^ 1), expected.insert(key, key ^ 1))
+                            }
+                            2 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                            _ => assert_eq!(map.get(&key), expected.get(&key)),
+                        }
+                        assert_eq!(map.len(), expected.len());
+                    }
+                    assert!(map.iter().eq(expected.iter()));
+                }
+
+                #[test]
+                fn test_borrowed_key_lookups() {
+                    let mut map = new_map();
+                    for i in 0..SIZE / 4 {
+                        map.insert(format!("key{:05}", i), i);
+                    }
+                    assert_eq!(map.get("key00003"), Some(&3));
+                    assert!(map.contains_key("key00010"));
+                    assert_eq!(map.remove("key00003"), Some(3));
+                    assert_eq!(map.get("key00003"), None);
+                }
+
+                #[test]
+                fn test_clear_clone_and_debug() {
+                    let mut map = new_map();
+                    let mut expected = BTreeMap::new();
+                    for i in 0..SIZE / 4 {
+                        map.insert(i, i * 2);
+                        expected.insert(i, i * 2);
+                    }
+                    assert_eq!(format!("{:?}", map), format!("{:?}", expected));
+
+                    let copy = map.clone();
+                    map.clear();
+                    assert!(map.is_empty());
+                    assert_eq!(map.get(&1), None);
+                    assert!(copy.iter().eq(expected.iter()));
+
+                    map.extend([(1, 1), (2, 2)]);
+                    assert_eq!(map.len(), 2);
+                    assert_eq!(copy.len(), expected.len());
+                }
+
+                #[test]
+                fn test_every_value_is_dropped_once() {
+                    let value = Rc::new(());
+                    {
+                        let mut map = new_map();
+                        for i in 0..SIZE {
+                            map.insert((i * 7919) % SIZE, Rc::clone(&value));
+                        }
+                        assert_eq!(Rc::strong_count(&value), SIZE as usize + 1);
+
+                        // Overwritten and removed values are handed back
+                        drop(map.insert(0, Rc::clone(&value)));
+                        for key in (0..SIZE).step_by(2) {
+                            drop(map.remove(&key));
+                        }
+                        assert_eq!(Rc::strong_count(&value), (SIZE / 2) as usize + 1);
+
+                        // Values left only to a clone are still dropped
+                        // once, whether the clone copied them or shares them
+                        let copy = map.clone();
+                        map.clear();
+                        assert_eq!(copy.len(), (SIZE / 2) as usize);
+                        assert_eq!(Rc::strong_count(&value), (SIZE / 2) as usize + 1);
+                        drop(copy);
+                    }
+                    assert_eq!(Rc::strong_count(&value), 1);
+                }
+            }
+        };
+    }
+
+    shared_map_tests!(vec_nodes_4, BPlusTreeMap<K, V>, BPlusTreeMap::with_branching_factor(4));
+    shared_map_tests!(vec_nodes_16, BPlusTreeMap<K, V>, BPlusTreeMap::with_branching_factor(16));
+    shared_map_tests!(array_nodes_4, BPlusTreeArrayMap<K, V, 4>, BPlusTreeArrayMap::new());
+    shared_map_tests!(array_nodes_5, BPlusTreeArrayMap<K, V, 5>, BPlusTreeArrayMap::new());
+    shared_map_tests!(array_nodes_16, BPlusTreeArrayMap<K, V, 16>, BPlusTreeArrayMap::new());
+    shared_map_tests!(shared_nodes_4, SharedBPlusTreeMap<K, V>, SharedBPlusTreeMap::new());
+    shared_map_tests!(
+        shared_nodes_5,
+        SharedBPlusTreeMap<K, V>,
+        SharedBPlusTreeMap::with_branching_factor(5)
+    );
+    shared_map_tests!(
+        shared_nodes_16,
+        SharedBPlusTreeMap<K, V>,
+        SharedBPlusTreeMap::with_branching_factor(16)
+    );
+}
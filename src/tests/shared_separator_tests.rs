@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod shared_separator_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Node};
+    use crate::separator::SeparatorKey;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    /// Every separator in the branches under `node`
+    fn separators<'a>(node: &'a Node<String, u64>, out: &mut Vec<&'a SeparatorKey<String>>) {
+        if let Node::Branch(branch) = node {
+            out.extend(branch.keys.iter());
+            for child in &branch.children {
+                separators(child, out);
+            }
+        }
+    }
+
+    fn all_separators(map: &BPlusTreeMap<String, u64>) -> Vec<&SeparatorKey<String>> {
+        let mut out = Vec::new();
+        if let Some(root) = map.root_node() {
+            separators(root, &mut out);
+        }
+        out
+    }
+
+    /// Long keys in a scattered order
+    fn long_key(i: u64) -> String {
+        format!("{}{:06}", "k".repeat(100), (i * 7919) % 100_000)
+    }
+
+    #[test]
+    fn test_string_keys_route_like_btree_map() {
+        for branching_factor in [4, 8, 32] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for i in 0..3000 {
+                let key = long_key(i % 1200);
+                if i % 5 == 4 {
+                    assert_eq!(map.remove(&key), expected.remove(&key));
+                } else {
+                    assert_eq!(map.insert(key.clone(), i), expected.insert(key, i));
+                }
+            }
+            assert_eq!(map.check_invariants(), Ok(()));
+            for i in 0..1300 {
+                // Looked up by `str`, through the separators' `Borrow`
+                let key = long_key(i);
+                assert_eq!(map.get(key.as_str()), expected.get(key.as_str()));
+                assert!(
+                    map.range::<str, _>((Bound::Included(key.as_str()), Bound::Unbounded))
+                        .take(3)
+                        .eq(expected
+                            .range::<str, _>((Bound::Included(key.as_str()), Bound::Unbounded))
+                            .take(3))
+                );
+            }
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_clones_share_their_separators() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..1000 {
+            map.insert(long_key(i), i);
+        }
+        let alone = map.memory_usage_with(String::capacity, |_| 0);
+        assert!(
+            all_separators(&map)
+                .iter()
+                .all(|separator| SeparatorKey::strong_count(separator) == 1)
+        );
+
+        let copy = map.clone();
+        for separator in all_separators(&map) {
+            assert_eq!(SeparatorKey::strong_count(separator), 2);
+        }
+        // Each map now owns half of every separator
+        let shared = map.memory_usage_with(String::capacity, |_| 0);
+        assert!(shared.key_bytes < alone.key_bytes);
+        assert!(copy.memory_usage_with(String::capacity, |_| 0).key_bytes < alone.key_bytes);
+
+        drop(copy);
+        assert_eq!(map.memory_usage_with(String::capacity, |_| 0), alone);
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(all(test, feature = "smallvec"))]
+mod smallvec_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Node};
+    use crate::node_vec::NODE_INLINE_LEN;
+    use crate::tests::counting_allocator::allocations_during;
+    use std::collections::BTreeMap;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_default_maps_keep_leaves_inline() {
+        let mut map = BPlusTreeMap::new();
+        let allocations = allocations_during(|| {
+            for i in 0..1000u64 {
+                map.insert((i * 7919) % 1000, i);
+            }
+        });
+        for leaf in map.walk_leaves() {
+            assert!(leaf.keys.len() <= NODE_INLINE_LEN);
+            assert!(!leaf.keys.spilled() && !leaf.values.spilled());
+        }
+        // Each leaf allocates only its box and the separator promoted when
+        // it was split off, which leaves the boxes and children of the
+        // branches, fewer than one allocation per leaf
+        assert!(
+            allocations < 3 * map.leaf_count(),
+            "{} allocations for {} leaves",
+            allocations,
+            map.leaf_count()
+        );
+    }
+
+    #[test]
+    fn test_inline_entries_are_counted_once() {
+        let mut map = BPlusTreeMap::new();
+        map.extend((0..3u64).map(|i| (i, i)));
+        let usage = map.memory_usage();
+        assert_eq!(usage.key_bytes, 3 * size_of::<u64>());
+        assert_eq!(usage.value_bytes, 3 * size_of::<u64>());
+        assert_eq!(usage.total_bytes(), size_of::<Node<u64, u64>>());
+    }
+
+    #[test]
+    fn test_nodes_of_any_width_match_btree_map() {
+        for branching_factor in [2, 3, 4, 5, 8, 16, 64] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+            for step in 0..3000u64 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let key = state % 500;
+                match step % 6 {
+                    0..=2 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                    3 => assert_eq!(map.pop_first(), expected.pop_first()),
+                    _ => assert_eq!(map.remove(&key), expected.remove(&key)),
+                }
+            }
+            assert!(map.iter().eq(expected.iter()), "bf {}", branching_factor);
+
+            map.retain(|key, _| key % 3 != 0);
+            expected.retain(|key, _| key % 3 != 0);
+            let upper = map.split_off(&250);
+            let expected_upper = expected.split_off(&250);
+            assert!(map.iter().eq(expected.iter()));
+            assert!(upper.iter().eq(expected_upper.iter()));
+            assert!(map.range(100..200).rev().eq(expected.range(100..200).rev()));
+            assert!(upper.into_iter().eq(expected_upper));
+            assert!(map.into_keys().eq(expected.into_keys()));
+        }
+    }
+}
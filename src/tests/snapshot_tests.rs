@@ -0,0 +1,202 @@
+#[cfg(test)]
+mod snapshot_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+    use crate::snapshot::{BinaryCodec, Checksum, SnapshotError};
+    use std::fs::File;
+    use std::io::{self, BufReader, BufWriter, ErrorKind};
+
+    fn numbered_map(branching_factor: usize, count: u32) -> BPlusTreeMap<u32, String> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            let key = (i * 7919) % count;
+            map.insert(key, format!("value_{}", key));
+        }
+        map
+    }
+
+    fn snapshot_of<K, V>(map: &BPlusTreeMap<K, V>) -> Vec<u8>
+    where
+        K: Ord + Clone + std::fmt::Debug + BinaryCodec,
+        V: Clone + std::fmt::Debug + BinaryCodec,
+    {
+        let mut bytes = Vec::new();
+        map.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Replaces the checksum at the end of `bytes` with the right one for
+    /// the rest of them, so that other checks can be reached
+    fn reseal(bytes: &mut [u8]) {
+        let end = bytes.len() - 8;
+        let mut checksum = Checksum::new();
+        checksum.update(&bytes[4..end]);
+        bytes[end..].copy_from_slice(&checksum.value().to_le_bytes());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        for count in [0, 1, 10, 1000] {
+            let map = numbered_map(5, count);
+            let bytes = snapshot_of(&map);
+            let restored = BPlusTreeMap::<u32, String>::load_from(&bytes[..]).unwrap();
+
+            assert_eq!(restored.len(), map.len());
+            assert_eq!(restored.branching_factor(), 5);
+            assert!(restored.iter().eq(map.iter()));
+            restored.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_snapshot_of_empty_map() {
+        let map: BPlusTreeMap<u32, String> = BPlusTreeMap::with_branching_factor(3);
+        let bytes = snapshot_of(&map);
+
+        // Magic, version, branching factor, count, an empty leaf index,
+        // its offset and the checksum
+        assert_eq!(bytes.len(), 4 + 1 + 8 + 8 + 8 + 8 + 8);
+        assert_eq!(&bytes[..4], b"BPT2");
+
+        let restored = BPlusTreeMap::<u32, String>::load_from(&bytes[..]).unwrap();
+        assert_eq!(restored.root_kind(), RootKind::Empty);
+        assert_eq!(restored.branching_factor(), 3);
+    }
+
+    #[test]
+    fn test_snapshot_through_a_file() {
+        let map = numbered_map(8, 5000);
+        let path = std::env::temp_dir().join(format!("bplus_snapshot_{}.bin", std::process::id()));
+
+        map.save_to(BufWriter::new(File::create(&path).unwrap()))
+            .unwrap();
+        let restored =
+            BPlusTreeMap::<u32, String>::load_from(BufReader::new(File::open(&path).unwrap()));
+        std::fs::remove_file(&path).unwrap();
+
+        let restored = restored.unwrap();
+        assert_eq!(restored.len(), 5000);
+        assert!(restored.iter().eq(map.iter()));
+    }
+
+    #[test]
+    fn test_snapshot_of_other_codecs() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in -50i64..50 {
+            map.insert(
+                i,
+                (i % 3 == 0, vec![i as u8; (i.unsigned_abs() % 5) as usize]),
+            );
+        }
+        let mut bytes = Vec::new();
+        map.save_to(&mut bytes).unwrap();
+        let restored = BPlusTreeMap::<i64, (bool, Vec<u8>)>::load_from(&bytes[..]).unwrap();
+        assert!(restored.iter().eq(map.iter()));
+    }
+
+    #[test]
+    fn test_truncated_snapshot_is_rejected() {
+        let bytes = snapshot_of(&numbered_map(4, 40));
+        for len in 0..bytes.len() {
+            match BPlusTreeMap::<u32, String>::load_from(&bytes[..len]) {
+                Err(SnapshotError::Io(error)) => {
+                    assert_eq!(error.kind(), ErrorKind::UnexpectedEof, "length {}", len)
+                }
+                other => panic!("length {}: {:?}", len, other.map(|map| map.len())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_flipped_byte_is_rejected() {
+        let bytes = snapshot_of(&numbered_map(4, 40));
+        for position in 0..bytes.len() {
+            for bit in [0x01, 0x80] {
+                let mut corrupt = bytes.clone();
+                corrupt[position] ^= bit;
+                let result = BPlusTreeMap::<u32, String>::load_from(&corrupt[..]);
+                assert!(
+                    result.is_err(),
+                    "flipped bit {:#x} of byte {}",
+                    bit,
+                    position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_header_errors() {
+        let bytes = snapshot_of(&numbered_map(4, 10));
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert!(matches!(
+            BPlusTreeMap::<u32, String>::load_from(&corrupt[..]),
+            Err(SnapshotError::BadMagic)
+        ));
+
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 9;
+        assert!(matches!(
+            BPlusTreeMap::<u32, String>::load_from(&corrupt[..]),
+            Err(SnapshotError::UnsupportedVersion(9))
+        ));
+
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 1;
+        assert!(matches!(
+            BPlusTreeMap::<u32, String>::load_from(&corrupt[..]),
+            Err(SnapshotError::ChecksumMismatch) | Err(SnapshotError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_resealed_snapshot_is_still_checked() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..3u32 {
+            map.insert(i, i);
+        }
+        let bytes = snapshot_of(&map);
+
+        // A branching factor of 1
+        let mut corrupt = bytes.clone();
+        corrupt[5..13].copy_from_slice(&1u64.to_le_bytes());
+        reseal(&mut corrupt);
+        let error = BPlusTreeMap::<u32, u32>::load_from(&corrupt[..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid snapshot: branching factor 1 is less than 2"
+        );
+
+        // Keys out of order: the first entry's key is made larger than the second's
+        let mut corrupt = bytes.clone();
+        corrupt[21..25].copy_from_slice(&7u32.to_le_bytes());
+        reseal(&mut corrupt);
+        let error = BPlusTreeMap::<u32, u32>::load_from(&corrupt[..]).unwrap_err();
+        assert!(matches!(error, SnapshotError::Invalid(_)), "{}", error);
+
+        // More entries claimed than stored
+        let mut corrupt = bytes.clone();
+        corrupt[13..21].copy_from_slice(&4u64.to_le_bytes());
+        reseal(&mut corrupt);
+        assert!(BPlusTreeMap::<u32, u32>::load_from(&corrupt[..]).is_err());
+    }
+
+    #[test]
+    fn test_save_reports_write_errors() {
+        struct FailingWriter;
+
+        impl io::Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let error = numbered_map(4, 10).save_to(FailingWriter).unwrap_err();
+        assert_eq!(error.to_string(), "snapshot I/O failed: disk full");
+    }
+}
@@ -0,0 +1,198 @@
+#[cfg(test)]
+mod snapshot_view_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::frozen::Snapshot;
+    use crate::tests::counting_allocator::allocations_during;
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+    use std::thread;
+
+    fn numbered_map(branching_factor: usize, count: u64) -> BPlusTreeMap<u64, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..count {
+            let key = (i * 7919) % count;
+            map.insert(key * 2, key);
+        }
+        map
+    }
+
+    #[test]
+    fn test_snapshot_holds_the_contents_when_it_was_taken() {
+        let mut map = numbered_map(4, 1000);
+        let snapshot = map.snapshot();
+        for key in (0..2000).step_by(4) {
+            map.remove(&key);
+        }
+        map.insert(1, 1);
+        *map.get_mut(&2).unwrap() = 100;
+        map.clear();
+
+        assert_eq!(snapshot.len(), 1000);
+        assert!(!snapshot.is_empty());
+        assert_eq!(snapshot.get(&0), Some(&0));
+        assert_eq!(snapshot.get(&2), Some(&1));
+        assert_eq!(snapshot.get(&1), None);
+        assert!(snapshot.contains_key(&1998));
+        assert!(
+            snapshot
+                .iter()
+                .map(|(k, v)| (*k, *v))
+                .eq((0..1000).map(|k| (k * 2, k)))
+        );
+        assert_eq!(snapshot.iter().len(), 1000);
+        assert_eq!(snapshot.iter().next_back(), Some((&1998, &999)));
+    }
+
+    #[test]
+    fn test_snapshot_of_an_empty_map() {
+        let map = BPlusTreeMap::<u64, u64>::new();
+        let snapshot = map.snapshot();
+        assert!(snapshot.is_empty());
+        assert_eq!(snapshot.len(), 0);
+        assert_eq!(snapshot.get(&0), None);
+        assert_eq!(snapshot.iter().next(), None);
+        assert_eq!(snapshot.range(..).next(), None);
+        assert_eq!(format!("{:?}", snapshot), "{}");
+    }
+
+    #[test]
+    fn test_ranges_match_btree_map() {
+        let map = numbered_map(5, 200);
+        let expected: BTreeMap<u64, u64> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        let snapshot = map.snapshot();
+        let bounds = [
+            Bound::Unbounded,
+            Bound::Included(0),
+            Bound::Excluded(0),
+            Bound::Included(101),
+            Bound::Excluded(101),
+            Bound::Included(200),
+            Bound::Excluded(200),
+            Bound::Included(398),
+            Bound::Excluded(1000),
+        ];
+        for start in bounds {
+            for end in bounds {
+                let in_order = match (start, end) {
+                    (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e)) => s <= e,
+                    (Bound::Included(s), Bound::Excluded(e)) => s <= e,
+                    (Bound::Excluded(s), Bound::Excluded(e)) => s < e,
+                    _ => true,
+                };
+                if !in_order {
+                    continue;
+                }
+                assert!(
+                    snapshot
+                        .range((start, end))
+                        .eq(expected.range((start, end))),
+                    "range {:?}..{:?}",
+                    start,
+                    end
+                );
+                assert!(
+                    snapshot
+                        .range((start, end))
+                        .rev()
+                        .eq(expected.range((start, end)).rev())
+                );
+            }
+        }
+        assert_eq!(snapshot.range(10..20).len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn test_range_with_start_after_end() {
+        let snapshot = numbered_map(4, 10).snapshot();
+        let _ = snapshot.range((Bound::Included(8), Bound::Excluded(4)));
+    }
+
+    #[test]
+    fn test_borrowed_key_lookups() {
+        let mut map = BPlusTreeMap::new();
+        for i in 0..100 {
+            map.insert(format!("key{:03}", i), i);
+        }
+        let snapshot = map.snapshot();
+        assert_eq!(snapshot.get("key042"), Some(&42));
+        assert!(!snapshot.contains_key("key100"));
+        let keys: Vec<&String> = snapshot
+            .range::<str, _>((Bound::Included("key010"), Bound::Excluded("key013")))
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, ["key010", "key011", "key012"]);
+    }
+
+    #[test]
+    fn test_snapshot_clones_each_entry_once_and_clones_share_them() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for key in 0..1000 {
+            map.insert(key, CountedValue(key));
+        }
+        let mut snapshot = None;
+        assert_eq!(clones_during(|| snapshot = Some(map.snapshot())), 1000);
+        let snapshot = snapshot.unwrap();
+
+        let mut copy = None;
+        let allocations = allocations_during(|| {
+            assert_eq!(clones_during(|| copy = Some(snapshot.clone())), 0);
+        });
+        assert_eq!(allocations, 0);
+        let copy = copy.unwrap();
+        assert!(std::ptr::eq(
+            copy.get(&5).unwrap(),
+            snapshot.get(&5).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Snapshot<String, Vec<u8>>>();
+    }
+
+    #[test]
+    fn test_readers_on_other_threads_while_the_map_changes() {
+        let mut map = numbered_map(4, 2000);
+        let snapshot = map.snapshot();
+        thread::scope(|scope| {
+            for reader in 0..4u64 {
+                let snapshot = snapshot.clone();
+                scope.spawn(move || {
+                    for round in 0..200 {
+                        let key = (reader * 7919 + round * 104_729) % 4000;
+                        let expected = (key % 2 == 0).then_some(key / 2);
+                        assert_eq!(snapshot.get(&key).copied(), expected);
+                        let sum: u64 = snapshot.range(key..key + 20).map(|(_, v)| v).sum();
+                        let first = key.div_ceil(2);
+                        let last = ((key + 19) / 2).min(1999);
+                        assert_eq!(sum, (first..=last).sum::<u64>());
+                        assert_eq!(snapshot.len(), 2000);
+                    }
+                    assert!(
+                        snapshot
+                            .iter()
+                            .map(|(k, v)| (*k, *v))
+                            .eq((0..2000).map(|k| (k * 2, k)))
+                    );
+                });
+            }
+
+            // The owner keeps writing, and taking newer snapshots, while
+            // the readers work through the first one
+            for round in 0..20u64 {
+                for key in 0..200 {
+                    map.insert(key * 2, round);
+                    map.remove(&(key * 2 + 400));
+                }
+                let newer = map.snapshot();
+                assert_eq!(newer.get(&0), Some(&round));
+                assert_eq!(newer.len(), map.len());
+            }
+        });
+        assert_eq!(snapshot.len(), 2000);
+        assert_eq!(snapshot.get(&0), Some(&0));
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod split_off_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, BranchNode, LeafNode, NodeVisitor, RootKind};
+    use std::collections::BTreeMap;
+
+    /// Records the largest number of keys held by any leaf
+    struct LargestLeaf {
+        keys: usize,
+    }
+
+    impl NodeVisitor<i32, i32> for LargestLeaf {
+        type Result = usize;
+
+        fn visit_leaf(&mut self, leaf: &LeafNode<i32, i32>) {
+            self.keys = self.keys.max(leaf.keys.len());
+        }
+
+        fn visit_branch(&mut self, _branch: &BranchNode<i32, i32>) {}
+
+        fn result(self) -> Self::Result {
+            self.keys
+        }
+    }
+
+    /// Builds a map and a matching BTreeMap holding `count` keys inserted in
+    /// a scattered order, so the tree isn't built left to right
+    fn scattered_maps(
+        branching_factor: usize,
+        count: i32,
+    ) -> (BPlusTreeMap<i32, i32>, BTreeMap<i32, i32>) {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        let mut expected = BTreeMap::new();
+        for i in 0..count {
+            let key = (i * 37) % count;
+            map.insert(key, key * 10);
+            expected.insert(key, key * 10);
+        }
+        (map, expected)
+    }
+
+    #[test]
+    fn test_split_off_in_the_middle() {
+        let (mut map, _) = scattered_maps(4, 100);
+        let other = map.split_off(&40);
+
+        assert_eq!(map.len(), 40);
+        assert_eq!(other.len(), 60);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..40));
+        assert!(other.iter().map(|(k, _)| *k).eq(40..100));
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(other.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_split_off_before_first_key() {
+        let (mut map, _) = scattered_maps(4, 50);
+        let other = map.split_off(&-1);
+
+        assert!(map.is_empty());
+        assert_eq!(map.root_kind(), RootKind::Empty);
+        assert_eq!(other.len(), 50);
+        assert!(other.iter().map(|(k, _)| *k).eq(0..50));
+        assert_eq!(other.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_split_off_after_last_key() {
+        let (mut map, _) = scattered_maps(4, 50);
+        let other = map.split_off(&50);
+
+        assert!(other.is_empty());
+        assert_eq!(other.root_kind(), RootKind::Empty);
+        assert_eq!(map.len(), 50);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..50));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_split_off_at_separator() {
+        let left_leaf = LeafNode::new(vec![1, 2, 3], vec![10, 20, 30]);
+        let right_leaf = LeafNode::new(vec![5, 6], vec![50, 60]);
+        let mut map = BPlusTreeMap::with_branch_root(4, left_leaf, right_leaf, Some(5));
+
+        let other = map.split_off(&5);
+
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+        assert_eq!(other.root_kind(), RootKind::Leaf);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&1, &10), (&2, &20), (&3, &30)]
+        );
+        assert_eq!(other.iter().collect::<Vec<_>>(), vec![(&5, &50), (&6, &60)]);
+        assert_eq!(map.len(), 3);
+        assert_eq!(other.len(), 2);
+    }
+
+    #[test]
+    fn test_split_off_empty_map() {
+        let mut map = BPlusTreeMap::<i32, i32>::new();
+        let other = map.split_off(&10);
+        assert!(map.is_empty());
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn test_split_off_keeps_branching_factor() {
+        let (mut map, _) = scattered_maps(2, 20);
+        let mut other = map.split_off(&10);
+
+        for i in 100..200 {
+            map.insert(i, i);
+            other.insert(i, i);
+        }
+
+        for half in [&map, &other] {
+            let mut visitor = LargestLeaf { keys: 0 };
+            half.accept(&mut visitor);
+            assert!(visitor.result() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_split_off_at_every_key_matches_btree_map() {
+        for branching_factor in 2..=7 {
+            for split_key in -1..=121 {
+                let (mut map, mut expected) = scattered_maps(branching_factor, 120);
+                let other = map.split_off(&split_key);
+                let expected_other = expected.split_off(&split_key);
+
+                let context = format!("bf {} split at {}", branching_factor, split_key);
+                assert_eq!(map.len(), expected.len(), "{}", context);
+                assert_eq!(other.len(), expected_other.len(), "{}", context);
+                assert!(map.iter().eq(expected.iter()), "{}", context);
+                assert!(other.iter().eq(expected_other.iter()), "{}", context);
+                for i in -1..=121 {
+                    assert_eq!(map.get(&i), expected.get(&i), "{} key {}", context, i);
+                    assert_eq!(
+                        other.get(&i),
+                        expected_other.get(&i),
+                        "{} key {}",
+                        context,
+                        i
+                    );
+                }
+
+                // Both halves keep working as ordinary maps
+                for (mut half, mut wanted) in [(map, expected), (other, expected_other)] {
+                    for i in (0..120).step_by(7) {
+                        assert_eq!(half.insert(i, -i), wanted.insert(i, -i), "{}", context);
+                    }
+                    for i in (0..120).step_by(3) {
+                        assert_eq!(half.remove(&i), wanted.remove(&i), "{}", context);
+                    }
+                    assert!(half.iter().eq(wanted.iter()), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_off_preserves_invariants() {
+        // Trees built by inserting at these branching factors are well formed,
+        // so both halves of any split must be too
+        for branching_factor in 4..=7 {
+            for split_key in -1..=301 {
+                let (mut map, _) = scattered_maps(branching_factor, 300);
+                assert_eq!(map.check_invariants(), Ok(()));
+
+                let other = map.split_off(&split_key);
+                let context = format!("bf {} split at {}", branching_factor, split_key);
+                assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                assert_eq!(other.check_invariants(), Ok(()), "{}", context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_off_tall_trees_leaves_no_single_child_branches() {
+        // Narrow trees are tall enough for the cut to leave chains of
+        // single-child branches along either side of it
+        for branching_factor in 2..=3 {
+            for split_key in (0..500).step_by(7) {
+                let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+                map.extend((0..500).map(|key| (key, key)));
+
+                let other = map.split_off(&split_key);
+                let context = format!("bf {} split at {}", branching_factor, split_key);
+                assert_eq!(map.check_invariants(), Ok(()), "{}", context);
+                assert_eq!(other.check_invariants(), Ok(()), "{}", context);
+                assert!(map.keys().copied().eq(0..split_key), "{}", context);
+                assert!(other.keys().copied().eq(split_key..500), "{}", context);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(all(test, feature = "proptest"))]
+mod strategy_tests {
+    use crate::bplus_tree_map::BPlusTreeMap;
+    use crate::strategy::{Op, bplus_tree_map, bplus_tree_ops};
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    proptest! {
+        #[test]
+        fn test_ops_match_btree_map(
+            branching_factor in 2usize..=16,
+            ops in bplus_tree_ops(0u8..32, any::<u16>(), 0..200),
+        ) {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut model = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        prop_assert_eq!(map.insert(key, value), model.insert(key, value));
+                    }
+                    Op::Remove(key) => prop_assert_eq!(map.remove(&key), model.remove(&key)),
+                    Op::Get(key) => prop_assert_eq!(map.get(&key), model.get(&key)),
+                }
+                let keys: Vec<u8> = map.keys().copied().collect();
+                prop_assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                prop_assert_eq!(map.len(), model.len());
+            }
+            prop_assert!(map.iter().eq(model.iter()));
+        }
+
+        #[test]
+        fn test_generated_maps_have_requested_size(
+            map in bplus_tree_map(any::<i32>(), any::<i32>(), 10..300),
+        ) {
+            prop_assert!((10..300).contains(&map.len()));
+            prop_assert_eq!(map.iter().count(), map.len());
+            prop_assert!((2..=16).contains(&map.branching_factor()));
+        }
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod sub_map_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind, SubMap};
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    /// Builds a multi-level tree holding the even numbers 0..60 and a
+    /// BTreeMap with the same contents to compare against
+    fn even_number_maps() -> (BPlusTreeMap<i32, i32>, BTreeMap<i32, i32>) {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        let mut expected = BTreeMap::new();
+        for i in (0..60).step_by(2) {
+            map.insert(i, i * 10);
+            expected.insert(i, i * 10);
+        }
+        (map, expected)
+    }
+
+    fn bounds(key: i32) -> [Bound<i32>; 3] {
+        [Bound::Included(key), Bound::Excluded(key), Bound::Unbounded]
+    }
+
+    fn contains(range: &(Bound<i32>, Bound<i32>), key: i32) -> bool {
+        let after_start = match range.0 {
+            Bound::Included(start) => key >= start,
+            Bound::Excluded(start) => key > start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match range.1 {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+
+    /// Every valid range with both ends at one of `keys`, which must be sorted
+    fn ranges(keys: &[i32]) -> Vec<(Bound<i32>, Bound<i32>)> {
+        let mut ranges = Vec::new();
+        for (i, &start_key) in keys.iter().enumerate() {
+            for &end_key in &keys[i..] {
+                for start in bounds(start_key) {
+                    for end in bounds(end_key) {
+                        let both_excluded =
+                            matches!((start, end), (Bound::Excluded(_), Bound::Excluded(_)));
+                        if !(both_excluded && start_key == end_key) {
+                            ranges.push((start, end));
+                        }
+                    }
+                }
+            }
+        }
+        ranges
+    }
+
+    /// Checks every query of `sub` against the entries of `expected` that
+    /// `keep` admits
+    fn assert_view_matches(
+        sub: &SubMap<'_, i32, i32>,
+        expected: &BTreeMap<i32, i32>,
+        keep: impl Fn(i32) -> bool,
+        context: &str,
+    ) {
+        let wanted: Vec<_> = expected.iter().filter(|(k, _)| keep(**k)).collect();
+
+        assert!(sub.iter().eq(wanted.iter().copied()), "{}", context);
+        assert!(
+            sub.iter().rev().eq(wanted.iter().rev().copied()),
+            "{}",
+            context
+        );
+        assert_eq!(sub.len(), wanted.len(), "{}", context);
+        assert_eq!(sub.is_empty(), wanted.is_empty(), "{}", context);
+        assert_eq!(
+            sub.first_key_value(),
+            wanted.first().copied(),
+            "{}",
+            context
+        );
+        assert_eq!(sub.last_key_value(), wanted.last().copied(), "{}", context);
+        for key in -3..63 {
+            let value = expected.get(&key).filter(|_| keep(key));
+            assert_eq!(sub.get(&key), value, "{} key {}", context, key);
+            assert_eq!(
+                sub.contains_key(&key),
+                value.is_some(),
+                "{} key {}",
+                context,
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_sub_map_with_all_bound_kinds() {
+        let (map, expected) = even_number_maps();
+        assert_eq!(map.root_kind(), RootKind::Branch);
+
+        // Ends on, between and outside the stored keys
+        let keys: Vec<i32> = (-2..62).collect();
+        for range in ranges(&keys) {
+            let sub = map.sub_map(range);
+            let context = format!("sub_map {:?}", range);
+            assert_view_matches(&sub, &expected, |key| contains(&range, key), &context);
+        }
+    }
+
+    #[test]
+    fn test_nested_sub_map_intersects_bounds() {
+        let (map, expected) = even_number_maps();
+        let ranges = ranges(&[-1, 0, 11, 30, 31, 59, 61]);
+
+        for outer in &ranges {
+            let sub = map.sub_map(*outer);
+            for inner in &ranges {
+                let nested = sub.sub_map(*inner);
+                let context = format!("sub_map {:?} then {:?}", outer, inner);
+                let keep = |key| contains(outer, key) && contains(inner, key);
+                assert_view_matches(&nested, &expected, keep, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sub_map_nested_three_deep() {
+        let (map, expected) = even_number_maps();
+        let sub = map
+            .sub_map(10..50)
+            .sub_map(..=40)
+            .sub_map((Bound::Excluded(10), Bound::Unbounded));
+
+        assert_view_matches(&sub, &expected, |key| key > 10 && key <= 40, "three deep");
+        assert_eq!(sub.first_key_value(), Some((&12, &120)));
+        assert_eq!(sub.last_key_value(), Some((&40, &400)));
+    }
+
+    #[test]
+    fn test_sub_map_at_the_same_key() {
+        let (map, _) = even_number_maps();
+
+        // Excluding a key is tighter than including it, from either side
+        let sub = map
+            .sub_map(10..=20)
+            .sub_map((Bound::Excluded(10), Bound::Excluded(20)));
+        let keys: Vec<_> = sub.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![12, 14, 16, 18]);
+        let sub = map
+            .sub_map((Bound::Excluded(10), Bound::Excluded(20)))
+            .sub_map(10..=20);
+        let keys: Vec<_> = sub.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![12, 14, 16, 18]);
+
+        let sub = map.sub_map(20..=20);
+        assert_eq!(sub.len(), 1);
+        assert_eq!(sub.get(&20), Some(&200));
+    }
+
+    #[test]
+    fn test_disjoint_nested_sub_map_is_empty() {
+        let (map, _) = even_number_maps();
+        let sub = map.sub_map(10..20).sub_map(30..40);
+
+        assert!(sub.is_empty());
+        assert_eq!(sub.len(), 0);
+        assert_eq!(sub.iter().next(), None);
+        assert_eq!(sub.first_key_value(), None);
+        assert_eq!(sub.get(&12), None);
+        assert_eq!(sub.get(&32), None);
+    }
+
+    #[test]
+    fn test_sub_map_on_empty_and_single_leaf_maps() {
+        let empty: BPlusTreeMap<i32, i32> = BPlusTreeMap::new();
+        let sub = empty.sub_map(..);
+        assert!(sub.is_empty());
+        assert_eq!(sub.get(&0), None);
+        assert_eq!(sub.last_key_value(), None);
+
+        let mut map = BPlusTreeMap::with_branching_factor(8);
+        for i in 0..5 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.root_kind(), RootKind::Leaf);
+        let sub = map.sub_map(1..4);
+        assert_eq!(format!("{:?}", sub), "{1: 1, 2: 2, 3: 3}");
+        assert_eq!(format!("{:?}", sub.clone().sub_map(2..)), "{2: 2, 3: 3}");
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn test_sub_map_with_start_after_end() {
+        let (map, _) = even_number_maps();
+        let _ = map.sub_map((Bound::Included(20), Bound::Excluded(10)));
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod try_insert_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+
+    #[test]
+    fn test_try_insert_vacant_key() {
+        let mut map = BPlusTreeMap::new();
+
+        let value = map.try_insert(1, "one".to_string()).unwrap();
+        assert_eq!(value, "one");
+        value.push_str("_changed");
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&"one_changed".to_string()));
+    }
+
+    #[test]
+    fn test_try_insert_occupied_key() {
+        let mut map = BPlusTreeMap::new();
+        map.insert(1, "one".to_string());
+
+        let error = map.try_insert(1, "uno".to_string()).unwrap_err();
+        assert_eq!(error.value, "uno");
+        assert_eq!(error.entry.key(), &1);
+        assert_eq!(error.entry.get(), "one");
+        assert_eq!(
+            error.to_string(),
+            "failed to insert \"uno\", key 1 already exists with value \"one\""
+        );
+
+        // The existing value was left alone
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&"one".to_string()));
+    }
+
+    #[test]
+    fn test_try_insert_error_gives_access_to_entry() {
+        let mut map = BPlusTreeMap::with_branching_factor(3);
+        for i in 0..20 {
+            map.insert(i, i);
+        }
+
+        let mut error = map.try_insert(7, 700).unwrap_err();
+        *error.entry.get_mut() += error.value;
+
+        assert_eq!(map.get(&7), Some(&707));
+        assert_eq!(map.len(), 20);
+    }
+
+    #[test]
+    fn test_try_insert_splitting_leaves() {
+        // With a branching factor of 2 most insertions split a leaf, which can
+        // move the new value away from where it was first placed
+        let mut map = BPlusTreeMap::with_branching_factor(2);
+        for i in 0..100 {
+            let key = (i * 37) % 100;
+            let value = map.try_insert(key, key * 10).unwrap();
+            assert_eq!(*value, key * 10);
+            *value += 1;
+        }
+        assert_eq!(map.root_kind(), RootKind::Branch);
+        assert_eq!(map.len(), 100);
+
+        for i in 0..100 {
+            assert_eq!(map.get(&i), Some(&(i * 10 + 1)), "key {}", i);
+            assert_eq!(map.try_insert(i, 0).unwrap_err().value, 0);
+        }
+        assert_eq!(map.len(), 100);
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod update_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, RootKind};
+
+    #[test]
+    fn test_update_existing_key() {
+        let mut map = BPlusTreeMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+
+        assert!(map.update("a", |v| *v += 10));
+        assert_eq!(map.get("a"), Some(&11));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_update_missing_key_does_not_call_closure() {
+        let mut map = BPlusTreeMap::new();
+        map.insert(1, 10);
+
+        let mut called = false;
+        assert!(!map.update(&2, |_| called = true));
+        assert!(!called);
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key(&2));
+
+        let mut empty = BPlusTreeMap::<i32, i32>::new();
+        assert!(!empty.update(&1, |_| called = true));
+        assert!(!called);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_update_on_multi_level_tree() {
+        for branching_factor in 2..=6 {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            for i in (0..300).step_by(3) {
+                map.insert(i, i.to_string());
+            }
+            assert_eq!(map.root_kind(), RootKind::Branch);
+
+            for i in 0..300 {
+                let mut calls = 0;
+                let found = map.update(&i, |v| {
+                    calls += 1;
+                    v.push('!');
+                });
+                assert_eq!(found, i % 3 == 0, "key {}", i);
+                assert_eq!(calls, if found { 1 } else { 0 });
+            }
+
+            assert_eq!(map.len(), 100);
+            for (k, v) in map.iter() {
+                assert_eq!(*v, format!("{}!", k));
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod vacant_entry_tests {
+    use crate::bplus_tree_map::{BPlusTreeMap, Entry};
+    use crate::tests::counting_clone::{CountedValue, clones_during};
+    use std::collections::BTreeMap;
+
+    fn filled(branching_factor: usize, len: u64) -> BPlusTreeMap<CountedValue, u64> {
+        let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+        for i in 0..len {
+            map.insert(CountedValue(i * 2), i);
+        }
+        map
+    }
+
+    #[test]
+    fn test_or_insert_into_a_vacant_slot_clones_no_keys() {
+        let mut map = filled(8, 1000);
+        // Make room in the leaf the new key belongs in
+        map.remove(&CountedValue(1002));
+        let clones = clones_during(|| {
+            *map.entry(CountedValue(1001)).or_insert(0) += 1;
+        });
+        assert_eq!(clones, 0);
+        assert_eq!(map.get(&CountedValue(1001)), Some(&1));
+    }
+
+    #[test]
+    fn test_or_insert_into_an_empty_map_clones_no_keys() {
+        let mut map = BPlusTreeMap::new();
+        let clones = clones_during(|| {
+            *map.entry(CountedValue(1)).or_insert(0) += 1;
+        });
+        assert_eq!(clones, 0);
+        assert_eq!(map.get(&CountedValue(1)), Some(&1));
+    }
+
+    #[test]
+    fn test_or_insert_into_a_full_leaf_clones_only_separators() {
+        let mut map = filled(8, 1000);
+        let mut inserted = map.clone();
+        // The last leaves are filled by appending, so the key splits one,
+        // and the split copies a key up as a separator. That is all a
+        // plain insert clones, and all the entry may clone too.
+        let separators = clones_during(|| {
+            inserted.insert(CountedValue(1989), 1);
+        });
+        assert!(separators > 0);
+        let clones = clones_during(|| {
+            *map.entry(CountedValue(1989)).or_insert(0) += 1;
+        });
+        assert_eq!(clones, separators);
+        assert_eq!(map.get(&CountedValue(1989)), Some(&1));
+        assert_eq!(map.shape(), inserted.shape());
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_inserted_values_are_the_ones_returned() {
+        for branching_factor in [4, 5, 8] {
+            let mut map = BPlusTreeMap::with_branching_factor(branching_factor);
+            let mut expected = BTreeMap::new();
+            for i in 0..3000u64 {
+                let key = (i * 7919) % 3000;
+                let Entry::Vacant(entry) = map.entry(key) else {
+                    panic!("{} was inserted twice", key);
+                };
+                // The reference handed back must be to the new value, even
+                // when the leaf it went into was split
+                let value = entry.insert(0);
+                *value = i;
+                expected.insert(key, i);
+                if i % 64 == 0 {
+                    assert_eq!(map.check_invariants(), Ok(()));
+                }
+            }
+            assert_eq!(map.check_invariants(), Ok(()));
+            assert!(map.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_vacant_entries_refill_a_map_emptied_by_removals() {
+        let mut map = BPlusTreeMap::with_branching_factor(4);
+        for i in 0..100u64 {
+            map.insert(i, i);
+        }
+        for i in 0..100u64 {
+            map.remove(&i);
+        }
+        assert!(map.is_empty());
+
+        *map.entry(7).or_insert(0) += 1;
+        map.entry(3).or_insert(3);
+        assert_eq!(map.get(&7), Some(&1));
+        assert!(map.iter().eq([(&3, &3), (&7, &1)]));
+    }
+}